@@ -19,7 +19,7 @@
 //! cargo run --example advanced_example
 //! ```
 
-use OPCDaclientRs::{OpcClient, OpcValue, OpcQuality, OpcDataCallback};
+use OPCDaclientRs::{set_language, Language, OpcClient, OpcValue, OpcQuality, OpcDataCallback};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
@@ -60,6 +60,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("=== OPC DA 高级示例开始 ===");
     println!("演示：多服务器、多组、异步订阅、批量操作");
     
+    // 状态描述使用中文
+    set_language(Language::Chinese);
+    
     let start_time = Instant::now();
     
     // 1. 创建 OPC 客户端
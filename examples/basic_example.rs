@@ -53,7 +53,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Read current value
     match item.read_sync() {
         Ok((value, quality, timestamp)) => {
-            println!("Current value: {:?}, Quality: {:?}, Timestamp: {} ms", value, quality, timestamp);
+            println!("Current value: {:?}, Quality: {:?}, Timestamp: {}", value, quality, timestamp);
             
             // Write a new value (if it's an integer type)
             if let OpcValue::Int32(current) = value {
@@ -63,7 +63,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 
                 // Read back to verify
                 let (updated_value, updated_quality, updated_timestamp) = item.read_sync()?;
-                println!("Updated value: {:?}, Quality: {:?}, Timestamp: {} ms", updated_value, updated_quality, updated_timestamp);
+                println!("Updated value: {:?}, Quality: {:?}, Timestamp: {}", updated_value, updated_quality, updated_timestamp);
             }
         }
         Err(e) => println!("Failed to read item: {}", e),
@@ -78,7 +78,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         // Read back to verify
         match item.read_sync() {
             Ok((value, quality, timestamp)) => {
-                println!("Read value: {:?}, Quality: {:?}, Timestamp: {} ms", value, quality, timestamp);
+                println!("Read value: {:?}, Quality: {:?}, Timestamp: {}", value, quality, timestamp);
             }
             Err(e) => println!("Failed to read: {}", e),
         }
@@ -0,0 +1,327 @@
+//! 异常检测模块
+//!
+//! 这个模块在订阅数据流中检测不合理的值：超出物理范围的值、变化过快的尖峰
+//! 和长时间不变的"冻结"值。传感器故障通常先表现为这类异常，
+//! 在数据已经流经的地方检测代价很低。
+//!
+//! ## 冻结值
+//!
+//! 变送器卡死时常常仍然报告 Good 质量。配置 `flatline_after` 后，
+//! 如果项持续收到 Good 质量的通知、值却在该时长内没有任何变化，
+//! 会报告一次 `Flatline` 事件；值变化或质量不再是 Good 后重新计时。
+//! 检测依赖于通知：服务器只在值变化时推送的项不会触发，
+//! 这类项需要服务器定期推送（例如时间戳变化）或由应用周期性刷新组。
+//!
+//! `AnomalyDetector` 实现了 `OpcDataCallback`，可以作为组的额外消费者
+//! （`OpcGroup::add_callback`）加入，检测到异常时调用 `AnomalyHandler`。
+//! 只检查质量为 Good 的数值，其他通知被忽略。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::{AnomalyDetector, AnomalyEvent, ItemLimits};
+//! use std::sync::Arc;
+//!
+//! let detector = Arc::new(AnomalyDetector::new(|event: &AnomalyEvent| {
+//!     eprintln!("{}: {}", event.item_name, event.kind);
+//! }));
+//! detector.set_limits("TI101.PV", ItemLimits {
+//!     min: Some(-40.0),
+//!     max: Some(400.0),
+//!     max_rate_per_sec: Some(5.0),
+//!     ..Default::default()
+//! });
+//!
+//! group.enable_async_subscription(my_callback)?;
+//! group.add_callback(detector.clone())?;
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use crate::types::{lock_or_recover, OpcDataCallback, OpcQuality, OpcValue};
+
+/// 项的合理性限制
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ItemLimits {
+    /// 物理下限
+    pub min: Option<f64>,
+    /// 物理上限
+    pub max: Option<f64>,
+    /// 最大变化速率（每秒）
+    pub max_rate_per_sec: Option<f64>,
+    /// 值保持不变超过此时长时报告冻结
+    pub flatline_after: Option<Duration>,
+}
+
+/// 异常类型
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnomalyKind {
+    /// 值超出物理范围
+    OutOfRange {
+        /// 当前值
+        value: f64,
+        /// 配置的下限
+        min: Option<f64>,
+        /// 配置的上限
+        max: Option<f64>,
+    },
+    /// 变化速率超过限制
+    Spike {
+        /// 上一个值
+        previous: f64,
+        /// 当前值
+        value: f64,
+        /// 实际变化速率（每秒）
+        rate_per_sec: f64,
+        /// 配置的最大变化速率（每秒）
+        max_rate_per_sec: f64,
+    },
+    /// 值在 Good 质量下长时间没有变化
+    Flatline {
+        /// 保持不变的值
+        value: f64,
+        /// 已保持不变的时长
+        unchanged_for: Duration,
+    },
+}
+
+impl std::fmt::Display for AnomalyKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnomalyKind::OutOfRange { value, min, max } => {
+                let bound = |b: &Option<f64>| b.map(|v| v.to_string()).unwrap_or_else(|| "-".to_string());
+                write!(f, "value {} out of range [{}, {}]", value, bound(min), bound(max))
+            }
+            AnomalyKind::Spike { previous, value, rate_per_sec, max_rate_per_sec } => write!(
+                f,
+                "spike from {} to {} ({:.3}/s, max {}/s)",
+                previous, value, rate_per_sec, max_rate_per_sec
+            ),
+            AnomalyKind::Flatline { value, unchanged_for } => {
+                write!(f, "value {} unchanged for {:?}", value, unchanged_for)
+            }
+        }
+    }
+}
+
+/// 异常事件
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnomalyEvent {
+    /// 组名
+    pub group_name: String,
+    /// 项名
+    pub item_name: String,
+    /// 异常类型
+    pub kind: AnomalyKind,
+    /// 触发异常的通知的时间戳（Unix 毫秒）
+    pub timestamp: u64,
+}
+
+/// 异常事件处理
+///
+/// 在数据变化回调的线程中调用，实现应尽快返回。
+pub trait AnomalyHandler: Send + Sync {
+    /// 检测到异常时调用
+    fn on_anomaly(&self, event: &AnomalyEvent);
+}
+
+impl<F: Fn(&AnomalyEvent) + Send + Sync> AnomalyHandler for F {
+    fn on_anomaly(&self, event: &AnomalyEvent) {
+        self(event)
+    }
+}
+
+/// 每个项的检测状态
+#[derive(Debug, Default)]
+struct ItemState {
+    limits: ItemLimits,
+    /// 上一个 Good 数值（值, 时间戳毫秒）
+    last: Option<(f64, u64)>,
+    /// 当前不变区间的值和起始时间戳
+    unchanged_since: Option<(f64, u64)>,
+    /// 当前不变区间是否已报告冻结
+    flatline_reported: bool,
+}
+
+/// 按项配置的异常检测器
+///
+/// 只检查已配置限制的项。
+pub struct AnomalyDetector {
+    handler: Box<dyn AnomalyHandler>,
+    items: Mutex<HashMap<String, ItemState>>,
+}
+
+impl AnomalyDetector {
+    /// 创建检测器
+    pub fn new(handler: impl AnomalyHandler + 'static) -> Self {
+        AnomalyDetector {
+            handler: Box::new(handler),
+            items: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 设置项的限制，替换已有的限制
+    pub fn set_limits(&self, item_name: &str, limits: ItemLimits) {
+        lock_or_recover(&self.items)
+            .entry(item_name.to_string())
+            .or_default()
+            .limits = limits;
+    }
+
+    /// 移除项的限制
+    pub fn remove_limits(&self, item_name: &str) {
+        lock_or_recover(&self.items).remove(item_name);
+    }
+
+    /// 检查一个样本，返回检测到的异常
+    ///
+    /// 不调用处理器，便于在订阅之外使用（例如检查历史数据）。
+    pub fn check(&self, item_name: &str, value: &OpcValue, quality: OpcQuality, timestamp: u64) -> Vec<AnomalyKind> {
+        let mut items = lock_or_recover(&self.items);
+        let Some(state) = items.get_mut(item_name) else {
+            return Vec::new();
+        };
+        let (OpcQuality::Good, Some(value)) = (quality, value.as_f64()) else {
+            state.unchanged_since = None;
+            state.flatline_reported = false;
+            return Vec::new();
+        };
+
+        let mut anomalies = Vec::new();
+        let limits = &state.limits;
+        if limits.min.is_some_and(|min| value < min) || limits.max.is_some_and(|max| value > max) {
+            anomalies.push(AnomalyKind::OutOfRange {
+                value,
+                min: limits.min,
+                max: limits.max,
+            });
+        }
+
+        if let (Some(max_rate), Some((previous, last_timestamp))) = (limits.max_rate_per_sec, state.last) {
+            if timestamp > last_timestamp {
+                let rate = (value - previous).abs() / ((timestamp - last_timestamp) as f64 / 1000.0);
+                if rate > max_rate {
+                    anomalies.push(AnomalyKind::Spike {
+                        previous,
+                        value,
+                        rate_per_sec: rate,
+                        max_rate_per_sec: max_rate,
+                    });
+                }
+            }
+        }
+
+        if let Some(flatline_after) = limits.flatline_after {
+            match state.unchanged_since {
+                Some((unchanged, since)) if unchanged == value => {
+                    let unchanged_for = Duration::from_millis(timestamp.saturating_sub(since));
+                    if unchanged_for >= flatline_after && !state.flatline_reported {
+                        state.flatline_reported = true;
+                        anomalies.push(AnomalyKind::Flatline { value, unchanged_for });
+                    }
+                }
+                _ => {
+                    state.unchanged_since = Some((value, timestamp));
+                    state.flatline_reported = false;
+                }
+            }
+        }
+
+        state.last = Some((value, timestamp));
+        anomalies
+    }
+}
+
+impl OpcDataCallback for AnomalyDetector {
+    fn on_data_change(&self, group_name: &str, item_name: &str, value: OpcValue, quality: OpcQuality, timestamp: u64) {
+        for kind in self.check(item_name, &value, quality, timestamp) {
+            self.handler.on_anomaly(&AnomalyEvent {
+                group_name: group_name.to_string(),
+                item_name: item_name.to_string(),
+                kind,
+                timestamp,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn detector() -> (AnomalyDetector, Arc<Mutex<Vec<AnomalyEvent>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let detector = AnomalyDetector::new(move |event: &AnomalyEvent| sink.lock().unwrap().push(event.clone()));
+        (detector, events)
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let (detector, events) = detector();
+        detector.set_limits("T", ItemLimits {
+            min: Some(0.0),
+            max: Some(100.0),
+            ..Default::default()
+        });
+
+        detector.on_data_change("G", "T", OpcValue::Double(50.0), OpcQuality::Good, 0);
+        detector.on_data_change("G", "T", OpcValue::Double(150.0), OpcQuality::Good, 1_000);
+        detector.on_data_change("G", "T", OpcValue::Double(-1.0), OpcQuality::Bad, 2_000);
+        detector.on_data_change("G", "Other", OpcValue::Double(-1.0), OpcQuality::Good, 2_000);
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0].kind, AnomalyKind::OutOfRange { value, .. } if value == 150.0));
+    }
+
+    #[test]
+    fn test_spike() {
+        let (detector, _) = detector();
+        detector.set_limits("T", ItemLimits {
+            max_rate_per_sec: Some(2.0),
+            ..Default::default()
+        });
+
+        assert!(detector.check("T", &OpcValue::Int32(10), OpcQuality::Good, 0).is_empty());
+        assert!(detector.check("T", &OpcValue::Int32(12), OpcQuality::Good, 1_000).is_empty());
+        let anomalies = detector.check("T", &OpcValue::Int32(20), OpcQuality::Good, 2_000);
+        assert_eq!(anomalies, vec![AnomalyKind::Spike {
+            previous: 12.0,
+            value: 20.0,
+            rate_per_sec: 8.0,
+            max_rate_per_sec: 2.0,
+        }]);
+    }
+
+    #[test]
+    fn test_flatline_reported_once_per_episode() {
+        let (detector, _) = detector();
+        detector.set_limits("T", ItemLimits {
+            flatline_after: Some(Duration::from_secs(10)),
+            ..Default::default()
+        });
+
+        let check = |value: f64, quality: OpcQuality, timestamp: u64| {
+            detector.check("T", &OpcValue::Double(value), quality, timestamp)
+        };
+        assert!(check(5.0, OpcQuality::Good, 0).is_empty());
+        assert!(check(5.0, OpcQuality::Good, 5_000).is_empty());
+        assert_eq!(check(5.0, OpcQuality::Good, 10_000), vec![AnomalyKind::Flatline {
+            value: 5.0,
+            unchanged_for: Duration::from_secs(10),
+        }]);
+        assert!(check(5.0, OpcQuality::Good, 15_000).is_empty());
+
+        // 值变化后重新计时
+        assert!(check(6.0, OpcQuality::Good, 16_000).is_empty());
+        assert!(check(6.0, OpcQuality::Good, 25_000).is_empty());
+
+        // 非 Good 质量中断不变区间
+        assert!(check(6.0, OpcQuality::Uncertain, 26_000).is_empty());
+        assert!(check(6.0, OpcQuality::Good, 30_000).is_empty());
+        assert_eq!(check(6.0, OpcQuality::Good, 40_000).len(), 1);
+    }
+}
@@ -0,0 +1,344 @@
+//! async/await 接口模块（需要 `async` 特性）
+//!
+//! 这个模块提供 `AsyncOpcClient`、`AsyncOpcGroup` 和 `AsyncOpcItem`，
+//! 读写返回可以 `.await` 的 future，订阅返回 `Stream<Item = DataChangeEvent>`。
+//!
+//! ## 线程模型
+//!
+//! OPC 对象不能跨线程使用，也不能在 future 中跨越 `.await` 持有。
+//! 因此每个 `AsyncOpcClient` 启动一个专用工作线程，由它创建并拥有客户端、
+//! 服务器连接、组和项；句柄只保存编号，通过通道把请求发给工作线程，
+//! 再通过 oneshot 通道等待结果。句柄都是 `Send + Sync`，可以在任意执行器的任务之间传递。
+//!
+//! 工作线程按收到的顺序依次执行请求，阻塞的 DCOM 调用不会占用执行器线程。
+//! 所有句柄（客户端、组和项）释放后，工作线程释放 OPC 对象并退出。
+//!
+//! 组和项的操作返回 `Operation`，可以直接 `.await`，也可以先用 `deadline` 设置期限：
+//! 工作线程取到请求时期限已过，请求不再执行，返回 `OpcError::Timeout`，
+//! 快速的画面读取不会被排在前面的慢速批量操作拖到过期后仍然占用工作线程。
+//! 接口不依赖运行时的计时器，future 在工作线程取到请求时才完成；
+//! 需要在期限到达时立即返回，可以再套用执行器的超时。
+//!
+//! 订阅通过现有的回调机制实现：回调把数据变化放入无界通道，
+//! `DataChangeStream` 从通道中读取。订阅关闭（组释放或连接断开）后流结束。
+//!
+//! 接口只使用 tokio 的同步原语，不依赖 tokio 运行时。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::{AsyncOpcClient, ConnectionString, OpcValue};
+//!
+//! let conn: ConnectionString = "progid=Matrikon.OPC.Simulation.1".parse()?;
+//! let client = AsyncOpcClient::connect(&conn).await?;
+//! let group = client.create_group("Plant", true, 1000, 0.0).await?;
+//! let item = group.add_item("Bucket Brigade.Int4").await?;
+//!
+//! item.write(OpcValue::Int32(42)).await?;
+//! let (value, quality, timestamp) = item.read().await?;
+//! let (value, _, _) = item.read().deadline(Duration::from_millis(500)).await?;
+//!
+//! let mut changes = group.subscribe().await?;
+//! while let Some(change) = changes.recv().await {
+//!     println!("{} = {:?}", change.item, change.value);
+//! }
+//! ```
+
+use std::future::{Future, IntoFuture};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc as channel, oneshot};
+use crate::connection::ConnectionString;
+use crate::error::{OpcError, OpcResult};
+use crate::server::DuplicateGroupPolicy;
+use crate::types::{ChannelCallback, DataChangeEvent, EventSender, OpcQuality, OpcTimestamp, OpcValue};
+use crate::worker::{async_reply, worker_stopped, Reply, Request, ServerLink, Worker};
+
+/// 异步 OPC 客户端，对应一个服务器连接
+///
+/// 克隆得到的句柄共享同一个工作线程和服务器连接。
+#[derive(Clone)]
+pub struct AsyncOpcClient {
+    link: Arc<ServerLink>,
+}
+
+impl AsyncOpcClient {
+    /// 启动工作线程并连接到服务器
+    ///
+    /// # 返回值
+    /// - `Ok(AsyncOpcClient)`: 工作线程已连接到服务器
+    /// - `Err(OpcError)`: 创建客户端或连接服务器失败
+    ///
+    /// # 注意
+    /// `connection.readonly` 为 `true` 时，所有项的 `write` 都返回 `OpcError::InvalidParameters`。
+    pub async fn connect(connection: &ConnectionString) -> OpcResult<Self> {
+        let (ready, ready_rx) = oneshot::channel();
+        let worker = Worker::start("opcda-async", async_reply(ready))?;
+        ready_rx.await.unwrap_or_else(|_| Err(worker_stopped()))?;
+
+        let id = worker
+            .call_async(None, |reply| Request::Connect {
+                connection: connection.clone(),
+                reply,
+            })
+            .await?;
+        Ok(AsyncOpcClient {
+            link: ServerLink::new(&worker, id, connection),
+        })
+    }
+
+    /// 创建组，参数与 `OpcServer::create_group` 相同
+    pub fn create_group(&self, name: &str, active: bool, update_rate: u32, deadband: f64) -> Operation<'_, AsyncOpcGroup> {
+        let server = self.link.id;
+        let group_name = name.to_string();
+        let name = name.to_string();
+        Operation::new(
+            &self.link.worker,
+            move |reply| Request::CreateGroup {
+                server,
+                name: group_name,
+                active,
+                update_rate,
+                deadband,
+                policy: DuplicateGroupPolicy::Fail,
+                reply,
+            },
+            move |id| AsyncOpcGroup {
+                id,
+                name,
+                link: Arc::clone(&self.link),
+            },
+        )
+    }
+}
+
+/// 异步 OPC 组
+///
+/// 释放组句柄时，组和组内的项在工作线程中一起释放，
+/// 之后该组的项句柄的读写返回错误。
+pub struct AsyncOpcGroup {
+    id: u64,
+    name: String,
+    link: Arc<ServerLink>,
+}
+
+impl AsyncOpcGroup {
+    /// 组名
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 向组中添加项
+    pub fn add_item(&self, name: &str) -> Operation<'_, AsyncOpcItem> {
+        let group = self.id;
+        let item_name = name.to_string();
+        let name = name.to_string();
+        Operation::new(
+            &self.link.worker,
+            move |reply| Request::AddItem {
+                group,
+                name: item_name,
+                reply,
+            },
+            move |id| AsyncOpcItem {
+                id,
+                name,
+                link: Arc::clone(&self.link),
+            },
+        )
+    }
+
+    /// 刷新组中的所有项，结果通过订阅送达
+    pub fn refresh(&self) -> Operation<'_, ()> {
+        let group = self.id;
+        Operation::new(&self.link.worker, move |reply| Request::Refresh { group, reply }, |()| ())
+    }
+
+    /// 订阅组的数据变化
+    ///
+    /// 第一次调用时启用组的异步订阅；之后的调用为同一订阅添加新的消费者，
+    /// 每个流都收到全部数据变化。
+    pub fn subscribe(&self) -> Operation<'_, DataChangeStream> {
+        let group = self.id;
+        let (sender, receiver) = channel::unbounded_channel();
+        let callback = Arc::new(ChannelCallback::new(sender));
+        Operation::new(
+            &self.link.worker,
+            move |reply| Request::Subscribe { group, callback, reply },
+            move |()| DataChangeStream { receiver },
+        )
+    }
+}
+
+impl Drop for AsyncOpcGroup {
+    fn drop(&mut self) {
+        self.link.worker.notify(Request::ReleaseGroup(self.id));
+    }
+}
+
+/// 异步 OPC 项
+pub struct AsyncOpcItem {
+    id: u64,
+    name: String,
+    link: Arc<ServerLink>,
+}
+
+impl AsyncOpcItem {
+    /// 项名
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 同步读取项值，在工作线程中执行
+    pub fn read(&self) -> Operation<'_, (OpcValue, OpcQuality, OpcTimestamp)> {
+        let item = self.id;
+        Operation::new(&self.link.worker, move |reply| Request::Read { item, reply }, |reading| reading)
+    }
+
+    /// 同步写入项值，在工作线程中执行
+    ///
+    /// # 返回值
+    /// - `Ok(())`: 写入成功
+    /// - `Err(OpcError::InvalidParameters)`: 连接字符串为只读
+    /// - `Err(OpcError)`: 写入失败
+    pub fn write(&self, value: OpcValue) -> Operation<'_, ()> {
+        if self.link.readonly {
+            return Operation::failed(OpcError::invalid_parameters(format!(
+                "Cannot write '{}' on a readonly connection",
+                self.name
+            )));
+        }
+        let item = self.id;
+        Operation::new(&self.link.worker, move |reply| Request::Write { item, value, reply }, |()| ())
+    }
+}
+
+impl Drop for AsyncOpcItem {
+    fn drop(&mut self) {
+        self.link.worker.notify(Request::ReleaseItem(self.id));
+    }
+}
+
+/// 等待结果的 future
+type OperationFuture<'a, T> = Pin<Box<dyn Future<Output = OpcResult<T>> + Send + 'a>>;
+
+/// 发给工作线程的一次操作
+///
+/// 直接 `.await` 或先调用 `deadline` 设置期限。
+#[must_use = "operations do nothing unless awaited"]
+pub struct Operation<'a, T> {
+    /// 按期限发送请求并等待结果
+    start: Box<dyn FnOnce(Option<Instant>) -> OperationFuture<'a, T> + Send + 'a>,
+    budget: Option<Duration>,
+}
+
+impl<'a, T: 'a> Operation<'a, T> {
+    fn new<R: Send + 'static>(
+        worker: &'a Worker,
+        request: impl FnOnce(Reply<R>) -> Request + Send + 'a,
+        finish: impl FnOnce(R) -> T + Send + 'a,
+    ) -> Self {
+        Operation {
+            start: Box::new(move |deadline| Box::pin(async move { worker.call_async(deadline, request).await.map(finish) })),
+            budget: None,
+        }
+    }
+
+    /// 不发送请求，直接以 `error` 完成
+    fn failed(error: OpcError) -> Self {
+        Operation {
+            start: Box::new(move |_| Box::pin(async move { Err(error) })),
+            budget: None,
+        }
+    }
+
+    /// 设置期限，从 `.await` 时开始计算
+    ///
+    /// 工作线程取到请求时已超过期限，请求不执行，结果为 `OpcError::Timeout`。
+    /// 已经开始执行的请求不会被中断。
+    pub fn deadline(mut self, budget: Duration) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+}
+
+impl<'a, T: 'a> IntoFuture for Operation<'a, T> {
+    type Output = OpcResult<T>;
+    type IntoFuture = OperationFuture<'a, T>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        let deadline = self.budget.map(|budget| Instant::now() + budget);
+        (self.start)(deadline)
+    }
+}
+
+/// 数据变化流，订阅关闭后结束
+pub struct DataChangeStream {
+    receiver: channel::UnboundedReceiver<DataChangeEvent>,
+}
+
+impl DataChangeStream {
+    /// 等待下一个数据变化，订阅关闭后返回 `None`
+    pub async fn recv(&mut self) -> Option<DataChangeEvent> {
+        self.receiver.recv().await
+    }
+}
+
+impl futures_core::Stream for DataChangeStream {
+    type Item = DataChangeEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<DataChangeEvent>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+impl EventSender for channel::UnboundedSender<DataChangeEvent> {
+    fn send_event(&self, event: DataChangeEvent) -> bool {
+        self.send(event).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    #[test]
+    fn test_handles_and_futures_are_send() {
+        let conn = ConnectionString::new("localhost", "Matrikon.OPC.Simulation.1");
+        assert_send(&AsyncOpcClient::connect(&conn));
+
+        let link = ServerLink::new(&Worker::stopped(), 1, &conn);
+        let group = AsyncOpcGroup { id: 2, name: "G".to_string(), link: Arc::clone(&link) };
+        let item = AsyncOpcItem { id: 3, name: "A".to_string(), link };
+        assert_send(&group.subscribe());
+        assert_send(&item.write(OpcValue::Int32(1)));
+        assert_send(&item.read());
+    }
+
+    #[tokio::test]
+    async fn test_operations_with_deadline_complete() {
+        let mut conn = ConnectionString::new("localhost", "Matrikon.OPC.Simulation.1");
+        conn.readonly = true;
+        let link = ServerLink::new(&Worker::stopped(), 1, &conn);
+        let item = AsyncOpcItem { id: 3, name: "A".to_string(), link };
+
+        // 只读连接的写入不发送请求
+        let err = item.write(OpcValue::Int32(1)).deadline(Duration::from_millis(500)).await.err().unwrap();
+        assert!(matches!(err, OpcError::InvalidParameters(_)));
+        let err = item.read().deadline(Duration::from_millis(500)).await.err().unwrap();
+        assert!(matches!(err, OpcError::OperationFailed(_)));
+    }
+
+    // 非 Windows 平台上工作线程创建客户端失败，错误返回给调用方
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_connect_reports_worker_setup_errors() {
+        let conn = ConnectionString::new("localhost", "Matrikon.OPC.Simulation.1");
+        let err = AsyncOpcClient::connect(&conn).await.err().unwrap();
+        assert!(err.is_unsupported_platform());
+    }
+}
@@ -0,0 +1,474 @@
+//! 后端抽象模块
+//!
+//! `DaServer`、`DaGroup`、`DaItem` 描述了服务器、组和项的基本操作：
+//! 查询状态、浏览、创建组、添加项、同步读写、订阅和刷新。
+//! `OpcServer`/`OpcGroup`/`OpcItem`（通过 DLL 访问真实服务器）和
+//! `sim` 模块的内存模拟器都实现了这些 trait。
+//!
+//! 针对 trait 编写的应用代码可以在任何操作系统上用模拟器做单元测试，
+//! 在 Windows 上不做修改地连接真实服务器。trait 只包含两种后端都能提供的操作，
+//! 兼容性配置、诊断、缓存等功能仍然通过具体类型使用。
+//!
+//! 与具体类型一样，实现不要求 `Send`，组和项应在创建它们的线程中使用。
+//!
+//! ## 运行时选择后端
+//!
+//! `Backend` 在运行时选择实现，`AnyClient`/`AnyServer`/`AnyGroup`/`AnyItem` 把两种后端
+//! 包装在同一组类型后面，同一个程序可以在 Linux 上连接模拟器、在 Windows 上连接真实服务器，
+//! 应用代码中不需要 `cfg(windows)`。需要兼容性配置等具体功能时匹配枚举的变体。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::backend::{DaGroup, DaItem, DaServer};
+//! use opc_da_client::OpcResult;
+//!
+//! fn read_level<S: DaServer>(server: &S) -> OpcResult<f64> {
+//!     let group = server.create_group("Level", false, 0, 0.0)?;
+//!     let item = group.add_item("Tank1.Level")?;
+//!     let (value, _, _) = item.read_sync()?;
+//!     Ok(value.as_f64().unwrap_or(0.0))
+//! }
+//!
+//! // 生产环境
+//! let level = read_level(&client.connect_to_local_server("Vendor.Server.1")?)?;
+//! // 单元测试
+//! let level = read_level(&SimServer::new().with_tag("Tank1.Level", OpcValue::Double(3.5)))?;
+//!
+//! // 由命令行参数选择后端，默认在 Windows 上连接真实服务器，其他系统上使用模拟器
+//! let backend: Backend = match args.backend {
+//!     Some(name) => name.parse()?,
+//!     None => Backend::platform_default(),
+//! };
+//! let client = backend.client()?;
+//! let level = read_level(&client.connect(&"progid=Matrikon.OPC.Simulation.1".parse()?)?)?;
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use crate::client::OpcClient;
+use crate::connection::ConnectionString;
+use crate::error::{OpcError, OpcResult};
+use crate::group::OpcGroup;
+use crate::item::OpcItem;
+use crate::server::OpcServer;
+use crate::sim::{SimGroup, SimItem, SimServer};
+use crate::types::{OpcDataCallback, OpcQuality, OpcTimestamp, OpcValue, ServerState, SubscriptionCloseReason};
+
+/// 服务器连接
+pub trait DaServer {
+    /// 组的类型
+    type Group: DaGroup;
+
+    /// 获取服务器状态和厂商信息
+    fn get_status(&self) -> OpcResult<(ServerState, String)>;
+
+    /// 创建组，参数含义同 `OpcServer::create_group`
+    fn create_group(&self, name: &str, active: bool, requested_update_rate: u32, deadband: f64) -> OpcResult<Self::Group>;
+
+    /// 获取服务器上的所有项名
+    fn get_item_names(&self) -> OpcResult<Vec<String>>;
+}
+
+/// 项的容器，负责订阅
+pub trait DaGroup {
+    /// 项的类型
+    type Item: DaItem;
+
+    /// 组名
+    fn name(&self) -> &str;
+
+    /// 组是否处于激活状态
+    fn is_active(&self) -> bool;
+
+    /// 服务器实际采用的更新速率（毫秒）
+    fn actual_update_rate(&self) -> u32;
+
+    /// 向组中添加项
+    fn add_item(&self, name: &str) -> OpcResult<Self::Item>;
+
+    /// 启用数据变化通知
+    fn enable_async_subscription(&self, callback: Arc<dyn OpcDataCallback>) -> OpcResult<()>;
+
+    /// 刷新组中的所有项，当前值通过订阅交付
+    fn refresh(&self) -> OpcResult<()>;
+}
+
+pub(crate) use self::internal::Subscriptions;
+
+mod internal {
+    use crate::types::SubscriptionCloseReason;
+
+    /// 组的订阅控制（内部使用）
+    ///
+    /// 定义在私有模块中，应用不能为自己的类型实现，但可以出现在公开类型的约束中。
+    pub trait Subscriptions {
+        /// 关闭组的所有订阅，消费者收到一次 `on_subscription_closed(reason)`
+        fn close_subscriptions(&self, reason: &SubscriptionCloseReason);
+
+        /// 连接中断时停止组的订阅而不关闭它
+        ///
+        /// 消费者收到 `on_connection_interrupted`，之后释放组时不再收到关闭通知，
+        /// 同一个回调可以在新连接上重新注册。
+        fn interrupt_subscriptions(&self, reason: &str);
+    }
+}
+
+/// 组中的一个项
+pub trait DaItem {
+    /// 项名
+    fn name(&self) -> &str;
+
+    /// 同步读取值、质量和时间戳
+    fn read_sync(&self) -> OpcResult<(OpcValue, OpcQuality, OpcTimestamp)>;
+
+    /// 同步写入值
+    fn write_sync(&self, value: &OpcValue) -> OpcResult<()>;
+}
+
+impl DaServer for OpcServer {
+    type Group = OpcGroup;
+
+    fn get_status(&self) -> OpcResult<(ServerState, String)> {
+        OpcServer::get_status(self)
+    }
+
+    fn create_group(&self, name: &str, active: bool, requested_update_rate: u32, deadband: f64) -> OpcResult<OpcGroup> {
+        OpcServer::create_group(self, name, active, requested_update_rate, deadband)
+    }
+
+    fn get_item_names(&self) -> OpcResult<Vec<String>> {
+        OpcServer::get_item_names(self)
+    }
+}
+
+impl DaGroup for OpcGroup {
+    type Item = OpcItem;
+
+    fn name(&self) -> &str {
+        OpcGroup::name(self)
+    }
+
+    fn is_active(&self) -> bool {
+        OpcGroup::is_active(self)
+    }
+
+    fn actual_update_rate(&self) -> u32 {
+        OpcGroup::actual_update_rate(self)
+    }
+
+    fn add_item(&self, name: &str) -> OpcResult<OpcItem> {
+        OpcGroup::add_item(self, name)
+    }
+
+    fn enable_async_subscription(&self, callback: Arc<dyn OpcDataCallback>) -> OpcResult<()> {
+        OpcGroup::enable_async_subscription(self, callback)
+    }
+
+    fn refresh(&self) -> OpcResult<()> {
+        OpcGroup::refresh(self)
+    }
+}
+
+impl Subscriptions for OpcGroup {
+    fn close_subscriptions(&self, reason: &SubscriptionCloseReason) {
+        OpcGroup::close_subscriptions(self, reason)
+    }
+
+    fn interrupt_subscriptions(&self, reason: &str) {
+        OpcGroup::interrupt_subscriptions(self, reason)
+    }
+}
+
+impl DaItem for OpcItem {
+    fn name(&self) -> &str {
+        OpcItem::name(self)
+    }
+
+    fn read_sync(&self) -> OpcResult<(OpcValue, OpcQuality, OpcTimestamp)> {
+        OpcItem::read_sync(self)
+    }
+
+    fn write_sync(&self, value: &OpcValue) -> OpcResult<()> {
+        OpcItem::write_sync(self, value)
+    }
+}
+
+/// 后端实现
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// 通过 OPCClientToolKit DLL 访问真实服务器，只能在 Windows 上使用
+    Toolkit,
+    /// 内存模拟器，可以在任何操作系统上使用
+    Simulator,
+}
+
+impl Backend {
+    /// 当前平台的默认后端：Windows 上为 `Toolkit`，其他系统上为 `Simulator`
+    pub fn platform_default() -> Self {
+        if cfg!(windows) {
+            Backend::Toolkit
+        } else {
+            Backend::Simulator
+        }
+    }
+
+    /// 创建使用此后端的客户端
+    ///
+    /// # 返回值
+    /// - `Ok(AnyClient)`: 创建成功
+    /// - `Err(OpcError::UnsupportedPlatform)`: 在非 Windows 平台上选择了 `Toolkit`
+    pub fn client(self) -> OpcResult<AnyClient> {
+        match self {
+            Backend::Toolkit => Ok(AnyClient::Toolkit(OpcClient::new()?)),
+            Backend::Simulator => Ok(AnyClient::simulator(default_simulator)),
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Backend::Toolkit => write!(f, "toolkit"),
+            Backend::Simulator => write!(f, "simulator"),
+        }
+    }
+}
+
+/// 按名称解析后端，不区分大小写：`toolkit` 或 `simulator`
+impl FromStr for Backend {
+    type Err = OpcError;
+
+    fn from_str(s: &str) -> OpcResult<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "toolkit" => Ok(Backend::Toolkit),
+            "simulator" => Ok(Backend::Simulator),
+            _ => Err(OpcError::invalid_parameters(format!(
+                "Unknown backend '{}', expected 'toolkit' or 'simulator'",
+                s
+            ))),
+        }
+    }
+}
+
+/// `Backend::Simulator` 为每个连接创建的模拟器：`SimServer::standard`，厂商信息为连接的 ProgID
+fn default_simulator(connection: &ConnectionString) -> SimServer {
+    let server = SimServer::standard();
+    server.set_vendor(&connection.progid);
+    server
+}
+
+/// 模拟器后端为每个连接创建模拟器的函数
+type SimulatorFactory = Box<dyn Fn(&ConnectionString) -> SimServer>;
+
+/// 由 `Backend::client` 创建的客户端
+pub enum AnyClient {
+    /// 真实服务器的客户端，由它建立的连接必须先于它释放
+    Toolkit(OpcClient),
+    /// 模拟器，每次连接创建一个新的模拟服务器
+    Simulator(SimulatorFactory),
+}
+
+impl AnyClient {
+    /// 使用自定义模拟器的客户端，`factory` 为每个连接创建模拟服务器
+    pub fn simulator(factory: impl Fn(&ConnectionString) -> SimServer + 'static) -> Self {
+        AnyClient::Simulator(Box::new(factory))
+    }
+
+    /// 客户端使用的后端
+    pub fn backend(&self) -> Backend {
+        match self {
+            AnyClient::Toolkit(_) => Backend::Toolkit,
+            AnyClient::Simulator(_) => Backend::Simulator,
+        }
+    }
+
+    /// 连接到服务器，参数含义同 `OpcClient::connect`
+    pub fn connect(&self, connection: &ConnectionString) -> OpcResult<AnyServer> {
+        match self {
+            AnyClient::Toolkit(client) => Ok(AnyServer::Toolkit(client.connect(connection)?)),
+            AnyClient::Simulator(factory) => Ok(AnyServer::Simulator(factory(connection))),
+        }
+    }
+}
+
+/// 任一后端的服务器连接
+pub enum AnyServer {
+    /// 真实服务器
+    Toolkit(OpcServer),
+    /// 模拟器
+    Simulator(SimServer),
+}
+
+/// 任一后端的组
+pub enum AnyGroup {
+    /// 真实服务器中的组
+    Toolkit(OpcGroup),
+    /// 模拟器中的组
+    Simulator(SimGroup),
+}
+
+/// 任一后端的项
+pub enum AnyItem {
+    /// 真实服务器中的项
+    Toolkit(OpcItem),
+    /// 模拟器中的项
+    Simulator(SimItem),
+}
+
+impl DaServer for AnyServer {
+    type Group = AnyGroup;
+
+    fn get_status(&self) -> OpcResult<(ServerState, String)> {
+        match self {
+            AnyServer::Toolkit(server) => server.get_status(),
+            AnyServer::Simulator(server) => server.get_status(),
+        }
+    }
+
+    fn create_group(&self, name: &str, active: bool, requested_update_rate: u32, deadband: f64) -> OpcResult<AnyGroup> {
+        match self {
+            AnyServer::Toolkit(server) => server
+                .create_group(name, active, requested_update_rate, deadband)
+                .map(AnyGroup::Toolkit),
+            AnyServer::Simulator(server) => server
+                .create_group(name, active, requested_update_rate, deadband)
+                .map(AnyGroup::Simulator),
+        }
+    }
+
+    fn get_item_names(&self) -> OpcResult<Vec<String>> {
+        match self {
+            AnyServer::Toolkit(server) => server.get_item_names(),
+            AnyServer::Simulator(server) => server.get_item_names(),
+        }
+    }
+}
+
+impl DaGroup for AnyGroup {
+    type Item = AnyItem;
+
+    fn name(&self) -> &str {
+        match self {
+            AnyGroup::Toolkit(group) => group.name(),
+            AnyGroup::Simulator(group) => group.name(),
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        match self {
+            AnyGroup::Toolkit(group) => group.is_active(),
+            AnyGroup::Simulator(group) => group.is_active(),
+        }
+    }
+
+    fn actual_update_rate(&self) -> u32 {
+        match self {
+            AnyGroup::Toolkit(group) => group.actual_update_rate(),
+            AnyGroup::Simulator(group) => group.actual_update_rate(),
+        }
+    }
+
+    fn add_item(&self, name: &str) -> OpcResult<AnyItem> {
+        match self {
+            AnyGroup::Toolkit(group) => group.add_item(name).map(AnyItem::Toolkit),
+            AnyGroup::Simulator(group) => group.add_item(name).map(AnyItem::Simulator),
+        }
+    }
+
+    fn enable_async_subscription(&self, callback: Arc<dyn OpcDataCallback>) -> OpcResult<()> {
+        match self {
+            AnyGroup::Toolkit(group) => group.enable_async_subscription(callback),
+            AnyGroup::Simulator(group) => group.enable_async_subscription(callback),
+        }
+    }
+
+    fn refresh(&self) -> OpcResult<()> {
+        match self {
+            AnyGroup::Toolkit(group) => group.refresh(),
+            AnyGroup::Simulator(group) => group.refresh(),
+        }
+    }
+}
+
+impl Subscriptions for AnyGroup {
+    fn close_subscriptions(&self, reason: &SubscriptionCloseReason) {
+        match self {
+            AnyGroup::Toolkit(group) => Subscriptions::close_subscriptions(group, reason),
+            AnyGroup::Simulator(group) => group.close_subscriptions(reason),
+        }
+    }
+
+    fn interrupt_subscriptions(&self, reason: &str) {
+        match self {
+            AnyGroup::Toolkit(group) => Subscriptions::interrupt_subscriptions(group, reason),
+            AnyGroup::Simulator(group) => group.interrupt_subscriptions(reason),
+        }
+    }
+}
+
+impl DaItem for AnyItem {
+    fn name(&self) -> &str {
+        match self {
+            AnyItem::Toolkit(item) => item.name(),
+            AnyItem::Simulator(item) => item.name(),
+        }
+    }
+
+    fn read_sync(&self) -> OpcResult<(OpcValue, OpcQuality, OpcTimestamp)> {
+        match self {
+            AnyItem::Toolkit(item) => item.read_sync(),
+            AnyItem::Simulator(item) => item.read_sync(),
+        }
+    }
+
+    fn write_sync(&self, value: &OpcValue) -> OpcResult<()> {
+        match self {
+            AnyItem::Toolkit(item) => item.write_sync(value),
+            AnyItem::Simulator(item) => item.write_sync(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 只依赖 trait 的应用代码
+    fn read_level<S: DaServer>(server: &S) -> OpcResult<OpcValue> {
+        let group = server.create_group("Level", true, 1000, 0.0)?;
+        let item = group.add_item("Bucket Brigade.Real8")?;
+        item.write_sync(&OpcValue::Double(3.5))?;
+        Ok(item.read_sync()?.0)
+    }
+
+    #[test]
+    fn test_backend_names() {
+        assert_eq!("Simulator".parse::<Backend>().unwrap(), Backend::Simulator);
+        assert_eq!(" toolkit ".parse::<Backend>().unwrap(), Backend::Toolkit);
+        assert!(matches!("native".parse::<Backend>(), Err(OpcError::InvalidParameters(_))));
+        assert_eq!(Backend::Simulator.to_string(), "simulator");
+        assert_eq!(Backend::platform_default() == Backend::Toolkit, cfg!(windows));
+    }
+
+    #[test]
+    fn test_simulator_backend_runs_trait_code() {
+        let client = Backend::Simulator.client().unwrap();
+        assert_eq!(client.backend(), Backend::Simulator);
+        let server = client.connect(&ConnectionString::new("localhost", "Matrikon.OPC.Simulation.1")).unwrap();
+        assert_eq!(server.get_status().unwrap(), (ServerState::Running, "Matrikon.OPC.Simulation.1".to_string()));
+        assert_eq!(read_level(&server).unwrap(), OpcValue::Double(3.5));
+
+        // 自定义模拟器
+        let client = AnyClient::simulator(|_| SimServer::new().with_tag("Tank1.Level", OpcValue::Int32(7)));
+        let server = client.connect(&ConnectionString::new("localhost", "Plant.Server.1")).unwrap();
+        assert_eq!(server.get_item_names().unwrap(), vec!["Tank1.Level".to_string()]);
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_toolkit_backend_is_unsupported_off_windows() {
+        assert!(Backend::Toolkit.client().err().unwrap().is_unsupported_platform());
+    }
+}
@@ -0,0 +1,208 @@
+//! 阻塞接口模块
+//!
+//! 在 tokio 等异步运行时中访问 OPC 服务器时，通常把阻塞调用放进 `spawn_blocking`
+//! 的线程池。`OpcServer` 等类型不是 `Send`，不能这样使用；直接在运行时的线程上
+//! 初始化 COM 又会得到难以理解的 COM 错误。这个模块的 `Client`、`Group` 和 `Item`
+//! 是 `Send + Sync` 的阻塞句柄，可以在任意线程池线程中调用：
+//!
+//! - 与 `SharedOpcClient` 相同，句柄背后是一个专用的工作线程（`opcda-blocking`），
+//!   所有 COM 对象都在该线程中创建、使用和释放，调用方线程不初始化 COM
+//! - 每次调用先检查调用方线程：在 COM 单线程套间（STA，例如 UI 线程或自己初始化了
+//!   STA 的线程）中阻塞会挂起该套间的消息循环，可能导致死锁，这种调用直接返回
+//!   `OpcError::ReentrantCall`，错误信息说明应改用 `spawn_blocking` 或普通线程
+//! - 在工作线程中（例如该连接的数据变化回调里）调用同样返回 `OpcError::ReentrantCall`
+//!
+//! 非 Windows 平台没有 COM 套间，套间检查总是通过。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::blocking;
+//! use std::sync::Arc;
+//!
+//! let conn: ConnectionString = "progid=Matrikon.OPC.Simulation.1".parse()?;
+//! let item = tokio::task::spawn_blocking(move || -> OpcResult<_> {
+//!     let client = blocking::Client::connect(&conn)?;
+//!     let group = client.create_group("Plant", true, 1000, 0.0)?;
+//!     Ok(Arc::new(group.add_item("Bucket Brigade.Int4")?))
+//! })
+//! .await??;
+//!
+//! let reader = Arc::clone(&item);
+//! let (value, quality, _) = tokio::task::spawn_blocking(move || reader.read_sync()).await??;
+//! ```
+
+use std::sync::mpsc::Receiver;
+use crate::connection::ConnectionString;
+use crate::error::{OpcError, OpcResult};
+use crate::shared::{SharedOpcClient, SharedOpcGroup, SharedOpcItem};
+use crate::types::{DataChangeEvent, OpcQuality, OpcTimestamp, OpcValue, ServerState};
+use crate::worker::Worker;
+
+/// 调用方线程的 COM 套间
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Apartment {
+    /// 未初始化 COM 或多线程套间，可以阻塞
+    Free,
+    /// 单线程套间
+    #[cfg_attr(not(windows), allow(dead_code))]
+    SingleThreaded,
+}
+
+#[cfg(windows)]
+fn current_apartment() -> Apartment {
+    use windows::Win32::System::Com as olecom;
+
+    let mut kind = olecom::APTTYPE::default();
+    let mut qualifier = olecom::APTTYPEQUALIFIER::default();
+    // 未初始化 COM 时返回 CO_E_NOTINITIALIZED
+    match unsafe { olecom::CoGetApartmentType(&mut kind, &mut qualifier) } {
+        Ok(()) if kind == olecom::APTTYPE_STA || kind == olecom::APTTYPE_MAINSTA => Apartment::SingleThreaded,
+        _ => Apartment::Free,
+    }
+}
+
+#[cfg(not(windows))]
+fn current_apartment() -> Apartment {
+    Apartment::Free
+}
+
+/// 检查当前线程是否可以阻塞等待工作线程
+fn check_thread(operation: &str) -> OpcResult<()> {
+    check_apartment(operation, current_apartment())
+}
+
+fn check_apartment(operation: &str, apartment: Apartment) -> OpcResult<()> {
+    match apartment {
+        Apartment::Free => Ok(()),
+        Apartment::SingleThreaded => Err(OpcError::ReentrantCall(format!(
+            "{} would block a COM single-threaded apartment it does not own; call it from spawn_blocking or a plain thread",
+            operation
+        ))),
+    }
+}
+
+/// 可以在任意线程中调用的阻塞客户端，对应一个服务器连接
+///
+/// 克隆得到的句柄共享同一个工作线程和服务器连接。
+#[derive(Clone)]
+pub struct Client {
+    inner: SharedOpcClient,
+}
+
+impl Client {
+    /// 启动工作线程并连接到服务器
+    ///
+    /// # 返回值
+    /// - `Ok(Client)`: 工作线程已连接到服务器
+    /// - `Err(OpcError::ReentrantCall)`: 在 COM 单线程套间中调用
+    /// - `Err(OpcError)`: 创建客户端或连接服务器失败
+    pub fn connect(connection: &ConnectionString) -> OpcResult<Self> {
+        check_thread("blocking::Client::connect")?;
+        let worker = Worker::start_blocking("opcda-blocking")?;
+        Ok(Client {
+            inner: SharedOpcClient::connect_with(&worker, connection)?,
+        })
+    }
+
+    /// 获取服务器状态，同 `OpcServer::get_status`
+    pub fn get_status(&self) -> OpcResult<(ServerState, String)> {
+        check_thread("blocking::Client::get_status")?;
+        self.inner.get_status()
+    }
+
+    /// 创建组，参数与 `OpcServer::create_group` 相同
+    pub fn create_group(&self, name: &str, active: bool, update_rate: u32, deadband: f64) -> OpcResult<Group> {
+        check_thread("blocking::Client::create_group")?;
+        Ok(Group {
+            inner: self.inner.create_group(name, active, update_rate, deadband)?,
+        })
+    }
+}
+
+/// 可以在任意线程中调用的阻塞组句柄
+pub struct Group {
+    inner: SharedOpcGroup,
+}
+
+impl Group {
+    /// 组名
+    pub fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    /// 向组中添加项
+    pub fn add_item(&self, name: &str) -> OpcResult<Item> {
+        check_thread("blocking::Group::add_item")?;
+        Ok(Item {
+            inner: self.inner.add_item(name)?,
+        })
+    }
+
+    /// 刷新组中的所有项，结果通过订阅送达
+    pub fn refresh(&self) -> OpcResult<()> {
+        check_thread("blocking::Group::refresh")?;
+        self.inner.refresh()
+    }
+
+    /// 订阅组的数据变化，同 `SharedOpcGroup::subscribe`
+    pub fn subscribe(&self) -> OpcResult<Receiver<DataChangeEvent>> {
+        check_thread("blocking::Group::subscribe")?;
+        self.inner.subscribe()
+    }
+}
+
+/// 可以在任意线程中调用的阻塞项句柄
+pub struct Item {
+    inner: SharedOpcItem,
+}
+
+impl Item {
+    /// 项名
+    pub fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    /// 同步读取项值，同 `OpcItem::read_sync`
+    pub fn read_sync(&self) -> OpcResult<(OpcValue, OpcQuality, OpcTimestamp)> {
+        check_thread("blocking::Item::read_sync")?;
+        self.inner.read_sync()
+    }
+
+    /// 同步写入项值，同 `OpcItem::write_sync`
+    pub fn write_sync(&self, value: &OpcValue) -> OpcResult<()> {
+        check_thread("blocking::Item::write_sync")?;
+        self.inner.write_sync(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+    #[test]
+    fn test_handles_are_send_sync() {
+        assert_send_sync::<Client>();
+        assert_send_sync::<Group>();
+        assert_send_sync::<Item>();
+    }
+
+    #[test]
+    fn test_single_threaded_apartment_is_rejected() {
+        assert!(check_apartment("blocking::Item::read_sync", Apartment::Free).is_ok());
+        let err = check_apartment("blocking::Item::read_sync", Apartment::SingleThreaded).unwrap_err();
+        assert!(matches!(&err, OpcError::ReentrantCall(message) if message.starts_with("blocking::Item::read_sync ")));
+        // 测试线程没有初始化 COM
+        assert!(check_thread("blocking::Item::read_sync").is_ok());
+    }
+
+    // 非 Windows 平台上工作线程创建客户端失败，错误返回给调用方
+    #[cfg(not(windows))]
+    #[test]
+    fn test_connect_reports_client_errors() {
+        let conn: ConnectionString = "progid=Matrikon.OPC.Simulation.1".parse().unwrap();
+        assert!(Client::connect(&conn).err().unwrap().is_unsupported_platform());
+    }
+}
@@ -0,0 +1,154 @@
+//! 缓存模块
+//!
+//! 这个模块提供客户端侧的缓存，用于减少对 OPC 服务器不必要的访问。
+//!
+//! ## 主要类型
+//!
+//! - `UnknownItemCache`: 未知项的否定缓存
+//!
+//! ## 否定缓存
+//!
+//! 配置错误的项名每次添加都会失败，但每次失败都需要一次到服务器的往返，
+//! 大量错误项会拖慢所有正常项的访问。否定缓存在 TTL 内记住添加失败的项名，
+//! 重复的尝试会直接返回 `OpcError::ItemNotFound`，而不再访问服务器。
+//!
+//! 缓存默认禁用，通过 `OpcServer::set_unknown_item_ttl` 启用。
+//! 服务器只返回成功或失败，无法区分项不存在和其他原因，因此启用后
+//! **每一次**添加失败都会被缓存，包括权限不足、服务器暂时繁忙等暂时性的失败；
+//! 在 TTL 内这些项也不会再尝试添加。
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// 未知项的默认缓存时间，为零表示默认禁用缓存
+pub const DEFAULT_UNKNOWN_ITEM_TTL: Duration = Duration::ZERO;
+
+/// 未知项的否定缓存
+///
+/// 记录添加失败的项名及失败时间，在 TTL 内认为该项仍然未知。
+/// 不区分失败的原因，每次添加失败都会被记录。TTL 为零时禁用缓存，这是默认值。
+///
+/// 同一服务器创建的所有组共享一个缓存。
+#[derive(Debug)]
+pub struct UnknownItemCache {
+    /// 缓存有效时间
+    ttl: Cell<Duration>,
+    /// 项名到失败时间的映射
+    entries: RefCell<HashMap<String, Instant>>,
+}
+
+impl UnknownItemCache {
+    /// 创建指定 TTL 的缓存
+    pub fn new(ttl: Duration) -> Self {
+        UnknownItemCache {
+            ttl: Cell::new(ttl),
+            entries: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// 获取缓存有效时间
+    pub fn ttl(&self) -> Duration {
+        self.ttl.get()
+    }
+
+    /// 设置缓存有效时间，为零时禁用缓存并清空已有记录
+    pub fn set_ttl(&self, ttl: Duration) {
+        self.ttl.set(ttl);
+        if ttl.is_zero() {
+            self.clear();
+        }
+    }
+
+    /// 检查项名是否在缓存中且未过期
+    ///
+    /// 过期的记录会被移除。
+    pub fn is_unknown(&self, item_name: &str) -> bool {
+        let ttl = self.ttl.get();
+        let mut entries = self.entries.borrow_mut();
+        match entries.get(item_name) {
+            Some(failed_at) if failed_at.elapsed() < ttl => true,
+            Some(_) => {
+                entries.remove(item_name);
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// 记录添加失败的项名
+    pub fn insert(&self, item_name: &str) {
+        if self.ttl.get().is_zero() {
+            return;
+        }
+        self.entries
+            .borrow_mut()
+            .insert(item_name.to_string(), Instant::now());
+    }
+
+    /// 移除项名的记录（例如项添加成功时）
+    pub fn remove(&self, item_name: &str) {
+        self.entries.borrow_mut().remove(item_name);
+    }
+
+    /// 清空所有记录
+    pub fn clear(&self) {
+        self.entries.borrow_mut().clear();
+    }
+
+    /// 当前记录的项名（包含可能已过期的记录）
+    pub fn items(&self) -> Vec<String> {
+        self.entries.borrow().keys().cloned().collect()
+    }
+}
+
+impl Default for UnknownItemCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_UNKNOWN_ITEM_TTL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_item_cache_hit_and_remove() {
+        let cache = UnknownItemCache::new(Duration::from_secs(30));
+        assert!(!cache.is_unknown("Bad.Tag"));
+
+        cache.insert("Bad.Tag");
+        assert!(cache.is_unknown("Bad.Tag"));
+        assert!(!cache.is_unknown("Good.Tag"));
+
+        cache.remove("Bad.Tag");
+        assert!(!cache.is_unknown("Bad.Tag"));
+    }
+
+    #[test]
+    fn test_unknown_item_cache_expiry() {
+        let cache = UnknownItemCache::new(Duration::from_millis(10));
+        cache.insert("Bad.Tag");
+        assert!(cache.is_unknown("Bad.Tag"));
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(!cache.is_unknown("Bad.Tag"));
+        assert!(cache.items().is_empty());
+    }
+
+    #[test]
+    fn test_unknown_item_cache_disabled() {
+        let cache = UnknownItemCache::new(Duration::from_secs(30));
+        cache.insert("Bad.Tag");
+        cache.set_ttl(Duration::ZERO);
+        assert!(!cache.is_unknown("Bad.Tag"));
+
+        cache.insert("Bad.Tag");
+        assert!(cache.items().is_empty());
+
+        // 默认禁用
+        let cache = UnknownItemCache::default();
+        cache.insert("Bad.Tag");
+        assert!(!cache.is_unknown("Bad.Tag"));
+    }
+}
@@ -23,9 +23,14 @@
 //! - 在非 Windows 平台上，创建客户端会返回错误
 //! - 一个进程通常只需要一个 `OpcClient` 实例
 
+use std::cell::RefCell;
 use std::ptr;
+use std::rc::{Rc, Weak};
+use crate::connection::ConnectionString;
+use crate::diagnostics::DiagnosticsReport;
 use crate::error::{OpcError, OpcResult};
-use crate::server::OpcServer;
+use crate::leaks::LeakReport;
+use crate::server::{OpcServer, ServerShared};
 use crate::utils;
 
 /// OPC 客户端，用于管理 OPC 连接
@@ -58,6 +63,8 @@ use crate::utils;
 pub struct OpcClient {
     /// 标记 OPC 库是否已初始化
     initialized: bool,
+    /// 由此客户端建立的服务器连接，用于诊断报告
+    servers: RefCell<Vec<Weak<ServerShared>>>,
 }
 
 impl OpcClient {
@@ -68,7 +75,7 @@ impl OpcClient {
     /// # 返回值
     /// - `Ok(OpcClient)`: 成功创建客户端
     /// - `Err(OpcError)`: 创建失败，可能的原因包括：
-    ///   - 非 Windows 平台（OPC DA 仅支持 Windows）
+    ///   - 非 Windows 平台（返回 `OpcError::UnsupportedPlatform`）
     ///   - OPC 库初始化失败
     ///   - COM 初始化失败
     /// 
@@ -89,14 +96,12 @@ impl OpcClient {
     /// # 注意
     /// - 一个进程通常只需要一个 `OpcClient` 实例
     /// - 客户端销毁时会自动清理 OPC 库资源
-    /// - 在非 Windows 平台上，此方法总是返回错误
+    /// - 在非 Windows 平台上，此方法总是返回 `OpcError::UnsupportedPlatform`
     pub fn new() -> OpcResult<Self> {
         #[cfg(not(windows))]
         {
             // 非 Windows 平台不支持 OPC DA
-            return Err(OpcError::ComInitializationFailed(
-                "OPC DA Client is only supported on Windows platforms".to_string()
-            ));
+            return Err(OpcError::unsupported_platform());
         }
         
         #[cfg(windows)]
@@ -108,6 +113,7 @@ impl OpcClient {
                 // 初始化成功，创建客户端实例
                 Ok(OpcClient {
                     initialized: true,
+                    servers: RefCell::new(Vec::new()),
                 })
             } else {
                 // 初始化失败，返回错误
@@ -233,7 +239,11 @@ impl OpcClient {
         // 检查服务器连接是否成功
         if result == 0 && !server_ptr.is_null() {
             // 连接成功，创建 OpcServer 对象
-            Ok(OpcServer::new(server_ptr, host_ptr))
+            let server = OpcServer::new(server_ptr, host_ptr, hostname, server_name);
+            let mut servers = self.servers.borrow_mut();
+            servers.retain(|weak| weak.strong_count() > 0);
+            servers.push(Rc::downgrade(server.shared()));
+            Ok(server)
         } else {
             // 连接失败，清理已创建的主机对象
             unsafe {
@@ -245,6 +255,62 @@ impl OpcClient {
         }
     }
     
+    /// 按连接字符串连接到服务器
+    /// 
+    /// 使用连接字符串中的 `host` 和 `progid`。
+    /// 
+    /// # 参数
+    /// - `connection`: 解析后的连接字符串
+    /// 
+    /// # 注意
+    /// `timeout` 和 `readonly` 由应用解释，工具库的连接调用不支持超时。
+    pub fn connect(&self, connection: &ConnectionString) -> OpcResult<OpcServer> {
+        self.connect_to_server(&connection.host, &connection.progid)
+    }
+    
+    /// 生成诊断报告
+    /// 
+    /// 报告包含由此客户端建立且仍然存在的服务器连接、每个连接的组、
+    /// 项数、订阅统计和失败计数，以及库版本和平台。生成时不访问服务器。
+    /// 
+    /// # 示例
+    /// ```ignore
+    /// let report = client.diagnostics_report();
+    /// println!("{}", report);
+    /// ```
+    pub fn diagnostics_report(&self) -> DiagnosticsReport {
+        DiagnosticsReport {
+            crate_version: env!("CARGO_PKG_VERSION"),
+            platform: std::env::consts::OS,
+            servers: self
+                .servers
+                .borrow()
+                .iter()
+                .filter_map(Weak::upgrade)
+                .map(|shared| shared.diagnostics())
+                .collect(),
+        }
+    }
+    
+    /// 列出由此客户端创建且仍然存在的服务器连接、组和项
+    /// 
+    /// 用于查找阻止服务器连接释放的组或项。启用 `debug-leaks` 特性时，
+    /// 报告中包含每个对象创建时的调用栈。
+    /// 
+    /// # 注意
+    /// 服务器连接释放后，其组和项如果仍然存在也会列出。
+    pub fn leak_report(&self) -> LeakReport {
+        LeakReport {
+            resources: self
+                .servers
+                .borrow()
+                .iter()
+                .filter_map(Weak::upgrade)
+                .flat_map(|shared| shared.live_resources())
+                .collect(),
+        }
+    }
+    
     /// 检查客户端是否已初始化
     /// 
     /// # 返回值
@@ -274,7 +340,16 @@ impl Drop for OpcClient {
     /// # 注意
     /// - 调用此方法后，不应再使用任何由此客户端创建的 OPC 对象
     /// - 如果客户端未初始化，则不执行任何操作
+    /// - 启用 `debug-leaks` 特性时，如果仍有存活的服务器、组或项，先将泄漏报告输出到标准错误
     fn drop(&mut self) {
+        #[cfg(feature = "debug-leaks")]
+        {
+            let report = self.leak_report();
+            if !report.is_empty() {
+                eprintln!("{}", report);
+            }
+        }
+        
         if self.initialized {
             unsafe {
                 // 调用 FFI 函数停止 OPC 库
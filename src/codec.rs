@@ -0,0 +1,387 @@
+//! 二进制编码模块（需要 `binary` 特性）
+//!
+//! 这个模块为 `OpcValue` 和数据变化提供紧凑的、带版本号的二进制编码，
+//! 用于通过进程间通信传递事件。在高事件速率下，JSON 的开销是可测量的。
+//!
+//! ## 编码格式
+//!
+//! 每条编码数据以一个版本字节开头（当前为 `FORMAT_VERSION`），之后的字段使用
+//! 与 postcard 相同的基本规则：
+//!
+//! - 无符号整数：LEB128 变长编码
+//! - 有符号整数：zigzag 之后按无符号整数编码
+//! - `f32` / `f64`：小端字节
+//! - 字符串：长度（变长编码）+ UTF-8 字节
+//! - 数组：元素个数（变长编码）+ 元素
+//! - `OpcValue`：一个类型标签字节 + 值
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::codec::{encode_value, decode_value};
+//! use opc_da_client::{DataChangeEvent, OpcQuality, OpcTimestamp, OpcValue};
+//!
+//! let bytes = encode_value(&OpcValue::Double(1.5));
+//! assert_eq!(decode_value(&bytes)?, OpcValue::Double(1.5));
+//!
+//! let change = DataChangeEvent {
+//!     group: "G".to_string(),
+//!     item: "Random.Real8".to_string(),
+//!     value: OpcValue::Double(1.5),
+//!     quality: OpcQuality::Good,
+//!     timestamp: OpcTimestamp::from_unix_ms(1_700_000_000_000),
+//! };
+//! let bytes = change.encode();
+//! assert_eq!(DataChangeEvent::decode(&bytes)?, change);
+//! ```
+
+use crate::types::{DataChangeEvent, OpcQuality, OpcTimestamp, OpcValue, OpcValueError};
+
+/// 当前的编码格式版本
+pub const FORMAT_VERSION: u8 = 1;
+
+impl DataChangeEvent {
+    /// 编码数据变化，时间戳按 Unix 毫秒编码
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        writer.string(&self.group);
+        writer.string(&self.item);
+        writer.value(&self.value);
+        writer.signed(self.quality.to_raw() as i64);
+        writer.unsigned(self.timestamp.unix_ms());
+        writer.finish()
+    }
+
+    /// 解码数据变化
+    pub fn decode(bytes: &[u8]) -> Result<Self, OpcValueError> {
+        let mut reader = Reader::new(bytes)?;
+        let change = DataChangeEvent {
+            group: reader.string()?,
+            item: reader.string()?,
+            value: reader.value()?,
+            quality: OpcQuality::from_raw(reader.signed()? as i32),
+            timestamp: OpcTimestamp::from_unix_ms(reader.unsigned()?),
+        };
+        reader.finish()?;
+        Ok(change)
+    }
+}
+
+/// 编码单个值
+pub fn encode_value(value: &OpcValue) -> Vec<u8> {
+    let mut writer = Writer::new();
+    writer.value(value);
+    writer.finish()
+}
+
+/// 解码单个值
+pub fn decode_value(bytes: &[u8]) -> Result<OpcValue, OpcValueError> {
+    let mut reader = Reader::new(bytes)?;
+    let value = reader.value()?;
+    reader.finish()?;
+    Ok(value)
+}
+
+fn invalid(msg: &str) -> OpcValueError {
+    OpcValueError::conversion_error(format!("Invalid binary encoding: {}", msg))
+}
+
+struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    fn new() -> Self {
+        Writer {
+            buf: vec![FORMAT_VERSION],
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.buf
+    }
+
+    fn unsigned(&mut self, mut v: u64) {
+        loop {
+            let byte = (v & 0x7f) as u8;
+            v >>= 7;
+            if v == 0 {
+                self.buf.push(byte);
+                return;
+            }
+            self.buf.push(byte | 0x80);
+        }
+    }
+
+    fn signed(&mut self, v: i64) {
+        self.unsigned(((v << 1) ^ (v >> 63)) as u64);
+    }
+
+    fn f32(&mut self, v: f32) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn f64(&mut self, v: f64) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn string(&mut self, s: &str) {
+        self.unsigned(s.len() as u64);
+        self.buf.extend_from_slice(s.as_bytes());
+    }
+
+    fn value(&mut self, value: &OpcValue) {
+        self.buf.push(value_tag(value));
+        match value {
+            OpcValue::Int8(v) => self.signed(*v as i64),
+            OpcValue::UInt8(v) => self.unsigned(*v as u64),
+            OpcValue::Int16(v) => self.signed(*v as i64),
+            OpcValue::UInt16(v) => self.unsigned(*v as u64),
+            OpcValue::Int32(v) => self.signed(*v as i64),
+            OpcValue::UInt32(v) => self.unsigned(*v as u64),
+            OpcValue::Int64(v) => self.signed(*v),
+            OpcValue::UInt64(v) => self.unsigned(*v),
+            OpcValue::INT(v) => self.signed(*v as i64),
+            OpcValue::UINT(v) => self.unsigned(*v as u64),
+            OpcValue::Float(v) => self.f32(*v),
+            OpcValue::Double(v) => self.f64(*v),
+            OpcValue::Bool(v) => self.buf.push(*v as u8),
+            OpcValue::Cy(v) => self.signed(*v),
+            OpcValue::Decimal(v) => self.string(&v.to_string()),
+            OpcValue::Date(v) => self.f64(*v),
+            OpcValue::String(v) => self.string(v),
+            OpcValue::ArrayInt16(v) => self.array(v, |w, x| w.signed(*x as i64)),
+            OpcValue::ArrayUInt16(v) => self.array(v, |w, x| w.unsigned(*x as u64)),
+            OpcValue::ArrayInt32(v) => self.array(v, |w, x| w.signed(*x as i64)),
+            OpcValue::ArrayUInt32(v) => self.array(v, |w, x| w.unsigned(*x as u64)),
+            OpcValue::ArrayInt64(v) => self.array(v, |w, x| w.signed(*x)),
+            OpcValue::ArrayUInt64(v) => self.array(v, |w, x| w.unsigned(*x)),
+            OpcValue::ArrayFloat(v) => self.array(v, |w, x| w.f32(*x)),
+            OpcValue::ArrayDouble(v) => self.array(v, |w, x| w.f64(*x)),
+            OpcValue::ArrayBool(v) => self.array(v, |w, x| w.buf.push(*x as u8)),
+            OpcValue::ArrayString(v) => self.array(v, |w, x| w.string(x)),
+        }
+    }
+
+    fn array<T>(&mut self, items: &[T], mut write: impl FnMut(&mut Self, &T)) {
+        self.unsigned(items.len() as u64);
+        for item in items {
+            write(self, item);
+        }
+    }
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8]) -> Result<Self, OpcValueError> {
+        match buf.first() {
+            Some(&FORMAT_VERSION) => Ok(Reader { buf, pos: 1 }),
+            Some(version) => Err(invalid(&format!("unsupported version {}", version))),
+            None => Err(invalid("empty input")),
+        }
+    }
+
+    fn finish(&self) -> Result<(), OpcValueError> {
+        if self.pos == self.buf.len() {
+            Ok(())
+        } else {
+            Err(invalid("trailing bytes"))
+        }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], OpcValueError> {
+        let end = self.pos.checked_add(len).filter(|&end| end <= self.buf.len());
+        let end = end.ok_or_else(|| invalid("unexpected end of input"))?;
+        let bytes = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, OpcValueError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn unsigned(&mut self) -> Result<u64, OpcValueError> {
+        let mut value: u64 = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid("varint too long"))
+    }
+
+    fn signed(&mut self) -> Result<i64, OpcValueError> {
+        let v = self.unsigned()?;
+        Ok(((v >> 1) as i64) ^ -((v & 1) as i64))
+    }
+
+    fn narrow<T: TryFrom<i64>>(&mut self) -> Result<T, OpcValueError> {
+        T::try_from(self.signed()?).map_err(|_| invalid("integer out of range"))
+    }
+
+    fn narrow_unsigned<T: TryFrom<u64>>(&mut self) -> Result<T, OpcValueError> {
+        T::try_from(self.unsigned()?).map_err(|_| invalid("integer out of range"))
+    }
+
+    fn f32(&mut self) -> Result<f32, OpcValueError> {
+        let bytes = self.bytes(4)?;
+        Ok(f32::from_le_bytes(bytes.try_into().expect("4 bytes")))
+    }
+
+    fn f64(&mut self) -> Result<f64, OpcValueError> {
+        let bytes = self.bytes(8)?;
+        Ok(f64::from_le_bytes(bytes.try_into().expect("8 bytes")))
+    }
+
+    fn bool(&mut self) -> Result<bool, OpcValueError> {
+        match self.byte()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(invalid("invalid bool")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, OpcValueError> {
+        let len = self.narrow_unsigned::<usize>()?;
+        let bytes = self.bytes(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| invalid("invalid UTF-8"))
+    }
+
+    fn array<T>(&mut self, mut read: impl FnMut(&mut Self) -> Result<T, OpcValueError>) -> Result<Vec<T>, OpcValueError> {
+        let len = self.narrow_unsigned::<usize>()?;
+        // 每个元素至少占一个字节，避免恶意长度导致过大的预分配
+        if len > self.buf.len() - self.pos {
+            return Err(invalid("array length exceeds input"));
+        }
+        (0..len).map(|_| read(self)).collect()
+    }
+
+    fn value(&mut self) -> Result<OpcValue, OpcValueError> {
+        Ok(match self.byte()? {
+            0 => OpcValue::Int8(self.narrow()?),
+            1 => OpcValue::UInt8(self.narrow_unsigned()?),
+            2 => OpcValue::Int16(self.narrow()?),
+            3 => OpcValue::UInt16(self.narrow_unsigned()?),
+            4 => OpcValue::Int32(self.narrow()?),
+            5 => OpcValue::UInt32(self.narrow_unsigned()?),
+            6 => OpcValue::Int64(self.signed()?),
+            7 => OpcValue::UInt64(self.unsigned()?),
+            8 => OpcValue::INT(self.narrow()?),
+            9 => OpcValue::UINT(self.narrow_unsigned()?),
+            10 => OpcValue::Float(self.f32()?),
+            11 => OpcValue::Double(self.f64()?),
+            12 => OpcValue::Bool(self.bool()?),
+            13 => OpcValue::Cy(self.signed()?),
+            14 => OpcValue::Decimal(self.string()?.parse()?),
+            15 => OpcValue::Date(self.f64()?),
+            16 => OpcValue::String(self.string()?),
+            17 => OpcValue::ArrayInt16(self.array(|r| r.narrow())?),
+            18 => OpcValue::ArrayUInt16(self.array(|r| r.narrow_unsigned())?),
+            19 => OpcValue::ArrayInt32(self.array(|r| r.narrow())?),
+            20 => OpcValue::ArrayUInt32(self.array(|r| r.narrow_unsigned())?),
+            21 => OpcValue::ArrayInt64(self.array(|r| r.signed())?),
+            22 => OpcValue::ArrayUInt64(self.array(|r| r.unsigned())?),
+            23 => OpcValue::ArrayFloat(self.array(|r| r.f32())?),
+            24 => OpcValue::ArrayDouble(self.array(|r| r.f64())?),
+            25 => OpcValue::ArrayBool(self.array(|r| r.bool())?),
+            26 => OpcValue::ArrayString(self.array(|r| r.string())?),
+            tag => return Err(invalid(&format!("unknown value tag {}", tag))),
+        })
+    }
+}
+
+/// 值的类型标签，一经发布不能修改
+fn value_tag(value: &OpcValue) -> u8 {
+    match value {
+        OpcValue::Int8(_) => 0,
+        OpcValue::UInt8(_) => 1,
+        OpcValue::Int16(_) => 2,
+        OpcValue::UInt16(_) => 3,
+        OpcValue::Int32(_) => 4,
+        OpcValue::UInt32(_) => 5,
+        OpcValue::Int64(_) => 6,
+        OpcValue::UInt64(_) => 7,
+        OpcValue::INT(_) => 8,
+        OpcValue::UINT(_) => 9,
+        OpcValue::Float(_) => 10,
+        OpcValue::Double(_) => 11,
+        OpcValue::Bool(_) => 12,
+        OpcValue::Cy(_) => 13,
+        OpcValue::Decimal(_) => 14,
+        OpcValue::Date(_) => 15,
+        OpcValue::String(_) => 16,
+        OpcValue::ArrayInt16(_) => 17,
+        OpcValue::ArrayUInt16(_) => 18,
+        OpcValue::ArrayInt32(_) => 19,
+        OpcValue::ArrayUInt32(_) => 20,
+        OpcValue::ArrayInt64(_) => 21,
+        OpcValue::ArrayUInt64(_) => 22,
+        OpcValue::ArrayFloat(_) => 23,
+        OpcValue::ArrayDouble(_) => 24,
+        OpcValue::ArrayBool(_) => 25,
+        OpcValue::ArrayString(_) => 26,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_round_trip() {
+        let values = vec![
+            OpcValue::Int8(-5),
+            OpcValue::UInt16(65535),
+            OpcValue::Int32(i32::MIN),
+            OpcValue::UInt64(u64::MAX),
+            OpcValue::INT(-1),
+            OpcValue::Float(1.25),
+            OpcValue::Double(-0.1),
+            OpcValue::Bool(true),
+            OpcValue::Cy(123_4567),
+            OpcValue::Decimal("-12.50".parse().unwrap()),
+            OpcValue::String("温度".to_string()),
+            OpcValue::ArrayInt16(vec![-1, 0, 1]),
+            OpcValue::ArrayBool(vec![true, false]),
+            OpcValue::ArrayString(vec!["a".to_string(), String::new()]),
+        ];
+        for value in values {
+            assert_eq!(decode_value(&encode_value(&value)).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn test_compact_encoding() {
+        assert_eq!(encode_value(&OpcValue::Int32(-1)), vec![FORMAT_VERSION, 4, 1]);
+        assert_eq!(encode_value(&OpcValue::UInt32(300)), vec![FORMAT_VERSION, 5, 0xac, 0x02]);
+    }
+
+    #[test]
+    fn test_data_change_round_trip() {
+        let change = DataChangeEvent {
+            group: "G".to_string(),
+            item: "Random.Real8".to_string(),
+            value: OpcValue::Double(1.5),
+            quality: OpcQuality::Good,
+            timestamp: OpcTimestamp::from_unix_ms(1_700_000_000_000),
+        };
+        assert_eq!(DataChangeEvent::decode(&change.encode()).unwrap(), change);
+    }
+
+    #[test]
+    fn test_decode_rejects_invalid_input() {
+        assert!(decode_value(&[]).is_err());
+        assert!(decode_value(&[FORMAT_VERSION + 1, 4, 1]).is_err());
+        assert!(decode_value(&[FORMAT_VERSION, 99]).is_err());
+        assert!(decode_value(&[FORMAT_VERSION, 0, 0xfe, 0x03]).is_err());
+        assert!(decode_value(&[FORMAT_VERSION, 4, 1, 0]).is_err());
+        assert!(decode_value(&[FORMAT_VERSION, 17, 0xff, 0xff, 0x03]).is_err());
+    }
+}
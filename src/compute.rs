@@ -0,0 +1,482 @@
+//! 计算通道模块
+//!
+//! 这个模块提供基于订阅数据计算的派生值：时间加权平均值和累积量
+//! （例如由流量累积得到体积）。
+//!
+//! ## 计算方法
+//!
+//! OPC DA 订阅按变化上报数据，两次通知之间值保持不变，因此按阶梯方式积分：
+//! 每个值一直有效，直到下一个通知到达。
+//!
+//! - 时间加权平均值 = 积分 / 有效时间
+//! - 累积量 = 积分 / 时间单位（例如流量单位为 m³/h 时，时间单位为 1 小时）
+//!
+//! 质量不是 Good 的通知会中断积分：从该通知到下一个 Good 通知之间的时间
+//! 既不计入积分，也不计入有效时间，而是记为排除时间。
+//!
+//! ## 质量传播
+//!
+//! 只对 Good 时间求平均时，坏数据占多数的区间也会得到一个看似正常的平均值。
+//! `average_with_quality` 按 `QualityThresholds` 给平均值附带质量：
+//! 排除时间占区间（有效时间 + 排除时间）的比例超过 `uncertain_ratio`（默认 20%）时
+//! 为 Uncertain，超过 `bad_ratio`（默认 50%）或没有有效时间时为 Bad。
+//!
+//! `ComputedChannels` 实现了 `Checkpoint`，可以通过 `Checkpointer` 周期性地保存到磁盘，
+//! 重启后恢复累积值。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::{OpcClient, ComputedChannels};
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! let channels = Arc::new(ComputedChannels::new());
+//! channels.track("FIC101.PV");
+//!
+//! let group = server.create_group("Flow", true, 1000, 0.0)?;
+//! let _item = group.add_item("FIC101.PV")?;
+//! group.enable_async_subscription(channels.clone())?;
+//!
+//! // m³/h 的流量累积为 m³
+//! let volume = channels.total("FIC101.PV", Duration::from_secs(3600));
+//! let average = channels.average("FIC101.PV");
+//! channels.reset("FIC101.PV");
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use crate::error::{OpcError, OpcResult};
+use crate::persist::Checkpoint;
+use crate::types::{lock_or_recover, OpcDataCallback, OpcQuality, OpcValue};
+
+/// 检查点文件头
+const CHECKPOINT_HEADER: &str = "# opcda computed channels v1";
+
+/// 计算值的质量传播阈值
+///
+/// 比例为排除时间（质量不是 Good 或值不是数值的时间）占区间总时间的比例。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QualityThresholds {
+    /// 排除比例超过此值时结果为 Uncertain
+    pub uncertain_ratio: f64,
+    /// 排除比例超过此值时结果为 Bad
+    pub bad_ratio: f64,
+}
+
+impl Default for QualityThresholds {
+    fn default() -> Self {
+        QualityThresholds {
+            uncertain_ratio: 0.2,
+            bad_ratio: 0.5,
+        }
+    }
+}
+
+impl QualityThresholds {
+    /// 排除比例对应的质量
+    pub fn quality(&self, excluded_ratio: f64) -> OpcQuality {
+        if excluded_ratio > self.bad_ratio {
+            OpcQuality::Bad
+        } else if excluded_ratio > self.uncertain_ratio {
+            OpcQuality::Uncertain
+        } else {
+            OpcQuality::Good
+        }
+    }
+}
+
+/// 阶梯积分器
+///
+/// 按时间戳累积值对时间的积分（值 × 毫秒），用于计算时间加权平均值和累积量。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Integrator {
+    /// 上一个有效样本（值, 时间戳毫秒）
+    last: Option<(f64, u64)>,
+    /// 积分（值 × 毫秒）
+    integral: f64,
+    /// 参与积分的有效时间（毫秒）
+    elapsed_ms: u64,
+    /// 上一个中断积分的样本的时间戳
+    excluded_since: Option<u64>,
+    /// 被中断积分的样本覆盖的时间（毫秒）
+    excluded_ms: u64,
+}
+
+impl Integrator {
+    /// 创建空积分器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 加入一个样本
+    ///
+    /// # 参数
+    /// - `value`: 样本值，`None` 或质量不是 Good 表示中断积分
+    /// - `quality`: 样本质量
+    /// - `timestamp`: 样本时间戳（Unix 毫秒）
+    ///
+    /// 时间戳早于上一个样本时，只重新开始积分，不累积这段时间。
+    pub fn update(&mut self, value: Option<f64>, quality: OpcQuality, timestamp: u64) {
+        if let Some((last_value, last_timestamp)) = self.last {
+            if timestamp > last_timestamp {
+                let dt = timestamp - last_timestamp;
+                self.integral += last_value * dt as f64;
+                self.elapsed_ms += dt;
+            }
+        } else if let Some(since) = self.excluded_since {
+            self.excluded_ms += timestamp.saturating_sub(since);
+        }
+
+        self.last = match value {
+            Some(v) if quality == OpcQuality::Good && v.is_finite() => Some((v, timestamp)),
+            _ => None,
+        };
+        self.excluded_since = if self.last.is_none() { Some(timestamp) } else { None };
+    }
+
+    /// 积分值（值 × 毫秒）
+    pub fn integral(&self) -> f64 {
+        self.integral
+    }
+
+    /// 参与积分的有效时间
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_millis(self.elapsed_ms)
+    }
+
+    /// 时间加权平均值，没有有效时间时返回 `None`
+    pub fn average(&self) -> Option<f64> {
+        if self.elapsed_ms == 0 {
+            None
+        } else {
+            Some(self.integral / self.elapsed_ms as f64)
+        }
+    }
+
+    /// 被质量不是 Good 的样本排除的时间
+    pub fn excluded(&self) -> Duration {
+        Duration::from_millis(self.excluded_ms)
+    }
+
+    /// 排除时间占区间总时间的比例，区间为空时返回 `None`
+    pub fn excluded_ratio(&self) -> Option<f64> {
+        let total_ms = self.elapsed_ms + self.excluded_ms;
+        if total_ms == 0 {
+            None
+        } else {
+            Some(self.excluded_ms as f64 / total_ms as f64)
+        }
+    }
+
+    /// 时间加权平均值及其质量
+    ///
+    /// 没有有效时间时返回 `None`；有效时间不足时平均值仍然返回，质量为 Bad。
+    pub fn average_with_quality(&self, thresholds: &QualityThresholds) -> Option<(f64, OpcQuality)> {
+        let average = self.average()?;
+        Some((average, thresholds.quality(self.excluded_ratio().unwrap_or(0.0))))
+    }
+
+    /// 按时间单位计算的累积量
+    ///
+    /// 例如值为每小时流量时，`unit` 为 1 小时，结果为累积体积。
+    pub fn total(&self, unit: Duration) -> f64 {
+        let unit_ms = unit.as_secs_f64() * 1000.0;
+        if unit_ms == 0.0 {
+            0.0
+        } else {
+            self.integral / unit_ms
+        }
+    }
+
+    /// 清零积分、有效时间和排除时间
+    ///
+    /// 保留上一个样本，使重置后的积分从该样本开始继续累积。
+    pub fn reset(&mut self) {
+        self.integral = 0.0;
+        self.elapsed_ms = 0;
+        self.excluded_ms = 0;
+    }
+}
+
+/// 按项维护的计算通道
+///
+/// 实现 `OpcDataCallback`，可以直接作为组的回调（或通过 `add_callback`
+/// 作为额外的消费者），为已跟踪的项维护积分器。未跟踪的项被忽略。
+#[derive(Debug, Default)]
+pub struct ComputedChannels {
+    channels: Mutex<HashMap<String, Integrator>>,
+    thresholds: QualityThresholds,
+}
+
+impl ComputedChannels {
+    /// 创建空的计算通道集合
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置平均值的质量传播阈值
+    pub fn with_quality_thresholds(mut self, thresholds: QualityThresholds) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
+    /// 质量传播阈值
+    pub fn quality_thresholds(&self) -> QualityThresholds {
+        self.thresholds
+    }
+
+    /// 开始跟踪一个项，已跟踪的项保持不变
+    pub fn track(&self, item_name: &str) {
+        lock_or_recover(&self.channels)
+            .entry(item_name.to_string())
+            .or_default();
+    }
+
+    /// 停止跟踪一个项，返回其积分器
+    pub fn untrack(&self, item_name: &str) -> Option<Integrator> {
+        lock_or_recover(&self.channels).remove(item_name)
+    }
+
+    /// 已跟踪的项名
+    pub fn items(&self) -> Vec<String> {
+        lock_or_recover(&self.channels).keys().cloned().collect()
+    }
+
+    /// 加入一个样本，未跟踪的项被忽略
+    pub fn update(&self, item_name: &str, value: &OpcValue, quality: OpcQuality, timestamp: u64) {
+        if let Some(integrator) = lock_or_recover(&self.channels).get_mut(item_name) {
+            integrator.update(value.as_f64(), quality, timestamp);
+        }
+    }
+
+    /// 项的时间加权平均值
+    pub fn average(&self, item_name: &str) -> Option<f64> {
+        lock_or_recover(&self.channels)
+            .get(item_name)
+            .and_then(Integrator::average)
+    }
+
+    /// 项的时间加权平均值及按阈值传播的质量
+    pub fn average_with_quality(&self, item_name: &str) -> Option<(f64, OpcQuality)> {
+        lock_or_recover(&self.channels)
+            .get(item_name)
+            .and_then(|integrator| integrator.average_with_quality(&self.thresholds))
+    }
+
+    /// 项按时间单位计算的累积量，未跟踪的项返回 `None`
+    pub fn total(&self, item_name: &str, unit: Duration) -> Option<f64> {
+        lock_or_recover(&self.channels)
+            .get(item_name)
+            .map(|integrator| integrator.total(unit))
+    }
+
+    /// 项的积分器快照
+    pub fn snapshot(&self, item_name: &str) -> Option<Integrator> {
+        lock_or_recover(&self.channels).get(item_name).cloned()
+    }
+
+    /// 重置一个项的平均值和累积量，返回项是否被跟踪
+    pub fn reset(&self, item_name: &str) -> bool {
+        match lock_or_recover(&self.channels).get_mut(item_name) {
+            Some(integrator) => {
+                integrator.reset();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 重置所有项
+    pub fn reset_all(&self) {
+        for integrator in lock_or_recover(&self.channels).values_mut() {
+            integrator.reset();
+        }
+    }
+}
+
+impl OpcDataCallback for ComputedChannels {
+    fn on_data_change(&self, _group_name: &str, item_name: &str, value: OpcValue, quality: OpcQuality, timestamp: u64) {
+        self.update(item_name, &value, quality, timestamp);
+    }
+}
+
+/// 检查点格式：文件头之后每行一个项，`项名\t积分\t有效时间毫秒\t排除时间毫秒`。
+///
+/// 恢复时不恢复上一个样本：停机期间的值未知，不应计入积分，
+/// 积分从重启后的第一个 Good 通知开始继续累积。
+impl Checkpoint for ComputedChannels {
+    fn checkpoint(&self) -> String {
+        let channels = lock_or_recover(&self.channels);
+        let mut names: Vec<&String> = channels.keys().collect();
+        names.sort();
+
+        let mut out = String::from(CHECKPOINT_HEADER);
+        out.push('\n');
+        for name in names {
+            let integrator = &channels[name];
+            out.push_str(&format!(
+                "{}\t{:?}\t{}\t{}\n",
+                name, integrator.integral, integrator.elapsed_ms, integrator.excluded_ms
+            ));
+        }
+        out
+    }
+
+    fn restore(&self, data: &str) -> OpcResult<usize> {
+        let mut lines = data.lines();
+        if lines.next() != Some(CHECKPOINT_HEADER) {
+            return Err(OpcError::invalid_parameters("Unrecognized computed channel checkpoint"));
+        }
+
+        let mut restored = Vec::new();
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            let invalid = || OpcError::invalid_parameters(format!("Invalid checkpoint line '{}'", line));
+            let mut fields = line.split('\t');
+            let (Some(name), Some(integral), Some(elapsed_ms), Some(excluded_ms), None) =
+                (fields.next(), fields.next(), fields.next(), fields.next(), fields.next())
+            else {
+                return Err(invalid());
+            };
+            let integral: f64 = integral.parse().map_err(|_| invalid())?;
+            let elapsed_ms: u64 = elapsed_ms.parse().map_err(|_| invalid())?;
+            let excluded_ms: u64 = excluded_ms.parse().map_err(|_| invalid())?;
+            restored.push((name.to_string(), integral, elapsed_ms, excluded_ms));
+        }
+
+        let count = restored.len();
+        let mut channels = lock_or_recover(&self.channels);
+        for (name, integral, elapsed_ms, excluded_ms) in restored {
+            let integrator = channels.entry(name).or_default();
+            integrator.integral = integral;
+            integrator.elapsed_ms = elapsed_ms;
+            integrator.excluded_ms = excluded_ms;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_integrator_step_average_and_total() {
+        let mut integrator = Integrator::new();
+        integrator.update(Some(10.0), OpcQuality::Good, 0);
+        integrator.update(Some(20.0), OpcQuality::Good, 1_000);
+        integrator.update(Some(0.0), OpcQuality::Good, 4_000);
+
+        // 10 × 1s + 20 × 3s = 70 值·秒，共 4 秒
+        assert_eq!(integrator.average(), Some(17.5));
+        assert_eq!(integrator.total(Duration::from_secs(1)), 70.0);
+        assert_eq!(integrator.elapsed(), Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_integrator_bad_quality_gap() {
+        let mut integrator = Integrator::new();
+        integrator.update(Some(10.0), OpcQuality::Good, 0);
+        integrator.update(None, OpcQuality::Bad, 1_000);
+        integrator.update(Some(30.0), OpcQuality::Good, 5_000);
+        integrator.update(Some(30.0), OpcQuality::Good, 6_000);
+
+        // 1 ~ 5 秒的空档不计入
+        assert_eq!(integrator.elapsed(), Duration::from_secs(2));
+        assert_eq!(integrator.excluded(), Duration::from_secs(4));
+        assert_eq!(integrator.average(), Some(20.0));
+    }
+
+    #[test]
+    fn test_average_quality_propagation() {
+        let thresholds = QualityThresholds::default();
+        let mut integrator = Integrator::new();
+        integrator.update(Some(10.0), OpcQuality::Good, 0);
+        integrator.update(Some(10.0), OpcQuality::Good, 9_000);
+        assert_eq!(integrator.average_with_quality(&thresholds), Some((10.0, OpcQuality::Good)));
+
+        // 1 秒 Uncertain：排除 10%，仍为 Good
+        integrator.update(Some(50.0), OpcQuality::Uncertain, 10_000);
+        integrator.update(Some(10.0), OpcQuality::Good, 11_000);
+        assert_eq!(integrator.excluded_ratio(), Some(1.0 / 11.0));
+        assert_eq!(integrator.average_with_quality(&thresholds), Some((10.0, OpcQuality::Good)));
+
+        // 再排除 4 秒：5/15 超过 20%
+        integrator.update(None, OpcQuality::Bad, 11_000);
+        integrator.update(Some(10.0), OpcQuality::Good, 15_000);
+        assert_eq!(integrator.average_with_quality(&thresholds), Some((10.0, OpcQuality::Uncertain)));
+
+        // 再排除 15 秒：20/30 超过 50%
+        integrator.update(None, OpcQuality::Bad, 15_000);
+        integrator.update(Some(10.0), OpcQuality::Good, 30_000);
+        assert_eq!(integrator.average_with_quality(&thresholds), Some((10.0, OpcQuality::Bad)));
+
+        integrator.reset();
+        assert_eq!(integrator.excluded_ratio(), None);
+        assert_eq!(integrator.average_with_quality(&thresholds), None);
+
+        let channels = ComputedChannels::new().with_quality_thresholds(QualityThresholds {
+            uncertain_ratio: 0.0,
+            bad_ratio: 1.0,
+        });
+        channels.track("Flow");
+        channels.update("Flow", &OpcValue::Double(1.0), OpcQuality::Good, 0);
+        channels.update("Flow", &OpcValue::Double(1.0), OpcQuality::Bad, 1_000);
+        channels.update("Flow", &OpcValue::Double(1.0), OpcQuality::Good, 2_000);
+        assert_eq!(channels.average_with_quality("Flow"), Some((1.0, OpcQuality::Uncertain)));
+    }
+
+    #[test]
+    fn test_integrator_reset_keeps_last_sample() {
+        let mut integrator = Integrator::new();
+        integrator.update(Some(5.0), OpcQuality::Good, 0);
+        integrator.update(Some(5.0), OpcQuality::Good, 1_000);
+        integrator.reset();
+        assert_eq!(integrator.average(), None);
+
+        integrator.update(Some(5.0), OpcQuality::Good, 2_000);
+        assert_eq!(integrator.total(Duration::from_secs(1)), 5.0);
+    }
+
+    #[test]
+    fn test_computed_channels_only_tracked_items() {
+        let channels = ComputedChannels::new();
+        channels.track("Flow");
+        channels.on_data_change("G", "Flow", OpcValue::Double(3600.0), OpcQuality::Good, 0);
+        channels.on_data_change("G", "Flow", OpcValue::Double(0.0), OpcQuality::Good, 60_000);
+        channels.on_data_change("G", "Other", OpcValue::Double(1.0), OpcQuality::Good, 0);
+
+        // 3600 m³/h 持续 1 分钟 = 60 m³
+        assert_eq!(channels.total("Flow", Duration::from_secs(3600)), Some(60.0));
+        assert_eq!(channels.total("Other", Duration::from_secs(3600)), None);
+
+        assert!(channels.reset("Flow"));
+        assert_eq!(channels.total("Flow", Duration::from_secs(3600)), Some(0.0));
+    }
+
+    #[test]
+    fn test_checkpoint_restore_rejects_garbage() {
+        let channels = ComputedChannels::new();
+        assert!(channels.restore("not a checkpoint").is_err());
+        assert!(channels.restore("# opcda computed channels v1\nFlow\tx\t1\t0\n").is_err());
+        assert!(channels.restore("# opcda computed channels v1\nFlow\t1.5\t1000\n").is_err());
+        assert_eq!(channels.restore("# opcda computed channels v1\nFlow\t1.5\t1000\t0\n").unwrap(), 1);
+        assert_eq!(channels.average("Flow"), Some(0.0015));
+    }
+
+    #[test]
+    fn test_checkpoint_round_trip_keeps_excluded_time() {
+        let channels = ComputedChannels::new();
+        channels.track("Flow");
+        channels.update("Flow", &OpcValue::Double(2.0), OpcQuality::Good, 0);
+        channels.update("Flow", &OpcValue::Double(2.0), OpcQuality::Bad, 3_000);
+        channels.update("Flow", &OpcValue::Double(2.0), OpcQuality::Good, 4_000);
+
+        let restored = ComputedChannels::new();
+        assert_eq!(restored.restore(&channels.checkpoint()).unwrap(), 1);
+        let integrator = restored.snapshot("Flow").unwrap();
+        assert_eq!(integrator.elapsed(), Duration::from_secs(3));
+        assert_eq!(integrator.excluded(), Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,240 @@
+//! 声明式配置模块（需要 `config` 特性）
+//!
+//! 用一个 TOML 文件描述要连接的服务器、每个服务器上的组（更新速率、死区）和项，
+//! `OpcConfig::apply` 按配置建立全部连接并返回按名称索引的 `ConnectedTopology`，
+//! 替代每个应用里重复的建立代码。
+//!
+//! ## 文件格式
+//!
+//! ```toml
+//! [servers.line1]
+//! connection = "host=10.0.0.5;progid=Kepware.KEPServerEX.V6;timeout=5s"
+//!
+//! [[servers.line1.groups]]
+//! name = "Fast"
+//! update_rate = 100
+//! items = ["Channel1.Device1.Speed", "Channel1.Device1.Torque"]
+//!
+//! [[servers.line1.groups]]
+//! name = "Slow"
+//! update_rate = 5000
+//! deadband = 0.5
+//! active = false
+//! items = ["Channel1.Device1.Counter"]
+//!
+//! [tags.speed]
+//! item = "Channel1.Device1.Speed"
+//! scale = 0.1
+//! ```
+//!
+//! `connection` 使用 `ConnectionString` 的格式；组的字段与 `GroupConfig` 相同，
+//! `active` 默认为 `true`，`deadband` 默认为 0。`[tags]` 表声明 `TagMap` 的标签，
+//! `scale` 默认为 1，`offset` 默认为 0。未知的字段视为错误，避免拼写错误被忽略。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::{OpcClient, OpcConfig};
+//!
+//! let config = OpcConfig::from_file("plant.toml")?;
+//! let client = OpcClient::new()?;
+//! let topology = config.apply(&client)?;
+//!
+//! let speed = topology.item("line1", "Fast", "Channel1.Device1.Speed").unwrap();
+//! println!("{:?}", speed.read_sync()?);
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+use serde::{Deserialize, Deserializer};
+use crate::client::OpcClient;
+use crate::connection::ConnectionString;
+use crate::error::{OpcError, OpcResult};
+use crate::group::OpcGroup;
+use crate::item::OpcItem;
+use crate::resilient::GroupConfig;
+use crate::server::OpcServer;
+use crate::tags::TagMap;
+
+/// 一个服务器的配置
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServerConfig {
+    /// 连接字符串
+    #[serde(deserialize_with = "connection_string")]
+    pub connection: ConnectionString,
+    /// 服务器上的组
+    #[serde(default)]
+    pub groups: Vec<GroupConfig>,
+}
+
+fn connection_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ConnectionString, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    text.parse().map_err(serde::de::Error::custom)
+}
+
+/// 服务器、组和项的配置
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OpcConfig {
+    /// 服务器名 -> 服务器配置
+    #[serde(default)]
+    pub servers: BTreeMap<String, ServerConfig>,
+    /// 标签名 -> 标签，见 `tags` 模块
+    #[serde(default)]
+    pub tags: TagMap,
+}
+
+impl OpcConfig {
+    /// 从 TOML 文件加载配置
+    ///
+    /// # 返回值
+    /// - `Ok(OpcConfig)`: 加载成功
+    /// - `Err(OpcError::Io)`: 读取文件失败
+    /// - `Err(OpcError::InvalidParameters)`: 文件格式错误，信息中包含出错的位置
+    pub fn from_file(path: impl AsRef<Path>) -> OpcResult<Self> {
+        std::fs::read_to_string(path)?.parse()
+    }
+
+    /// 按配置连接所有服务器、创建组并添加项
+    ///
+    /// 任何一步失败时释放已经建立的对象并返回错误，错误信息中包含服务器、组或项的名称。
+    pub fn apply(&self, client: &OpcClient) -> OpcResult<ConnectedTopology> {
+        // 每个对象创建后立即放入 topology，中途失败时由它按项、组、服务器的顺序释放
+        let mut topology = ConnectedTopology::default();
+        for (server_name, server_config) in &self.servers {
+            let server = client
+                .connect(&server_config.connection)
+                .map_err(|e| context(format!("server '{}'", server_name), e))?;
+            let server = topology.servers.entry(server_name.clone()).or_insert(server);
+            for group_config in &server_config.groups {
+                let group_key = (server_name.clone(), group_config.name.clone());
+                if topology.groups.contains_key(&group_key) {
+                    return Err(OpcError::invalid_parameters(format!(
+                        "Duplicate group '{}' on server '{}'",
+                        group_config.name, server_name
+                    )));
+                }
+                let group = server
+                    .create_group(
+                        &group_config.name,
+                        group_config.active,
+                        group_config.update_rate,
+                        group_config.deadband,
+                    )
+                    .map_err(|e| context(format!("group '{}/{}'", server_name, group_config.name), e))?;
+                let group = topology.groups.entry(group_key).or_insert(group);
+                for item_name in &group_config.items {
+                    let item = group.add_item(item_name).map_err(|e| {
+                        context(format!("item '{}/{}/{}'", server_name, group_config.name, item_name), e)
+                    })?;
+                    topology
+                        .items
+                        .insert((server_name.clone(), group_config.name.clone(), item_name.clone()), item);
+                }
+            }
+        }
+        Ok(topology)
+    }
+}
+
+impl FromStr for OpcConfig {
+    type Err = OpcError;
+
+    /// 解析 TOML 格式的配置
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        toml::from_str(s).map_err(|e| OpcError::invalid_parameters(format!("Invalid configuration: {}", e)))
+    }
+}
+
+/// 在错误信息前加上出错的对象
+fn context(what: String, error: OpcError) -> OpcError {
+    OpcError::operation_failed(format!("{}: {}", what, error))
+}
+
+/// 按配置建立的服务器、组和项
+///
+/// 字段按项、组、服务器的顺序释放。
+#[derive(Default)]
+pub struct ConnectedTopology {
+    items: BTreeMap<(String, String, String), OpcItem>,
+    groups: BTreeMap<(String, String), OpcGroup>,
+    servers: BTreeMap<String, OpcServer>,
+}
+
+impl ConnectedTopology {
+    /// 按名称查找服务器
+    pub fn server(&self, server: &str) -> Option<&OpcServer> {
+        self.servers.get(server)
+    }
+
+    /// 按服务器名和组名查找组
+    pub fn group(&self, server: &str, group: &str) -> Option<&OpcGroup> {
+        self.groups.get(&(server.to_string(), group.to_string()))
+    }
+
+    /// 按服务器名、组名和项名查找项
+    pub fn item(&self, server: &str, group: &str, item: &str) -> Option<&OpcItem> {
+        self.items.get(&(server.to_string(), group.to_string(), item.to_string()))
+    }
+
+    /// 所有服务器名
+    pub fn server_names(&self) -> impl Iterator<Item = &str> {
+        self.servers.keys().map(String::as_str)
+    }
+
+    /// 服务器上的所有组名
+    pub fn group_names<'a>(&'a self, server: &'a str) -> impl Iterator<Item = &'a str> {
+        self.groups
+            .keys()
+            .filter(move |(s, _)| s == server)
+            .map(|(_, group)| group.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::Tag;
+
+    const SAMPLE: &str = r#"
+[servers.line1]
+connection = "host=10.0.0.5;progid=Kepware.KEPServerEX.V6"
+
+[[servers.line1.groups]]
+name = "Fast"
+update_rate = 100
+items = ["Speed", "Torque"]
+
+[[servers.line1.groups]]
+name = "Slow"
+update_rate = 5000
+deadband = 0.5
+active = false
+
+[tags.speed]
+item = "Speed"
+scale = 0.1
+"#;
+
+    #[test]
+    fn test_parse_config() {
+        let config: OpcConfig = SAMPLE.parse().unwrap();
+        let line1 = &config.servers["line1"];
+        assert_eq!(line1.connection, ConnectionString::new("10.0.0.5", "Kepware.KEPServerEX.V6"));
+        assert_eq!(line1.groups[0], GroupConfig::new("Fast", 100, &["Speed", "Torque"]));
+        assert!(!line1.groups[1].active);
+        assert_eq!(line1.groups[1].deadband, 0.5);
+        assert!(line1.groups[1].items.is_empty());
+        assert_eq!(config.tags.get("speed"), Some(&Tag::scaled("Speed", 0.1, 0.0)));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let typo = SAMPLE.replace("update_rate = 100", "update_rat = 100");
+        assert!(matches!(typo.parse::<OpcConfig>(), Err(OpcError::InvalidParameters(_))));
+        let bad_connection = SAMPLE.replace("host=10.0.0.5;", "hots=10.0.0.5;");
+        assert!(bad_connection.parse::<OpcConfig>().is_err());
+    }
+}
@@ -0,0 +1,236 @@
+//! 连接字符串模块
+//!
+//! 这个模块解析 `key=value;key=value` 形式的连接字符串，例如：
+//!
+//! ```text
+//! host=10.0.0.5;progid=Kepware.KEPServerEX.V6;timeout=5s;readonly=true
+//! ```
+//!
+//! 支持的键（不区分大小写）：
+//!
+//! - `progid`: 服务器 ProgID，必需
+//! - `host`: 主机名或地址，默认 `localhost`
+//! - `timeout`: 超时时间，支持 `ms`、`s`、`m` 单位，例如 `500ms`、`5s`
+//! - `readonly`: 是否只读，`true`/`false`/`1`/`0`/`yes`/`no`
+//!
+//! 值两端的空白会被去掉，空的段（例如结尾的 `;`）会被忽略。
+//! 解析错误会指出出错的键。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::ConnectionString;
+//!
+//! let conn: ConnectionString = "host=10.0.0.5;progid=Kepware.KEPServerEX.V6;readonly=true".parse()?;
+//! let server = client.connect(&conn)?;
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// 连接字符串解析错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ConnectionStringError {
+    /// 段不是 `key=value` 形式
+    #[error("malformed segment '{0}', expected key=value")]
+    Malformed(String),
+    /// 不支持的键
+    #[error("unknown key '{0}'")]
+    UnknownKey(String),
+    /// 键出现了多次
+    #[error("duplicate key '{0}'")]
+    DuplicateKey(String),
+    /// 缺少必需的键
+    #[error("missing required key '{0}'")]
+    MissingKey(String),
+    /// 值无效
+    #[error("invalid value '{value}' for key '{key}': {reason}")]
+    InvalidValue {
+        /// 出错的键
+        key: String,
+        /// 原始值
+        value: String,
+        /// 原因
+        reason: String,
+    },
+}
+
+/// 解析后的连接字符串
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionString {
+    /// 主机名或地址
+    pub host: String,
+    /// 服务器 ProgID
+    pub progid: String,
+    /// 超时时间
+    pub timeout: Option<Duration>,
+    /// 是否只读
+    pub readonly: bool,
+}
+
+impl ConnectionString {
+    /// 创建连接到指定服务器的连接字符串，其余选项使用默认值
+    pub fn new(host: &str, progid: &str) -> Self {
+        ConnectionString {
+            host: host.to_string(),
+            progid: progid.to_string(),
+            timeout: None,
+            readonly: false,
+        }
+    }
+
+    /// 解析连接字符串
+    pub fn parse(input: &str) -> Result<Self, ConnectionStringError> {
+        let mut host = None;
+        let mut progid = None;
+        let mut timeout = None;
+        let mut readonly = None;
+
+        for segment in input.split(';').map(str::trim).filter(|s| !s.is_empty()) {
+            let Some((key, value)) = segment.split_once('=') else {
+                return Err(ConnectionStringError::Malformed(segment.to_string()));
+            };
+            let key = key.trim().to_ascii_lowercase();
+            let value = value.trim();
+            let invalid = |reason: &str| ConnectionStringError::InvalidValue {
+                key: key.clone(),
+                value: value.to_string(),
+                reason: reason.to_string(),
+            };
+
+            let duplicate = match key.as_str() {
+                "host" => {
+                    if value.is_empty() {
+                        return Err(invalid("host must not be empty"));
+                    }
+                    host.replace(value.to_string()).is_some()
+                }
+                "progid" => {
+                    if value.is_empty() {
+                        return Err(invalid("progid must not be empty"));
+                    }
+                    progid.replace(value.to_string()).is_some()
+                }
+                "timeout" => {
+                    let parsed = parse_duration(value).ok_or_else(|| invalid("expected a duration such as 500ms, 5s or 1m"))?;
+                    timeout.replace(parsed).is_some()
+                }
+                "readonly" => {
+                    let parsed = parse_bool(value).ok_or_else(|| invalid("expected true or false"))?;
+                    readonly.replace(parsed).is_some()
+                }
+                _ => return Err(ConnectionStringError::UnknownKey(key)),
+            };
+            if duplicate {
+                return Err(ConnectionStringError::DuplicateKey(key));
+            }
+        }
+
+        Ok(ConnectionString {
+            host: host.unwrap_or_else(|| "localhost".to_string()),
+            progid: progid.ok_or_else(|| ConnectionStringError::MissingKey("progid".to_string()))?,
+            timeout,
+            readonly: readonly.unwrap_or(false),
+        })
+    }
+}
+
+impl FromStr for ConnectionString {
+    type Err = ConnectionStringError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ConnectionString::parse(s)
+    }
+}
+
+impl fmt::Display for ConnectionString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "host={};progid={}", self.host, self.progid)?;
+        if let Some(timeout) = self.timeout {
+            write!(f, ";timeout={}ms", timeout.as_millis())?;
+        }
+        if self.readonly {
+            write!(f, ";readonly=true")?;
+        }
+        Ok(())
+    }
+}
+
+/// 解析带单位的时长
+fn parse_duration(value: &str) -> Option<Duration> {
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number.parse().ok()?;
+    match unit.trim() {
+        "ms" => Some(Duration::from_millis(number)),
+        "s" => Some(Duration::from_secs(number)),
+        "m" => Some(Duration::from_secs(number.checked_mul(60)?)),
+        _ => None,
+    }
+}
+
+/// 解析布尔值
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" => Some(true),
+        "false" | "0" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_connection_string() {
+        let conn: ConnectionString = "host=10.0.0.5; ProgID = Kepware.KEPServerEX.V6;timeout=5s;readonly=true;"
+            .parse()
+            .unwrap();
+        assert_eq!(conn.host, "10.0.0.5");
+        assert_eq!(conn.progid, "Kepware.KEPServerEX.V6");
+        assert_eq!(conn.timeout, Some(Duration::from_secs(5)));
+        assert!(conn.readonly);
+        assert_eq!(
+            conn.to_string(),
+            "host=10.0.0.5;progid=Kepware.KEPServerEX.V6;timeout=5000ms;readonly=true"
+        );
+        assert_eq!(conn.to_string().parse::<ConnectionString>().unwrap(), conn);
+
+        let local = ConnectionString::parse("progid=Matrikon.OPC.Simulation.1").unwrap();
+        assert_eq!(local, ConnectionString::new("localhost", "Matrikon.OPC.Simulation.1"));
+    }
+
+    #[test]
+    fn test_parse_connection_string_errors() {
+        assert_eq!(
+            ConnectionString::parse("host=a;progid=b;timeout=5h").unwrap_err(),
+            ConnectionStringError::InvalidValue {
+                key: "timeout".to_string(),
+                value: "5h".to_string(),
+                reason: "expected a duration such as 500ms, 5s or 1m".to_string(),
+            }
+        );
+        assert_eq!(
+            ConnectionString::parse("progid=b;readonly=maybe").unwrap_err().to_string(),
+            "invalid value 'maybe' for key 'readonly': expected true or false"
+        );
+        assert_eq!(
+            ConnectionString::parse("progid=b;user=x").unwrap_err(),
+            ConnectionStringError::UnknownKey("user".to_string())
+        );
+        assert_eq!(
+            ConnectionString::parse("progid=b;progid=c").unwrap_err(),
+            ConnectionStringError::DuplicateKey("progid".to_string())
+        );
+        assert_eq!(
+            ConnectionString::parse("host=a").unwrap_err(),
+            ConnectionStringError::MissingKey("progid".to_string())
+        );
+        assert_eq!(
+            ConnectionString::parse("progid=b;readonly").unwrap_err(),
+            ConnectionStringError::Malformed("readonly".to_string())
+        );
+    }
+}
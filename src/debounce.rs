@@ -0,0 +1,306 @@
+//! 数字量去抖模块
+//!
+//! 触点抖动的数字量输入会在很短时间内反复翻转，每次翻转都作为数据变化推送，
+//! 直接送入报警处理会产生大量报警。`Debouncer` 放在组和应用回调之间：
+//! 布尔项的新状态必须保持设定的稳定时长才转发给下游回调，
+//! 稳定时长内翻转回原状态的变化被丢弃。
+//!
+//! 只有 Good 质量的 `OpcValue::Bool` 参与去抖。其他类型的值和非 Good 质量的通知
+//! 立即转发，并取消该项等待中的翻转；质量恢复后的第一个布尔值同样立即转发。
+//! 项的第一个值立即转发，作为之后比较的基准。
+//!
+//! 稳定时长由 `check` 按本地时钟判断，应用需要周期性调用 `check`，
+//! 间隔决定了转发的额外延迟。转发的通知带有翻转时收到的质量和时间戳。
+//! 一个去抖器按项名跟踪，多个组包含同名项时应为每个组创建单独的去抖器。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::Debouncer;
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! let debouncer = Arc::new(Debouncer::new(Duration::from_millis(500), alarm_callback));
+//! debouncer.set_stable_time("Door.Open", Duration::from_secs(2));
+//! group.enable_async_subscription(debouncer.clone())?;
+//!
+//! loop {
+//!     debouncer.check();
+//!     std::thread::sleep(Duration::from_millis(100));
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use crate::types::{lock_or_recover, DataChangeEvent, OpcDataCallback, OpcQuality, OpcValue, QualitySummary, SubscriptionCloseReason};
+
+/// 等待稳定的翻转
+#[derive(Debug, Clone)]
+struct PendingTransition {
+    value: bool,
+    quality: OpcQuality,
+    timestamp: u64,
+    /// 收到翻转的本地时刻
+    since: Instant,
+}
+
+/// 每个项的去抖状态
+#[derive(Debug, Default)]
+struct ItemState {
+    group_name: String,
+    /// 最后转发给下游的布尔值，质量变差后清除
+    reported: Option<bool>,
+    pending: Option<PendingTransition>,
+    /// 单独设置的稳定时长
+    stable_time: Option<Duration>,
+}
+
+/// 要转发给下游的通知
+struct Forward {
+    group_name: String,
+    item_name: String,
+    value: OpcValue,
+    quality: OpcQuality,
+    timestamp: u64,
+}
+
+/// 布尔项的去抖过滤器
+pub struct Debouncer {
+    stable_time: Duration,
+    downstream: Arc<dyn OpcDataCallback>,
+    items: Mutex<HashMap<String, ItemState>>,
+}
+
+impl Debouncer {
+    /// 创建去抖器，布尔项的新状态保持 `stable_time` 后转发给 `downstream`
+    pub fn new(stable_time: Duration, downstream: Arc<dyn OpcDataCallback>) -> Self {
+        Debouncer {
+            stable_time,
+            downstream,
+            items: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 默认的稳定时长
+    pub fn stable_time(&self) -> Duration {
+        self.stable_time
+    }
+
+    /// 为单个项设置稳定时长
+    ///
+    /// `Duration::ZERO` 关闭该项的去抖。
+    pub fn set_stable_time(&self, item_name: &str, stable_time: Duration) {
+        lock_or_recover(&self.items)
+            .entry(item_name.to_string())
+            .or_default()
+            .stable_time = Some(stable_time);
+    }
+
+    /// 恢复项的默认稳定时长
+    pub fn clear_stable_time(&self, item_name: &str) {
+        if let Some(state) = lock_or_recover(&self.items).get_mut(item_name) {
+            state.stable_time = None;
+        }
+    }
+
+    /// 正在等待稳定的项名，按名称排序
+    pub fn pending_items(&self) -> Vec<String> {
+        let mut pending: Vec<String> = lock_or_recover(&self.items)
+            .iter()
+            .filter(|(_, state)| state.pending.is_some())
+            .map(|(name, _)| name.clone())
+            .collect();
+        pending.sort();
+        pending
+    }
+
+    /// 转发已经保持稳定时长的翻转
+    ///
+    /// 在调用 `check` 的线程中调用下游回调。返回转发的通知数。
+    pub fn check(&self) -> usize {
+        let forwards = self.check_at(Instant::now());
+        for forward in &forwards {
+            self.forward(forward);
+        }
+        forwards.len()
+    }
+
+    fn check_at(&self, now: Instant) -> Vec<Forward> {
+        let mut items = lock_or_recover(&self.items);
+        let mut forwards: Vec<Forward> = items
+            .iter_mut()
+            .filter_map(|(item_name, state)| {
+                let stable_time = state.stable_time.unwrap_or(self.stable_time);
+                let pending = state.pending.as_ref()?;
+                if now.saturating_duration_since(pending.since) < stable_time {
+                    return None;
+                }
+                let pending = state.pending.take()?;
+                state.reported = Some(pending.value);
+                Some(Forward {
+                    group_name: state.group_name.clone(),
+                    item_name: item_name.clone(),
+                    value: OpcValue::Bool(pending.value),
+                    quality: pending.quality,
+                    timestamp: pending.timestamp,
+                })
+            })
+            .collect();
+        forwards.sort_by(|a, b| a.item_name.cmp(&b.item_name));
+        forwards
+    }
+
+    /// 记录一次通知，需要立即转发时返回要转发的通知
+    fn record_at(
+        &self,
+        group_name: &str,
+        item_name: &str,
+        value: OpcValue,
+        quality: OpcQuality,
+        timestamp: u64,
+        now: Instant,
+    ) -> Option<Forward> {
+        let mut items = lock_or_recover(&self.items);
+        let state = items.entry(item_name.to_string()).or_default();
+        if state.group_name != group_name {
+            state.group_name = group_name.to_string();
+        }
+        let forward = Forward {
+            group_name: group_name.to_string(),
+            item_name: item_name.to_string(),
+            value,
+            quality,
+            timestamp,
+        };
+
+        let value = match forward.value {
+            OpcValue::Bool(value) if quality == OpcQuality::Good => value,
+            _ => {
+                state.pending = None;
+                state.reported = None;
+                return Some(forward);
+            }
+        };
+        let stable_time = state.stable_time.unwrap_or(self.stable_time);
+        match state.reported {
+            // 翻转回已报告的状态：抖动，丢弃等待中的翻转
+            Some(reported) if reported == value => {
+                state.pending = None;
+                None
+            }
+            Some(_) if !stable_time.is_zero() => {
+                // 同一翻转的重复通知不重新计时
+                if state.pending.as_ref().is_none_or(|pending| pending.value != value) {
+                    state.pending = Some(PendingTransition { value, quality, timestamp, since: now });
+                }
+                None
+            }
+            _ => {
+                state.pending = None;
+                state.reported = Some(value);
+                Some(forward)
+            }
+        }
+    }
+
+    fn forward(&self, forward: &Forward) {
+        self.downstream.on_data_change(
+            &forward.group_name,
+            &forward.item_name,
+            forward.value.clone(),
+            forward.quality,
+            forward.timestamp,
+        );
+    }
+}
+
+impl OpcDataCallback for Debouncer {
+    fn on_data_change(&self, group_name: &str, item_name: &str, value: OpcValue, quality: OpcQuality, timestamp: u64) {
+        if let Some(forward) = self.record_at(group_name, item_name, value, quality, timestamp, Instant::now()) {
+            self.forward(&forward);
+        }
+    }
+
+    fn on_subscription_closed(&self, group_name: &str, reason: &SubscriptionCloseReason) {
+        self.downstream.on_subscription_closed(group_name, reason);
+    }
+
+    fn on_connection_interrupted(&self, group_name: &str, reason: &str) {
+        self.downstream.on_connection_interrupted(group_name, reason);
+    }
+
+    fn on_quality_threshold(&self, group_name: &str, summary: &QualitySummary, exceeded: bool) {
+        self.downstream.on_quality_threshold(group_name, summary, exceeded);
+    }
+
+    fn on_backfill(&self, group_name: &str, batch: &[DataChangeEvent]) {
+        self.downstream.on_backfill(group_name, batch);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 记录下游收到的时间戳
+    #[derive(Default)]
+    struct Timestamps(Mutex<Vec<u64>>);
+
+    impl OpcDataCallback for Timestamps {
+        fn on_data_change(&self, _group_name: &str, _item_name: &str, _value: OpcValue, _quality: OpcQuality, timestamp: u64) {
+            self.0.lock().unwrap().push(timestamp);
+        }
+    }
+
+    fn debouncer(stable_time: Duration) -> (Debouncer, Arc<Timestamps>) {
+        let downstream = Arc::new(Timestamps::default());
+        (Debouncer::new(stable_time, downstream.clone()), downstream)
+    }
+
+    #[test]
+    fn test_chattering_input_is_suppressed() {
+        let (debouncer, _events) = debouncer(Duration::from_secs(2));
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let record = |value: bool, timestamp: u64, now: Instant| {
+            debouncer.record_at("G", "DI1", OpcValue::Bool(value), OpcQuality::Good, timestamp, now)
+        };
+
+        // 第一个值立即转发
+        assert!(record(false, 1_000, at(0)).is_some());
+        // 抖动：稳定时长内翻转回原状态
+        assert!(record(true, 2_000, at(1)).is_none());
+        assert_eq!(debouncer.pending_items(), vec!["DI1".to_string()]);
+        assert!(record(false, 2_500, at(1)).is_none());
+        assert!(debouncer.pending_items().is_empty());
+        assert!(debouncer.check_at(at(10)).is_empty());
+
+        // 保持稳定时长的翻转被转发，带翻转时的时间戳
+        assert!(record(true, 3_000, at(20)).is_none());
+        assert!(record(true, 3_500, at(21)).is_none());
+        assert!(debouncer.check_at(at(21)).is_empty());
+        let forwards = debouncer.check_at(at(22));
+        assert_eq!(forwards.len(), 1);
+        assert_eq!(forwards[0].value, OpcValue::Bool(true));
+        assert_eq!(forwards[0].timestamp, 3_000);
+        assert!(debouncer.check_at(at(30)).is_empty());
+    }
+
+    #[test]
+    fn test_non_boolean_and_bad_quality_pass_through() {
+        let (debouncer, events) = debouncer(Duration::from_secs(3600));
+        debouncer.set_stable_time("Fast", Duration::ZERO);
+
+        debouncer.on_data_change("G", "DI1", OpcValue::Bool(false), OpcQuality::Good, 1);
+        debouncer.on_data_change("G", "DI1", OpcValue::Bool(true), OpcQuality::Good, 2);
+        debouncer.on_data_change("G", "DI1", OpcValue::Bool(true), OpcQuality::Bad, 3);
+        // 质量恢复后的第一个值立即转发
+        debouncer.on_data_change("G", "DI1", OpcValue::Bool(false), OpcQuality::Good, 4);
+        debouncer.on_data_change("G", "TI1", OpcValue::Double(1.5), OpcQuality::Good, 5);
+        debouncer.on_data_change("G", "Fast", OpcValue::Bool(false), OpcQuality::Good, 6);
+        debouncer.on_data_change("G", "Fast", OpcValue::Bool(true), OpcQuality::Good, 7);
+
+        assert_eq!(*events.0.lock().unwrap(), vec![1, 3, 4, 5, 6, 7]);
+    }
+}
@@ -0,0 +1,178 @@
+//! 状态描述模块
+//!
+//! 这个模块将原始的 OPC 质量码和服务器状态码转换为可读的描述，
+//! 用于日志和界面显示。描述支持英文和中文两种语言。
+//!
+//! 质量码按 OPC DA 规范拆分为主状态（Good/Uncertain/Bad）、子状态和限值位，
+//! 例如 `0x18` 描述为 `Bad: comm failure`，`0x56` 描述为
+//! `Uncertain: engineering units exceeded, high limited`。
+//!
+//! `QualityCode`、`ServerStateCode` 和 `ServerState` 的 `Display` 使用进程级的语言设置
+//! （`set_language`，默认英文），便于直接用于格式化输出。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::{set_language, Language};
+//!
+//! set_language(Language::Chinese);
+//! let (state, vendor) = server.get_status()?;
+//! println!("服务器状态: {}, 厂商: {}", state, vendor);
+//! ```
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// 描述语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    /// 英文
+    #[default]
+    English,
+    /// 中文
+    Chinese,
+}
+
+static LANGUAGE: AtomicU8 = AtomicU8::new(0);
+
+/// 设置 `Display` 使用的描述语言
+pub fn set_language(language: Language) {
+    LANGUAGE.store(language as u8, Ordering::Relaxed);
+}
+
+/// 当前 `Display` 使用的描述语言
+pub fn language() -> Language {
+    match LANGUAGE.load(Ordering::Relaxed) {
+        1 => Language::Chinese,
+        _ => Language::English,
+    }
+}
+
+/// 按语言选择文本
+fn pick(language: Language, english: &'static str, chinese: &'static str) -> &'static str {
+    match language {
+        Language::English => english,
+        Language::Chinese => chinese,
+    }
+}
+
+/// 描述原始质量码
+pub fn describe_quality(code: i32, language: Language) -> String {
+    let (status, substatus) = match code & 0xC0 {
+        0xC0 => (
+            pick(language, "Good", "好"),
+            match code & 0xFC {
+                0xC0 => None,
+                0xD8 => Some(pick(language, "local override", "本地强制")),
+                _ => Some(pick(language, "unknown substatus", "未知子状态")),
+            },
+        ),
+        0x40 => (
+            pick(language, "Uncertain", "不确定"),
+            match code & 0xFC {
+                0x40 => None,
+                0x44 => Some(pick(language, "last usable value", "最后可用值")),
+                0x50 => Some(pick(language, "sensor not accurate", "传感器不精确")),
+                0x54 => Some(pick(language, "engineering units exceeded", "超出工程单位范围")),
+                0x58 => Some(pick(language, "sub-normal", "低于正常")),
+                _ => Some(pick(language, "unknown substatus", "未知子状态")),
+            },
+        ),
+        0x00 => (
+            pick(language, "Bad", "坏"),
+            match code & 0xFC {
+                0x00 => None,
+                0x04 => Some(pick(language, "configuration error", "配置错误")),
+                0x08 => Some(pick(language, "not connected", "未连接")),
+                0x0C => Some(pick(language, "device failure", "设备故障")),
+                0x10 => Some(pick(language, "sensor failure", "传感器故障")),
+                0x14 => Some(pick(language, "last known value", "最后已知值")),
+                0x18 => Some(pick(language, "comm failure", "通讯故障")),
+                0x1C => Some(pick(language, "out of service", "停止服务")),
+                0x20 => Some(pick(language, "waiting for initial data", "等待初始数据")),
+                _ => Some(pick(language, "unknown substatus", "未知子状态")),
+            },
+        ),
+        // 0x80 在规范中未定义
+        _ => return format!("{} (0x{:02X})", pick(language, "Invalid quality", "无效质量"), code),
+    };
+    let limit = match code & 0x03 {
+        1 => Some(pick(language, "low limited", "低限")),
+        2 => Some(pick(language, "high limited", "高限")),
+        3 => Some(pick(language, "constant", "常量")),
+        _ => None,
+    };
+
+    let details: Vec<&str> = substatus.into_iter().chain(limit).collect();
+    if details.is_empty() {
+        status.to_string()
+    } else {
+        let (colon, comma) = match language {
+            Language::English => (": ", ", "),
+            Language::Chinese => ("：", "，"),
+        };
+        format!("{}{}{}", status, colon, details.join(comma))
+    }
+}
+
+/// 描述服务器状态码（`OPCSERVERSTATE`）
+pub fn describe_server_state(state: u32, language: Language) -> String {
+    let text = match state {
+        1 => pick(language, "Running", "运行"),
+        2 => pick(language, "Failed", "故障"),
+        3 => pick(language, "No configuration", "未配置"),
+        4 => pick(language, "Suspended", "挂起"),
+        5 => pick(language, "Test", "测试"),
+        6 => pick(language, "Communication fault", "通讯故障"),
+        _ => return format!("{} ({})", pick(language, "Unknown", "未知"), state),
+    };
+    text.to_string()
+}
+
+/// 原始质量码，`Display` 输出当前语言的描述
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QualityCode(pub i32);
+
+impl std::fmt::Display for QualityCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&describe_quality(self.0, language()))
+    }
+}
+
+/// 服务器状态码，`Display` 输出当前语言的描述
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerStateCode(pub u32);
+
+impl std::fmt::Display for ServerStateCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&describe_server_state(self.0, language()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_quality() {
+        assert_eq!(describe_quality(0xC0, Language::English), "Good");
+        assert_eq!(describe_quality(0xD8, Language::English), "Good: local override");
+        assert_eq!(describe_quality(0x18, Language::English), "Bad: comm failure");
+        assert_eq!(
+            describe_quality(0x56, Language::English),
+            "Uncertain: engineering units exceeded, high limited"
+        );
+        assert_eq!(describe_quality(0x03, Language::English), "Bad: constant");
+        assert_eq!(describe_quality(0x80, Language::English), "Invalid quality (0x80)");
+
+        assert_eq!(describe_quality(0x08, Language::Chinese), "坏：未连接");
+        assert_eq!(describe_quality(0x45, Language::Chinese), "不确定：最后可用值，低限");
+    }
+
+    #[test]
+    fn test_describe_server_state() {
+        assert_eq!(describe_server_state(1, Language::English), "Running");
+        assert_eq!(describe_server_state(6, Language::Chinese), "通讯故障");
+        assert_eq!(describe_server_state(9, Language::English), "Unknown (9)");
+        assert_eq!(ServerStateCode(4).to_string(), "Suspended");
+    }
+}
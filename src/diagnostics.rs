@@ -0,0 +1,275 @@
+//! 诊断报告模块
+//!
+//! 这个模块生成客户端当前状态的结构化快照：每个服务器连接、
+//! 生效的兼容性配置、组的属性和项数、订阅的通知计数与质量汇总、
+//! 以及失败计数。报告可以直接格式化为文本，附加到技术支持工单中。
+//!
+//! 报告只包含库在本地记录的信息，生成时不访问服务器。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! let client = OpcClient::new()?;
+//! let server = client.connect_to_local_server("Matrikon.OPC.Simulation.1")?;
+//! let group = server.create_group("Plant", true, 1000, 0.0)?;
+//!
+//! let report = client.diagnostics_report();
+//! std::fs::write("opcda-diagnostics.txt", report.to_string())?;
+//! ```
+
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::rc::Rc;
+use std::sync::{Arc, Weak};
+use crate::leaks::{capture_backtrace, LiveResource, ResourceKind};
+use crate::server::ServerShared;
+use crate::types::{OpcCallbackContainer, QualitySummary};
+
+/// 诊断报告
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagnosticsReport {
+    /// 库版本
+    pub crate_version: &'static str,
+    /// 目标平台
+    pub platform: &'static str,
+    /// 由客户端建立且仍然存在的服务器连接
+    pub servers: Vec<ServerDiagnostics>,
+}
+
+/// 服务器连接的诊断信息
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerDiagnostics {
+    /// 主机名
+    pub host: String,
+    /// 服务器名（ProgID）
+    pub server_name: String,
+    /// 生效的兼容性配置名称
+    pub quirks: String,
+    /// 未知项否定缓存中的项数
+    pub unknown_items: usize,
+    /// 仍然存在的组
+    pub groups: Vec<GroupDiagnostics>,
+}
+
+/// 组的诊断信息
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupDiagnostics {
+    /// 组名
+    pub name: String,
+    /// 创建时是否激活
+    pub active: bool,
+    /// 请求的更新速率（毫秒）
+    pub requested_update_rate: u32,
+    /// 服务器返回的实际更新速率（毫秒）
+    pub actual_update_rate: u32,
+    /// 死区值（百分比）
+    pub deadband: f64,
+    /// 组创建时继承的兼容性配置名称
+    pub quirks: String,
+    /// 仍然存在的项数
+    pub items: usize,
+    /// 添加项失败的次数
+    pub add_failures: u64,
+    /// 项读写失败的次数
+    pub io_failures: u64,
+    /// 是否启用了异步订阅
+    pub subscribed: bool,
+    /// 订阅分发的数据变化通知数
+    pub notifications: u64,
+    /// 订阅收到的质量汇总
+    pub quality: QualitySummary,
+}
+
+impl fmt::Display for DiagnosticsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "OPC DA client diagnostics")?;
+        writeln!(f, "version: {}", self.crate_version)?;
+        writeln!(f, "platform: {}", self.platform)?;
+        writeln!(f, "servers: {}", self.servers.len())?;
+        for server in &self.servers {
+            writeln!(f)?;
+            writeln!(f, "server {} on {}", server.server_name, server.host)?;
+            writeln!(f, "  quirks: {}", server.quirks)?;
+            writeln!(f, "  unknown items cached: {}", server.unknown_items)?;
+            writeln!(f, "  groups: {}", server.groups.len())?;
+            for group in &server.groups {
+                writeln!(
+                    f,
+                    "  group {}: active={} rate={}ms (requested {}ms) deadband={} quirks={}",
+                    group.name,
+                    group.active,
+                    group.actual_update_rate,
+                    group.requested_update_rate,
+                    group.deadband,
+                    group.quirks
+                )?;
+                writeln!(
+                    f,
+                    "    items={} add_failures={} io_failures={}",
+                    group.items, group.add_failures, group.io_failures
+                )?;
+                if group.subscribed {
+                    writeln!(
+                        f,
+                        "    subscription: notifications={} good={} uncertain={} bad={}",
+                        group.notifications, group.quality.good, group.quality.uncertain, group.quality.bad
+                    )?;
+                } else {
+                    writeln!(f, "    subscription: none")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 组的运行统计，由组、组的项和服务器共享
+///
+/// 服务器只持有弱引用，用于生成诊断报告。
+#[derive(Debug, Default)]
+pub(crate) struct GroupStats {
+    pub name: String,
+    pub active: bool,
+    pub requested_update_rate: u32,
+    pub actual_update_rate: u32,
+    pub deadband: f64,
+    pub quirks: String,
+    /// 组是否仍然存在
+    pub alive: Cell<bool>,
+    /// 组创建时的调用栈
+    pub backtrace: Option<String>,
+    /// 仍然存在的项（登记编号 -> 项名和创建时的调用栈）
+    pub items: RefCell<BTreeMap<u64, (String, Option<String>)>>,
+    /// 下一个项登记编号
+    pub next_item: Cell<u64>,
+    pub add_failures: Cell<u64>,
+    pub io_failures: Cell<u64>,
+    /// 当前生效的订阅
+    pub subscription: RefCell<Weak<OpcCallbackContainer>>,
+}
+
+impl GroupStats {
+    /// 生成组的诊断信息
+    pub fn diagnostics(&self) -> GroupDiagnostics {
+        let subscription = self.subscription.borrow().upgrade();
+        GroupDiagnostics {
+            name: self.name.clone(),
+            active: self.active,
+            requested_update_rate: self.requested_update_rate,
+            actual_update_rate: self.actual_update_rate,
+            deadband: self.deadband,
+            quirks: self.quirks.clone(),
+            items: self.items.borrow().len(),
+            add_failures: self.add_failures.get(),
+            io_failures: self.io_failures.get(),
+            subscribed: subscription.is_some(),
+            notifications: subscription.as_ref().map_or(0, |c| c.notifications()),
+            quality: subscription.as_ref().map(|c| c.quality_summary()).unwrap_or_default(),
+        }
+    }
+
+    /// 记录当前生效的订阅
+    pub fn set_subscription(&self, container: &Arc<OpcCallbackContainer>) {
+        *self.subscription.borrow_mut() = Arc::downgrade(container);
+    }
+
+    /// 列出组和组内仍然存在的项
+    pub fn live_resources(&self, owner: &str) -> Vec<LiveResource> {
+        let group = self.alive.get().then(|| LiveResource {
+            kind: ResourceKind::Group,
+            name: self.name.clone(),
+            owner: Some(owner.to_string()),
+            backtrace: self.backtrace.clone(),
+        });
+        let items = self.items.borrow().values().map(|(name, backtrace)| LiveResource {
+            kind: ResourceKind::Item,
+            name: name.clone(),
+            owner: Some(self.name.clone()),
+            backtrace: backtrace.clone(),
+        }).collect::<Vec<_>>();
+        group.into_iter().chain(items).collect()
+    }
+}
+
+/// 项在所属组统计中的登记，释放时注销
+///
+/// 同时持有服务器共享状态，使组和服务器都释放后仍能在泄漏报告中找到该项。
+pub(crate) struct ItemRegistration {
+    stats: Rc<GroupStats>,
+    server: Rc<ServerShared>,
+    id: u64,
+}
+
+impl ItemRegistration {
+    /// 在组统计中登记一个项
+    pub fn new(stats: &Rc<GroupStats>, server: &Rc<ServerShared>, name: &str) -> Self {
+        let id = stats.next_item.get();
+        stats.next_item.set(id + 1);
+        stats.items.borrow_mut().insert(id, (name.to_string(), capture_backtrace()));
+        ItemRegistration {
+            stats: Rc::clone(stats),
+            server: Rc::clone(server),
+            id,
+        }
+    }
+
+    /// 所属组的统计
+    pub fn stats(&self) -> &GroupStats {
+        &self.stats
+    }
+
+    /// 所属服务器的连接编号
+    pub fn server_id(&self) -> u64 {
+        self.server.id
+    }
+}
+
+impl Drop for ItemRegistration {
+    fn drop(&mut self) {
+        self.stats.items.borrow_mut().remove(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_display() {
+        let stats = Rc::new(GroupStats {
+            name: "Plant".to_string(),
+            active: true,
+            requested_update_rate: 500,
+            actual_update_rate: 1000,
+            quirks: "none".to_string(),
+            ..Default::default()
+        });
+        let server = Rc::new(ServerShared::default());
+        let items: Vec<_> = ["A", "B", "C"]
+            .iter()
+            .map(|name| ItemRegistration::new(&stats, &server, name))
+            .collect();
+        stats.add_failures.set(1);
+
+        let report = DiagnosticsReport {
+            crate_version: "0.1.0",
+            platform: "windows",
+            servers: vec![ServerDiagnostics {
+                host: "localhost".to_string(),
+                server_name: "Matrikon.OPC.Simulation.1".to_string(),
+                quirks: "matrikon".to_string(),
+                unknown_items: 1,
+                groups: vec![stats.diagnostics()],
+            }],
+        };
+        let text = report.to_string();
+        assert!(text.contains("server Matrikon.OPC.Simulation.1 on localhost"));
+        assert!(text.contains("group Plant: active=true rate=1000ms (requested 500ms) deadband=0 quirks=none"));
+        assert!(text.contains("items=3 add_failures=1 io_failures=0"));
+        assert!(text.contains("subscription: none"));
+
+        drop(items);
+        assert!(stats.items.borrow().is_empty());
+    }
+}
@@ -0,0 +1,342 @@
+//! 回调分发线程模块
+//!
+//! 数据变化回调在工具包的 COM 线程中执行，这些线程由 DLL 创建，库无法命名，
+//! 也无法设置它们的 CPU 亲和性和优先级，在性能分析和系统调优时不容易找到。
+//! `Dispatcher` 放在组和应用回调之间：回调线程只把通知放入队列，
+//! 由名为 `opcda-dispatch-<组名>` 的专用线程按顺序调用下游回调，
+//! 该线程可以绑定到指定的 CPU 并设置优先级（`DispatchOptions`）。
+//! 这样下游的耗时处理也不会占用服务器的回调线程。
+//!
+//! 队列满时新的数据变化被丢弃并计入 `dropped`，不阻塞回调线程；
+//! 订阅关闭、连接中断、质量阈值和补读通知不会被丢弃。
+//! 释放分发器时，线程先处理完队列中的通知再退出。
+//!
+//! Linux 的线程名最长 15 字节，超出部分在系统工具中显示时被截断。
+//! CPU 亲和性和优先级目前只在 Windows 上支持。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::{DispatchOptions, Dispatcher, ThreadPriority};
+//! use std::sync::Arc;
+//!
+//! let dispatcher = Arc::new(Dispatcher::spawn("Fast", Arc::new(MyCallback), DispatchOptions {
+//!     affinity: Some(0b0100),
+//!     priority: Some(ThreadPriority::AboveNormal),
+//!     ..DispatchOptions::default()
+//! })?);
+//! group.enable_async_subscription(dispatcher.clone())?;
+//! ```
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use crate::error::{OpcError, OpcResult};
+use crate::types::{ChangeOrigin, DataChangeEvent, OpcDataCallback, OpcQuality, OpcValue, QualitySummary, SubscriptionCloseReason};
+
+/// 分发线程的优先级，对应 Windows 的线程优先级
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadPriority {
+    Lowest,
+    BelowNormal,
+    Normal,
+    AboveNormal,
+    Highest,
+    TimeCritical,
+}
+
+/// 分发线程的设置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DispatchOptions {
+    /// 队列中最多等待的通知数
+    pub capacity: usize,
+    /// CPU 亲和性掩码，第 n 位表示可以在第 n 个逻辑处理器上运行
+    pub affinity: Option<usize>,
+    /// 线程优先级
+    pub priority: Option<ThreadPriority>,
+}
+
+impl Default for DispatchOptions {
+    fn default() -> Self {
+        DispatchOptions {
+            capacity: 10_000,
+            affinity: None,
+            priority: None,
+        }
+    }
+}
+
+/// 排队的通知
+enum Message {
+    DataChange {
+        group_name: String,
+        item_name: String,
+        value: OpcValue,
+        quality: OpcQuality,
+        timestamp: u64,
+        origin: ChangeOrigin,
+    },
+    Closed(String, SubscriptionCloseReason),
+    Interrupted(String, String),
+    QualityThreshold(String, QualitySummary, bool),
+    Backfill(String, Vec<DataChangeEvent>),
+}
+
+/// 在专用线程中调用下游回调的分发器
+pub struct Dispatcher {
+    sender: Option<SyncSender<Message>>,
+    thread: Option<JoinHandle<()>>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl Dispatcher {
+    /// 启动名为 `opcda-dispatch-<group_name>` 的分发线程，通知转发给 `downstream`
+    ///
+    /// # 返回值
+    /// - `Ok(Dispatcher)`: 线程已启动，亲和性和优先级已设置
+    /// - `Err(OpcError::InvalidParameters)`: 容量或亲和性掩码为 0
+    /// - `Err(OpcError::UnsupportedPlatform)`: 在非 Windows 平台上设置亲和性或优先级
+    /// - `Err(OpcError)`: 启动线程或设置亲和性、优先级失败
+    pub fn spawn(group_name: &str, downstream: Arc<dyn OpcDataCallback>, options: DispatchOptions) -> OpcResult<Self> {
+        if options.capacity == 0 {
+            return Err(OpcError::invalid_parameters("Dispatcher capacity must be positive"));
+        }
+        if options.affinity == Some(0) {
+            return Err(OpcError::invalid_parameters("Dispatcher affinity mask must select at least one processor"));
+        }
+        let (sender, receiver) = mpsc::sync_channel(options.capacity);
+        let (ready, started) = mpsc::channel();
+        let thread = thread::Builder::new()
+            .name(format!("opcda-dispatch-{}", group_name))
+            .spawn(move || {
+                let applied = configure_current_thread(&options);
+                let ok = applied.is_ok();
+                let _ = ready.send(applied);
+                if ok {
+                    run(&receiver, downstream.as_ref());
+                }
+            })?;
+        let dispatcher = Dispatcher {
+            sender: Some(sender),
+            thread: Some(thread),
+            dropped: Arc::new(AtomicU64::new(0)),
+        };
+        started.recv().unwrap_or_else(|_| Err(OpcError::OperationFailed("Dispatcher thread exited".to_string())))?;
+        Ok(dispatcher)
+    }
+
+    /// 因队列已满被丢弃的数据变化数
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// 放入数据变化，队列满时丢弃
+    fn offer(&self, message: Message) {
+        if let Some(sender) = &self.sender {
+            if let Err(TrySendError::Full(_)) = sender.try_send(message) {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// 放入不能丢弃的通知，队列满时等待
+    fn send(&self, message: Message) {
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(message);
+        }
+    }
+}
+
+impl OpcDataCallback for Dispatcher {
+    fn on_data_change(&self, group_name: &str, item_name: &str, value: OpcValue, quality: OpcQuality, timestamp: u64) {
+        self.on_data_change_with_origin(group_name, item_name, value, quality, timestamp, ChangeOrigin::Server);
+    }
+
+    fn on_data_change_with_origin(
+        &self,
+        group_name: &str,
+        item_name: &str,
+        value: OpcValue,
+        quality: OpcQuality,
+        timestamp: u64,
+        origin: ChangeOrigin,
+    ) {
+        self.offer(Message::DataChange {
+            group_name: group_name.to_string(),
+            item_name: item_name.to_string(),
+            value,
+            quality,
+            timestamp,
+            origin,
+        });
+    }
+
+    fn on_subscription_closed(&self, group_name: &str, reason: &SubscriptionCloseReason) {
+        self.send(Message::Closed(group_name.to_string(), reason.clone()));
+    }
+
+    fn on_connection_interrupted(&self, group_name: &str, reason: &str) {
+        self.send(Message::Interrupted(group_name.to_string(), reason.to_string()));
+    }
+
+    fn on_quality_threshold(&self, group_name: &str, summary: &QualitySummary, exceeded: bool) {
+        self.send(Message::QualityThreshold(group_name.to_string(), summary.clone(), exceeded));
+    }
+
+    fn on_backfill(&self, group_name: &str, batch: &[DataChangeEvent]) {
+        self.send(Message::Backfill(group_name.to_string(), batch.to_vec()));
+    }
+}
+
+impl Drop for Dispatcher {
+    /// 处理完队列中的通知后停止线程
+    fn drop(&mut self) {
+        self.sender.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// 分发线程：按顺序调用下游回调，发送端释放后退出
+fn run(receiver: &Receiver<Message>, downstream: &dyn OpcDataCallback) {
+    for message in receiver {
+        match message {
+            Message::DataChange { group_name, item_name, value, quality, timestamp, origin } => {
+                downstream.on_data_change_with_origin(&group_name, &item_name, value, quality, timestamp, origin)
+            }
+            Message::Closed(group_name, reason) => downstream.on_subscription_closed(&group_name, &reason),
+            Message::Interrupted(group_name, reason) => downstream.on_connection_interrupted(&group_name, &reason),
+            Message::QualityThreshold(group_name, summary, exceeded) => {
+                downstream.on_quality_threshold(&group_name, &summary, exceeded)
+            }
+            Message::Backfill(group_name, batch) => downstream.on_backfill(&group_name, &batch),
+        }
+    }
+}
+
+/// 设置当前线程的亲和性和优先级
+#[cfg(windows)]
+fn configure_current_thread(options: &DispatchOptions) -> OpcResult<()> {
+    use windows::Win32::System::Threading as threading;
+
+    let thread = unsafe { threading::GetCurrentThread() };
+    if let Some(mask) = options.affinity {
+        if unsafe { threading::SetThreadAffinityMask(thread, mask) } == 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+    }
+    if let Some(priority) = options.priority {
+        let priority = match priority {
+            ThreadPriority::Lowest => threading::THREAD_PRIORITY_LOWEST,
+            ThreadPriority::BelowNormal => threading::THREAD_PRIORITY_BELOW_NORMAL,
+            ThreadPriority::Normal => threading::THREAD_PRIORITY_NORMAL,
+            ThreadPriority::AboveNormal => threading::THREAD_PRIORITY_ABOVE_NORMAL,
+            ThreadPriority::Highest => threading::THREAD_PRIORITY_HIGHEST,
+            ThreadPriority::TimeCritical => threading::THREAD_PRIORITY_TIME_CRITICAL,
+        };
+        unsafe { threading::SetThreadPriority(thread, priority) }
+            .map_err(|e| OpcError::OperationFailed(format!("SetThreadPriority failed: {}", e)))?;
+    }
+    Ok(())
+}
+
+/// 设置当前线程的亲和性和优先级
+#[cfg(not(windows))]
+fn configure_current_thread(options: &DispatchOptions) -> OpcResult<()> {
+    if options.affinity.is_some() || options.priority.is_some() {
+        return Err(OpcError::UnsupportedPlatform(format!(
+            "Dispatcher affinity and priority are only supported on Windows (current: {})",
+            std::env::consts::OS
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// 记录收到通知的线程名和内容
+    #[derive(Default)]
+    struct Recorder {
+        events: Mutex<Vec<(Option<String>, String)>>,
+        /// 处理数据变化前等待的通道
+        gate: Mutex<Option<Receiver<()>>>,
+    }
+
+    impl Recorder {
+        fn push(&self, event: String) {
+            let name = thread::current().name().map(str::to_string);
+            self.events.lock().unwrap().push((name, event));
+        }
+    }
+
+    impl OpcDataCallback for Recorder {
+        fn on_data_change(&self, _group_name: &str, item_name: &str, value: OpcValue, _quality: OpcQuality, _timestamp: u64) {
+            if let Some(gate) = self.gate.lock().unwrap().as_ref() {
+                let _ = gate.recv();
+            }
+            self.push(format!("{}={:?}", item_name, value));
+        }
+
+        fn on_subscription_closed(&self, group_name: &str, _reason: &SubscriptionCloseReason) {
+            self.push(format!("closed {}", group_name));
+        }
+    }
+
+    #[test]
+    fn test_forwards_in_order_on_named_thread() {
+        let recorder = Arc::new(Recorder::default());
+        let dispatcher = Dispatcher::spawn("Fast", recorder.clone(), DispatchOptions::default()).unwrap();
+        dispatcher.on_data_change("Fast", "A", OpcValue::Int32(1), OpcQuality::Good, 1);
+        dispatcher.on_data_change("Fast", "B", OpcValue::Int32(2), OpcQuality::Good, 2);
+        dispatcher.on_subscription_closed("Fast", &SubscriptionCloseReason::GroupDropped);
+        drop(dispatcher);
+
+        let name = Some("opcda-dispatch-Fast".to_string());
+        assert_eq!(
+            *recorder.events.lock().unwrap(),
+            vec![
+                (name.clone(), "A=Int32(1)".to_string()),
+                (name.clone(), "B=Int32(2)".to_string()),
+                (name, "closed Fast".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_full_queue_drops_data_changes() {
+        let (open, gate) = mpsc::channel();
+        let recorder = Arc::new(Recorder { gate: Mutex::new(Some(gate)), ..Recorder::default() });
+        let options = DispatchOptions { capacity: 1, ..DispatchOptions::default() };
+        let dispatcher = Dispatcher::spawn("G", recorder.clone(), options).unwrap();
+
+        // 第一条被线程取走后阻塞在 gate 上，第二条留在队列中，之后的被丢弃
+        dispatcher.on_data_change("G", "A", OpcValue::Int32(1), OpcQuality::Good, 1);
+        while dispatcher.sender.as_ref().unwrap().try_send(Message::Interrupted("G".to_string(), String::new())).is_ok() {
+            thread::yield_now();
+        }
+        dispatcher.on_data_change("G", "A", OpcValue::Int32(3), OpcQuality::Good, 3);
+        assert_eq!(dispatcher.dropped(), 1);
+
+        open.send(()).unwrap();
+        drop(open);
+        drop(dispatcher);
+        assert_eq!(recorder.events.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        let recorder: Arc<dyn OpcDataCallback> = Arc::new(Recorder::default());
+        let options = DispatchOptions { affinity: Some(0), ..DispatchOptions::default() };
+        assert!(matches!(Dispatcher::spawn("G", recorder.clone(), options), Err(OpcError::InvalidParameters(_))));
+        #[cfg(not(windows))]
+        {
+            let options = DispatchOptions { priority: Some(ThreadPriority::Highest), ..DispatchOptions::default() };
+            assert!(Dispatcher::spawn("G", recorder, options).err().unwrap().is_unsupported_platform());
+        }
+    }
+}
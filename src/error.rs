@@ -15,6 +15,7 @@
 //! 3. **错误转换**: 将底层错误转换为用户友好的错误
 //! 4. **错误链**: 保留原始错误信息
 
+use crate::connection::ConnectionStringError;
 use crate::types::OpcValueError;
 
 /// OPC 操作结果类型
@@ -50,6 +51,9 @@ pub type OpcResult<T> = Result<T, OpcError>;
 /// 6. **资源错误**: 服务器、组、项找不到
 /// 7. **订阅错误**: 异步订阅失败
 /// 8. **超时错误**: 操作超时
+/// 9. **平台错误**: 在不支持的平台上运行
+/// 10. **限制错误**: 超过客户端配置的软限制
+/// 11. **重入错误**: 在数据变化回调中调用了会访问服务器的接口
 /// 
 /// ## 示例
 /// 
@@ -117,7 +121,6 @@ pub enum OpcError {
     /// 表示 Windows COM 系统初始化失败。
     /// 
     /// # 可能的原因
-    /// - 非 Windows 平台
     /// - COM 库未安装
     /// - 权限不足
     #[error("COM initialization failed: {0}")]
@@ -162,6 +165,33 @@ pub enum OpcError {
     /// 表示操作在指定时间内未完成。
     #[error("Operation timed out: {0}")]
     Timeout(String),
+    
+    /// 平台不支持错误
+    /// 
+    /// 表示在非 Windows 平台上调用了需要 OPC DA 运行时的操作。
+    /// 跨平台应用可以匹配此错误并切换到其他实现（例如仿真后端）。
+    #[error("Unsupported platform: {0}")]
+    UnsupportedPlatform(String),
+    
+    /// I/O 错误
+    /// 
+    /// 表示读写本地文件失败，例如保存或恢复检查点。
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    
+    /// 超过软限制
+    /// 
+    /// 表示创建组或添加项会超过服务器连接配置的软限制（`ResourceLimits`），
+    /// 操作没有发送到服务器。
+    #[error("Soft limit exceeded: {0}")]
+    LimitExceeded(String),
+    
+    /// 回调中的重入调用
+    /// 
+    /// 表示在某个服务器的数据变化回调中调用了访问同一服务器的接口，这可能导致死锁，
+    /// 操作没有发送到服务器。见 `reentrancy` 模块。
+    #[error("Reentrant call: {0}")]
+    ReentrantCall(String),
 }
 
 impl OpcError {
@@ -209,6 +239,26 @@ impl OpcError {
     pub fn invalid_parameters(msg: impl Into<String>) -> Self {
         OpcError::InvalidParameters(msg.into())
     }
+    
+    /// 创建当前平台不支持 OPC DA 的错误
+    /// 
+    /// 错误消息中包含当前的操作系统名称。
+    pub fn unsupported_platform() -> Self {
+        OpcError::UnsupportedPlatform(
+            format!("OPC DA Client is only supported on Windows platforms (current: {})", std::env::consts::OS)
+        )
+    }
+    
+    /// 检查错误是否表示平台不支持
+    pub fn is_unsupported_platform(&self) -> bool {
+        matches!(self, OpcError::UnsupportedPlatform(_))
+    }
+}
+
+impl From<ConnectionStringError> for OpcError {
+    fn from(err: ConnectionStringError) -> Self {
+        OpcError::InvalidParameters(format!("connection string: {}", err))
+    }
 }
 
 #[cfg(test)]
@@ -228,6 +278,8 @@ mod tests {
         let group_error = OpcError::GroupCreationFailed("test group".to_string());
         let async_error = OpcError::AsyncSubscriptionFailed("test async".to_string());
         let timeout_error = OpcError::Timeout("test timeout".to_string());
+        let platform_error = OpcError::unsupported_platform();
+        let io_error = OpcError::from(std::io::Error::new(std::io::ErrorKind::NotFound, "test io"));
         
         // Test display formatting
         assert!(op_failed.to_string().contains("OPC operation failed"));
@@ -240,6 +292,10 @@ mod tests {
         assert!(group_error.to_string().contains("Failed to create group"));
         assert!(async_error.to_string().contains("Failed to enable async subscription"));
         assert!(timeout_error.to_string().contains("Operation timed out"));
+        assert!(platform_error.to_string().contains("Unsupported platform"));
+        assert!(io_error.to_string().contains("I/O error"));
+        assert!(platform_error.is_unsupported_platform());
+        assert!(!timeout_error.is_unsupported_platform());
     }
     
     #[test]
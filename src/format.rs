@@ -0,0 +1,434 @@
+//! 值格式化模块
+//!
+//! 这个模块提供可配置的值、质量和时间戳的文本格式化，
+//! 供日志、文件导出以及命令行输出等场景统一使用。
+//!
+//! 不同现场对输出格式的要求各不相同（小数位数、时间戳格式、质量显示方式），
+//! `FormatProfile` 将这些选择集中在一处配置，而不需要修改输出代码。
+//!
+//! ## 时间戳策略
+//!
+//! 时间戳格式有一个进程级的默认值（`set_default_timestamp_style`，初始为 Unix 毫秒）。
+//! 新建的 `FormatProfile` 和自由函数 `format_timestamp` 使用这个默认值，
+//! 使各个输出位置在不单独配置时保持一致。
+//!
+//! `OpcTimestamp` 自身的格式是固定的：`Display` 输出 ISO 8601 UTC 时间，
+//! 启用 `serde` 特性时序列化为 Unix 毫秒整数。需要其他格式的序列化字段使用
+//! `iso8601` / `filetime` 模块（`#[serde(with = "...")]`），
+//! 按运行时配置输出时使用 `TimestampStyle::serialize`。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::format::{FormatProfile, TimestampStyle, QualityStyle};
+//! use opc_da_client::{OpcValue, OpcQuality};
+//!
+//! let mut profile = FormatProfile::default();
+//! profile.default_decimals = Some(2);
+//! profile.set_item_decimals("Random.Real8", 4);
+//! profile.timestamp_style = TimestampStyle::Iso8601;
+//! profile.quality_style = QualityStyle::Code;
+//!
+//! assert_eq!(profile.format_value("Random.Real8", &OpcValue::Double(1.23456789)), "1.2346");
+//! assert_eq!(profile.format_quality(OpcQuality::Good), "192");
+//! ```
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU8, Ordering};
+use crate::types::{OpcQuality, OpcValue};
+#[cfg(feature = "serde")]
+use crate::types::OpcTimestamp;
+
+/// Unix 纪元（1970-01-01）与 FILETIME 纪元（1601-01-01）之间的毫秒数
+const FILETIME_EPOCH_OFFSET_MS: u64 = 11_644_473_600_000;
+
+/// 时间戳格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimestampStyle {
+    /// Unix 毫秒数，例如 `1700000000123`
+    #[default]
+    EpochMillis,
+    /// ISO 8601 / RFC 3339 UTC 时间，例如 `2023-11-14T22:13:20.123Z`
+    Iso8601,
+    /// Windows FILETIME，自 1601-01-01 起的 100 纳秒间隔数，例如 `133444736001230000`
+    Filetime,
+}
+
+impl TimestampStyle {
+    /// 按此格式格式化时间戳（Unix 毫秒）
+    pub fn format(&self, timestamp_ms: u64) -> String {
+        match self {
+            TimestampStyle::EpochMillis => timestamp_ms.to_string(),
+            TimestampStyle::Iso8601 => format_iso8601(timestamp_ms),
+            TimestampStyle::Filetime => unix_ms_to_filetime(timestamp_ms).to_string(),
+        }
+    }
+
+    /// 按此格式序列化时间戳：Unix 毫秒和 FILETIME 为整数，ISO 8601 为字符串
+    ///
+    /// 用于按 `FormatProfile::timestamp_style` 等运行时配置输出的自定义 `Serialize` 实现。
+    #[cfg(feature = "serde")]
+    pub fn serialize<S: serde::Serializer>(&self, timestamp: OpcTimestamp, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            TimestampStyle::EpochMillis => serializer.serialize_u64(timestamp.unix_ms()),
+            TimestampStyle::Iso8601 => serializer.serialize_str(&format_iso8601(timestamp.unix_ms())),
+            TimestampStyle::Filetime => serializer.serialize_u64(timestamp.to_filetime()),
+        }
+    }
+}
+
+/// 以 ISO 8601 UTC 字符串序列化 `OpcTimestamp` 字段
+///
+/// 用法：`#[serde(with = "opc_da_client::format::iso8601")]`
+#[cfg(feature = "serde")]
+pub mod iso8601 {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use crate::types::OpcTimestamp;
+
+    pub fn serialize<S: Serializer>(timestamp: &OpcTimestamp, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::format_iso8601(timestamp.unix_ms()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<OpcTimestamp, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        super::parse_iso8601(&text)
+            .map(OpcTimestamp::from_unix_ms)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid ISO 8601 timestamp '{}'", text)))
+    }
+}
+
+/// 以 Windows FILETIME 整数序列化 `OpcTimestamp` 字段
+///
+/// 用法：`#[serde(with = "opc_da_client::format::filetime")]`
+#[cfg(feature = "serde")]
+pub mod filetime {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use crate::types::OpcTimestamp;
+
+    pub fn serialize<S: Serializer>(timestamp: &OpcTimestamp, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(timestamp.to_filetime())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<OpcTimestamp, D::Error> {
+        u64::deserialize(deserializer).map(OpcTimestamp::from_filetime)
+    }
+}
+
+static DEFAULT_TIMESTAMP_STYLE: AtomicU8 = AtomicU8::new(0);
+
+/// 设置进程级的默认时间戳格式
+///
+/// 只影响之后新建的 `FormatProfile` 和 `format_timestamp` 的输出。
+pub fn set_default_timestamp_style(style: TimestampStyle) {
+    DEFAULT_TIMESTAMP_STYLE.store(style as u8, Ordering::Relaxed);
+}
+
+/// 进程级的默认时间戳格式
+pub fn default_timestamp_style() -> TimestampStyle {
+    match DEFAULT_TIMESTAMP_STYLE.load(Ordering::Relaxed) {
+        1 => TimestampStyle::Iso8601,
+        2 => TimestampStyle::Filetime,
+        _ => TimestampStyle::EpochMillis,
+    }
+}
+
+/// 按默认时间戳格式格式化时间戳（Unix 毫秒）
+pub fn format_timestamp(timestamp_ms: u64) -> String {
+    default_timestamp_style().format(timestamp_ms)
+}
+
+/// Unix 毫秒转换为 FILETIME，超出 FILETIME 范围的时间为 `u64::MAX`
+pub fn unix_ms_to_filetime(timestamp_ms: u64) -> u64 {
+    timestamp_ms
+        .checked_add(FILETIME_EPOCH_OFFSET_MS)
+        .and_then(|ms| ms.checked_mul(10_000))
+        .unwrap_or(u64::MAX)
+}
+
+/// FILETIME 转换为 Unix 毫秒，早于 1970 年的时间返回 0
+pub fn filetime_to_unix_ms(filetime: u64) -> u64 {
+    (filetime / 10_000).saturating_sub(FILETIME_EPOCH_OFFSET_MS)
+}
+
+/// 质量格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QualityStyle {
+    /// 文本形式，例如 `Good`
+    #[default]
+    Text,
+    /// 原始质量码，例如 `192`
+    Code,
+}
+
+/// 格式化配置
+///
+/// 控制值的小数位数（可按项单独设置）、时间戳格式和质量格式。
+/// 新建的配置使用进程级的默认时间戳格式。
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatProfile {
+    /// 浮点值的默认小数位数，`None` 表示使用完整精度
+    pub default_decimals: Option<usize>,
+    /// 按项设置的小数位数，优先于默认值
+    pub item_decimals: HashMap<String, usize>,
+    /// 时间戳格式
+    pub timestamp_style: TimestampStyle,
+    /// 质量格式
+    pub quality_style: QualityStyle,
+}
+
+impl Default for FormatProfile {
+    fn default() -> Self {
+        FormatProfile {
+            default_decimals: None,
+            item_decimals: HashMap::new(),
+            timestamp_style: default_timestamp_style(),
+            quality_style: QualityStyle::default(),
+        }
+    }
+}
+
+impl FormatProfile {
+    /// 设置某个项的小数位数
+    pub fn set_item_decimals(&mut self, item_name: &str, decimals: usize) {
+        self.item_decimals.insert(item_name.to_string(), decimals);
+    }
+
+    /// 获取某个项生效的小数位数
+    pub fn decimals_for(&self, item_name: &str) -> Option<usize> {
+        self.item_decimals
+            .get(item_name)
+            .copied()
+            .or(self.default_decimals)
+    }
+
+    /// 格式化值
+    ///
+    /// 浮点值（包括货币类型）按项的小数位数格式化，其他类型使用自然的文本形式。
+    /// 数组格式化为 `[a, b, c]`。
+    pub fn format_value(&self, item_name: &str, value: &OpcValue) -> String {
+        let decimals = self.decimals_for(item_name);
+        let float = |v: f64| match decimals {
+            Some(d) => format!("{:.*}", d, v),
+            None => v.to_string(),
+        };
+        let join = |parts: Vec<String>| format!("[{}]", parts.join(", "));
+
+        match value {
+            OpcValue::Int8(v) => v.to_string(),
+            OpcValue::UInt8(v) => v.to_string(),
+            OpcValue::Int16(v) => v.to_string(),
+            OpcValue::UInt16(v) => v.to_string(),
+            OpcValue::Int32(v) => v.to_string(),
+            OpcValue::UInt32(v) => v.to_string(),
+            OpcValue::Int64(v) => v.to_string(),
+            OpcValue::UInt64(v) => v.to_string(),
+            OpcValue::INT(v) => v.to_string(),
+            OpcValue::UINT(v) => v.to_string(),
+            OpcValue::Float(v) => float(*v as f64),
+            OpcValue::Double(v) => float(*v),
+            OpcValue::Bool(v) => v.to_string(),
+            OpcValue::Cy(v) => float(*v as f64 / 10000.0),
+            OpcValue::Decimal(v) => v.to_string(),
+            OpcValue::Date(v) => v.to_string(),
+            OpcValue::String(v) => v.clone(),
+            OpcValue::ArrayInt16(v) => join(v.iter().map(|x| x.to_string()).collect()),
+            OpcValue::ArrayUInt16(v) => join(v.iter().map(|x| x.to_string()).collect()),
+            OpcValue::ArrayInt32(v) => join(v.iter().map(|x| x.to_string()).collect()),
+            OpcValue::ArrayUInt32(v) => join(v.iter().map(|x| x.to_string()).collect()),
+            OpcValue::ArrayInt64(v) => join(v.iter().map(|x| x.to_string()).collect()),
+            OpcValue::ArrayUInt64(v) => join(v.iter().map(|x| x.to_string()).collect()),
+            OpcValue::ArrayFloat(v) => join(v.iter().map(|x| float(*x as f64)).collect()),
+            OpcValue::ArrayDouble(v) => join(v.iter().map(|x| float(*x)).collect()),
+            OpcValue::ArrayBool(v) => join(v.iter().map(|x| x.to_string()).collect()),
+            OpcValue::ArrayString(v) => join(v.clone()),
+        }
+    }
+
+    /// 格式化质量
+    pub fn format_quality(&self, quality: OpcQuality) -> String {
+        match self.quality_style {
+            QualityStyle::Text => quality.to_string(),
+            QualityStyle::Code => quality.to_raw().to_string(),
+        }
+    }
+
+    /// 格式化时间戳（Unix 毫秒）
+    pub fn format_timestamp(&self, timestamp_ms: u64) -> String {
+        self.timestamp_style.format(timestamp_ms)
+    }
+}
+
+/// 将 Unix 毫秒格式化为 ISO 8601 UTC 时间
+pub fn format_iso8601(timestamp_ms: u64) -> String {
+    let millis = timestamp_ms % 1000;
+    let total_seconds = timestamp_ms / 1000;
+    let days = (total_seconds / 86_400) as i64;
+    let seconds_of_day = total_seconds % 86_400;
+    let (year, month, day) = civil_from_days(days);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        (seconds_of_day % 3600) / 60,
+        seconds_of_day % 60,
+        millis
+    )
+}
+
+/// 解析 ISO 8601 UTC 时间（`YYYY-MM-DDTHH:MM:SS[.fff]Z`）为 Unix 毫秒
+///
+/// 接受 `format_iso8601` 的输出，小数秒超过 3 位时截断到毫秒。
+/// 格式不符或早于 1970 年时返回 `None`。
+pub fn parse_iso8601(text: &str) -> Option<u64> {
+    let (date, time) = text.strip_suffix('Z')?.split_once('T')?;
+    let mut date_parts = date.splitn(3, '-');
+    let year: i64 = date_parts.next()?.parse().ok()?;
+    let month: u32 = date_parts.next()?.parse().ok()?;
+    let day: u32 = date_parts.next()?.parse().ok()?;
+
+    let (clock, fraction) = match time.split_once('.') {
+        Some((clock, fraction)) => (clock, Some(fraction)),
+        None => (time, None),
+    };
+    let mut clock_parts = clock.splitn(3, ':');
+    let hour: u64 = clock_parts.next()?.parse().ok()?;
+    let minute: u64 = clock_parts.next()?.parse().ok()?;
+    let second: u64 = clock_parts.next()?.parse().ok()?;
+    if hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    let millis = match fraction {
+        Some(digits) if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) => {
+            format!("{:0<3}", &digits[..digits.len().min(3)]).parse().ok()?
+        }
+        Some(_) => return None,
+        None => 0,
+    };
+
+    let days = days_from_civil(year, month, day)?;
+    let days = u64::try_from(days).ok()?;
+    days.checked_mul(86_400_000)?
+        .checked_add((hour * 3600 + minute * 60 + second) * 1000 + millis)
+}
+
+/// 将公历日期转换为自 1970-01-01 起的天数，日期无效时返回 `None`
+fn days_from_civil(year: i64, month: u32, day: u32) -> Option<i64> {
+    if !(1..=12).contains(&month) || day == 0 {
+        return None;
+    }
+    let y = if month <= 2 { year.checked_sub(1)? } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 } as i64;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era.checked_mul(146_097)?.checked_add(doe - 719_468)?;
+    // 拒绝 2 月 30 日之类不存在的日期
+    (civil_from_days(days) == (year, month, day)).then_some(days)
+}
+
+/// 将自 1970-01-01 起的天数转换为公历日期（Howard Hinnant 算法）
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_value_decimals() {
+        let mut profile = FormatProfile {
+            default_decimals: Some(2),
+            ..Default::default()
+        };
+        profile.set_item_decimals("Precise", 4);
+
+        assert_eq!(profile.format_value("Any", &OpcValue::Double(1.23456)), "1.23");
+        assert_eq!(profile.format_value("Precise", &OpcValue::Double(1.23456)), "1.2346");
+        assert_eq!(profile.format_value("Any", &OpcValue::Int32(42)), "42");
+        assert_eq!(profile.format_value("Any", &OpcValue::Cy(12345)), "1.23");
+        assert_eq!(profile.format_value("Any", &OpcValue::ArrayFloat(vec![1.0, 2.5])), "[1.00, 2.50]");
+        assert_eq!(FormatProfile::default().format_value("Any", &OpcValue::Double(0.5)), "0.5");
+    }
+
+    #[test]
+    fn test_format_quality_and_timestamp() {
+        let mut profile = FormatProfile::default();
+        assert_eq!(profile.format_quality(OpcQuality::Good), "Good");
+        assert_eq!(profile.format_timestamp(1_700_000_000_123), "1700000000123");
+
+        profile.quality_style = QualityStyle::Code;
+        profile.timestamp_style = TimestampStyle::Iso8601;
+        assert_eq!(profile.format_quality(OpcQuality::Good), "192");
+        assert_eq!(profile.format_timestamp(1_700_000_000_123), "2023-11-14T22:13:20.123Z");
+    }
+
+    #[test]
+    fn test_format_iso8601_edges() {
+        assert_eq!(format_iso8601(0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(format_iso8601(951_782_400_000), "2000-02-29T00:00:00.000Z");
+    }
+
+    #[test]
+    fn test_filetime_conversion() {
+        assert_eq!(unix_ms_to_filetime(0), 116_444_736_000_000_000);
+        assert_eq!(TimestampStyle::Filetime.format(1_700_000_000_123), "133444736001230000");
+        assert_eq!(filetime_to_unix_ms(133_444_736_001_230_000), 1_700_000_000_123);
+        assert_eq!(filetime_to_unix_ms(0), 0);
+        assert_eq!(unix_ms_to_filetime(u64::MAX), u64::MAX);
+        assert_eq!(unix_ms_to_filetime(u64::MAX / 10_000), u64::MAX);
+    }
+
+    #[test]
+    fn test_parse_iso8601() {
+        for ms in [0, 951_782_400_000, 1_700_000_000_123] {
+            assert_eq!(parse_iso8601(&format_iso8601(ms)), Some(ms));
+        }
+        assert_eq!(parse_iso8601("2023-11-14T22:13:20Z"), Some(1_700_000_000_000));
+        assert_eq!(parse_iso8601("2023-11-14T22:13:20.1234Z"), Some(1_700_000_000_123));
+        assert_eq!(parse_iso8601("2023-02-30T00:00:00Z"), None);
+        assert_eq!(parse_iso8601("1969-12-31T23:59:59Z"), None);
+        assert_eq!(parse_iso8601("2023-11-14 22:13:20Z"), None);
+        assert_eq!(parse_iso8601("2023-11-14T22:13:20"), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_timestamp_serialize_helpers() {
+        #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+        struct Record {
+            #[serde(with = "iso8601")]
+            at: OpcTimestamp,
+            #[serde(with = "filetime")]
+            raw: OpcTimestamp,
+        }
+
+        let timestamp = OpcTimestamp::from_unix_ms(1_700_000_000_123);
+        let record = Record { at: timestamp, raw: timestamp };
+        let json = serde_json::to_string(&record).unwrap();
+        assert_eq!(json, r#"{"at":"2023-11-14T22:13:20.123Z","raw":133444736001230000}"#);
+        assert_eq!(serde_json::from_str::<Record>(&json).unwrap(), record);
+
+        let cases = [
+            (TimestampStyle::EpochMillis, serde_json::json!(1_700_000_000_123u64)),
+            (TimestampStyle::Iso8601, serde_json::json!("2023-11-14T22:13:20.123Z")),
+            (TimestampStyle::Filetime, serde_json::json!(133_444_736_001_230_000u64)),
+        ];
+        for (style, expected) in cases {
+            assert_eq!(style.serialize(timestamp, serde_json::value::Serializer).unwrap(), expected);
+        }
+    }
+}
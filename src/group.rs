@@ -24,12 +24,21 @@
 //! 激活的组可以接收异步数据变化通知。用户需要实现 `OpcDataCallback` trait
 //! 并调用 `enable_async_subscription` 来启用订阅。
 
+use std::cell::{Cell, RefCell};
 use std::ptr;
-use std::sync::Arc;
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::backend::DaGroup;
+use crate::diagnostics::{GroupStats, ItemRegistration};
 use crate::error::{OpcError, OpcResult};
 use crate::item::OpcItem;
-use crate::types::{OpcValue, OpcQuality, OpcDataCallback, OpcCallbackContainer};
+use crate::quirks::QuirkProfile;
+use crate::server::ServerShared;
+use crate::types::{lock_or_recover, ChannelCallback, DataChangeEvent, OpcValue, OpcQuality, OpcTimestamp, OpcDataCallback, OpcCallbackContainer, PendingDataChange, QualitySummary, SubscriptionCloseReason};
 use crate::utils;
+use crate::writes::{EchoHandling, LastWrite, WriteTracker};
 
 /// OPC 组，包含多个 OPC 项
 /// 
@@ -42,6 +51,13 @@ use crate::utils;
 /// ## 内部结构
 /// 
 /// - `ptr`: 指向底层 OPC 组对象的指针
+/// - `name`: 创建时使用的组名
+/// - `quirks`: 创建时从服务器继承的厂商兼容性配置
+/// - `deadband`: 创建时请求的死区值
+/// - `callbacks`: 已注册的回调容器，生命周期与组相同
+/// - `shared`: 与服务器共享的状态（未知项否定缓存、订阅列表）
+/// - `writes`: 项写入跟踪，用于识别回声
+/// - `stats`: 运行统计（项数、失败计数、当前订阅），用于诊断报告
 /// 
 /// ## 示例
 /// 
@@ -64,6 +80,114 @@ use crate::utils;
 pub struct OpcGroup {
     /// 指向底层 OPC 组对象的指针
     ptr: *mut std::ffi::c_void,
+    /// 组名
+    name: String,
+    /// 厂商兼容性配置
+    quirks: QuirkProfile,
+    /// 死区值（百分比）
+    deadband: f64,
+    /// 已注册的回调容器，最后一个为当前生效的回调
+    /// 
+    /// 服务器持有指向这些容器的指针，因此它们必须在组释放之后才能释放。
+    callbacks: RefCell<Vec<Arc<OpcCallbackContainer>>>,
+    /// 与服务器共享的状态
+    shared: Rc<ServerShared>,
+    /// 重放缓冲区容量，`None` 表示未启用
+    replay_capacity: Cell<Option<usize>>,
+    /// Bad 质量比例的通知阈值，`None` 表示未启用
+    quality_threshold: Cell<Option<f64>>,
+    /// 项写入跟踪，与组的项和订阅共享
+    writes: Arc<WriteTracker>,
+    /// 运行统计，与组的项和服务器共享
+    stats: Rc<GroupStats>,
+}
+
+/// 组调用期间的重入保护
+/// 
+/// 在存在期间，数据变化通知会被排队；释放时按顺序分发排队的通知。
+struct GroupCallGuard {
+    container: Option<Arc<OpcCallbackContainer>>,
+}
+
+impl Drop for GroupCallGuard {
+    fn drop(&mut self) {
+        if let Some(container) = &self.container {
+            container.end_call();
+        }
+    }
+}
+
+/// 订阅的弱引用句柄
+/// 
+/// 不保持组、服务器连接或订阅存活，可以跨线程传递，适合界面的视图模型持有。
+/// 组释放后句柄失效：`is_alive` 返回 `false`，其余方法返回 `None` 或错误；
+/// 观察者通过 `on_subscription_closed` 得知数据源已经消失。
+/// 
+/// 句柄只覆盖订阅本身，不能升级回 `OpcGroup` 或 `OpcItem`：组和项只能在创建它们的
+/// 线程中使用，由所有者持有。也没有单个项的弱句柄，只关心部分项的观察者
+/// 在 `on_data_change` 中按项名过滤。
+/// 
+/// ## 示例
+/// 
+/// ```ignore
+/// let handle = group.subscription()?;
+/// 
+/// // 视图模型持有观察者，组只持有弱引用；窗口关闭、观察者释放后自动移除
+/// let view_model = Arc::new(TagViewModel::default());
+/// handle.observe(&view_model)?;
+/// 
+/// // 组释放时观察者收到 on_subscription_closed，之后句柄失效
+/// drop(group);
+/// assert!(!handle.is_alive());
+/// ```
+#[derive(Clone)]
+pub struct WeakSubscription {
+    group_name: String,
+    container: Weak<OpcCallbackContainer>,
+}
+
+impl WeakSubscription {
+    /// 组名
+    pub fn group_name(&self) -> &str {
+        &self.group_name
+    }
+    
+    /// 组和订阅是否仍然存在
+    pub fn is_alive(&self) -> bool {
+        self.container.upgrade().is_some_and(|container| !container.is_closed())
+    }
+    
+    /// 添加一个弱引用的观察者
+    /// 
+    /// 订阅不保持观察者存活，观察者释放后自动移除。
+    /// 组释放或订阅关闭时观察者收到 `on_subscription_closed`。
+    /// 
+    /// # 返回值
+    /// - `Ok(())`: 成功添加观察者
+    /// - `Err(OpcError::AsyncSubscriptionFailed)`: 组已释放或订阅已关闭
+    pub fn observe<C: OpcDataCallback + 'static>(&self, observer: &Arc<C>) -> OpcResult<()> {
+        match self.container.upgrade().filter(|container| !container.is_closed()) {
+            Some(container) => {
+                let observer: Weak<dyn OpcDataCallback> = Arc::downgrade(observer) as Weak<C>;
+                container.add_weak_consumer(observer);
+                Ok(())
+            }
+            None => Err(OpcError::AsyncSubscriptionFailed(format!(
+                "Subscription of group '{}' is gone",
+                self.group_name
+            ))),
+        }
+    }
+    
+    /// 订阅收到的质量汇总，组已释放时返回 `None`
+    pub fn quality_summary(&self) -> Option<QualitySummary> {
+        self.container.upgrade().map(|container| container.quality_summary())
+    }
+    
+    /// 订阅分发的数据变化通知数，组已释放时返回 `None`
+    pub fn notifications(&self) -> Option<u64> {
+        self.container.upgrade().map(|container| container.notifications())
+    }
 }
 
 impl OpcGroup {
@@ -71,13 +195,43 @@ impl OpcGroup {
     /// 
     /// # 参数
     /// - `group_ptr`: 指向底层 OPC 组对象的指针
+    /// - `name`: 组名
+    /// - `quirks`: 厂商兼容性配置
+    /// - `deadband`: 死区值（百分比）
+    /// - `shared`: 与服务器共享的状态
+    /// - `stats`: 已在服务器登记的运行统计
     /// 
     /// # 注意
     /// 这个方法仅供内部使用，用户应该通过 `OpcServer::create_group` 获取 `OpcGroup` 实例。
-    pub(crate) fn new(group_ptr: *mut std::ffi::c_void) -> Self {
+    pub(crate) fn new(
+        group_ptr: *mut std::ffi::c_void,
+        name: &str,
+        quirks: QuirkProfile,
+        deadband: f64,
+        shared: Rc<ServerShared>,
+        stats: Rc<GroupStats>,
+    ) -> Self {
         OpcGroup {
             ptr: group_ptr,
+            name: name.to_string(),
+            quirks,
+            deadband,
+            callbacks: RefCell::new(Vec::new()),
+            shared,
+            replay_capacity: Cell::new(None),
+            quality_threshold: Cell::new(None),
+            writes: Arc::new(WriteTracker::default()),
+            stats,
+        }
+    }
+    
+    /// 开始一次可能触发重入回调的组调用
+    fn begin_call(&self) -> GroupCallGuard {
+        let container = self.callbacks.borrow().last().cloned();
+        if let Some(container) = &container {
+            container.begin_call();
         }
+        GroupCallGuard { container }
     }
     
     /// 向组中添加 OPC 项
@@ -94,6 +248,8 @@ impl OpcGroup {
     ///   - 项名格式无效
     ///   - 权限不足
     ///   - 服务器资源不足
+    ///   - 启用了否定缓存且项名在有效期内添加失败过（不会访问服务器）
+    ///   - 超过服务器连接的软限制（`OpcError::LimitExceeded`，不会访问服务器）
     /// 
     /// # 示例
     /// ```
@@ -113,24 +269,69 @@ impl OpcGroup {
     /// - 同一个项可以添加到多个组中
     /// - 项会继承组的属性（更新速率、死区值）
     pub fn add_item(&self, name: &str) -> OpcResult<OpcItem> {
+        crate::reentrancy::check("OpcGroup::add_item", Some(self.shared.id))?;
+        // 最近添加失败的项直接返回，避免重复访问服务器
+        if self.shared.unknown_items.is_unknown(name) {
+            return Err(OpcError::ItemNotFound(
+                format!("Item '{}' is cached as unknown", name)
+            ));
+        }
+        
+        self.shared.check_item_limit(&self.stats)?;
+        
         // 将项名转换为 UTF-16 宽字符串
         let item_name_wide = utils::to_wide_string(name);
         let mut item_ptr: *mut std::ffi::c_void = ptr::null_mut();
         
+        // 调用期间的数据变化通知将被排队
+        let _guard = self.begin_call();
+        
         // 调用 FFI 函数添加项
         let result = unsafe {
             crate::ffi::opc_group_add_item(self.ptr, item_name_wide.as_ptr(), &mut item_ptr)
         };
         
         if result == 0 && !item_ptr.is_null() {
-            Ok(OpcItem::new(item_ptr))
+            self.shared.unknown_items.remove(name);
+            Ok(OpcItem::new(item_ptr, name, Some(Arc::clone(&self.writes)), Some(ItemRegistration::new(&self.stats, &self.shared, name))))
         } else {
+            self.shared.unknown_items.insert(name);
+            self.stats.add_failures.set(self.stats.add_failures.get() + 1);
             Err(OpcError::ItemNotFound(
                 format!("Failed to add item '{}' to group", name)
             ))
         }
     }
     
+    /// 分批按节奏添加大量项
+    /// 
+    /// 一次性添加上万个项会让部分服务器在激活时因初始数据突发而崩溃。
+    /// 这个方法每添加 `batch_size` 个项后等待 `interval`，
+    /// 例如 `batch_size = 500`、`interval = 1s` 即每秒 500 个项。
+    /// 每批完成后调用 `progress(已处理数, 总数)`。
+    /// 
+    /// # 参数
+    /// - `names`: 项名列表
+    /// - `batch_size`: 每批添加的项数，`0` 按 `1` 处理
+    /// - `interval`: 两批之间的等待时间
+    /// - `progress`: 进度回调
+    /// 
+    /// # 返回值
+    /// 与 `names` 顺序一致的每个项的添加结果，单个项失败不会中断其余项
+    /// 
+    /// # 注意
+    /// - 调用期间阻塞当前线程
+    /// - 等待期间已添加项的数据变化通知照常分发
+    pub fn add_items_paced<S: AsRef<str>>(
+        &self,
+        names: &[S],
+        batch_size: usize,
+        interval: Duration,
+        progress: impl FnMut(usize, usize),
+    ) -> Vec<OpcResult<OpcItem>> {
+        add_items_paced(self, names, batch_size, interval, progress)
+    }
+    
     /// 启用异步数据变化通知
     /// 
     /// 这个方法启用组的异步数据变化订阅。当组中的项值发生变化时，
@@ -173,36 +374,173 @@ impl OpcGroup {
     /// - 回调函数可能在后台线程中调用
     /// - 回调对象必须实现 `Send + Sync`
     /// - 启用订阅后，组会开始接收数据变化通知
+    /// - 如果兼容性配置要求，启用后会立即刷新组以获取初始值；刷新失败不影响订阅，
+    ///   也不作为错误返回，需要时可以再调用 `refresh`
     pub fn enable_async_subscription(&self, callback: Arc<dyn OpcDataCallback>) -> OpcResult<()> {
+        crate::reentrancy::check("OpcGroup::enable_async_subscription", Some(self.shared.id))?;
         // 创建回调容器，将 Rust 回调包装为 FFI 可用的形式
-        let container = Box::into_raw(Box::new(OpcCallbackContainer {
+        let container = Arc::new(OpcCallbackContainer::new(
+            &self.name,
             callback,
-        }));
+            self.quirks.clone(),
+            self.deadband,
+        )
+        .with_write_tracker(Arc::clone(&self.writes))
+        .with_server(self.shared.id));
+        container.set_replay_capacity(self.replay_capacity.get());
+        container.set_quality_threshold(self.quality_threshold.get());
         
         // 调用 FFI 函数启用异步订阅
         let result = unsafe {
             crate::ffi::opc_group_enable_async(
                 self.ptr,
                 opc_data_change_callback,
-                container as *mut std::ffi::c_void,
+                Arc::as_ptr(&container) as *mut std::ffi::c_void,
             )
         };
         
         if result == 0 {
+            // 容器由组持有，直到组被释放
+            self.shared.register_subscription(&container);
+            self.stats.set_subscription(&container);
+            self.callbacks.borrow_mut().push(container);
+            // 订阅已经生效，刷新失败时不返回错误，初始值随之后的数据变化到达
+            if self.quirks.refresh_after_subscribe {
+                let _ = self.refresh();
+            }
             Ok(())
         } else {
-            // 启用失败，清理已分配的内存
-            unsafe {
-                let _ = Box::from_raw(container);
-            }
             Err(OpcError::AsyncSubscriptionFailed(
                 "Failed to enable async subscription".to_string()
             ))
         }
     }
     
+    /// 启用或禁用订阅重放缓冲区
+    /// 
+    /// 启用后，组会保存每个项的最新值以及最近 `capacity` 条数据变化通知。
+    /// 通过 `add_callback` 后加入的消费者会立即收到这些数据，无需刷新组。
+    /// 
+    /// # 参数
+    /// - `capacity`: 保存的最近通知数量，`None` 表示禁用缓冲区
+    /// 
+    /// # 注意
+    /// - 可以在启用订阅之前或之后调用
+    /// - 禁用或重新设置会清空已缓冲的数据
+    pub fn set_replay_buffer(&self, capacity: Option<usize>) {
+        self.replay_capacity.set(capacity);
+        if let Some(container) = self.callbacks.borrow().last() {
+            container.set_replay_capacity(capacity);
+        }
+    }
+    
+    /// 获取组内各项的质量汇总
+    /// 
+    /// 基于当前订阅收到的每个项最近一次通知的质量统计，不访问服务器。
+    /// 尚未收到通知的项不计入；未启用订阅时返回空汇总。
+    pub fn quality_summary(&self) -> QualitySummary {
+        self.callbacks
+            .borrow()
+            .last()
+            .map(|container| container.quality_summary())
+            .unwrap_or_default()
+    }
+    
+    /// 设置 Bad 质量比例的通知阈值
+    /// 
+    /// Bad 项占比超过 `threshold`（例如 `0.1` 表示 10%）或回落到阈值以下时，
+    /// 订阅的所有回调会收到 `OpcDataCallback::on_quality_threshold`。
+    /// 
+    /// # 参数
+    /// - `threshold`: Bad 项比例阈值，`None` 表示禁用通知
+    /// 
+    /// # 注意
+    /// - 可以在启用订阅之前或之后调用
+    pub fn set_quality_threshold(&self, threshold: Option<f64>) {
+        self.quality_threshold.set(threshold);
+        if let Some(container) = self.callbacks.borrow().last() {
+            container.set_quality_threshold(threshold);
+        }
+    }
+    
+    /// 向已启用的订阅添加额外的回调
+    /// 
+    /// 如果启用了重放缓冲区，新回调会先收到缓冲的数据（每个项的最新值和最近的通知），
+    /// 之后与其他回调一起接收新的数据变化通知。
+    /// 
+    /// # 参数
+    /// - `callback`: 新的回调对象
+    /// 
+    /// # 返回值
+    /// - `Ok(())`: 成功添加回调
+    /// - `Err(OpcError::AsyncSubscriptionFailed)`: 组尚未启用异步订阅
+    pub fn add_callback(&self, callback: Arc<dyn OpcDataCallback>) -> OpcResult<()> {
+        let container = self.callbacks.borrow().last().cloned();
+        match container {
+            Some(container) => {
+                container.add_consumer(callback);
+                Ok(())
+            }
+            None => Err(OpcError::AsyncSubscriptionFailed(
+                "Async subscription is not enabled for this group".to_string()
+            )),
+        }
+    }
+    
+    /// 以通道的形式订阅组的数据变化
+    /// 
+    /// 组尚未启用异步订阅时启用订阅，否则为当前订阅添加一个消费者，
+    /// 因此可以与 `OpcDataCallback` 回调同时使用。组释放或订阅关闭后，
+    /// 接收端取完剩余的事件后返回断开。
+    /// 
+    /// # 返回值
+    /// - `Ok(Receiver<DataChangeEvent>)`: 数据变化的接收端
+    /// - `Err(OpcError::AsyncSubscriptionFailed)`: 启用订阅失败
+    /// 
+    /// # 示例
+    /// ```ignore
+    /// let changes = group.subscribe()?;
+    /// for event in changes {
+    ///     println!("{}.{} = {:?} ({:?})", event.group, event.item, event.value, event.quality);
+    /// }
+    /// ```
+    pub fn subscribe(&self) -> OpcResult<Receiver<DataChangeEvent>> {
+        let (sender, receiver) = mpsc::channel();
+        let callback = Arc::new(ChannelCallback::new(sender));
+        if self.callbacks.borrow().is_empty() {
+            self.enable_async_subscription(callback)?;
+        } else {
+            self.add_callback(callback)?;
+        }
+        Ok(receiver)
+    }
+    
+    /// 获取当前订阅的弱引用句柄
+    /// 
+    /// # 返回值
+    /// - `Ok(WeakSubscription)`: 当前生效订阅的句柄
+    /// - `Err(OpcError::AsyncSubscriptionFailed)`: 组尚未启用异步订阅
+    pub fn subscription(&self) -> OpcResult<WeakSubscription> {
+        match self.callbacks.borrow().last() {
+            Some(container) => Ok(WeakSubscription {
+                group_name: container.group_name().to_string(),
+                container: Arc::downgrade(container),
+            }),
+            None => Err(OpcError::AsyncSubscriptionFailed(
+                "Async subscription is not enabled for this group".to_string()
+            )),
+        }
+    }
+    
+    /// 添加一个弱引用的观察者，见 `WeakSubscription::observe`
+    pub fn observe<C: OpcDataCallback + 'static>(&self, observer: &Arc<C>) -> OpcResult<()> {
+        self.subscription()?.observe(observer)
+    }
+    
     /// Refresh all items in the group
     pub fn refresh(&self) -> OpcResult<()> {
+        crate::reentrancy::check("OpcGroup::refresh", Some(self.shared.id))?;
+        let _guard = self.begin_call();
         let result = unsafe {
             crate::ffi::opc_group_refresh(self.ptr)
         };
@@ -215,20 +553,111 @@ impl OpcGroup {
     }
     
     /// Read item value synchronously
-    pub fn read_sync(&self, item: &OpcItem) -> OpcResult<(OpcValue, OpcQuality, u64)> {
+    pub fn read_sync(&self, item: &OpcItem) -> OpcResult<(OpcValue, OpcQuality, OpcTimestamp)> {
         item.read_sync()
     }
     
+    /// 读取多个项，每个项优先使用有效期内的缓存结果
+    /// 
+    /// 对每个项调用 `OpcItem::read_cached`，结果顺序与 `items` 相同，
+    /// 单个项读取失败不影响其他项。
+    pub fn read_items_cached(&self, items: &[&OpcItem], ttl: Duration) -> Vec<OpcResult<(OpcValue, OpcQuality, OpcTimestamp)>> {
+        items.iter().map(|item| item.read_cached(ttl)).collect()
+    }
+    
     /// Write item value synchronously
     pub fn write_sync(&self, item: &OpcItem, value: &OpcValue) -> OpcResult<()> {
         item.write_sync(value)
     }
     
     
+    /// Get the group name used at creation
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    
+    /// Get the vendor quirk profile applied to this group
+    pub fn quirks(&self) -> &QuirkProfile {
+        &self.quirks
+    }
+    
+    /// 创建时是否激活
+    pub fn is_active(&self) -> bool {
+        self.stats.active
+    }
+    
+    /// 服务器实际采用的更新速率（毫秒）
+    /// 
+    /// 服务器可以把请求的速率调整为自己支持的速率，例如把 100 毫秒的请求改为 1000 毫秒。
+    pub fn actual_update_rate(&self) -> u32 {
+        self.stats.actual_update_rate
+    }
+    
+    /// 创建时请求的更新速率（毫秒）
+    pub fn requested_update_rate(&self) -> u32 {
+        self.stats.requested_update_rate
+    }
+    
+    /// 服务器是否调整了请求的更新速率
+    /// 
+    /// # 示例
+    /// ```ignore
+    /// let group = server.create_group("Fast", true, 100, 0.0)?;
+    /// if group.is_update_rate_revised() {
+    ///     eprintln!("update rate revised: {} ms -> {} ms", group.requested_update_rate(), group.actual_update_rate());
+    /// }
+    /// ```
+    pub fn is_update_rate_revised(&self) -> bool {
+        self.stats.actual_update_rate != self.stats.requested_update_rate
+    }
+    
+    /// 创建时请求的死区值（百分比）
+    /// 
+    /// 工具库不返回服务器修订后的死区，这里总是请求的值。
+    pub fn deadband(&self) -> f64 {
+        self.deadband
+    }
+    
+    /// 设置回声数据变化的处理方式
+    /// 
+    /// 通过本组的项成功写入后，服务器通常会把写入的值作为数据变化推送回来。
+    /// 这个方法决定如何处理这些回声：不识别、标记为 `ChangeOrigin::SelfWrite`
+    /// 或者不分发。
+    /// 
+    /// # 参数
+    /// - `handling`: 回声处理方式
+    /// - `window`: 写入后识别回声的时间窗口
+    pub fn set_echo_handling(&self, handling: EchoHandling, window: Duration) {
+        self.writes.set_echo_handling(handling, window);
+    }
+    
+    /// Get the last successful write to an item made through this group
+    pub fn last_write(&self, item_name: &str) -> Option<LastWrite> {
+        self.writes.last_write(item_name)
+    }
+    
     /// Get the raw group pointer (for internal use)
     pub(crate) fn as_ptr(&self) -> *mut std::ffi::c_void {
         self.ptr
     }
+    
+    /// 关闭组的所有订阅（内部使用）
+    /// 
+    /// 之后到达的数据变化不再分发，消费者收到一次关闭通知。
+    pub(crate) fn close_subscriptions(&self, reason: &SubscriptionCloseReason) {
+        for container in self.callbacks.borrow().iter() {
+            container.close(reason);
+        }
+    }
+    
+    /// 因连接中断停止组的所有订阅（内部使用）
+    /// 
+    /// 消费者收到 `on_connection_interrupted`，之后释放组时不再收到关闭通知。
+    pub(crate) fn interrupt_subscriptions(&self, reason: &str) {
+        for container in self.callbacks.borrow().iter() {
+            container.interrupt(reason);
+        }
+    }
 }
 
 impl Drop for OpcGroup {
@@ -236,9 +665,32 @@ impl Drop for OpcGroup {
         unsafe {
             crate::ffi::opc_group_free(self.ptr);
         }
+        self.stats.alive.set(false);
+        // 组释放后不会再有数据变化，通知订阅的消费者
+        self.close_subscriptions(&SubscriptionCloseReason::GroupDropped);
     }
 }
 
+/// `OpcGroup::add_items_paced` 的实现，对任何后端的组通用
+fn add_items_paced<G: DaGroup, S: AsRef<str>>(
+    group: &G,
+    names: &[S],
+    batch_size: usize,
+    interval: Duration,
+    mut progress: impl FnMut(usize, usize),
+) -> Vec<OpcResult<G::Item>> {
+    let total = names.len();
+    let mut results = Vec::with_capacity(total);
+    for (index, batch) in names.chunks(batch_size.max(1)).enumerate() {
+        if index > 0 && !interval.is_zero() {
+            std::thread::sleep(interval);
+        }
+        results.extend(batch.iter().map(|name| group.add_item(name.as_ref())));
+        progress(results.len(), total);
+    }
+    results
+}
+
 /// Internal callback function for FFI
 extern "C" fn opc_data_change_callback(
     user_data: *mut std::ffi::c_void,
@@ -268,6 +720,103 @@ extern "C" fn opc_data_change_callback(
     
     let opc_quality = OpcQuality::from_raw(quality);
     
-    // Call the user-provided callback
-    container.callback.on_data_change(&group_name_str, &item_name_str, opc_value, opc_quality, timestamp_ms);
-}
\ No newline at end of file
+    // Apply vendor quirk workarounds
+    let timestamp_ms = if container.quirks.ignore_server_timestamps {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(timestamp_ms)
+    } else {
+        timestamp_ms
+    };
+    
+    if container.quirks.deadband_ignored && !passes_relative_change_filter(container, &item_name_str, &opc_value) {
+        return;
+    }
+    
+    // Recognize echoes of our own writes
+    let Some(origin) = container.writes.classify(&item_name_str, &opc_value) else {
+        return;
+    };
+    
+    // Call the user-provided callback (queued while a group call is in flight)
+    container.dispatch(PendingDataChange {
+        group_name: group_name_str,
+        item_name: item_name_str,
+        value: opc_value,
+        quality: opc_quality,
+        timestamp: timestamp_ms,
+        origin,
+    });
+}
+
+/// Client-side relative-change filter for servers that ignore the group deadband
+///
+/// This is not OPC deadband: OPC applies the percentage to the item's EU range,
+/// which the toolkit does not expose. Instead a change passes when it differs
+/// from the last delivered value by more than the group deadband percentage of
+/// that value's magnitude, so values near zero pass on almost any change.
+/// Non-numeric values are only filtered when they are unchanged.
+fn passes_relative_change_filter(container: &OpcCallbackContainer, item_name: &str, value: &OpcValue) -> bool {
+    let mut last_values = lock_or_recover(&container.last_values);
+    
+    let passes = match last_values.get(item_name) {
+        None => true,
+        Some(last) => match (last.as_f64(), value.as_f64()) {
+            (Some(old), Some(new)) => {
+                let threshold = old.abs() * container.deadband / 100.0;
+                (new - old).abs() > threshold || (threshold == 0.0 && new != old)
+            }
+            _ => last != value,
+        },
+    };
+    
+    if passes {
+        last_values.insert(item_name.to_string(), value.clone());
+    }
+    passes
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+    use crate::backend::{DaItem, DaServer};
+    use crate::sim::SimServer;
+
+    #[test]
+    fn test_add_items_paced_batches_and_reports_progress() {
+        let server = SimServer::new();
+        for index in 0..5 {
+            server.add_tag(&format!("Tag{}", index), OpcValue::Int32(index));
+        }
+        let group = server.create_group("Paced", true, 1000, 0.0).unwrap();
+        let names = ["Tag0", "Tag1", "Missing", "Tag3", "Tag4"];
+
+        let mut progress = Vec::new();
+        let started = Instant::now();
+        let results = add_items_paced(&group, &names, 2, Duration::from_millis(20), |done, total| {
+            progress.push((done, total))
+        });
+
+        // 三批之间等待两次
+        assert!(started.elapsed() >= Duration::from_millis(40));
+        assert_eq!(progress, vec![(2, 5), (4, 5), (5, 5)]);
+        // 结果与项名顺序一致，单个项失败不影响其余项
+        assert_eq!(results.len(), 5);
+        assert!(matches!(results[2], Err(OpcError::ItemNotFound(_))));
+        let added: Vec<&str> = results.iter().filter_map(|result| result.as_ref().ok()).map(|item| item.name()).collect();
+        assert_eq!(added, vec!["Tag0", "Tag1", "Tag3", "Tag4"]);
+    }
+
+    #[test]
+    fn test_add_items_paced_treats_zero_batch_size_as_one() {
+        let server = SimServer::new().with_tag("A", OpcValue::Bool(true)).with_tag("B", OpcValue::Bool(false));
+        let group = server.create_group("Paced", true, 1000, 0.0).unwrap();
+
+        let mut progress = Vec::new();
+        let results = add_items_paced(&group, &["A", "B"], 0, Duration::ZERO, |done, total| progress.push((done, total)));
+
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(progress, vec![(1, 2), (2, 2)]);
+    }
+}
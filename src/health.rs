@@ -0,0 +1,177 @@
+//! 健康检查 HTTP 端点模块（需要 `metrics` 特性）
+//!
+//! 工厂 IT 通常用 HTTP 探针监控服务。`HealthServer` 用标准库的 `TcpListener`
+//! 在独立线程（`opcda-health`）中提供两个只读端点，不引入 HTTP 框架：
+//!
+//! - `GET /healthz`：`MetricsExporter::health` 的 JSON 快照，健康时返回 200，
+//!   已知断线时返回 503，探针只看状态码即可
+//! - `GET /metrics`：`MetricsExporter::render` 的 Prometheus 文本
+//!
+//! 连接逐个处理，每个请求读取超时为 2 秒，适合低频的监控探针，不适合对外提供服务。
+//! SNMP 等其他监控方式可以直接读取 `MetricsExporter::health` 的快照。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::{HealthServer, MetricsExporter};
+//! use std::sync::Arc;
+//!
+//! let metrics = Arc::new(MetricsExporter::new());
+//! group.add_callback(metrics.clone())?;
+//! connection.set_listener(metrics.listener());
+//! let _health = HealthServer::bind("0.0.0.0:9108", metrics.clone())?;
+//! ```
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use crate::error::OpcResult;
+use crate::metrics::MetricsExporter;
+
+/// 每个请求的读取超时
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// `/healthz` 和 `/metrics` 的 HTTP 服务
+///
+/// 释放时停止服务线程。
+pub struct HealthServer {
+    address: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl HealthServer {
+    /// 监听 `address` 并启动服务线程
+    ///
+    /// 端口为 0 时由系统分配，可以通过 `local_addr` 查看。
+    ///
+    /// # 返回值
+    /// - `Ok(HealthServer)`: 已开始监听
+    /// - `Err(OpcError::Io)`: 绑定地址或启动线程失败
+    pub fn bind(address: impl ToSocketAddrs, metrics: Arc<MetricsExporter>) -> OpcResult<Self> {
+        let listener = TcpListener::bind(address)?;
+        let address = listener.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let thread = thread::Builder::new()
+            .name("opcda-health".to_string())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    if thread_stop.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    // 单个连接的错误不影响之后的请求
+                    if let Ok(stream) = stream {
+                        let _ = respond(stream, &metrics);
+                    }
+                }
+            })?;
+        Ok(HealthServer {
+            address,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    /// 实际监听的地址
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+}
+
+impl Drop for HealthServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // 连接一次以唤醒阻塞在 accept 中的线程，监听所有地址时连接本机
+        let mut wake = self.address;
+        if wake.ip().is_unspecified() {
+            wake.set_ip(match wake {
+                SocketAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+                SocketAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+            });
+        }
+        let _ = TcpStream::connect_timeout(&wake, READ_TIMEOUT);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// 读取一个请求并返回响应
+fn respond(stream: TcpStream, metrics: &MetricsExporter) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // 读完请求头，忽略内容
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim_end().is_empty() {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let path = target.split('?').next().unwrap_or("");
+    let (status, content_type, body) = match (method, path) {
+        ("GET", "/healthz") => {
+            let health = metrics.health();
+            let status = if health.is_healthy() { "200 OK" } else { "503 Service Unavailable" };
+            (status, "application/json", health.to_json())
+        }
+        ("GET", "/metrics") => ("200 OK", "text/plain; version=0.0.4", metrics.render()),
+        ("GET", _) => ("404 Not Found", "text/plain", "not found\n".to_string()),
+        _ => ("405 Method Not Allowed", "text/plain", "method not allowed\n".to_string()),
+    };
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use crate::resilient::ConnectionEvent;
+
+    fn get(address: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_healthz_and_metrics() {
+        let metrics = Arc::new(MetricsExporter::new());
+        let server = HealthServer::bind("127.0.0.1:0", Arc::clone(&metrics)).unwrap();
+        let address = server.local_addr();
+
+        let response = get(address, "/healthz");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        assert!(response.contains("Content-Type: application/json\r\n"));
+        assert!(response.ends_with("\"last_error\":null}"));
+
+        metrics.record_connection_event(&ConnectionEvent::Lost { reason: "heartbeat failed".to_string() });
+        let response = get(address, "/healthz?verbose=1");
+        assert!(response.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+        assert!(response.contains("\"connected\":false"));
+        assert!(response.contains("\"last_error\":\"heartbeat failed\""));
+
+        assert!(get(address, "/metrics").contains("opcda_connection_lost_total 1\n"));
+        assert!(get(address, "/other").starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+        drop(server);
+        assert!(TcpStream::connect_timeout(&address, Duration::from_millis(200)).is_err());
+    }
+}
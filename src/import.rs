@@ -0,0 +1,366 @@
+//! 厂商标签配置导入模块（需要 `config` 特性）
+//!
+//! 现场的 OPC 服务器通常已经配置好了标签，重新枚举服务器再写一遍配置既费时又容易出错。
+//! `ImportedTags` 读取两种常见的导出文件，转换为 `OpcConfig` 的组和标签：
+//!
+//! - KEPServerEX 的 CSV 标签导出（一个设备一个文件）：按表头中的列名读取
+//!   `Tag Name`、`Scan Rate`、`Scaling`、`Raw Low`、`Raw High`、`Scaled Low`、`Scaled High`、
+//!   `Clamp Low` 和 `Clamp High`，其他列被忽略。文件中没有通道和设备名，项 ID 为
+//!   `<device>.<Tag Name>`，`device` 由调用方给出，例如 `Channel1.Device1`
+//! - MatrikonOPC 的别名 CSV 文件：没有表头，按位置读取别名组、别名、项路径、数据类型、
+//!   只读、始终轮询、更新速率、缩放类型、原始值下限、原始值上限、工程值下限、
+//!   工程值上限和限幅。别名为 `<别名组>.<别名>`，组为空时只用别名
+//!
+//! 每个标签成为 `TagMap` 中的一个别名，项按扫描速率（更新速率）分到名为 `<速率>ms`
+//! 的组中。线性缩放转换为 `Scaling`；平方根等其他缩放无法表示，标签不带缩放导入，
+//! 并在 `warnings` 中说明。空行和以 `#` 或 `;` 开头的行被跳过，字段可以用双引号包含逗号，
+//! 但不能跨行。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::{ImportedTags, OpcConfig};
+//!
+//! let text = std::fs::read_to_string("Device1.csv")?;
+//! let imported = ImportedTags::from_kepware_csv(&text, "Channel1.Device1")?;
+//! for warning in &imported.warnings {
+//!     eprintln!("{}", warning);
+//! }
+//! let mut config = OpcConfig::default();
+//! imported.merge_into(&mut config, "line1", "host=10.0.0.5;progid=Kepware.KEPServerEX.V6".parse()?)?;
+//! let topology = config.apply(&client)?;
+//! ```
+
+use std::collections::BTreeMap;
+use crate::config::{OpcConfig, ServerConfig};
+use crate::connection::ConnectionString;
+use crate::error::{OpcError, OpcResult};
+use crate::resilient::GroupConfig;
+use crate::scaling::Scaling;
+use crate::tags::{Tag, TagMap};
+
+/// KEPServerEX 标签的默认扫描速率（毫秒）
+const KEPWARE_DEFAULT_SCAN_RATE: u32 = 100;
+
+/// MatrikonOPC 别名未设置更新速率时使用的速率（毫秒）
+const MATRIKON_DEFAULT_UPDATE_RATE: u32 = 1000;
+
+/// 从厂商导出文件读取的组和标签
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportedTags {
+    /// 按速率分组的项，按速率从快到慢排列
+    pub groups: Vec<GroupConfig>,
+    /// 别名 -> 标签
+    pub tags: TagMap,
+    /// 无法完整导入的标签的说明，例如不支持的缩放
+    pub warnings: Vec<String>,
+}
+
+impl ImportedTags {
+    /// 读取 KEPServerEX 的 CSV 标签导出
+    ///
+    /// `device` 是标签所在的通道和设备，加在标签名前组成项 ID，为空时项 ID 就是标签名。
+    ///
+    /// # 返回值
+    /// - `Ok(ImportedTags)`: 读取成功
+    /// - `Err(OpcError::InvalidParameters)`: 缺少 `Tag Name` 列、标签名重复或数值无法解析，
+    ///   信息中包含行号
+    pub fn from_kepware_csv(text: &str, device: &str) -> OpcResult<Self> {
+        let mut records = records(text);
+        let Some((_, header)) = records.next() else {
+            return Err(OpcError::invalid_parameters("Kepware CSV export is empty"));
+        };
+        let column = |name: &str| header.iter().position(|field| field.trim().eq_ignore_ascii_case(name));
+        let name_column = column("Tag Name")
+            .ok_or_else(|| OpcError::invalid_parameters("Kepware CSV export has no 'Tag Name' column"))?;
+        let [rate, scaling, raw_lo, raw_hi, eu_lo, eu_hi, clamp_lo, clamp_hi] = [
+            "Scan Rate", "Scaling", "Raw Low", "Raw High", "Scaled Low", "Scaled High", "Clamp Low", "Clamp High",
+        ]
+        .map(column);
+
+        let mut collector = Collector::default();
+        for (line, record) in records {
+            let field = |index: Option<usize>| index.and_then(|i| record.get(i)).map_or("", |f| f.trim());
+            let name = field(Some(name_column));
+            if name.is_empty() {
+                return Err(line_error("Kepware CSV", line, "empty tag name"));
+            }
+            let item_id = if device.is_empty() { name.to_string() } else { format!("{}.{}", device, name) };
+            let rate = parse_or(field(rate), KEPWARE_DEFAULT_SCAN_RATE, "Kepware CSV", line)?;
+            let scaling = match field(scaling).to_ascii_lowercase().as_str() {
+                "" | "none" => None,
+                "linear" => {
+                    let ends = [raw_lo, raw_hi, eu_lo, eu_hi].map(field);
+                    let clamp = is_set(field(clamp_lo)) || is_set(field(clamp_hi));
+                    Some(linear(ends, clamp, "Kepware CSV", line)?)
+                }
+                other => {
+                    collector.warnings.push(format!(
+                        "Kepware CSV line {}: '{}' uses unsupported scaling '{}', imported unscaled",
+                        line, name, other
+                    ));
+                    None
+                }
+            };
+            collector.add(name, &item_id, rate, scaling, "Kepware CSV", line)?;
+        }
+        Ok(collector.finish())
+    }
+
+    /// 读取 MatrikonOPC 的别名 CSV 文件
+    ///
+    /// 更新速率为空或 0 时使用 1000 毫秒。缩放类型 `0` 表示不缩放，`1` 表示线性缩放。
+    ///
+    /// # 返回值
+    /// - `Ok(ImportedTags)`: 读取成功
+    /// - `Err(OpcError::InvalidParameters)`: 缺少别名或项路径、别名重复或数值无法解析，
+    ///   信息中包含行号
+    pub fn from_matrikon_aliases(text: &str) -> OpcResult<Self> {
+        let mut collector = Collector::default();
+        for (line, record) in records(text) {
+            let field = |index: usize| record.get(index).map_or("", |f| f.trim());
+            let (group, name, item_id) = (field(0), field(1), field(2));
+            if name.is_empty() || item_id.is_empty() {
+                return Err(line_error("Matrikon alias file", line, "alias name and item path are required"));
+            }
+            let alias = if group.is_empty() { name.to_string() } else { format!("{}.{}", group, name) };
+            let rate = match parse_or(field(6), 0, "Matrikon alias file", line)? {
+                0 => MATRIKON_DEFAULT_UPDATE_RATE,
+                rate => rate,
+            };
+            let scaling = match field(7) {
+                "" | "0" => None,
+                "1" => {
+                    let ends = [8, 9, 10, 11].map(field);
+                    Some(linear(ends, is_set(field(12)), "Matrikon alias file", line)?)
+                }
+                other => {
+                    collector.warnings.push(format!(
+                        "Matrikon alias file line {}: '{}' uses unsupported scaling type {}, imported unscaled",
+                        line, alias, other
+                    ));
+                    None
+                }
+            };
+            collector.add(&alias, item_id, rate, scaling, "Matrikon alias file", line)?;
+        }
+        Ok(collector.finish())
+    }
+
+    /// 把导入的组和标签作为名为 `server` 的服务器加入配置
+    ///
+    /// # 返回值
+    /// - `Ok(())`: 已加入
+    /// - `Err(OpcError::InvalidParameters)`: 配置中已有同名的服务器或标签，配置不变
+    pub fn merge_into(self, config: &mut OpcConfig, server: &str, connection: ConnectionString) -> OpcResult<()> {
+        if config.servers.contains_key(server) {
+            return Err(OpcError::invalid_parameters(format!("Configuration already has server '{}'", server)));
+        }
+        if let Some(alias) = self.tags.aliases().find(|alias| config.tags.get(alias).is_some()) {
+            return Err(OpcError::invalid_parameters(format!("Configuration already has tag '{}'", alias)));
+        }
+        for alias in self.tags.aliases() {
+            if let Some(tag) = self.tags.get(alias) {
+                config.tags.insert(alias, tag.clone());
+            }
+        }
+        config.servers.insert(
+            server.to_string(),
+            ServerConfig {
+                connection,
+                groups: self.groups,
+            },
+        );
+        Ok(())
+    }
+}
+
+/// 逐行收集标签，按速率分组
+#[derive(Default)]
+struct Collector {
+    /// 速率 -> 项 ID
+    rates: BTreeMap<u32, Vec<String>>,
+    tags: TagMap,
+    warnings: Vec<String>,
+}
+
+impl Collector {
+    fn add(&mut self, alias: &str, item_id: &str, rate: u32, scaling: Option<Scaling>, source: &str, line: usize) -> OpcResult<()> {
+        let tag = match scaling {
+            Some(scaling) => Tag::with_scaling(item_id, scaling),
+            None => Tag::new(item_id),
+        };
+        if self.tags.insert(alias, tag).is_some() {
+            return Err(line_error(source, line, &format!("duplicate tag '{}'", alias)));
+        }
+        let items = self.rates.entry(rate).or_default();
+        if !items.iter().any(|item| item == item_id) {
+            items.push(item_id.to_string());
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> ImportedTags {
+        ImportedTags {
+            groups: self
+                .rates
+                .into_iter()
+                .map(|(rate, items)| GroupConfig::new(&format!("{}ms", rate), rate, &items))
+                .collect(),
+            tags: self.tags,
+            warnings: self.warnings,
+        }
+    }
+}
+
+fn line_error(source: &str, line: usize, message: &str) -> OpcError {
+    OpcError::invalid_parameters(format!("{} line {}: {}", source, line, message))
+}
+
+/// 解析数值，空字段使用 `default`
+fn parse_or<T: std::str::FromStr>(field: &str, default: T, source: &str, line: usize) -> OpcResult<T> {
+    if field.is_empty() {
+        return Ok(default);
+    }
+    field
+        .parse()
+        .map_err(|_| line_error(source, line, &format!("invalid number '{}'", field)))
+}
+
+/// 由原始值下限、上限和工程值下限、上限构造线性缩放
+fn linear(ends: [&str; 4], clamp: bool, source: &str, line: usize) -> OpcResult<Scaling> {
+    let [raw_lo, raw_hi, eu_lo, eu_hi] = [0, 1, 2, 3].map(|i| ends[i].parse::<f64>());
+    let (Ok(raw_lo), Ok(raw_hi), Ok(eu_lo), Ok(eu_hi)) = (raw_lo, raw_hi, eu_lo, eu_hi) else {
+        return Err(line_error(source, line, &format!("invalid linear scaling range {:?}", ends)));
+    };
+    let scaling = Scaling::new(raw_lo, raw_hi, eu_lo, eu_hi);
+    scaling.validate().map_err(|e| line_error(source, line, &e.to_string()))?;
+    Ok(if clamp { scaling.clamped() } else { scaling })
+}
+
+fn is_set(field: &str) -> bool {
+    field == "1" || field.eq_ignore_ascii_case("true") || field.eq_ignore_ascii_case("yes")
+}
+
+/// 带行号（从 1 开始）的 CSV 记录，跳过空行和注释行
+fn records(text: &str) -> impl Iterator<Item = (usize, Vec<String>)> + '_ {
+    text.trim_start_matches('\u{feff}')
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let line = line.trim();
+            !line.is_empty() && !line.starts_with('#') && !line.starts_with(';')
+        })
+        .map(|(index, line)| (index + 1, split_record(line)))
+}
+
+/// 按逗号拆分一行，双引号内的逗号不拆分，`""` 表示一个双引号
+fn split_record(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEPWARE: &str = "\u{feff}Tag Name,Address,Data Type,Respect Data Type,Client Access,Scan Rate,Scaling,Raw Low,Raw High,Scaled Low,Scaled High,Scaled Data Type,Clamp Low,Clamp High,Eng Units,Description,Negate Value
+\"Speed\",\"40001\",Word,1,R/W,100,None,,,,,,,,,\"Motor speed, rpm\",0
+\"Tank.Level\",\"40002\",Word,1,RO,500,Linear,0,27648,0,150,Float,1,0,\"%\",\"Level \"\"A\"\"\",0
+\"Flow\",\"40003\",Word,1,RO,500,Square Root,0,27648,0,10,Float,0,0,,,0
+\"Counter\",\"40004\",DWord,1,RO,,None,,,,,,,,,,0
+";
+
+    #[test]
+    fn test_kepware_csv() {
+        let imported = ImportedTags::from_kepware_csv(KEPWARE, "Channel1.Device1").unwrap();
+        assert_eq!(
+            imported.groups,
+            vec![
+                GroupConfig::new("100ms", 100, &["Channel1.Device1.Speed", "Channel1.Device1.Counter"]),
+                GroupConfig::new("500ms", 500, &["Channel1.Device1.Tank.Level", "Channel1.Device1.Flow"]),
+            ]
+        );
+        assert_eq!(imported.tags.get("Speed"), Some(&Tag::new("Channel1.Device1.Speed")));
+        assert_eq!(
+            imported.tags.get("Tank.Level"),
+            Some(&Tag::with_scaling("Channel1.Device1.Tank.Level", Scaling::new(0.0, 27648.0, 0.0, 150.0).clamped()))
+        );
+        assert_eq!(imported.tags.get("Flow"), Some(&Tag::new("Channel1.Device1.Flow")));
+        assert_eq!(
+            imported.warnings,
+            vec!["Kepware CSV line 4: 'Flow' uses unsupported scaling 'square root', imported unscaled".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_kepware_csv_errors() {
+        let error = |text: &str| ImportedTags::from_kepware_csv(text, "").unwrap_err().to_string();
+        assert!(error("").contains("empty"));
+        assert!(error("Address,Scan Rate\n40001,100\n").contains("'Tag Name'"));
+        assert!(error("Tag Name,Scan Rate\nA,fast\n").contains("line 2: invalid number 'fast'"));
+        assert!(error("Tag Name,Scan Rate\nA,100\nA,200\n").contains("line 3: duplicate tag 'A'"));
+        let empty_range = "Tag Name,Scaling,Raw Low,Raw High,Scaled Low,Scaled High\nA,Linear,0,0,0,100\n";
+        assert!(error(empty_range).contains("line 2: Invalid parameters"));
+    }
+
+    #[test]
+    fn test_matrikon_aliases() {
+        let text = "; exported aliases
+Plant.Line1,Speed,Device1.40001,2,0,0,250,0
+Plant.Line1,Level,Device1.40002,4,1,0,250,1,0,27648,0,150,0
+,Mode,Device1.40010,2,0,0,0,0
+Plant.Line1,Flow,Device1.40003,4,1,0,250,2,0,100
+";
+        let imported = ImportedTags::from_matrikon_aliases(text).unwrap();
+        assert_eq!(
+            imported.groups,
+            vec![
+                GroupConfig::new("250ms", 250, &["Device1.40001", "Device1.40002", "Device1.40003"]),
+                GroupConfig::new("1000ms", 1000, &["Device1.40010"]),
+            ]
+        );
+        assert_eq!(
+            imported.tags.get("Plant.Line1.Level"),
+            Some(&Tag::with_scaling("Device1.40002", Scaling::new(0.0, 27648.0, 0.0, 150.0)))
+        );
+        assert_eq!(imported.tags.resolve("Mode").unwrap(), "Device1.40010");
+        assert_eq!(imported.warnings.len(), 1);
+        assert!(imported.warnings[0].starts_with("Matrikon alias file line 5: 'Plant.Line1.Flow'"));
+
+        let missing_item = ImportedTags::from_matrikon_aliases("G,Speed,,2\n").unwrap_err();
+        assert!(missing_item.to_string().contains("line 1: alias name and item path are required"));
+    }
+
+    #[test]
+    fn test_merge_into() {
+        let connection = ConnectionString::new("10.0.0.5", "Kepware.KEPServerEX.V6");
+        let mut config = OpcConfig::default();
+        config.tags.insert("Speed", Tag::new("Other.Speed"));
+
+        let imported = ImportedTags::from_kepware_csv(KEPWARE, "Channel1.Device1").unwrap();
+        assert!(imported.clone().merge_into(&mut config, "line1", connection.clone()).is_err());
+        assert!(config.servers.is_empty());
+
+        config.tags.remove("Speed");
+        imported.clone().merge_into(&mut config, "line1", connection.clone()).unwrap();
+        assert_eq!(config.servers["line1"].groups, imported.groups);
+        assert_eq!(config.tags.len(), 4);
+        assert!(imported.merge_into(&mut config, "line1", connection).is_err());
+    }
+}
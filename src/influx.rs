@@ -0,0 +1,525 @@
+//! InfluxDB 行协议输出模块（需要 `influx` 特性）
+//!
+//! `InfluxSink` 作为组的订阅消费者，把每次数据变化转换为一行 InfluxDB 行协议：
+//!
+//! ```text
+//! opcda,group=Line1,item=Tank1.Level,quality=Good value=87.5 1700000000123
+//! ```
+//!
+//! 行先进入内存缓冲区，由名为 `opcda-influx` 的发送线程按批次 POST 到写入接口，
+//! 回调线程不等待网络。整数写为 `i` 后缀的整数字段，布尔值和字符串保持原类型，
+//! 数组按 `FormatProfile` 的默认格式写为字符串；NaN 和无穷大无法表示，不写入。
+//! 时间戳精度为毫秒，写入 URL 需要带 `precision=ms`。
+//!
+//! ## 重试与存储转发
+//!
+//! 发送失败的批次留在缓冲区中，按 `initial_backoff` 起加倍、不超过 `max_backoff` 的间隔重试，
+//! 成功后才从缓冲区移除，因此数据库或网络短时不可用时不丢数据。
+//! 缓冲区达到 `capacity` 行后新的数据变化被丢弃并计入 `dropped`。
+//! 设置 `spool` 后，关闭时仍未发送的行保存到该文件，下次创建时最先发送。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::{InfluxConfig, InfluxSink};
+//! use std::sync::Arc;
+//!
+//! let mut config = InfluxConfig::new("http://influx:8086/api/v2/write?org=plant&bucket=opc&precision=ms");
+//! config.token = Some(std::env::var("INFLUX_TOKEN")?);
+//! config.spool = Some("influx.spool".into());
+//! let sink = Arc::new(InfluxSink::spawn(config)?);
+//! group.add_callback(sink.clone())?;
+//! ```
+
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use crate::error::{OpcError, OpcResult};
+use crate::format::FormatProfile;
+use crate::types::{lock_or_recover, OpcDataCallback, OpcQuality, OpcValue};
+
+/// 输出配置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InfluxConfig {
+    /// 写入接口的完整 URL，例如 `http://host:8086/api/v2/write?org=o&bucket=b&precision=ms`
+    /// 或 1.x 的 `http://host:8086/write?db=opc&precision=ms`
+    pub url: String,
+    /// 以 `Authorization: Token <token>` 发送的令牌
+    pub token: Option<String>,
+    /// 测量名
+    pub measurement: String,
+    /// 每批最多发送的行数
+    pub batch_size: usize,
+    /// 缓冲区不足一批时，最长等待多久发送
+    pub flush_interval: Duration,
+    /// 缓冲区最多保存的行数
+    pub capacity: usize,
+    /// 第一次重试前的等待时间，之后每次失败加倍
+    pub initial_backoff: Duration,
+    /// 重试等待时间的上限
+    pub max_backoff: Duration,
+    /// 每次 HTTP 请求的超时
+    pub timeout: Duration,
+    /// 关闭时保存未发送的行的文件
+    pub spool: Option<PathBuf>,
+}
+
+impl InfluxConfig {
+    /// 使用默认设置，写入 `url`
+    pub fn new(url: &str) -> Self {
+        InfluxConfig {
+            url: url.to_string(),
+            token: None,
+            measurement: "opcda".to_string(),
+            batch_size: 5_000,
+            flush_interval: Duration::from_secs(1),
+            capacity: 100_000,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            timeout: Duration::from_secs(10),
+            spool: None,
+        }
+    }
+}
+
+/// 发送一批行协议文本
+///
+/// 在发送线程中调用。返回错误时这一批留在缓冲区中稍后重试。
+pub trait LineTransport: Send + 'static {
+    /// 发送以换行分隔的若干行
+    fn send(&mut self, body: &str) -> OpcResult<()>;
+}
+
+/// 通过 HTTP POST 发送
+struct HttpTransport {
+    agent: ureq::Agent,
+    url: String,
+    token: Option<String>,
+}
+
+impl LineTransport for HttpTransport {
+    fn send(&mut self, body: &str) -> OpcResult<()> {
+        let mut request = self.agent.post(&self.url).header("Content-Type", "text/plain; charset=utf-8");
+        if let Some(token) = &self.token {
+            request = request.header("Authorization", &format!("Token {}", token));
+        }
+        request
+            .send(body)
+            .map(|_| ())
+            .map_err(|e| OpcError::OperationFailed(format!("InfluxDB write failed: {}", e)))
+    }
+}
+
+#[derive(Default)]
+struct Buffer {
+    lines: VecDeque<String>,
+    sent: u64,
+    dropped: u64,
+    last_error: Option<String>,
+    /// `flush` 请求立即发送
+    flush: bool,
+    shutdown: bool,
+}
+
+struct Shared {
+    buffer: Mutex<Buffer>,
+    /// 有新行、请求发送或发送完成时通知
+    changed: Condvar,
+}
+
+/// 批量写入 InfluxDB 的数据变化消费者
+pub struct InfluxSink {
+    config: InfluxConfig,
+    shared: Arc<Shared>,
+    sender: Option<JoinHandle<()>>,
+}
+
+impl InfluxSink {
+    /// 通过 HTTP 写入 `config.url`
+    ///
+    /// # 返回值
+    /// - `Ok(InfluxSink)`: 发送线程已启动
+    /// - `Err(OpcError::InvalidParameters)`: URL 没有 `precision=ms`，或批次、容量为 0
+    /// - `Err(OpcError::Io)`: 读取 spool 文件或启动线程失败
+    pub fn spawn(config: InfluxConfig) -> OpcResult<Self> {
+        let agent = ureq::Agent::config_builder()
+            .timeout_global(Some(config.timeout))
+            .build()
+            .new_agent();
+        let transport = HttpTransport {
+            agent,
+            url: config.url.clone(),
+            token: config.token.clone(),
+        };
+        Self::with_transport(config, transport)
+    }
+
+    /// 使用自定义的传输方式，用于测试或其他协议
+    pub fn with_transport(config: InfluxConfig, transport: impl LineTransport) -> OpcResult<Self> {
+        if !config.url.contains("precision=ms") {
+            return Err(OpcError::invalid_parameters("InfluxDB write URL must set precision=ms"));
+        }
+        if config.batch_size == 0 || config.capacity == 0 {
+            return Err(OpcError::invalid_parameters("InfluxDB batch size and capacity must be positive"));
+        }
+        let mut buffer = Buffer::default();
+        if let Some(spool) = &config.spool {
+            match fs::read_to_string(spool) {
+                Ok(content) => {
+                    buffer.lines.extend(content.lines().filter(|line| !line.is_empty()).map(str::to_string));
+                    fs::remove_file(spool)?;
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        let shared = Arc::new(Shared {
+            buffer: Mutex::new(buffer),
+            changed: Condvar::new(),
+        });
+        let sender_shared = Arc::clone(&shared);
+        let sender_config = config.clone();
+        let sender = thread::Builder::new()
+            .name("opcda-influx".to_string())
+            .spawn(move || send_loop(&sender_shared, &sender_config, transport))?;
+        Ok(InfluxSink {
+            config,
+            shared,
+            sender: Some(sender),
+        })
+    }
+
+    /// 把一次数据变化加入缓冲区
+    ///
+    /// 返回是否加入。缓冲区已满时丢弃，无法表示的值（NaN、无穷大）不加入。
+    pub fn record(&self, group_name: &str, item_name: &str, value: &OpcValue, quality: OpcQuality, timestamp: u64) -> bool {
+        let Some(line) = format_line(&self.config.measurement, group_name, item_name, value, quality, timestamp) else {
+            return false;
+        };
+        let mut buffer = lock_or_recover(&self.shared.buffer);
+        if buffer.lines.len() >= self.config.capacity {
+            buffer.dropped += 1;
+            return false;
+        }
+        buffer.lines.push_back(line);
+        if buffer.lines.len() >= self.config.batch_size {
+            self.shared.changed.notify_all();
+        }
+        true
+    }
+
+    /// 立即发送缓冲区中的行，等待缓冲区清空
+    ///
+    /// 发送失败时继续按退避间隔重试，直到清空或超过 `timeout`。返回缓冲区是否已清空。
+    pub fn flush(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut buffer = lock_or_recover(&self.shared.buffer);
+        buffer.flush = true;
+        self.shared.changed.notify_all();
+        while !buffer.lines.is_empty() {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            buffer = wait(&self.shared, buffer, deadline - now);
+        }
+        true
+    }
+
+    /// 缓冲区中尚未发送的行数
+    pub fn pending(&self) -> usize {
+        lock_or_recover(&self.shared.buffer).lines.len()
+    }
+
+    /// 已发送的行数
+    pub fn sent(&self) -> u64 {
+        lock_or_recover(&self.shared.buffer).sent
+    }
+
+    /// 因缓冲区已满被丢弃的数据变化数
+    pub fn dropped(&self) -> u64 {
+        lock_or_recover(&self.shared.buffer).dropped
+    }
+
+    /// 最近一次发送失败的原因
+    pub fn last_error(&self) -> Option<String> {
+        lock_or_recover(&self.shared.buffer).last_error.clone()
+    }
+}
+
+impl OpcDataCallback for InfluxSink {
+    fn on_data_change(&self, group_name: &str, item_name: &str, value: OpcValue, quality: OpcQuality, timestamp: u64) {
+        // 丢弃的记录已计入 dropped
+        self.record(group_name, item_name, &value, quality, timestamp);
+    }
+}
+
+impl Drop for InfluxSink {
+    /// 停止发送线程，未发送的行保存到 spool 文件
+    fn drop(&mut self) {
+        lock_or_recover(&self.shared.buffer).shutdown = true;
+        self.shared.changed.notify_all();
+        if let Some(sender) = self.sender.take() {
+            let _ = sender.join();
+        }
+        let buffer = lock_or_recover(&self.shared.buffer);
+        if let (Some(spool), false) = (&self.config.spool, buffer.lines.is_empty()) {
+            let content: String = buffer.lines.iter().map(|line| format!("{}\n", line)).collect();
+            let _ = fs::write(spool, content);
+        }
+    }
+}
+
+fn wait<'a>(shared: &'a Shared, buffer: MutexGuard<'a, Buffer>, timeout: Duration) -> MutexGuard<'a, Buffer> {
+    match shared.changed.wait_timeout(buffer, timeout) {
+        Ok((buffer, _)) => buffer,
+        Err(poisoned) => poisoned.into_inner().0,
+    }
+}
+
+/// 发送线程：攒够一批或到达发送间隔时发送，失败后退避重试
+///
+/// 关闭时再尝试发送一次剩余的行，失败即退出。
+fn send_loop(shared: &Shared, config: &InfluxConfig, mut transport: impl LineTransport) {
+    let mut backoff = config.initial_backoff;
+    let mut next_send = Instant::now() + config.flush_interval;
+    loop {
+        let batch: Vec<String> = {
+            let mut buffer = lock_or_recover(&shared.buffer);
+            loop {
+                let now = Instant::now();
+                let due = buffer.lines.len() >= config.batch_size || buffer.flush || now >= next_send;
+                if buffer.shutdown || (due && !buffer.lines.is_empty()) {
+                    break;
+                }
+                if now >= next_send {
+                    next_send = now + config.flush_interval;
+                }
+                buffer = wait(shared, buffer, next_send - now);
+            }
+            if buffer.lines.is_empty() {
+                return;
+            }
+            buffer.lines.iter().take(config.batch_size).cloned().collect()
+        };
+
+        let result = transport.send(&batch.join("\n"));
+        let mut buffer = lock_or_recover(&shared.buffer);
+        match result {
+            Ok(()) => {
+                buffer.lines.drain(..batch.len());
+                buffer.sent += batch.len() as u64;
+                if buffer.lines.is_empty() {
+                    buffer.flush = false;
+                }
+                backoff = config.initial_backoff;
+                next_send = Instant::now() + config.flush_interval;
+                shared.changed.notify_all();
+            }
+            Err(e) => {
+                buffer.last_error = Some(e.to_string());
+                if buffer.shutdown {
+                    return;
+                }
+                // 退避期间只有关闭能唤醒
+                let retry_at = Instant::now() + backoff;
+                while !buffer.shutdown {
+                    let now = Instant::now();
+                    if now >= retry_at {
+                        break;
+                    }
+                    buffer = wait(shared, buffer, retry_at - now);
+                }
+                backoff = (backoff * 2).min(config.max_backoff);
+                next_send = Instant::now();
+            }
+        }
+    }
+}
+
+/// 转换为一行行协议，无法表示的值返回 `None`
+fn format_line(measurement: &str, group_name: &str, item_name: &str, value: &OpcValue, quality: OpcQuality, timestamp: u64) -> Option<String> {
+    let field = match value {
+        OpcValue::Bool(v) => v.to_string(),
+        OpcValue::String(v) => string_field(v),
+        OpcValue::Int8(v) => format!("{}i", v),
+        OpcValue::UInt8(v) => format!("{}i", v),
+        OpcValue::Int16(v) => format!("{}i", v),
+        OpcValue::UInt16(v) => format!("{}i", v),
+        OpcValue::Int32(v) => format!("{}i", v),
+        OpcValue::UInt32(v) => format!("{}i", v),
+        OpcValue::Int64(v) => format!("{}i", v),
+        OpcValue::INT(v) => format!("{}i", v),
+        other if other.is_array() => string_field(&FormatProfile::default().format_value(item_name, other)),
+        other => {
+            let v = other.as_f64().filter(|v| v.is_finite())?;
+            format!("{:?}", v)
+        }
+    };
+    let mut line = escape(measurement, &[',', ' ']);
+    if !group_name.is_empty() {
+        line.push_str(",group=");
+        line.push_str(&escape(group_name, &[',', '=', ' ']));
+    }
+    line.push_str(",item=");
+    line.push_str(&escape(item_name, &[',', '=', ' ']));
+    line.push_str(&format!(",quality={} value={} {}", quality, field, timestamp));
+    Some(line)
+}
+
+/// 用反斜杠转义 `special` 中的字符
+fn escape(text: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if special.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// 带引号的字符串字段
+fn string_field(text: &str) -> String {
+    format!("\"{}\"", escape(text, &['"', '\\']))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    /// 记录发送的批次，前 `failures` 次返回错误
+    struct Recording {
+        batches: Arc<Mutex<Vec<String>>>,
+        failures: u32,
+    }
+
+    impl LineTransport for Recording {
+        fn send(&mut self, body: &str) -> OpcResult<()> {
+            if self.failures > 0 {
+                self.failures -= 1;
+                return Err(OpcError::OperationFailed("connection refused".to_string()));
+            }
+            self.batches.lock().unwrap().push(body.to_string());
+            Ok(())
+        }
+    }
+
+    fn config() -> InfluxConfig {
+        let mut config = InfluxConfig::new("http://localhost:8086/write?db=opc&precision=ms");
+        config.batch_size = 2;
+        config.flush_interval = Duration::from_secs(3600);
+        config.initial_backoff = Duration::from_millis(10);
+        config
+    }
+
+    #[test]
+    fn test_line_format() {
+        let line = |value: OpcValue| format_line("opc da", "Line 1", "Tank,1=a", &value, OpcQuality::Good, 1_000);
+        assert_eq!(
+            line(OpcValue::Double(1.0)).unwrap(),
+            "opc\\ da,group=Line\\ 1,item=Tank\\,1\\=a,quality=Good value=1.0 1000"
+        );
+        assert!(line(OpcValue::Int32(-5)).unwrap().contains(" value=-5i "));
+        assert!(line(OpcValue::Bool(true)).unwrap().contains(" value=true "));
+        assert!(line(OpcValue::String("say \"hi\"".to_string())).unwrap().contains(" value=\"say \\\"hi\\\"\" "));
+        assert!(line(OpcValue::Double(f64::NAN)).is_none());
+        assert_eq!(
+            format_line("m", "", "A", &OpcValue::Float(2.5), OpcQuality::Bad, 7).unwrap(),
+            "m,item=A,quality=Bad value=2.5 7"
+        );
+    }
+
+    #[test]
+    fn test_batches_and_retry() {
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let sink = InfluxSink::with_transport(config(), Recording { batches: Arc::clone(&batches), failures: 1 }).unwrap();
+        for timestamp in 1..=3 {
+            sink.on_data_change("G", "A", OpcValue::Int16(timestamp as i16), OpcQuality::Good, timestamp);
+        }
+        // 第一批失败后重试，不足一批的第三行由 flush 发送
+        assert!(sink.flush(Duration::from_secs(5)));
+        assert_eq!(sink.sent(), 3);
+        assert_eq!(sink.last_error().unwrap(), "OPC operation failed: connection refused");
+        assert_eq!(
+            *batches.lock().unwrap(),
+            vec![
+                "opcda,group=G,item=A,quality=Good value=1i 1\nopcda,group=G,item=A,quality=Good value=2i 2".to_string(),
+                "opcda,group=G,item=A,quality=Good value=3i 3".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_capacity_and_spool() {
+        let spool = std::env::temp_dir().join(format!("opcda-influx-{}.spool", std::process::id()));
+        let _ = fs::remove_file(&spool);
+        let mut config = config();
+        config.capacity = 2;
+        config.spool = Some(spool.clone());
+
+        // 一直失败的数据库：缓冲区满后丢弃，关闭时保存未发送的行
+        let sink = InfluxSink::with_transport(config.clone(), Recording { batches: Arc::default(), failures: u32::MAX }).unwrap();
+        assert!(sink.record("G", "A", &OpcValue::Bool(true), OpcQuality::Good, 1));
+        assert!(sink.record("G", "A", &OpcValue::Bool(false), OpcQuality::Good, 2));
+        assert!(!sink.record("G", "A", &OpcValue::Bool(true), OpcQuality::Good, 3));
+        assert_eq!(sink.dropped(), 1);
+        drop(sink);
+        assert_eq!(fs::read_to_string(&spool).unwrap().lines().count(), 2);
+
+        // 下次启动时最先发送
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let sink = InfluxSink::with_transport(config, Recording { batches: Arc::clone(&batches), failures: 0 }).unwrap();
+        assert!(!spool.exists());
+        assert!(sink.flush(Duration::from_secs(5)));
+        assert_eq!(
+            *batches.lock().unwrap(),
+            vec!["opcda,group=G,item=A,quality=Good value=true 1\nopcda,group=G,item=A,quality=Good value=false 2".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_http_write() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut head = Vec::new();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    content_length = value.trim().parse().unwrap();
+                }
+                head.push(line.trim_end().to_string());
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            stream.write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n").unwrap();
+            (head, String::from_utf8(body).unwrap())
+        });
+
+        let mut config = config();
+        config.url = format!("http://{}/api/v2/write?org=plant&bucket=opc&precision=ms", address);
+        config.token = Some("secret".to_string());
+        let sink = InfluxSink::spawn(config).unwrap();
+        sink.record("G", "A", &OpcValue::Double(1.5), OpcQuality::Good, 1_000);
+        assert!(sink.flush(Duration::from_secs(5)));
+
+        let (head, body) = server.join().unwrap();
+        assert_eq!(head[0], "POST /api/v2/write?org=plant&bucket=opc&precision=ms HTTP/1.1");
+        assert!(head.iter().any(|line| line.eq_ignore_ascii_case("authorization: Token secret")));
+        assert_eq!(body, "opcda,group=G,item=A,quality=Good value=1.5 1000");
+        assert!(InfluxSink::spawn(InfluxConfig::new("http://localhost/write?db=opc")).is_err());
+    }
+}
@@ -9,6 +9,8 @@
 //! - 同步写入项值
 //! - 异步读取项值
 //! - 异步写入项值
+//! - 带有效期的读取缓存（`read_cached`）
+//! - 原始值与工程单位的线性缩放（`read_scaled` / `write_scaled`）
 //! - 管理项生命周期
 //! 
 //! ## 项属性
@@ -28,8 +30,81 @@
 //! - 布尔值（Boolean）
 //! - 时间（DateTime）
 
+use std::cell::{Cell, RefCell};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use crate::diagnostics::ItemRegistration;
 use crate::error::{OpcError, OpcResult};
-use crate::types::{OpcValue, OpcQuality};
+use crate::scaling::Scaling;
+use crate::types::{OpcValue, OpcQuality, OpcTimestamp, OpcValueError};
+use crate::writes::WriteTracker;
+
+/// 同步读取的结果：值、质量和时间戳
+type Reading = (OpcValue, OpcQuality, OpcTimestamp);
+
+/// 最近一次成功同步读取的结果和读取时间
+#[derive(Default)]
+struct ReadCache(RefCell<Option<(Instant, Reading)>>);
+
+impl ReadCache {
+    /// 执行一次读取，成功时更新缓存
+    fn refresh(&self, read: impl FnOnce() -> OpcResult<Reading>) -> OpcResult<Reading> {
+        let reading = read()?;
+        *self.0.borrow_mut() = Some((Instant::now(), reading.clone()));
+        Ok(reading)
+    }
+    
+    /// 缓存结果距今不超过 `ttl` 时直接返回，否则执行一次读取
+    fn read(&self, ttl: Duration, read: impl FnOnce() -> OpcResult<Reading>) -> OpcResult<Reading> {
+        if let Some((read_at, reading)) = self.0.borrow().as_ref() {
+            if !ttl.is_zero() && read_at.elapsed() <= ttl {
+                return Ok(reading.clone());
+            }
+        }
+        self.refresh(read)
+    }
+    
+    fn clear(&self) {
+        self.0.borrow_mut().take();
+    }
+    
+    /// 最近一次读取的值，不考虑有效期
+    fn last_value(&self) -> Option<OpcValue> {
+        self.0.borrow().as_ref().map(|(_, (value, _, _))| value.clone())
+    }
+}
+
+/// 把读取的原始值转换为工程单位
+fn to_eu(scaling: &Scaling, (value, quality, timestamp): Reading) -> OpcResult<(f64, OpcQuality, OpcTimestamp)> {
+    let raw = value.as_f64().ok_or_else(|| {
+        OpcValueError::conversion_error(format!("Cannot scale {} value", value.type_name()))
+    })?;
+    Ok((scaling.to_eu(raw), quality, timestamp))
+}
+
+/// 把工程值转换为要写入的原始值，有数值模板时转换为模板的类型
+fn to_raw(scaling: &Scaling, eu: f64, template: Option<OpcValue>) -> OpcResult<OpcValue> {
+    let raw = OpcValue::Double(scaling.to_raw(eu));
+    match template {
+        Some(template) if template.as_f64().is_some() && !template.is_array() => {
+            Ok(crate::mirror::coerce_to(raw, &template)?)
+        }
+        _ => Ok(raw),
+    }
+}
+
+/// 写权限探测策略
+/// 
+/// 用于 `OpcItem::can_write`，决定在没有已知写权限信息时是否进行探测。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteProbe {
+    /// 不探测，只返回已知（已缓存）的结果
+    None,
+    /// 读取当前值并原样写回，根据写入是否成功判断写权限
+    /// 
+    /// 这会对设备产生一次真实的写操作，必须由调用者显式选择。
+    WriteBack,
+}
 
 /// OPC 项，表示单个数据点
 /// 
@@ -42,6 +117,7 @@ use crate::types::{OpcValue, OpcQuality};
 /// ## 内部结构
 /// 
 /// - `ptr`: 指向底层 OPC 项对象的指针
+/// - `write_access`: 缓存的写权限（任何一次同步写入成功后确认可写，失败不缓存）
 /// 
 /// ## 示例
 /// 
@@ -63,6 +139,18 @@ use crate::types::{OpcValue, OpcQuality};
 pub struct OpcItem {
     /// 指向底层 OPC 项对象的指针
     ptr: *mut std::ffi::c_void,
+    /// 添加项时使用的项名
+    name: String,
+    /// 缓存的写权限，`None` 表示未知
+    write_access: Cell<Option<bool>>,
+    /// 所属组的写入跟踪
+    writes: Option<Arc<WriteTracker>>,
+    /// 在所属组运行统计中的登记
+    registration: Option<ItemRegistration>,
+    /// 最近一次成功同步读取的结果和读取时间
+    last_read: ReadCache,
+    /// 附加的线性缩放
+    scaling: Cell<Option<Scaling>>,
 }
 
 impl OpcItem {
@@ -70,12 +158,52 @@ impl OpcItem {
     /// 
     /// # 参数
     /// - `item_ptr`: 指向底层 OPC 项对象的指针
+    /// - `name`: 项名
+    /// - `writes`: 所属组的写入跟踪
+    /// - `registration`: 在所属组运行统计中的登记
     /// 
     /// # 注意
     /// 这个方法仅供内部使用，用户应该通过 `OpcGroup::add_item` 获取 `OpcItem` 实例。
-    pub(crate) fn new(item_ptr: *mut std::ffi::c_void) -> Self {
+    pub(crate) fn new(
+        item_ptr: *mut std::ffi::c_void,
+        name: &str,
+        writes: Option<Arc<WriteTracker>>,
+        registration: Option<ItemRegistration>,
+    ) -> Self {
         OpcItem {
             ptr: item_ptr,
+            name: name.to_string(),
+            write_access: Cell::new(None),
+            writes,
+            registration,
+            last_read: ReadCache::default(),
+            scaling: Cell::new(None),
+        }
+    }
+    
+    /// 项名
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    
+    /// 所属服务器的连接编号，用于重入检查
+    fn server_id(&self) -> Option<u64> {
+        self.registration.as_ref().map(ItemRegistration::server_id)
+    }
+    
+    /// 记录失败的读写，供诊断报告统计
+    fn record_failure(&self) {
+        if let Some(registration) = &self.registration {
+            let stats = registration.stats();
+            stats.io_failures.set(stats.io_failures.get() + 1);
+        }
+    }
+    
+    /// 记录成功的写入，供所属组识别回声；缓存的读取结果随之失效
+    fn record_write(&self, value: &OpcValue) {
+        self.invalidate_cache();
+        if let Some(writes) = &self.writes {
+            writes.record(&self.name, value);
         }
     }
     
@@ -87,7 +215,7 @@ impl OpcItem {
     /// - `Ok((value, quality, timestamp))`: 成功读取值、质量和时间戳
     ///   - `value`: 项的值，类型为 `OpcValue`
     ///   - `quality`: 值的质量，类型为 `OpcQuality`
-    ///   - `timestamp`: 时间戳，Unix毫秒，类型为 `u64`
+    ///   - `timestamp`: 时间戳，类型为 `OpcTimestamp`
     /// - `Err(OpcError)`: 读取失败，可能的原因包括：
     ///   - 项不可读
     ///   - 服务器连接中断
@@ -105,7 +233,7 @@ impl OpcItem {
     /// 
     /// match item.read_sync() {
     ///     Ok((value, quality, timestamp)) => {
-    ///         println!("读取成功: 值 = {:?}, 质量 = {:?}, 时间戳 = {} ms", value, quality, timestamp);
+    ///         println!("读取成功: 值 = {:?}, 质量 = {:?}, 时间戳 = {}", value, quality, timestamp);
     ///         // 可以将值转换为具体类型
     ///         if let Ok(int_value) = i32::try_from(value) {
     ///             println!("整数值: {}", int_value);
@@ -119,7 +247,13 @@ impl OpcItem {
     /// - 这是阻塞操作，在慢速网络上可能会有延迟
     /// - 返回的值需要根据类型进行转换
     /// - 质量指示数据的可靠性
-    pub fn read_sync(&self) -> OpcResult<(OpcValue, OpcQuality, u64)> {
+    pub fn read_sync(&self) -> OpcResult<(OpcValue, OpcQuality, OpcTimestamp)> {
+        self.last_read.refresh(|| self.read_uncached())
+    }
+    
+    /// 同步读取项值，不更新缓存
+    fn read_uncached(&self) -> OpcResult<Reading> {
+        crate::reentrancy::check("OpcItem::read_sync", self.server_id())?;
         // 创建临时缓冲区存储值（64字节足够大多数类型）
         let mut temp_buffer: [u8; 64] = [0; 64];
         let mut quality: i32 = 0;
@@ -153,12 +287,88 @@ impl OpcItem {
             // 我们需要在转换后释放它
             Self::free_allocated_string_memory(&mut temp_buffer, value_type);
             
-            Ok((opc_value, opc_quality, timestamp_ms))
+            Ok((opc_value, opc_quality, OpcTimestamp::from_unix_ms(timestamp_ms)))
         } else {
+            self.record_failure();
             Err(OpcError::operation_failed("Failed to read item synchronously"))
         }
     }
     
+    /// 读取项值，优先使用有效期内的缓存结果
+    /// 
+    /// 最近一次成功的同步读取（`read_sync`、`read_cached` 或组的同名方法）
+    /// 距今不超过 `ttl` 时直接返回其结果，不访问服务器；否则执行一次同步读取
+    /// 并更新缓存。读取失败时不更新缓存，也不返回过期的结果。
+    /// 成功写入项后缓存立即失效。
+    /// 
+    /// # 参数
+    /// - `ttl`: 缓存结果的有效期，`Duration::ZERO` 表示总是读取
+    /// 
+    /// # 示例
+    /// ```ignore
+    /// use std::time::Duration;
+    /// 
+    /// // 一秒内的重复请求共享同一次读取
+    /// let (value, quality, timestamp) = item.read_cached(Duration::from_secs(1))?;
+    /// ```
+    pub fn read_cached(&self, ttl: Duration) -> OpcResult<(OpcValue, OpcQuality, OpcTimestamp)> {
+        self.last_read.read(ttl, || self.read_uncached())
+    }
+    
+    /// 丢弃缓存的读取结果，下一次 `read_cached` 总是访问服务器
+    pub fn invalidate_cache(&self) {
+        self.last_read.clear();
+    }
+    
+    /// 附加或移除线性缩放，供 `read_scaled` 和 `write_scaled` 使用
+    /// 
+    /// # 返回值
+    /// - `Ok(())`: 设置成功
+    /// - `Err(OpcError::InvalidParameters)`: 缩放的端点无效，见 `Scaling::validate`
+    pub fn set_scaling(&self, scaling: Option<Scaling>) -> OpcResult<()> {
+        if let Some(scaling) = &scaling {
+            scaling.validate()?;
+        }
+        self.scaling.set(scaling);
+        Ok(())
+    }
+    
+    /// 附加的线性缩放
+    pub fn scaling(&self) -> Option<Scaling> {
+        self.scaling.get()
+    }
+    
+    /// 同步读取项值并转换为工程单位
+    /// 
+    /// # 返回值
+    /// - `Ok((eu, quality, timestamp))`: 工程值、质量和时间戳
+    /// - `Err(OpcError::InvalidParameters)`: 没有附加缩放
+    /// - `Err(OpcError::ValueConversionError)`: 值不是数值
+    pub fn read_scaled(&self) -> OpcResult<(f64, OpcQuality, OpcTimestamp)> {
+        let scaling = self.require_scaling()?;
+        to_eu(&scaling, self.read_sync()?)
+    }
+    
+    /// 把工程值转换为原始值后同步写入
+    /// 
+    /// 缓存中有最近一次读取的值时，原始值按该值的类型转换（整数按
+    /// `CoercionPolicy::Round` 舍入），否则以 `Double` 写入，由服务器转换为项的类型。
+    /// 
+    /// # 返回值
+    /// - `Ok(())`: 写入成功
+    /// - `Err(OpcError::InvalidParameters)`: 没有附加缩放
+    /// - `Err(OpcError::ValueConversionError)`: 原始值超出项的类型范围
+    pub fn write_scaled(&self, eu: f64) -> OpcResult<()> {
+        let scaling = self.require_scaling()?;
+        self.write_sync(&to_raw(&scaling, eu, self.last_read.last_value())?)
+    }
+    
+    fn require_scaling(&self) -> OpcResult<Scaling> {
+        self.scaling.get().ok_or_else(|| {
+            OpcError::invalid_parameters(format!("No scaling attached to item {}", self.name))
+        })
+    }
+    
     fn free_allocated_string_memory(temp_buffer: &mut [u8; 64], value_type: u32) {
         const VT_BSTR: u32 = 8;
         const VT_LPSTR: u32 = 30;
@@ -191,9 +401,13 @@ impl OpcItem {
     
     /// Write item value synchronously
     pub fn write_sync(&self, value: &OpcValue) -> OpcResult<()> {
+        crate::reentrancy::check("OpcItem::write_sync", self.server_id())?;
         // Temporary holders for string data to keep them alive during FFI call
         let mut _wide_holder: Option<Vec<u16>> = None;
         let mut _ansi_holder: Option<std::ffi::CString> = None;
+        let mut _decimal_holder: Option<crate::types::RawDecimal> = None;
+        #[cfg(windows)]
+        let mut _array_holder: Option<crate::types::OwnedSafeArray> = None;
         let (value_ptr, value_type) = match value {
             // Numeric types
             OpcValue::Int8(v) => (v as *const i8 as *const std::ffi::c_void, value.raw_type()),
@@ -220,17 +434,30 @@ impl OpcItem {
                 let ptr_ptr: *const *const u16 = &ptr;
                 (ptr_ptr as *const std::ffi::c_void, value.raw_type())
             }
-            // Decimal type - not implemented
-            OpcValue::Decimal(_) => {
-                return Err(OpcError::operation_failed("Decimal writes not implemented"));
+            // Decimal type - pass a Windows DECIMAL structure
+            OpcValue::Decimal(d) => {
+                let raw = _decimal_holder.insert(d.to_raw());
+                (raw as *const crate::types::RawDecimal as *const std::ffi::c_void, value.raw_type())
             }
             
-            // Array types - not implemented
+            // Array types - build a SAFEARRAY that lives until the call returns
             OpcValue::ArrayInt16(_) | OpcValue::ArrayUInt16(_) | OpcValue::ArrayInt32(_) |
             OpcValue::ArrayUInt32(_) | OpcValue::ArrayInt64(_) | OpcValue::ArrayUInt64(_) |
             OpcValue::ArrayFloat(_) | OpcValue::ArrayDouble(_) | OpcValue::ArrayBool(_) |
             OpcValue::ArrayString(_) => {
-                return Err(OpcError::operation_failed("Array writes not implemented"));
+                #[cfg(windows)]
+                {
+                    let array = crate::types::OwnedSafeArray::from_value(value)?
+                        .expect("array variants always produce a SAFEARRAY");
+                    (_array_holder.insert(array).value_ptr(), value.raw_type())
+                }
+                #[cfg(not(windows))]
+                {
+                    return Err(OpcError::UnsupportedPlatform(format!(
+                        "Array writes are only supported on Windows platforms (current: {})",
+                        std::env::consts::OS
+                    )));
+                }
             }
         };
         
@@ -239,14 +466,19 @@ impl OpcItem {
         };
         
         if result == 0 {
+            // 写入成功说明项可写；失败的原因未知，不缓存
+            self.write_access.set(Some(true));
+            self.record_write(value);
             Ok(())
         } else {
+            self.record_failure();
             Err(OpcError::operation_failed("Failed to write item synchronously"))
         }
     }
     
     /// Read item value asynchronously
     pub fn read_async(&self) -> OpcResult<()> {
+        crate::reentrancy::check("OpcItem::read_async", self.server_id())?;
         let result = unsafe {
             crate::ffi::opc_item_read_async(self.ptr)
         };
@@ -254,15 +486,20 @@ impl OpcItem {
         if result == 0 {
             Ok(())
         } else {
+            self.record_failure();
             Err(OpcError::operation_failed("Failed to read item asynchronously"))
         }
     }
     
     /// Write item value asynchronously
     pub fn write_async(&self, value: &OpcValue) -> OpcResult<()> {
+        crate::reentrancy::check("OpcItem::write_async", self.server_id())?;
         // Temporary holders for string data to keep them alive during FFI call
         let mut _wide_holder: Option<Vec<u16>> = None;
         let mut _ansi_holder: Option<std::ffi::CString> = None;
+        let mut _decimal_holder: Option<crate::types::RawDecimal> = None;
+        #[cfg(windows)]
+        let mut _array_holder: Option<crate::types::OwnedSafeArray> = None;
         let (value_ptr, value_type) = match value {
             // Numeric types
             OpcValue::Int8(v) => (v as *const i8 as *const std::ffi::c_void, value.raw_type()),
@@ -290,17 +527,30 @@ impl OpcItem {
                 (ptr_ptr as *const std::ffi::c_void, value.raw_type())
             }
         
-            // Decimal type - not implemented
-            OpcValue::Decimal(_) => {
-                return Err(OpcError::operation_failed("Decimal writes not implemented"));
+            // Decimal type - pass a Windows DECIMAL structure
+            OpcValue::Decimal(d) => {
+                let raw = _decimal_holder.insert(d.to_raw());
+                (raw as *const crate::types::RawDecimal as *const std::ffi::c_void, value.raw_type())
             }
             
-            // Array types - not implemented
+            // Array types - build a SAFEARRAY that lives until the call returns
             OpcValue::ArrayInt16(_) | OpcValue::ArrayUInt16(_) | OpcValue::ArrayInt32(_) |
             OpcValue::ArrayUInt32(_) | OpcValue::ArrayInt64(_) | OpcValue::ArrayUInt64(_) |
             OpcValue::ArrayFloat(_) | OpcValue::ArrayDouble(_) | OpcValue::ArrayBool(_) |
             OpcValue::ArrayString(_) => {
-                return Err(OpcError::operation_failed("Array writes not implemented"));
+                #[cfg(windows)]
+                {
+                    let array = crate::types::OwnedSafeArray::from_value(value)?
+                        .expect("array variants always produce a SAFEARRAY");
+                    (_array_holder.insert(array).value_ptr(), value.raw_type())
+                }
+                #[cfg(not(windows))]
+                {
+                    return Err(OpcError::UnsupportedPlatform(format!(
+                        "Array writes are only supported on Windows platforms (current: {})",
+                        std::env::consts::OS
+                    )));
+                }
             }
         };
         
@@ -309,12 +559,60 @@ impl OpcItem {
         };
         
         if result == 0 {
+            self.record_write(value);
             Ok(())
         } else {
+            self.record_failure();
             Err(OpcError::operation_failed("Failed to write item asynchronously"))
         }
     }
     
+    /// 检查项是否可写
+    /// 
+    /// 返回缓存的写权限信息；没有缓存信息时，根据 `probe` 决定是否探测。
+    /// 任何一次成功的 `write_sync`（包括写回探测）都会缓存为可写，之后的调用不再访问服务器。
+    /// 工具库不报告写入失败的原因，无法区分权限不足和通信故障等暂时的错误，
+    /// 因此失败不缓存，下一次调用重新探测。
+    /// 
+    /// # 参数
+    /// - `probe`: 探测策略
+    ///   - `WriteProbe::None`: 不探测，没有缓存信息时返回 `Ok(None)`
+    ///   - `WriteProbe::WriteBack`: 读取当前值并原样写回
+    /// 
+    /// # 返回值
+    /// - `Ok(Some(true))`: 项可写
+    /// - `Ok(Some(false))`: 服务器拒绝了写回
+    /// - `Ok(None)`: 写权限未知（未探测，或当前值的类型无法写回）
+    /// - `Err(OpcError)`: 探测时读取当前值失败，或写回在调用服务器之前失败（例如在回调中重入调用）
+    /// 
+    /// # 注意
+    /// - 写回探测会对设备产生一次真实写入，对于触发动作的项（如命令、脉冲）请勿使用
+    /// - 值质量不为 Good 时不进行写回，返回 `Ok(None)`
+    pub fn can_write(&self, probe: WriteProbe) -> OpcResult<Option<bool>> {
+        if let Some(writable) = self.write_access.get() {
+            return Ok(Some(writable));
+        }
+        
+        match probe {
+            WriteProbe::None => Ok(None),
+            WriteProbe::WriteBack => {
+                let (value, quality, _) = self.read_sync()?;
+                
+                // 不写回不可靠的值
+                if quality != OpcQuality::Good {
+                    return Ok(None);
+                }
+                
+                match self.write_sync(&value) {
+                    Ok(()) => Ok(Some(true)),
+                    // 服务器返回失败，原因未知，不缓存
+                    Err(OpcError::OperationFailed(_)) => Ok(Some(false)),
+                    Err(e) => Err(e),
+                }
+            }
+        }
+    }
+    
     /// Get the raw item pointer (for internal use)
     pub(crate) fn as_ptr(&self) -> *mut std::ffi::c_void {
         self.ptr
@@ -327,4 +625,80 @@ impl Drop for OpcItem {
             crate::ffi::opc_item_free(self.ptr);
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{DaGroup, DaItem, DaServer};
+    use crate::sim::SimServer;
+    use crate::types::ServerState;
+
+    const HOUR: Duration = Duration::from_secs(3600);
+
+    fn value_of(reading: OpcResult<Reading>) -> OpcValue {
+        reading.unwrap().0
+    }
+
+    #[test]
+    fn test_read_cache_serves_fresh_results_until_invalidated() {
+        let server = SimServer::new().with_tag("FI101.PV", OpcValue::Double(1.0));
+        let group = server.create_group("Cache", true, 1000, 0.0).unwrap();
+        let item = group.add_item("FI101.PV").unwrap();
+        let cache = ReadCache::default();
+
+        assert_eq!(value_of(cache.read(HOUR, || item.read_sync())), OpcValue::Double(1.0));
+        server.set_value("FI101.PV", OpcValue::Double(2.0), OpcQuality::Good).unwrap();
+        // 有效期内不访问服务器
+        assert_eq!(value_of(cache.read(HOUR, || item.read_sync())), OpcValue::Double(1.0));
+        // 有效期为零时总是读取
+        assert_eq!(value_of(cache.read(Duration::ZERO, || item.read_sync())), OpcValue::Double(2.0));
+
+        server.set_value("FI101.PV", OpcValue::Double(3.0), OpcQuality::Good).unwrap();
+        cache.clear();
+        assert_eq!(value_of(cache.read(HOUR, || item.read_sync())), OpcValue::Double(3.0));
+    }
+
+    #[test]
+    fn test_read_cache_does_not_store_or_return_stale_results_on_failure() {
+        let server = SimServer::new().with_tag("FI101.PV", OpcValue::Double(1.0));
+        let group = server.create_group("Cache", true, 1000, 0.0).unwrap();
+        let item = group.add_item("FI101.PV").unwrap();
+        let cache = ReadCache::default();
+        cache.refresh(|| item.read_sync()).unwrap();
+
+        server.set_state(ServerState::CommFault);
+        assert!(cache.read(Duration::ZERO, || item.read_sync()).is_err());
+        assert_eq!(cache.last_value(), Some(OpcValue::Double(1.0)));
+
+        cache.clear();
+        assert!(cache.read(HOUR, || item.read_sync()).is_err());
+        assert_eq!(cache.last_value(), None);
+    }
+
+    #[test]
+    fn test_scaled_read_and_write_round_trip() {
+        let scaling = Scaling::new(0.0, 27648.0, 0.0, 150.0);
+        let server = SimServer::new()
+            .with_tag("TT101.Raw", OpcValue::Int16(13824))
+            .with_tag("Status", OpcValue::String("OK".to_string()));
+        let group = server.create_group("Scaled", true, 1000, 0.0).unwrap();
+        let item = group.add_item("TT101.Raw").unwrap();
+        let cache = ReadCache::default();
+
+        let (eu, quality, _) = to_eu(&scaling, cache.refresh(|| item.read_sync()).unwrap()).unwrap();
+        assert_eq!((eu, quality), (75.0, OpcQuality::Good));
+
+        // 最近一次读取的值作为模板，原始值按 Int16 舍入
+        let raw = to_raw(&scaling, 100.0, cache.last_value()).unwrap();
+        assert_eq!(raw, OpcValue::Int16(18432));
+        item.write_sync(&raw).unwrap();
+        assert_eq!(server.value("TT101.Raw").unwrap().0, OpcValue::Int16(18432));
+
+        // 没有模板时以 Double 写入，超出模板类型范围时报错
+        assert_eq!(to_raw(&scaling, 75.0, None).unwrap(), OpcValue::Double(13824.0));
+        assert!(to_raw(&scaling, 1000.0, cache.last_value()).is_err());
+
+        let status = group.add_item("Status").unwrap();
+        assert!(matches!(to_eu(&scaling, status.read_sync().unwrap()), Err(OpcError::ValueConversionError(_))));
+    }
+}
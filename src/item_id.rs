@@ -0,0 +1,211 @@
+//! 项 ID 规范化与校验模块
+//!
+//! 不同 OPC 服务器对项 ID 的约定不同：分隔符可能是 `.`、`/` 或 `\`，
+//! 有的服务器区分大小写，有的不区分。配置文件中细微的大小写或分隔符差异
+//! 是最常见的配置错误之一。
+//!
+//! 这个模块提供按服务器约定校验和规范化项 ID 的工具函数。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::ItemIdRules;
+//!
+//! let rules = ItemIdRules::default();
+//! assert_eq!(rules.normalize(" Random . Int2 ")?, "Random.Int2");
+//! assert!(rules.validate("Random..Int2").is_err());
+//! ```
+
+use crate::error::{OpcError, OpcResult};
+
+/// 常见的项 ID 分隔符
+pub const COMMON_SEPARATORS: [char; 4] = ['.', '/', '\\', ':'];
+
+/// 项 ID 约定
+///
+/// 描述某个服务器的项 ID 格式：分隔符、是否区分大小写以及最大长度。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemIdRules {
+    /// 层级分隔符
+    pub separator: char,
+    /// 是否区分大小写
+    pub case_sensitive: bool,
+    /// 最大长度（字符数），`None` 表示不限制
+    pub max_length: Option<usize>,
+}
+
+impl Default for ItemIdRules {
+    fn default() -> Self {
+        ItemIdRules {
+            separator: '.',
+            case_sensitive: true,
+            max_length: None,
+        }
+    }
+}
+
+impl ItemIdRules {
+    /// 根据项名列表推断约定
+    ///
+    /// 选择在项名中出现最多的常见分隔符，没有任何分隔符时使用 `.`。
+    /// 大小写敏感性无法从项名推断，保持为区分大小写。
+    pub fn infer<S: AsRef<str>>(item_names: &[S]) -> Self {
+        let separator = COMMON_SEPARATORS
+            .iter()
+            .map(|&sep| {
+                let count: usize = item_names
+                    .iter()
+                    .map(|name| name.as_ref().matches(sep).count())
+                    .sum();
+                (sep, count)
+            })
+            .filter(|&(_, count)| count > 0)
+            .max_by_key(|&(_, count)| count)
+            .map(|(sep, _)| sep)
+            .unwrap_or('.');
+
+        ItemIdRules {
+            separator,
+            ..Default::default()
+        }
+    }
+
+    /// 校验项 ID
+    ///
+    /// # 校验规则
+    /// - 不能为空或只包含空白字符
+    /// - 不能包含控制字符
+    /// - 不能有空的层级（以分隔符开头或结尾，或连续的分隔符）
+    /// - 不能超过最大长度
+    ///
+    /// # 返回值
+    /// - `Ok(())`: 项 ID 有效
+    /// - `Err(OpcError::InvalidParameters)`: 项 ID 无效，错误消息说明原因
+    pub fn validate(&self, item_id: &str) -> OpcResult<()> {
+        if item_id.trim().is_empty() {
+            return Err(OpcError::invalid_parameters("Item ID is empty"));
+        }
+
+        if let Some(c) = item_id.chars().find(|c| c.is_control()) {
+            return Err(OpcError::invalid_parameters(format!(
+                "Item ID '{}' contains control character U+{:04X}",
+                item_id.escape_debug(),
+                c as u32
+            )));
+        }
+
+        if let Some(max_length) = self.max_length {
+            let length = item_id.chars().count();
+            if length > max_length {
+                return Err(OpcError::invalid_parameters(format!(
+                    "Item ID '{}' is {} characters long (max {})",
+                    item_id, length, max_length
+                )));
+            }
+        }
+
+        if item_id
+            .split(self.separator)
+            .any(|segment| segment.trim().is_empty())
+        {
+            return Err(OpcError::invalid_parameters(format!(
+                "Item ID '{}' has an empty segment (separator '{}')",
+                item_id, self.separator
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// 规范化项 ID
+    ///
+    /// 去除整体以及每个层级首尾的空白字符，然后校验结果。
+    /// 不改变大小写，因为服务器可能区分大小写。
+    pub fn normalize(&self, item_id: &str) -> OpcResult<String> {
+        let separator = self.separator.to_string();
+        let normalized = item_id
+            .trim()
+            .split(self.separator)
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join(&separator);
+        self.validate(&normalized)?;
+        Ok(normalized)
+    }
+
+    /// 用于比较和查找的键
+    ///
+    /// 不区分大小写的服务器返回小写形式，否则返回去除首尾空白的原始形式。
+    pub fn comparison_key(&self, item_id: &str) -> String {
+        let trimmed = item_id.trim();
+        if self.case_sensitive {
+            trimmed.to_string()
+        } else {
+            trimmed.to_lowercase()
+        }
+    }
+
+    /// 按此约定判断两个项 ID 是否指向同一个项
+    pub fn equivalent(&self, a: &str, b: &str) -> bool {
+        match (self.normalize(a), self.normalize(b)) {
+            (Ok(a), Ok(b)) => self.comparison_key(&a) == self.comparison_key(&b),
+            _ => false,
+        }
+    }
+
+    /// 在项名列表中查找与给定项 ID 等价的项名
+    ///
+    /// 用于将配置中的项 ID 与服务器浏览结果对应，返回服务器使用的写法。
+    pub fn find_match<'a, S: AsRef<str>>(&self, item_id: &str, item_names: &'a [S]) -> Option<&'a str> {
+        item_names
+            .iter()
+            .map(|name| name.as_ref())
+            .find(|name| self.equivalent(item_id, name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_item_id() {
+        let rules = ItemIdRules::default();
+        assert!(rules.validate("Random.Int2").is_ok());
+        assert!(rules.validate("").is_err());
+        assert!(rules.validate("   ").is_err());
+        assert!(rules.validate("Random..Int2").is_err());
+        assert!(rules.validate(".Random").is_err());
+        assert!(rules.validate("Random\tInt2").is_err());
+
+        let limited = ItemIdRules {
+            max_length: Some(5),
+            ..Default::default()
+        };
+        assert!(limited.validate("Random.Int2").is_err());
+    }
+
+    #[test]
+    fn test_normalize_and_compare() {
+        let rules = ItemIdRules::default();
+        assert_eq!(rules.normalize("  Random . Int2 ").unwrap(), "Random.Int2");
+        assert!(!rules.equivalent("random.int2", "Random.Int2"));
+
+        let insensitive = ItemIdRules {
+            case_sensitive: false,
+            ..Default::default()
+        };
+        assert!(insensitive.equivalent("random.int2", " Random . Int2"));
+
+        let names = ["Random.Int2", "Random.Real4"];
+        assert_eq!(insensitive.find_match("RANDOM.REAL4", &names), Some("Random.Real4"));
+        assert_eq!(rules.find_match("RANDOM.REAL4", &names), None);
+    }
+
+    #[test]
+    fn test_infer_separator() {
+        assert_eq!(ItemIdRules::infer(&["Channel1/Device1/Tag1", "Channel1/Device1/Tag2"]).separator, '/');
+        assert_eq!(ItemIdRules::infer(&["Random.Int2"]).separator, '.');
+        assert_eq!(ItemIdRules::infer::<&str>(&[]).separator, '.');
+    }
+}
@@ -0,0 +1,373 @@
+//! 变化日志模块
+//!
+//! 这个模块为订阅的数据变化分配单调递增的序号，并在保留窗口内保存最近的变化，
+//! 使下游消费者（例如写入数据库的转发进程）重新连接后可以从上次确认的序号继续，
+//! 而不是只能依赖瞬时的回调。
+//!
+//! ## 序号与确认
+//!
+//! - 每个数据变化获得一个序号，从 1 开始，永不重复使用
+//! - 消费者处理完成后调用 `acknowledge`，已确认的变化从日志中移除
+//! - 重新连接的消费者调用 `entries_after(acknowledged, max)` 继续读取
+//! - 保留窗口满时丢弃最旧的未确认变化；请求的序号已被丢弃时返回 `JournalGap`，
+//!   由消费者决定如何补齐（例如对所有项执行一次同步读取）
+//!
+//! ## 持久化
+//!
+//! `ChangeJournal` 实现了 `Checkpoint`，检查点只保存序号的预留上限和已确认的序号，
+//! 不保存变化本身。重启前未确认的变化已经丢失，消费者按 `JournalGap` 处理。
+//!
+//! 每次保存检查点时预留一块序号（`with_sequence_block`，默认 `DEFAULT_SEQUENCE_BLOCK` 个），
+//! 检查点中记录的是这块序号的上限。重启后序号从上次保存的上限开始，
+//! 因此不会与重启前分配过的序号重复。预留的序号用掉一半时 `checkpoint_due` 返回 `true`，
+//! `Checkpointer::maybe_save` 随即保存并预留下一块；只要两次 `maybe_save` 之间的变化数
+//! 不超过半块，已分配的序号就总是在磁盘上的上限之内。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::{ChangeJournal, Checkpointer};
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! let journal = Arc::new(ChangeJournal::new(100_000));
+//! let mut checkpointer = Checkpointer::new("journal.ckpt", Duration::from_secs(10));
+//! checkpointer.restore(&*journal)?;
+//!
+//! group.enable_async_subscription(journal.clone())?;
+//!
+//! // 转发循环
+//! loop {
+//!     match journal.entries_after(journal.acknowledged(), 500) {
+//!         Ok(entries) => {
+//!             if let Some(last) = entries.last() {
+//!                 store.insert(&entries)?;
+//!                 journal.acknowledge(last.sequence);
+//!             }
+//!         }
+//!         Err(gap) => {
+//!             // 补齐丢失的变化后，确认到仍然保留的第一个序号之前，从那里继续读取
+//!             resync(gap)?;
+//!             journal.acknowledge(gap.next_available - 1);
+//!         }
+//!     }
+//!     checkpointer.maybe_save(&*journal)?;
+//! }
+//! ```
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use crate::error::{OpcError, OpcResult};
+use crate::persist::Checkpoint;
+use crate::types::{lock_or_recover, OpcDataCallback, OpcQuality, OpcTimestamp, OpcValue};
+
+/// 检查点文件头
+const CHECKPOINT_HEADER: &str = "# opcda change journal v1";
+
+/// 每次保存检查点时默认预留的序号数
+pub const DEFAULT_SEQUENCE_BLOCK: u64 = 100_000;
+
+/// 日志中的一个数据变化
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    /// 序号
+    pub sequence: u64,
+    /// 组名
+    pub group_name: String,
+    /// 项名
+    pub item_name: String,
+    /// 值
+    pub value: OpcValue,
+    /// 质量
+    pub quality: OpcQuality,
+    /// 时间戳
+    pub timestamp: OpcTimestamp,
+}
+
+/// 请求的变化已不在保留窗口内
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("journal entries {first_missing}..{next_available} are no longer retained")]
+pub struct JournalGap {
+    /// 丢失的第一个序号
+    pub first_missing: u64,
+    /// 仍然可以读取的第一个序号
+    pub next_available: u64,
+}
+
+#[derive(Debug)]
+struct JournalState {
+    entries: VecDeque<JournalEntry>,
+    next_sequence: u64,
+    acknowledged: u64,
+    /// 已写入磁盘的序号上限，小于它的序号重启后不会再分配
+    reserved: u64,
+    /// 最近一次 `checkpoint` 预留的上限，写入磁盘后成为 `reserved`
+    pending_reserved: u64,
+}
+
+/// 数据变化日志
+///
+/// 作为订阅回调使用，可以同时注册到多个组。
+#[derive(Debug)]
+pub struct ChangeJournal {
+    capacity: usize,
+    sequence_block: u64,
+    state: Mutex<JournalState>,
+}
+
+impl ChangeJournal {
+    /// 创建日志，最多保留 `capacity` 个未确认的变化
+    pub fn new(capacity: usize) -> Self {
+        ChangeJournal {
+            capacity: capacity.max(1),
+            sequence_block: DEFAULT_SEQUENCE_BLOCK,
+            state: Mutex::new(JournalState {
+                entries: VecDeque::new(),
+                next_sequence: 1,
+                acknowledged: 0,
+                reserved: 0,
+                pending_reserved: 0,
+            }),
+        }
+    }
+
+    /// 设置每次保存检查点时预留的序号数，最小为 2
+    pub fn with_sequence_block(mut self, block: u64) -> Self {
+        self.sequence_block = block.max(2);
+        self
+    }
+
+    /// 记录一个数据变化，返回分配的序号
+    pub fn record(&self, group_name: &str, item_name: &str, value: OpcValue, quality: OpcQuality, timestamp: u64) -> u64 {
+        let mut state = lock_or_recover(&self.state);
+        let sequence = state.next_sequence;
+        state.next_sequence += 1;
+        if state.entries.len() == self.capacity {
+            state.entries.pop_front();
+        }
+        state.entries.push_back(JournalEntry {
+            sequence,
+            group_name: group_name.to_string(),
+            item_name: item_name.to_string(),
+            value,
+            quality,
+            timestamp: OpcTimestamp::from_unix_ms(timestamp),
+        });
+        sequence
+    }
+
+    /// 读取序号大于 `after` 的变化，最多 `max` 个，按序号排列
+    ///
+    /// # 返回值
+    /// - `Ok(entries)`: 没有新变化时为空
+    /// - `Err(JournalGap)`: 紧接 `after` 的未确认变化已被丢弃
+    pub fn entries_after(&self, after: u64, max: usize) -> Result<Vec<JournalEntry>, JournalGap> {
+        let state = lock_or_recover(&self.state);
+        let next_available = state
+            .entries
+            .front()
+            .map_or(state.next_sequence, |entry| entry.sequence);
+        // 已确认的变化被主动移除，不算丢失
+        let first_wanted = after.max(state.acknowledged).saturating_add(1);
+        if first_wanted < next_available {
+            return Err(JournalGap {
+                first_missing: first_wanted,
+                next_available,
+            });
+        }
+        Ok(state
+            .entries
+            .iter()
+            .filter(|entry| entry.sequence > after)
+            .take(max)
+            .cloned()
+            .collect())
+    }
+
+    /// 确认 `sequence` 及之前的变化已处理，并从日志中移除
+    ///
+    /// 确认的序号只会前进，较小的序号被忽略。
+    pub fn acknowledge(&self, sequence: u64) {
+        let mut state = lock_or_recover(&self.state);
+        if sequence <= state.acknowledged {
+            return;
+        }
+        state.acknowledged = sequence;
+        while state.entries.front().is_some_and(|entry| entry.sequence <= sequence) {
+            state.entries.pop_front();
+        }
+    }
+
+    /// 最后确认的序号，没有确认过时为 0
+    pub fn acknowledged(&self) -> u64 {
+        lock_or_recover(&self.state).acknowledged
+    }
+
+    /// 下一个变化将获得的序号
+    pub fn next_sequence(&self) -> u64 {
+        lock_or_recover(&self.state).next_sequence
+    }
+
+    /// 日志中保留的变化数
+    pub fn len(&self) -> usize {
+        lock_or_recover(&self.state).entries.len()
+    }
+
+    /// 日志中是否没有保留的变化
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl OpcDataCallback for ChangeJournal {
+    fn on_data_change(&self, group_name: &str, item_name: &str, value: OpcValue, quality: OpcQuality, timestamp: u64) {
+        self.record(group_name, item_name, value, quality, timestamp);
+    }
+}
+
+/// 检查点格式：文件头之后为 `next=预留的序号上限` 和 `acknowledged=已确认序号` 两行。
+///
+/// 恢复时下一个序号从预留的上限开始，日志中保留的变化被清空。
+impl Checkpoint for ChangeJournal {
+    fn checkpoint(&self) -> String {
+        let mut state = lock_or_recover(&self.state);
+        let limit = state.next_sequence.saturating_add(self.sequence_block);
+        state.pending_reserved = state.pending_reserved.max(limit);
+        format!("{}\nnext={}\nacknowledged={}\n", CHECKPOINT_HEADER, limit, state.acknowledged)
+    }
+
+    fn restore(&self, data: &str) -> OpcResult<usize> {
+        let mut lines = data.lines();
+        if lines.next() != Some(CHECKPOINT_HEADER) {
+            return Err(OpcError::invalid_parameters("Unrecognized change journal checkpoint"));
+        }
+
+        let mut next = None;
+        let mut acknowledged = None;
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| OpcError::invalid_parameters(format!("Malformed journal checkpoint line '{}'", line)))?;
+            let value: u64 = value
+                .trim()
+                .parse()
+                .map_err(|_| OpcError::invalid_parameters(format!("Invalid journal checkpoint value '{}'", line)))?;
+            match key.trim() {
+                "next" => next = Some(value),
+                "acknowledged" => acknowledged = Some(value),
+                _ => return Err(OpcError::invalid_parameters(format!("Unknown journal checkpoint key '{}'", key))),
+            }
+        }
+        let (Some(next), Some(acknowledged)) = (next, acknowledged) else {
+            return Err(OpcError::invalid_parameters("Incomplete change journal checkpoint"));
+        };
+
+        let mut state = lock_or_recover(&self.state);
+        state.entries.clear();
+        state.next_sequence = state.next_sequence.max(next);
+        state.acknowledged = state.acknowledged.max(acknowledged);
+        // 上限已经用完，下一次 maybe_save 立即预留新的一块
+        state.reserved = state.reserved.max(state.next_sequence);
+        Ok(1)
+    }
+
+    /// 预留的序号用掉一半时需要保存
+    fn checkpoint_due(&self) -> bool {
+        let state = lock_or_recover(&self.state);
+        state.next_sequence.saturating_add(self.sequence_block / 2) > state.reserved
+    }
+
+    fn checkpoint_saved(&self) {
+        let mut state = lock_or_recover(&self.state);
+        state.reserved = state.reserved.max(state.pending_reserved);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persist::Checkpointer;
+    use std::time::Duration;
+
+    fn record(journal: &ChangeJournal, n: i32) -> u64 {
+        journal.record("G", "A", OpcValue::Int32(n), OpcQuality::Good, n as u64)
+    }
+
+    #[test]
+    fn test_journal_resume_and_gap() {
+        let journal = ChangeJournal::new(3);
+        for n in 1..=3 {
+            assert_eq!(record(&journal, n), n as u64);
+        }
+
+        let entries = journal.entries_after(0, 2).unwrap();
+        assert_eq!(entries.iter().map(|e| e.sequence).collect::<Vec<_>>(), vec![1, 2]);
+        journal.acknowledge(2);
+        assert_eq!(journal.len(), 1);
+        assert!(journal.entries_after(journal.acknowledged(), 10).unwrap().len() == 1);
+
+        // 超过保留窗口后最旧的未确认变化被丢弃
+        for n in 4..=6 {
+            record(&journal, n);
+        }
+        assert_eq!(
+            journal.entries_after(2, 10).unwrap_err(),
+            JournalGap { first_missing: 3, next_available: 4 }
+        );
+        assert_eq!(journal.entries_after(3, 10).unwrap().len(), 3);
+        assert!(journal.entries_after(6, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_journal_checkpoint() {
+        let journal = ChangeJournal::new(10);
+        for n in 1..=5 {
+            record(&journal, n);
+        }
+        journal.acknowledge(4);
+        let saved = journal.checkpoint();
+        assert_eq!(saved, "# opcda change journal v1\nnext=100006\nacknowledged=4\n");
+
+        let restarted = ChangeJournal::new(10);
+        restarted.restore(&saved).unwrap();
+        assert_eq!(restarted.acknowledged(), 4);
+        assert_eq!(restarted.next_sequence(), 6 + DEFAULT_SEQUENCE_BLOCK);
+        assert!(restarted.entries_after(4, 10).is_err());
+        assert!(restarted.checkpoint_due());
+        assert!(restarted.restore("next=1").is_err());
+        assert!(restarted.restore("# opcda change journal v2\nnext=1\nacknowledged=0\n").is_err());
+    }
+
+    #[test]
+    fn test_journal_sequence_block() {
+        let path = std::env::temp_dir().join(format!("opcda-journal-{}.ckpt", std::process::id()));
+        let journal = ChangeJournal::new(100).with_sequence_block(10);
+        let mut checkpointer = Checkpointer::new(&path, Duration::from_secs(3600));
+        assert!(journal.checkpoint_due());
+        assert!(checkpointer.maybe_save(&journal).unwrap());
+
+        // 上限为 11，用掉一半之前不需要保存
+        for n in 1..=5 {
+            record(&journal, n);
+        }
+        assert!(!journal.checkpoint_due());
+        assert!(!checkpointer.maybe_save(&journal).unwrap());
+        record(&journal, 6);
+        assert!(journal.checkpoint_due());
+        assert!(checkpointer.maybe_save(&journal).unwrap());
+        assert!(!journal.checkpoint_due());
+
+        // 上限为 17，未写入磁盘的预留不生效
+        for n in 7..=12 {
+            record(&journal, n);
+        }
+        journal.checkpoint();
+        assert!(journal.checkpoint_due());
+
+        let restarted = ChangeJournal::new(100);
+        checkpointer.restore(&restarted).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(restarted.next_sequence(), 17);
+        assert!(restarted.next_sequence() >= journal.next_sequence());
+    }
+}
@@ -0,0 +1,147 @@
+//! 资源泄漏检测模块
+//!
+//! 库在内部登记每个由客户端创建、由 COM 对象支撑的服务器连接、组和项。
+//! `OpcClient::leak_report` 列出其中仍然存在的对象，用于查找是哪个组或项
+//! 让服务器连接无法释放。
+//!
+//! ## `debug-leaks` 特性
+//!
+//! 启用 `debug-leaks` 特性后：
+//! - 每个对象在创建时记录调用栈，出现在报告中
+//! - `OpcClient` 释放时如果仍有存活的对象，将报告输出到标准错误
+//!
+//! 记录调用栈有明显的开销，只建议在调试时启用。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! let report = client.leak_report();
+//! if !report.is_empty() {
+//!     eprintln!("{}", report);
+//! }
+//! ```
+
+use std::fmt;
+
+/// 资源类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResourceKind {
+    /// 服务器连接
+    Server,
+    /// 组
+    Group,
+    /// 项
+    Item,
+}
+
+impl fmt::Display for ResourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourceKind::Server => write!(f, "server"),
+            ResourceKind::Group => write!(f, "group"),
+            ResourceKind::Item => write!(f, "item"),
+        }
+    }
+}
+
+/// 仍然存在的资源
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LiveResource {
+    /// 资源类型
+    pub kind: ResourceKind,
+    /// 名称（服务器为 `服务器名@主机名`）
+    pub name: String,
+    /// 所属的组或服务器，服务器本身为 `None`
+    pub owner: Option<String>,
+    /// 创建时的调用栈，仅在启用 `debug-leaks` 特性时记录
+    pub backtrace: Option<String>,
+}
+
+/// 存活资源报告
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LeakReport {
+    /// 仍然存在的资源，按服务器、组、项的层次排列
+    pub resources: Vec<LiveResource>,
+}
+
+impl LeakReport {
+    /// 没有存活的资源
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+    }
+
+    /// 指定类型的存活资源数量
+    pub fn count(&self, kind: ResourceKind) -> usize {
+        self.resources.iter().filter(|r| r.kind == kind).count()
+    }
+}
+
+impl fmt::Display for LeakReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "live OPC resources: {} server(s), {} group(s), {} item(s)",
+            self.count(ResourceKind::Server),
+            self.count(ResourceKind::Group),
+            self.count(ResourceKind::Item)
+        )?;
+        for resource in &self.resources {
+            match &resource.owner {
+                Some(owner) => writeln!(f, "  {} {} (in {})", resource.kind, resource.name, owner)?,
+                None => writeln!(f, "  {} {}", resource.kind, resource.name)?,
+            }
+            if let Some(backtrace) = &resource.backtrace {
+                for line in backtrace.lines() {
+                    writeln!(f, "      {}", line)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 记录创建时的调用栈（需要 `debug-leaks` 特性）
+#[cfg(feature = "debug-leaks")]
+pub(crate) fn capture_backtrace() -> Option<String> {
+    Some(std::backtrace::Backtrace::force_capture().to_string())
+}
+
+/// 记录创建时的调用栈（需要 `debug-leaks` 特性）
+#[cfg(not(feature = "debug-leaks"))]
+pub(crate) fn capture_backtrace() -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leak_report_display() {
+        let report = LeakReport {
+            resources: vec![
+                LiveResource {
+                    kind: ResourceKind::Group,
+                    name: "Plant".to_string(),
+                    owner: Some("Matrikon.OPC.Simulation.1@localhost".to_string()),
+                    backtrace: Some("0: main\n1: start".to_string()),
+                },
+                LiveResource {
+                    kind: ResourceKind::Item,
+                    name: "Random.Int2".to_string(),
+                    owner: Some("Plant".to_string()),
+                    backtrace: None,
+                },
+            ],
+        };
+        assert_eq!(report.count(ResourceKind::Item), 1);
+        assert_eq!(
+            report.to_string(),
+            "live OPC resources: 0 server(s), 1 group(s), 1 item(s)\n\
+             \x20 group Plant (in Matrikon.OPC.Simulation.1@localhost)\n\
+             \x20     0: main\n\
+             \x20     1: start\n\
+             \x20 item Random.Int2 (in Plant)\n"
+        );
+    }
+}
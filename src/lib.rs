@@ -95,6 +95,48 @@
 //! - `item.rs` - 项读写操作
 //! - `types.rs` - 核心数据类型和转换
 //! - `error.rs` - 错误类型和处理
+//! - `quirks.rs` - 厂商兼容性配置
+//! - `cache.rs` - 客户端缓存
+//! - `debounce.rs` - 布尔项的去抖过滤
+//! - `item_id.rs` - 项 ID 规范化与校验
+//! - `format.rs` - 值、质量和时间戳的格式化配置
+//! - `mirror.rs` - 服务器之间的项镜像
+//! - `compute.rs` - 时间加权平均值和累积量计算
+//! - `persist.rs` - 计算状态的检查点持久化
+//! - `scope.rs` - 按顺序释放资源的结构化作用域
+//! - `writes.rs` - 写入跟踪与回声识别
+//! - `namespace.rs` - 命名空间导出
+//! - `snapshot.rs` - 值快照的 JSON 导出（需要 `serde` 特性）
+//! - `logger.rs` - 按大小或时长滚动的 CSV 数据记录，可压缩和清理旧文件
+//! - `metrics.rs` - Prometheus 指标导出和健康状态（需要 `metrics` 特性）
+//! - `health.rs` - `/healthz` 和 `/metrics` 的 HTTP 端点（需要 `metrics` 特性）
+//! - `codec.rs` - 值和数据变化的二进制编码（需要 `binary` 特性）
+//! - `replay.rs` - 订阅数据的录制与回放（需要 `binary` 特性）
+//! - `anomaly.rs` - 订阅数据的异常检测
+//! - `staleness.rs` - 时间戳停滞检测
+//! - `describe.rs` - 质量码和服务器状态的本地化描述
+//! - `diagnostics.rs` - 诊断报告
+//! - `leaks.rs` - 存活资源登记与泄漏报告（`debug-leaks` 特性记录调用栈）
+//! - `limits.rs` - 组和项数量的软限制
+//! - `connection.rs` - 连接字符串解析
+//! - `writer.rs` - 跨线程的只写句柄
+//! - `journal.rs` - 带持久化序号的数据变化日志
+//! - `reentrancy.rs` - 回调中重入调用的检测与延后执行
+//! - `resilient.rs` - 心跳检测与自动重连
+//! - `shared.rs` - 基于工作线程的 `Send + Sync` 句柄
+//! - `blocking.rs` - 可在 `spawn_blocking` 等线程池中调用的阻塞句柄，拒绝在 COM 单线程套间中阻塞
+//! - `dispatch.rs` - 在命名的专用线程（`opcda-dispatch-<组名>`）中调用回调，可设置 CPU 亲和性和优先级
+//! - `worker.rs` - `Writer`、共享句柄和异步接口共用的工作线程（内部）
+//! - `session.rs` - 多服务器连接的登记、延迟连接和健康检查
+//! - `scaling.rs` - 原始值与工程单位的线性缩放
+//! - `tags.rs` - 标签别名与线性缩放
+//! - `backend.rs` - 服务器、组和项的后端 trait 和运行时的后端选择
+//! - `sim.rs` - 实现后端 trait 的内存模拟服务器
+//! - `config.rs` - 从 TOML 文件声明服务器、组和项（需要 `config` 特性）
+//! - `import.rs` - 导入 KEPServerEX CSV 标签导出和 MatrikonOPC 别名文件（需要 `config` 特性）
+//! - `script.rs` - 挂在项上的 rhai 脚本（需要 `scripting` 特性）
+//! - `influx.rs` - 以 InfluxDB 行协议批量写入数据变化（需要 `influx` 特性）
+//! - `async_client.rs` - 基于工作线程的 async/await 接口（需要 `async` 特性）
 //! - `utils.rs` - 字符串转换工具函数
 //! 
 //! ## 依赖关系
@@ -114,14 +156,110 @@ pub mod client;
 pub mod server;
 pub mod group;
 pub mod item;
+pub mod quirks;
+pub mod cache;
+pub mod debounce;
+pub mod item_id;
+pub mod format;
+pub mod mirror;
+pub mod compute;
+pub mod persist;
+pub mod scope;
+pub mod writes;
+pub mod namespace;
+#[cfg(feature = "serde")]
+pub mod snapshot;
+pub mod logger;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "metrics")]
+pub mod health;
+#[cfg(feature = "binary")]
+pub mod codec;
+#[cfg(feature = "binary")]
+pub mod replay;
+pub mod anomaly;
+pub mod staleness;
+pub mod describe;
+pub mod diagnostics;
+pub mod leaks;
+pub mod limits;
+pub mod connection;
+pub mod writer;
+pub mod journal;
+pub mod reentrancy;
+pub mod resilient;
+pub mod shared;
+pub mod blocking;
+pub mod dispatch;
+mod worker;
+pub mod session;
+pub mod scaling;
+pub mod tags;
+pub mod backend;
+pub mod sim;
+#[cfg(feature = "config")]
+pub mod config;
+#[cfg(feature = "config")]
+pub mod import;
+#[cfg(feature = "scripting")]
+pub mod script;
+#[cfg(feature = "influx")]
+pub mod influx;
+#[cfg(feature = "async")]
+pub mod async_client;
 
 // Re-export main types
 pub use client::OpcClient;
 pub use error::{OpcError, OpcResult};
-pub use types::{OpcValue, OpcQuality, OpcDataCallback};
-pub use server::OpcServer;
-pub use group::OpcGroup;
-pub use item::OpcItem;
+pub use types::{OpcValue, OpcQuality, OpcDecimal, OpcTimestamp, OpcDataCallback, SubscriptionCloseReason, ChangeOrigin, DataChangeEvent, QualitySummary, ServerState};
+pub use server::{DuplicateGroupPolicy, GroupCreation, OpcServer};
+pub use group::{OpcGroup, WeakSubscription};
+pub use item::{OpcItem, WriteProbe};
+pub use quirks::{QuirkProfile, QuirkRegistry};
+pub use debounce::Debouncer;
+pub use item_id::ItemIdRules;
+pub use format::{default_timestamp_style, set_default_timestamp_style, FormatProfile, TimestampStyle, QualityStyle};
+pub use mirror::{CoercionPolicy, Mirror, MirrorRule, MirrorReport};
+pub use compute::{ComputedChannels, Integrator, QualityThresholds};
+pub use persist::{Checkpoint, Checkpointer};
+pub use scope::{scope, scope_with, OpcScope};
+pub use writes::{EchoHandling, LastWrite};
+pub use namespace::{NamespaceFormat, NamespaceEntry};
+pub use anomaly::{AnomalyDetector, AnomalyEvent, AnomalyHandler, AnomalyKind, ItemLimits};
+pub use logger::{CsvLogger, LogCompression, LogRetention, LogRotation};
+#[cfg(feature = "metrics")]
+pub use metrics::{HealthStatus, MetricsExporter};
+#[cfg(feature = "metrics")]
+pub use health::HealthServer;
+pub use staleness::{StalenessEvent, StalenessHandler, StalenessMonitor};
+pub use describe::{describe_quality, describe_server_state, language, set_language, Language, QualityCode, ServerStateCode};
+pub use diagnostics::{DiagnosticsReport, GroupDiagnostics, ServerDiagnostics};
+pub use leaks::{LeakReport, LiveResource, ResourceKind};
+pub use limits::ResourceLimits;
+pub use connection::{ConnectionString, ConnectionStringError};
+pub use writer::Writer;
+pub use journal::{ChangeJournal, JournalEntry, JournalGap};
+#[cfg(feature = "binary")]
+pub use replay::{RecordedChange, ReplaySpeed, SessionRecorder, SessionRecording};
+pub use shared::{SharedOpcClient, SharedOpcGroup, SharedOpcItem};
+pub use dispatch::{DispatchOptions, Dispatcher, ThreadPriority};
+pub use session::{OpcSessionManager, SessionStatus};
+pub use scaling::Scaling;
+pub use tags::{Tag, TagMap, TaggedItems};
+pub use backend::{AnyClient, AnyGroup, AnyItem, AnyServer, Backend, DaGroup, DaItem, DaServer};
+pub use sim::{SimGroup, SimItem, SimServer, SimSignal};
+pub use resilient::{ConnectionEvent, ConnectionListener, GroupConfig, ReconnectPolicy, ResilientConnection};
+#[cfg(feature = "config")]
+pub use config::{ConnectedTopology, OpcConfig, ServerConfig};
+#[cfg(feature = "config")]
+pub use import::ImportedTags;
+#[cfg(feature = "scripting")]
+pub use script::{ScriptHooks, ScriptLimits, ScriptWriter};
+#[cfg(feature = "influx")]
+pub use influx::{InfluxConfig, InfluxSink, LineTransport};
+#[cfg(feature = "async")]
+pub use async_client::{AsyncOpcClient, AsyncOpcGroup, AsyncOpcItem, DataChangeStream, Operation};
 
 
 // 内部 FFI 绑定模块
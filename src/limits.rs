@@ -0,0 +1,94 @@
+//! 资源软限制模块
+//!
+//! 部分服务器（尤其是一些 DCS 的 OPC 接口）在组或项数量超过某个规模后
+//! 会静默地工作异常，或者返回难以理解的资源错误。软限制在客户端提前检查
+//! 这些数量，超过时返回说明具体限制的 `OpcError::LimitExceeded`，
+//! 而不访问服务器。
+//!
+//! 限制按服务器连接配置，默认不限制，可以在运行时通过 `OpcServer::limits` 查询。
+//! 计数只包括仍然存在的组和项。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::ResourceLimits;
+//!
+//! server.set_limits(ResourceLimits {
+//!     max_items_per_group: Some(2000),
+//!     max_total_items: Some(20_000),
+//!     ..Default::default()
+//! });
+//! ```
+
+use crate::error::{OpcError, OpcResult};
+
+/// 服务器连接的软限制，`None` 表示不限制
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ResourceLimits {
+    /// 每个服务器连接的最大组数
+    pub max_groups_per_server: Option<usize>,
+    /// 每个组的最大项数
+    pub max_items_per_group: Option<usize>,
+    /// 每个服务器连接所有组的最大项总数
+    pub max_total_items: Option<usize>,
+}
+
+impl ResourceLimits {
+    /// 检查是否可以再创建一个组
+    pub(crate) fn check_group(&self, groups: usize) -> OpcResult<()> {
+        match self.max_groups_per_server {
+            Some(max) if groups >= max => Err(OpcError::LimitExceeded(format!(
+                "server already has {} groups (max_groups_per_server = {})",
+                groups, max
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// 检查是否可以向组中再添加一个项
+    pub(crate) fn check_item(&self, group_name: &str, group_items: usize, total_items: usize) -> OpcResult<()> {
+        if let Some(max) = self.max_items_per_group {
+            if group_items >= max {
+                return Err(OpcError::LimitExceeded(format!(
+                    "group '{}' already has {} items (max_items_per_group = {})",
+                    group_name, group_items, max
+                )));
+            }
+        }
+        if let Some(max) = self.max_total_items {
+            if total_items >= max {
+                return Err(OpcError::LimitExceeded(format!(
+                    "server already has {} items (max_total_items = {})",
+                    total_items, max
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits() {
+        let limits = ResourceLimits {
+            max_groups_per_server: Some(2),
+            max_items_per_group: Some(10),
+            max_total_items: Some(15),
+        };
+        assert!(limits.check_group(1).is_ok());
+        assert!(matches!(limits.check_group(2), Err(OpcError::LimitExceeded(_))));
+
+        assert!(limits.check_item("G", 9, 14).is_ok());
+        let err = limits.check_item("G", 10, 10).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Soft limit exceeded: group 'G' already has 10 items (max_items_per_group = 10)"
+        );
+        assert!(limits.check_item("G", 5, 15).is_err());
+
+        assert!(ResourceLimits::default().check_item("G", usize::MAX - 1, usize::MAX - 1).is_ok());
+    }
+}
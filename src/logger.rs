@@ -0,0 +1,585 @@
+//! CSV 数据记录模块
+//!
+//! `CsvLogger` 作为组的订阅消费者，把每次数据变化追加到 CSV 文件，
+//! 列为 `timestamp,group,item,value,quality`。文件按大小或时长滚动，
+//! 文件名为 `<前缀>-<打开时间>.csv`（UTC，例如 `line1-2024-03-01T08-00-00.000Z.csv`），
+//! 每个文件都带表头，可以直接用表格软件打开。适合调试和投运时的临时数据采集。
+//!
+//! 值、质量和时间戳按 `FormatProfile` 格式化，默认使用进程级的时间戳格式。
+//! 每条记录写入后立即刷新到文件。回调中无法返回错误，写入失败的记录被丢弃，
+//! 可以通过 `dropped` 和 `last_error` 查看。
+//!
+//! 作为 `ResilientConnection` 的监听器（`listener`）时，记录器在连接断开和恢复时
+//! 写入停机标记行，分析时可以区分"值没有变化"和"当时没有连接"。标记行的 group 列为空，
+//! item 列为 `outage-start` 或 `outage-end`，value 列为断开原因或恢复情况，
+//! 时间戳为本地时钟的当前时间。
+//!
+//! 滚动出的文件可以在关闭后压缩（`gzip` 特性为 `.csv.gz`，`zstd` 特性为 `.csv.zst`），
+//! 正在写入的文件始终是普通 CSV。`LogRetention` 按文件数和修改时间清理同一前缀的旧文件，
+//! 清理在每次关闭文件后进行。压缩和清理失败不丢弃记录，原因记录在 `last_error` 中。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::logger::{CsvLogger, LogCompression, LogRetention, LogRotation};
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! let logger = Arc::new(CsvLogger::new("logs", "line1", LogRotation {
+//!     max_bytes: Some(10 * 1024 * 1024),
+//!     max_age: Some(Duration::from_secs(3600)),
+//! })?)
+//! .with_compression(LogCompression::Gzip)
+//! .with_retention(LogRetention {
+//!     max_files: Some(168),
+//!     max_age: Some(Duration::from_secs(7 * 24 * 3600)),
+//! }));
+//! group.enable_async_subscription(logger.clone())?;
+//! connection.set_listener(logger.listener());
+//! ```
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use crate::error::OpcResult;
+use crate::format::{format_iso8601, FormatProfile};
+use crate::namespace::csv_field;
+use crate::resilient::{ConnectionEvent, ConnectionListener};
+use crate::types::{lock_or_recover, OpcDataCallback, OpcQuality, OpcValue};
+
+/// CSV 表头
+const HEADER: &str = "timestamp,group,item,value,quality\n";
+
+/// 停机开始标记行的 item 列
+pub const OUTAGE_START: &str = "outage-start";
+
+/// 停机结束标记行的 item 列
+pub const OUTAGE_END: &str = "outage-end";
+
+/// 滚动策略，两个条件都为 `None` 时只写一个文件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LogRotation {
+    /// 文件达到此大小（字节）后换新文件
+    pub max_bytes: Option<u64>,
+    /// 文件打开超过此时长后换新文件
+    pub max_age: Option<Duration>,
+}
+
+/// 关闭文件后的压缩方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogCompression {
+    /// 不压缩
+    #[default]
+    None,
+    /// gzip 压缩为 `.csv.gz`
+    #[cfg(feature = "gzip")]
+    Gzip,
+    /// zstd 压缩为 `.csv.zst`
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl LogCompression {
+    /// 压缩后文件名追加的扩展名
+    fn extension(self) -> Option<&'static str> {
+        match self {
+            LogCompression::None => None,
+            #[cfg(feature = "gzip")]
+            LogCompression::Gzip => Some("gz"),
+            #[cfg(feature = "zstd")]
+            LogCompression::Zstd => Some("zst"),
+        }
+    }
+
+    /// 把 `source` 压缩到 `target`
+    #[cfg_attr(not(any(feature = "gzip", feature = "zstd")), allow(unused_variables))]
+    fn compress(self, source: &Path, target: &Path) -> OpcResult<()> {
+        match self {
+            LogCompression::None => Ok(()),
+            #[cfg(feature = "gzip")]
+            LogCompression::Gzip => {
+                let output = BufWriter::new(File::create(target)?);
+                let mut encoder = flate2::write::GzEncoder::new(output, flate2::Compression::default());
+                std::io::copy(&mut File::open(source)?, &mut encoder)?;
+                encoder.finish()?.flush()?;
+                Ok(())
+            }
+            #[cfg(feature = "zstd")]
+            LogCompression::Zstd => {
+                let output = BufWriter::new(File::create(target)?);
+                let mut encoder = zstd::stream::write::Encoder::new(output, 0)?;
+                std::io::copy(&mut File::open(source)?, &mut encoder)?;
+                encoder.finish()?.flush()?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// 旧文件的保留策略，两个条件都为 `None` 时保留全部文件
+///
+/// 只清理已关闭的、文件名属于本记录器前缀的文件（包括压缩后的文件），
+/// 不清理正在写入的文件。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LogRetention {
+    /// 最多保留的已关闭文件数，超出时删除最旧的
+    pub max_files: Option<usize>,
+    /// 删除修改时间早于此时长的已关闭文件
+    pub max_age: Option<Duration>,
+}
+
+/// 当前写入的文件
+struct LogFile {
+    writer: BufWriter<File>,
+    path: PathBuf,
+    bytes: u64,
+    opened_at: Instant,
+}
+
+#[derive(Default)]
+struct LogState {
+    file: Option<LogFile>,
+    dropped: u64,
+    last_error: Option<String>,
+    /// 已写入停机开始标记，尚未写入结束标记
+    in_outage: bool,
+}
+
+/// 按大小或时长滚动的 CSV 数据记录器
+pub struct CsvLogger {
+    directory: PathBuf,
+    prefix: String,
+    rotation: LogRotation,
+    compression: LogCompression,
+    retention: LogRetention,
+    format: FormatProfile,
+    state: Mutex<LogState>,
+}
+
+impl CsvLogger {
+    /// 创建记录器，目录不存在时创建
+    ///
+    /// 第一个文件在收到第一条记录时创建。
+    ///
+    /// # 返回值
+    /// - `Ok(CsvLogger)`: 创建成功
+    /// - `Err(OpcError::Io)`: 创建目录失败
+    pub fn new(directory: impl Into<PathBuf>, prefix: &str, rotation: LogRotation) -> OpcResult<Self> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+        Ok(CsvLogger {
+            directory,
+            prefix: prefix.to_string(),
+            rotation,
+            compression: LogCompression::default(),
+            retention: LogRetention::default(),
+            format: FormatProfile::default(),
+            state: Mutex::new(LogState::default()),
+        })
+    }
+
+    /// 使用指定的格式化配置
+    pub fn with_format(mut self, format: FormatProfile) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// 关闭文件后按 `compression` 压缩
+    pub fn with_compression(mut self, compression: LogCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// 关闭文件后按 `retention` 清理旧文件
+    pub fn with_retention(mut self, retention: LogRetention) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// 追加一条记录，需要时先滚动文件
+    pub fn record(&self, group_name: &str, item_name: &str, value: &OpcValue, quality: OpcQuality, timestamp: u64) -> OpcResult<()> {
+        let line = format!(
+            "{},{},{},{},{}\n",
+            csv_field(&self.format.format_timestamp(timestamp)),
+            csv_field(group_name),
+            csv_field(item_name),
+            csv_field(&self.format.format_value(item_name, value)),
+            csv_field(&self.format.format_quality(quality))
+        );
+        let mut state = lock_or_recover(&self.state);
+        self.write_line(&mut state, &line)
+    }
+
+    /// 根据连接状态变化写入停机标记
+    ///
+    /// `Lost` 写入开始标记，之后的第一个 `Restored` 写入结束标记；
+    /// 重连失败和首次连接不写入。
+    pub fn record_connection_event(&self, event: &ConnectionEvent) -> OpcResult<()> {
+        let mut state = lock_or_recover(&self.state);
+        let (marker, detail) = match event {
+            ConnectionEvent::Lost { reason } if !state.in_outage => (OUTAGE_START, reason.clone()),
+            ConnectionEvent::Restored { attempts, .. } if state.in_outage => {
+                (OUTAGE_END, format!("restored after {} attempt(s)", attempts))
+            }
+            _ => return Ok(()),
+        };
+        state.in_outage = marker == OUTAGE_START;
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let line = format!(
+            "{},,{},{},\n",
+            csv_field(&self.format.format_timestamp(now_ms)),
+            marker,
+            csv_field(&detail)
+        );
+        self.write_line(&mut state, &line)
+    }
+
+    /// 用于 `ResilientConnection::set_listener` 的监听器
+    pub fn listener(&self) -> impl ConnectionListener + '_ {
+        // 错误已记录在 dropped 和 last_error 中
+        move |event: &ConnectionEvent| {
+            let _ = self.record_connection_event(event);
+        }
+    }
+
+    /// 关闭当前文件，下一条记录写入新文件
+    ///
+    /// 关闭后按配置压缩文件并清理旧文件。
+    pub fn rotate(&self) -> OpcResult<()> {
+        let mut state = lock_or_recover(&self.state);
+        match state.file.take() {
+            Some(file) => self.close(file),
+            None => Ok(()),
+        }
+    }
+
+    /// 记录文件所在的目录
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// 当前写入的文件路径
+    pub fn current_path(&self) -> Option<PathBuf> {
+        lock_or_recover(&self.state).file.as_ref().map(|file| file.path.clone())
+    }
+
+    /// 因写入失败被丢弃的记录数
+    pub fn dropped(&self) -> u64 {
+        lock_or_recover(&self.state).dropped
+    }
+
+    /// 最近一次写入失败的原因
+    pub fn last_error(&self) -> Option<String> {
+        lock_or_recover(&self.state).last_error.clone()
+    }
+
+    fn write_line(&self, state: &mut LogState, line: &str) -> OpcResult<()> {
+        let result = self.append(state, line.as_bytes());
+        if let Err(e) = &result {
+            state.dropped += 1;
+            state.last_error = Some(e.to_string());
+        }
+        result
+    }
+
+    fn append(&self, state: &mut LogState, line: &[u8]) -> OpcResult<()> {
+        let expired = state.file.as_ref().is_some_and(|file| {
+            self.rotation.max_bytes.is_some_and(|max| file.bytes >= max)
+                || self.rotation.max_age.is_some_and(|max| file.opened_at.elapsed() >= max)
+        });
+        if expired {
+            if let Some(file) = state.file.take() {
+                // 旧文件的压缩和清理失败不影响这条记录
+                if let Err(e) = self.close(file) {
+                    state.last_error = Some(e.to_string());
+                }
+            }
+        }
+        let file = match &mut state.file {
+            Some(file) => file,
+            None => state.file.insert(self.open()?),
+        };
+        file.writer.write_all(line)?;
+        file.writer.flush()?;
+        file.bytes += line.len() as u64;
+        Ok(())
+    }
+
+    /// 关闭文件，按配置压缩并清理旧文件
+    fn close(&self, file: LogFile) -> OpcResult<()> {
+        let LogFile { writer, path, .. } = file;
+        writer.into_inner().map_err(|e| e.into_error())?;
+        if let Some(extension) = self.compression.extension() {
+            let mut target = path.clone().into_os_string();
+            target.push(".");
+            target.push(extension);
+            self.compression.compress(&path, Path::new(&target))?;
+            fs::remove_file(&path)?;
+        }
+        self.prune()
+    }
+
+    /// 按保留策略删除已关闭的旧文件
+    ///
+    /// 在持有状态锁、没有打开的文件时调用。
+    fn prune(&self) -> OpcResult<()> {
+        if self.retention == LogRetention::default() {
+            return Ok(());
+        }
+        let mut closed: Vec<(SystemTime, PathBuf)> = Vec::new();
+        for entry in fs::read_dir(&self.directory)? {
+            let entry = entry?;
+            if !self.owns(&entry.file_name().to_string_lossy()) {
+                continue;
+            }
+            closed.push((entry.metadata()?.modified()?, entry.path()));
+        }
+        // 最旧的在前
+        closed.sort();
+
+        let now = SystemTime::now();
+        let expired = closed
+            .iter()
+            .take_while(|(modified, _)| {
+                self.retention.max_age.is_some_and(|max| now.duration_since(*modified).unwrap_or_default() >= max)
+            })
+            .count();
+        let excess = self.retention.max_files.map_or(0, |max| closed.len().saturating_sub(max));
+        for (_, path) in closed.iter().take(expired.max(excess)) {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// 文件名是否为本记录器写出的文件：`<前缀>-<时间>[-序号].csv[.gz|.zst]`
+    fn owns(&self, name: &str) -> bool {
+        let Some(rest) = name.strip_prefix(self.prefix.as_str()).and_then(|rest| rest.strip_prefix('-')) else {
+            return false;
+        };
+        let stem = [".csv", ".csv.gz", ".csv.zst"]
+            .iter()
+            .find_map(|suffix| rest.strip_suffix(suffix));
+        // 时间部分形如 2024-03-01T08-00-00.000Z
+        stem.is_some_and(|stem| stem.as_bytes().get(10) == Some(&b'T') && stem.starts_with(|c: char| c.is_ascii_digit()))
+    }
+
+    /// 打开新文件并写入表头，同名文件已存在时加序号
+    fn open(&self) -> OpcResult<LogFile> {
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let stamp = format_iso8601(now_ms).replace(':', "-");
+        let mut attempt = 1;
+        loop {
+            let name = if attempt == 1 {
+                format!("{}-{}.csv", self.prefix, stamp)
+            } else {
+                format!("{}-{}-{}.csv", self.prefix, stamp, attempt)
+            };
+            let path = self.directory.join(name);
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => {
+                    let mut writer = BufWriter::new(file);
+                    writer.write_all(HEADER.as_bytes())?;
+                    return Ok(LogFile {
+                        writer,
+                        path,
+                        bytes: HEADER.len() as u64,
+                        opened_at: Instant::now(),
+                    });
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => attempt += 1,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+impl OpcDataCallback for CsvLogger {
+    fn on_data_change(&self, group_name: &str, item_name: &str, value: OpcValue, quality: OpcQuality, timestamp: u64) {
+        // 错误已记录在 dropped 和 last_error 中
+        let _ = self.record(group_name, item_name, &value, quality, timestamp);
+    }
+}
+
+impl Drop for CsvLogger {
+    fn drop(&mut self) {
+        let _ = self.rotate();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::TimestampStyle;
+
+    #[test]
+    fn test_records_and_rotation() {
+        let directory = std::env::temp_dir().join(format!("opcda-csv-logger-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+
+        let format = FormatProfile {
+            timestamp_style: TimestampStyle::EpochMillis,
+            ..FormatProfile::default()
+        };
+        let logger = CsvLogger::new(&directory, "line1", LogRotation {
+            max_bytes: Some(60),
+            max_age: None,
+        })
+        .unwrap()
+        .with_format(format);
+
+        logger.on_data_change("G", "Tank,1", OpcValue::Double(1.5), OpcQuality::Good, 1_000);
+        let first = logger.current_path().unwrap();
+        logger.on_data_change("G", "Tank,1", OpcValue::Double(2.5), OpcQuality::Bad, 2_000);
+        let second = logger.current_path().unwrap();
+        assert_ne!(first, second);
+        assert_eq!(
+            fs::read_to_string(&first).unwrap(),
+            "timestamp,group,item,value,quality\n1000,G,\"Tank,1\",1.5,Good\n"
+        );
+        assert_eq!(
+            fs::read_to_string(&second).unwrap(),
+            "timestamp,group,item,value,quality\n2000,G,\"Tank,1\",2.5,Bad\n"
+        );
+        assert_eq!(logger.dropped(), 0);
+
+        drop(logger);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_outage_markers() {
+        let directory = std::env::temp_dir().join(format!("opcda-csv-outage-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+
+        let format = FormatProfile {
+            timestamp_style: TimestampStyle::EpochMillis,
+            ..FormatProfile::default()
+        };
+        let logger = CsvLogger::new(&directory, "line1", LogRotation::default()).unwrap().with_format(format);
+        {
+            let mut listener = logger.listener();
+            // 首次连接不是停机结束
+            listener.on_connection_event(&ConnectionEvent::Restored { attempts: 1, failed_items: Vec::new() });
+            listener.on_connection_event(&ConnectionEvent::Lost { reason: "heartbeat failed: timeout".to_string() });
+            listener.on_connection_event(&ConnectionEvent::ReconnectFailed {
+                attempt: 1,
+                error: "refused".to_string(),
+                retry_in: std::time::Duration::from_secs(1),
+            });
+            listener.on_connection_event(&ConnectionEvent::Restored { attempts: 2, failed_items: Vec::new() });
+        }
+        logger.on_data_change("G", "Tank1", OpcValue::Double(1.5), OpcQuality::Good, 1_000);
+
+        let content = fs::read_to_string(logger.current_path().unwrap()).unwrap();
+        let rows: Vec<Vec<&str>> = content.lines().skip(1).map(|line| line.splitn(2, ',').collect()).collect();
+        assert_eq!(rows.len(), 3);
+        assert!(rows[..2].iter().all(|row| row[0].parse::<u64>().is_ok()));
+        assert_eq!(rows[0][1], ",outage-start,heartbeat failed: timeout,");
+        assert_eq!(rows[1][1], ",outage-end,restored after 2 attempt(s),");
+        assert_eq!(rows[2], vec!["1000", "G,Tank1,1.5,Good"]);
+
+        drop(logger);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    /// 本记录器写出的文件名，按名称排序
+    fn logged_files(directory: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.starts_with("line1-"))
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_retention() {
+        let directory = std::env::temp_dir().join(format!("opcda-csv-retention-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        // 其他前缀和其他程序的文件不清理
+        fs::write(directory.join("line10-2024-03-01T08-00-00.000Z.csv"), "").unwrap();
+        fs::write(directory.join("line1-notes.csv"), "").unwrap();
+
+        let logger = CsvLogger::new(&directory, "line1", LogRotation { max_bytes: Some(1), max_age: None })
+            .unwrap()
+            .with_retention(LogRetention { max_files: Some(1), max_age: None });
+        for timestamp in 0..4 {
+            logger.on_data_change("G", "Tank1", OpcValue::Double(1.5), OpcQuality::Good, timestamp);
+        }
+        // 一个已关闭的文件和正在写入的文件
+        let current = logger.current_path().unwrap();
+        let names = logged_files(&directory);
+        assert_eq!(names.len(), 3, "{:?}", names);
+        assert!(names.contains(&"line1-notes.csv".to_string()));
+        assert!(names.contains(&current.file_name().unwrap().to_string_lossy().into_owned()));
+        assert!(directory.join("line10-2024-03-01T08-00-00.000Z.csv").exists());
+        assert_eq!(logger.last_error(), None);
+        drop(logger);
+
+        // 按时长清理全部已关闭的文件
+        let logger = CsvLogger::new(&directory, "line1", LogRotation::default())
+            .unwrap()
+            .with_retention(LogRetention { max_files: None, max_age: Some(Duration::ZERO) });
+        logger.on_data_change("G", "Tank1", OpcValue::Double(1.5), OpcQuality::Good, 0);
+        logger.rotate().unwrap();
+        assert_eq!(logged_files(&directory), vec!["line1-notes.csv".to_string()]);
+
+        drop(logger);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    /// 记录一条数据后滚动，返回唯一的压缩文件
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    fn compressed_file(name: &str, compression: LogCompression) -> (PathBuf, PathBuf) {
+        let directory = std::env::temp_dir().join(format!("opcda-csv-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        let format = FormatProfile {
+            timestamp_style: TimestampStyle::EpochMillis,
+            ..FormatProfile::default()
+        };
+        let logger = CsvLogger::new(&directory, "line1", LogRotation::default())
+            .unwrap()
+            .with_format(format)
+            .with_compression(compression);
+        logger.on_data_change("G", "Tank1", OpcValue::Double(1.5), OpcQuality::Good, 1_000);
+        let path = logger.current_path().unwrap();
+        logger.rotate().unwrap();
+        assert!(!path.exists());
+        let names = logged_files(&directory);
+        assert_eq!(names.len(), 1);
+        (directory.join(&names[0]), directory)
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip_compression() {
+        use std::io::Read;
+
+        let (path, directory) = compressed_file("gzip", LogCompression::Gzip);
+        assert!(path.to_string_lossy().ends_with(".csv.gz"));
+        let mut content = String::new();
+        flate2::read::GzDecoder::new(File::open(&path).unwrap()).read_to_string(&mut content).unwrap();
+        assert_eq!(content, "timestamp,group,item,value,quality\n1000,G,Tank1,1.5,Good\n");
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd_compression() {
+        let (path, directory) = compressed_file("zstd", LogCompression::Zstd);
+        assert!(path.to_string_lossy().ends_with(".csv.zst"));
+        let content = zstd::stream::decode_all(File::open(&path).unwrap()).unwrap();
+        assert_eq!(content, b"timestamp,group,item,value,quality\n1000,G,Tank1,1.5,Good\n");
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
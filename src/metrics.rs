@@ -0,0 +1,430 @@
+//! Prometheus 指标模块（需要 `metrics` 特性）
+//!
+//! `MetricsExporter` 作为组的订阅消费者记录每个项的最新值、质量和时间戳，
+//! 同时统计数据变化次数、同步读取的耗时和错误，以及 `ResilientConnection`
+//! 报告的断线和重连次数。`render` 以 Prometheus 文本格式（0.0.4）输出这些指标，
+//! 由应用自己的 HTTP 服务在 `/metrics` 上返回。
+//!
+//! `health` 汇总连接状态、最近的回调速率、重连次数和最近的错误，
+//! 由 `HealthServer`（`health` 模块）在 `/healthz` 上以 JSON 返回；
+//! 其他监控方式（例如 SNMP 代理的扩展脚本）也可以直接读取这份快照。
+//!
+//! `render_diagnostics` 把客户端的诊断报告（`OpcClient::diagnostics_report`）
+//! 转换为同样格式的组级指标：项数、添加失败、读写失败和通知次数。
+//!
+//! ## 指标
+//!
+//! - `opcda_item_value{group,item}`: 数值项的最新值，非数值项没有此指标
+//! - `opcda_item_quality{group,item}`: 最新的原始质量码（Good 为 192）
+//! - `opcda_item_timestamp_seconds{group,item}`: 最新的服务器时间戳
+//! - `opcda_data_changes_total{group}`: 收到的数据变化通知数
+//! - `opcda_read_duration_seconds`（summary）和 `opcda_read_errors_total`: 经 `timed_read` 的同步读取
+//! - `opcda_connection_lost_total`、`opcda_reconnect_failures_total`、`opcda_reconnects_total`: 连接事件
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::metrics::{render_diagnostics, MetricsExporter};
+//! use std::sync::Arc;
+//!
+//! let metrics = Arc::new(MetricsExporter::new());
+//! group.enable_async_subscription(metrics.clone())?;
+//! connection.set_listener(metrics.listener());
+//!
+//! let (value, _, _) = metrics.timed_read(&item)?;
+//!
+//! // HTTP 处理函数中
+//! let body = metrics.render() + &render_diagnostics(&client.diagnostics_report());
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use crate::diagnostics::{DiagnosticsReport, GroupDiagnostics};
+use crate::error::OpcResult;
+use crate::item::OpcItem;
+use crate::namespace::json_string;
+use crate::resilient::{ConnectionEvent, ConnectionListener};
+use crate::types::{lock_or_recover, OpcDataCallback, OpcQuality, OpcTimestamp, OpcValue};
+
+/// 一个项的最新样本
+#[derive(Debug, Clone, Copy)]
+struct ItemSample {
+    value: Option<f64>,
+    quality: OpcQuality,
+    timestamp: u64,
+}
+
+/// 同步读取的统计
+#[derive(Debug, Default)]
+struct ReadStats {
+    count: u64,
+    errors: u64,
+    seconds: f64,
+}
+
+/// 计算回调速率的时间窗口
+const RATE_WINDOW: Duration = Duration::from_secs(10);
+
+/// 回调速率：上一个完整窗口的平均值
+#[derive(Debug, Default)]
+struct RateWindow {
+    started: Option<Instant>,
+    count: u64,
+    per_sec: f64,
+}
+
+impl RateWindow {
+    /// 当前窗口已满时结算速率并开始新窗口
+    fn roll(&mut self, now: Instant) {
+        let started = *self.started.get_or_insert(now);
+        let elapsed = now.saturating_duration_since(started);
+        if elapsed >= RATE_WINDOW {
+            self.per_sec = self.count as f64 / elapsed.as_secs_f64();
+            self.count = 0;
+            self.started = Some(now);
+        }
+    }
+}
+
+/// 由连接事件得到的状态
+#[derive(Debug, Default)]
+struct ConnectionHealth {
+    connected: Option<bool>,
+    last_error: Option<String>,
+}
+
+/// 健康状态快照
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthStatus {
+    /// 连接状态，还没有收到连接事件时为 `None`
+    pub connected: Option<bool>,
+    /// 最近一个统计窗口（10 秒）内每秒的数据变化回调数
+    pub callbacks_per_sec: f64,
+    /// 收到的数据变化通知总数
+    pub data_changes: u64,
+    /// 检测到断线的次数
+    pub connection_lost: u64,
+    /// 重连成功的次数
+    pub reconnects: u64,
+    /// 重连失败的次数
+    pub reconnect_failures: u64,
+    /// 最近一次断线或重连失败的原因
+    pub last_error: Option<String>,
+}
+
+impl HealthStatus {
+    /// 没有已知的断线时为健康
+    pub fn is_healthy(&self) -> bool {
+        self.connected != Some(false)
+    }
+
+    /// 以 JSON 对象输出
+    pub fn to_json(&self) -> String {
+        let connected = match self.connected {
+            Some(connected) => connected.to_string(),
+            None => "null".to_string(),
+        };
+        let last_error = match &self.last_error {
+            Some(error) => json_string(error),
+            None => "null".to_string(),
+        };
+        format!(
+            "{{\"healthy\":{},\"connected\":{},\"callbacks_per_sec\":{},\"data_changes\":{},\"connection_lost\":{},\"reconnects\":{},\"reconnect_failures\":{},\"last_error\":{}}}",
+            self.is_healthy(),
+            connected,
+            self.callbacks_per_sec,
+            self.data_changes,
+            self.connection_lost,
+            self.reconnects,
+            self.reconnect_failures,
+            last_error
+        )
+    }
+}
+
+/// Prometheus 指标收集器
+#[derive(Default)]
+pub struct MetricsExporter {
+    items: Mutex<BTreeMap<(String, String), ItemSample>>,
+    data_changes: Mutex<BTreeMap<String, u64>>,
+    reads: Mutex<ReadStats>,
+    connection_lost: AtomicU64,
+    reconnect_failures: AtomicU64,
+    reconnects: AtomicU64,
+    connection: Mutex<ConnectionHealth>,
+    rate: Mutex<RateWindow>,
+}
+
+impl MetricsExporter {
+    /// 创建空的收集器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 同步读取项并记录耗时和结果
+    pub fn timed_read(&self, item: &OpcItem) -> OpcResult<(OpcValue, OpcQuality, OpcTimestamp)> {
+        let started = Instant::now();
+        let result = item.read_sync();
+        self.record_read(started.elapsed(), result.is_ok());
+        result
+    }
+
+    /// 记录一次在其他地方执行的读取
+    pub fn record_read(&self, duration: Duration, success: bool) {
+        let mut reads = lock_or_recover(&self.reads);
+        reads.count += 1;
+        reads.seconds += duration.as_secs_f64();
+        if !success {
+            reads.errors += 1;
+        }
+    }
+
+    /// 记录一个连接事件
+    pub fn record_connection_event(&self, event: &ConnectionEvent) {
+        let counter = match event {
+            ConnectionEvent::Lost { .. } => &self.connection_lost,
+            ConnectionEvent::ReconnectFailed { .. } => &self.reconnect_failures,
+            ConnectionEvent::Restored { .. } => &self.reconnects,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+
+        let mut connection = lock_or_recover(&self.connection);
+        match event {
+            ConnectionEvent::Lost { reason } => {
+                connection.connected = Some(false);
+                connection.last_error = Some(reason.clone());
+            }
+            ConnectionEvent::ReconnectFailed { error, .. } => connection.last_error = Some(error.clone()),
+            ConnectionEvent::Restored { .. } => connection.connected = Some(true),
+        }
+    }
+
+    /// 当前的健康状态
+    pub fn health(&self) -> HealthStatus {
+        self.health_at(Instant::now())
+    }
+
+    fn health_at(&self, now: Instant) -> HealthStatus {
+        let callbacks_per_sec = {
+            let mut rate = lock_or_recover(&self.rate);
+            rate.roll(now);
+            rate.per_sec
+        };
+        let connection = lock_or_recover(&self.connection);
+        HealthStatus {
+            connected: connection.connected,
+            callbacks_per_sec,
+            data_changes: lock_or_recover(&self.data_changes).values().sum(),
+            connection_lost: self.connection_lost.load(Ordering::Relaxed),
+            reconnects: self.reconnects.load(Ordering::Relaxed),
+            reconnect_failures: self.reconnect_failures.load(Ordering::Relaxed),
+            last_error: connection.last_error.clone(),
+        }
+    }
+
+    fn count_callback(&self, now: Instant) {
+        let mut rate = lock_or_recover(&self.rate);
+        rate.roll(now);
+        rate.count += 1;
+    }
+
+    /// 用于 `ResilientConnection::set_listener` 的监听器
+    pub fn listener(&self) -> impl ConnectionListener + '_ {
+        move |event: &ConnectionEvent| self.record_connection_event(event)
+    }
+
+    /// 以 Prometheus 文本格式输出所有指标
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        {
+            let items = lock_or_recover(&self.items);
+            family(&mut out, "opcda_item_value", "gauge", "Last numeric value of the item");
+            for ((group, item), sample) in items.iter() {
+                if let Some(value) = sample.value {
+                    sample_line(&mut out, "opcda_item_value", &[("group", group), ("item", item)], value);
+                }
+            }
+            family(&mut out, "opcda_item_quality", "gauge", "Last raw OPC quality code of the item");
+            for ((group, item), sample) in items.iter() {
+                let labels = [("group", group.as_str()), ("item", item.as_str())];
+                sample_line(&mut out, "opcda_item_quality", &labels, sample.quality.to_raw() as f64);
+            }
+            family(&mut out, "opcda_item_timestamp_seconds", "gauge", "Last server timestamp of the item");
+            for ((group, item), sample) in items.iter() {
+                let labels = [("group", group.as_str()), ("item", item.as_str())];
+                sample_line(&mut out, "opcda_item_timestamp_seconds", &labels, sample.timestamp as f64 / 1000.0);
+            }
+        }
+
+        family(&mut out, "opcda_data_changes_total", "counter", "Data change notifications received");
+        for (group, count) in lock_or_recover(&self.data_changes).iter() {
+            sample_line(&mut out, "opcda_data_changes_total", &[("group", group)], *count as f64);
+        }
+
+        {
+            let reads = lock_or_recover(&self.reads);
+            family(&mut out, "opcda_read_duration_seconds", "summary", "Duration of synchronous reads");
+            sample_line(&mut out, "opcda_read_duration_seconds_sum", &[], reads.seconds);
+            sample_line(&mut out, "opcda_read_duration_seconds_count", &[], reads.count as f64);
+            family(&mut out, "opcda_read_errors_total", "counter", "Failed synchronous reads");
+            sample_line(&mut out, "opcda_read_errors_total", &[], reads.errors as f64);
+        }
+
+        let counters = [
+            ("opcda_connection_lost_total", "Connections detected as lost", &self.connection_lost),
+            ("opcda_reconnect_failures_total", "Failed reconnection attempts", &self.reconnect_failures),
+            ("opcda_reconnects_total", "Successful reconnections", &self.reconnects),
+        ];
+        for (name, help, counter) in counters {
+            family(&mut out, name, "counter", help);
+            sample_line(&mut out, name, &[], counter.load(Ordering::Relaxed) as f64);
+        }
+        out
+    }
+}
+
+impl OpcDataCallback for MetricsExporter {
+    fn on_data_change(&self, group_name: &str, item_name: &str, value: OpcValue, quality: OpcQuality, timestamp: u64) {
+        let value = if value.is_array() { None } else { value.as_f64() };
+        lock_or_recover(&self.items).insert(
+            (group_name.to_string(), item_name.to_string()),
+            ItemSample { value, quality, timestamp },
+        );
+        *lock_or_recover(&self.data_changes)
+            .entry(group_name.to_string())
+            .or_default() += 1;
+        self.count_callback(Instant::now());
+    }
+}
+
+/// 把诊断报告转换为 Prometheus 文本格式的组级指标
+pub fn render_diagnostics(report: &DiagnosticsReport) -> String {
+    type Extract = fn(&GroupDiagnostics) -> u64;
+    let metrics: [(&str, &str, &str, Extract); 4] = [
+        ("opcda_group_items", "gauge", "Items in the group", |g| g.items as u64),
+        ("opcda_group_add_failures_total", "counter", "Failed item additions", |g| g.add_failures),
+        ("opcda_group_io_failures_total", "counter", "Failed reads and writes", |g| g.io_failures),
+        (
+            "opcda_group_notifications_total",
+            "counter",
+            "Data change notifications received by the subscription",
+            |g| g.notifications,
+        ),
+    ];
+    let mut out = String::new();
+    for (name, kind, help, extract) in metrics {
+        family(&mut out, name, kind, help);
+        for server in &report.servers {
+            for group in &server.groups {
+                let labels = [
+                    ("host", server.host.as_str()),
+                    ("server", server.server_name.as_str()),
+                    ("group", group.name.as_str()),
+                ];
+                sample_line(&mut out, name, &labels, extract(group) as f64);
+            }
+        }
+    }
+    out
+}
+
+/// 输出指标的 HELP 和 TYPE 行
+fn family(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// 输出一个样本
+fn sample_line(out: &mut String, name: &str, labels: &[(&str, &str)], value: f64) {
+    out.push_str(name);
+    if !labels.is_empty() {
+        let labels: Vec<String> = labels
+            .iter()
+            .map(|(key, value)| format!("{}=\"{}\"", key, escape_label(value)))
+            .collect();
+        let _ = write!(out, "{{{}}}", labels.join(","));
+    }
+    let value = if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    };
+    let _ = writeln!(out, " {}", value);
+}
+
+/// 标签值转义：反斜杠、双引号和换行
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let metrics = MetricsExporter::new();
+        metrics.on_data_change("G", "Tank\"1\"", OpcValue::Int32(7), OpcQuality::Good, 1_500);
+        metrics.on_data_change("G", "Name", OpcValue::String("x".to_string()), OpcQuality::Bad, 2_000);
+        metrics.record_read(Duration::from_millis(250), false);
+        metrics.record_connection_event(&ConnectionEvent::Lost { reason: "heartbeat".to_string() });
+
+        let text = metrics.render();
+        assert!(text.contains("# TYPE opcda_item_value gauge\nopcda_item_value{group=\"G\",item=\"Tank\\\"1\\\"\"} 7\n"));
+        assert!(!text.contains("opcda_item_value{group=\"G\",item=\"Name\"}"));
+        assert!(text.contains("opcda_item_quality{group=\"G\",item=\"Name\"} 0\n"));
+        assert!(text.contains("opcda_item_timestamp_seconds{group=\"G\",item=\"Tank\\\"1\\\"\"} 1.5\n"));
+        assert!(text.contains("opcda_data_changes_total{group=\"G\"} 2\n"));
+        assert!(text.contains("opcda_read_duration_seconds_sum 0.25\nopcda_read_duration_seconds_count 1\n"));
+        assert!(text.contains("opcda_read_errors_total 1\n"));
+        assert!(text.contains("opcda_connection_lost_total 1\n"));
+        assert!(text.contains("opcda_reconnects_total 0\n"));
+    }
+
+    #[test]
+    fn test_health() {
+        let metrics = MetricsExporter::new();
+        let start = Instant::now();
+        let health = metrics.health_at(start);
+        assert_eq!(health.connected, None);
+        assert!(health.is_healthy());
+
+        // 窗口内共 50 次回调
+        for _ in 0..49 {
+            metrics.count_callback(start);
+        }
+        metrics.on_data_change("G", "A", OpcValue::Int32(1), OpcQuality::Good, 0);
+        metrics.record_connection_event(&ConnectionEvent::Restored { attempts: 1, failed_items: Vec::new() });
+        metrics.record_connection_event(&ConnectionEvent::Lost { reason: "heartbeat \"timeout\"".to_string() });
+        let health = metrics.health_at(start + Duration::from_secs(10));
+        assert_eq!(health.callbacks_per_sec, 5.0);
+        assert_eq!(health.data_changes, 1);
+        assert_eq!(health.connected, Some(false));
+        assert!(!health.is_healthy());
+        assert_eq!(
+            health.to_json(),
+            "{\"healthy\":false,\"connected\":false,\"callbacks_per_sec\":5,\"data_changes\":1,\"connection_lost\":1,\"reconnects\":1,\"reconnect_failures\":0,\"last_error\":\"heartbeat \\\"timeout\\\"\"}"
+        );
+
+        metrics.record_connection_event(&ConnectionEvent::Restored { attempts: 2, failed_items: Vec::new() });
+        let health = metrics.health_at(start + Duration::from_secs(30));
+        assert!(health.is_healthy());
+        assert_eq!(health.callbacks_per_sec, 0.0);
+        assert_eq!(health.last_error.as_deref(), Some("heartbeat \"timeout\""));
+    }
+
+    #[test]
+    fn test_sample_line_special_values() {
+        let mut out = String::new();
+        sample_line(&mut out, "m", &[("k", "a\\b\nc")], f64::NEG_INFINITY);
+        assert_eq!(out, "m{k=\"a\\\\b\\nc\"} -Inf\n");
+    }
+}
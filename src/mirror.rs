@@ -0,0 +1,606 @@
+//! 服务器镜像模块
+//!
+//! 这个模块提供 OPC DA 到 OPC DA 的镜像工具：订阅源服务器上的项，
+//! 并将数据变化写入目标服务器上的对应项，常用于把旧 DCS 的 DA 服务器
+//! 桥接到新的汇聚 DA 服务器。
+//!
+//! ## 功能
+//!
+//! - 重命名：源项和目标项可以使用不同的项名
+//! - 缩放：数值按 `value * scale + offset` 转换
+//! - 类型转换：按目标项当前值的类型转换写入的值，溢出和精度损失按 `CoercionPolicy` 处理
+//! - 限速：每个目标项可以设置最小写入间隔，间隔内只保留最新值
+//!
+//! ## 线程模型
+//!
+//! OPC 对象不能跨线程使用，而数据变化回调可能在后台线程中调用。
+//! 因此回调只把数据变化放入队列（每个项只保留最新值），
+//! 由创建 `Mirror` 的线程周期性调用 `pump` 执行写入。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::{OpcClient, Mirror, MirrorRule};
+//!
+//! let client = OpcClient::new()?;
+//! let source = client.connect_to_server("old-dcs", "Vendor.OPC.1")?;
+//! let destination = client.connect_to_local_server("Matrikon.OPC.Simulation.1")?;
+//!
+//! let rules = vec![
+//!     MirrorRule::new("FIC101.PV", "Area1.FIC101.PV"),
+//!     MirrorRule {
+//!         scale: 0.1,
+//!         ..MirrorRule::new("TI205.RAW", "Area1.TI205.PV")
+//!     },
+//! ];
+//!
+//! let mirror = Mirror::new(&source, &destination, "Mirror", 1000, rules)?;
+//! loop {
+//!     let report = mirror.pump();
+//!     for (item, error) in &report.errors {
+//!         eprintln!("镜像 {} 失败: {}", item, error);
+//!     }
+//!     std::thread::sleep(std::time::Duration::from_millis(200));
+//! }
+//! ```
+
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use crate::error::{OpcError, OpcResult};
+use crate::group::OpcGroup;
+use crate::item::OpcItem;
+use crate::server::OpcServer;
+use crate::types::{lock_or_recover, OpcDataCallback, OpcDecimal, OpcQuality, OpcValue, OpcValueError};
+
+/// 镜像规则
+///
+/// 描述一个源项到目标项的映射。
+#[derive(Debug, Clone, PartialEq)]
+pub struct MirrorRule {
+    /// 源服务器上的项名
+    pub source: String,
+    /// 目标服务器上的项名
+    pub destination: String,
+    /// 缩放系数
+    pub scale: f64,
+    /// 缩放偏移
+    pub offset: f64,
+    /// 最小写入间隔，`Duration::ZERO` 表示不限速
+    pub min_interval: Duration,
+    /// 类型转换的溢出策略
+    pub coercion: CoercionPolicy,
+}
+
+impl MirrorRule {
+    /// 创建不缩放、不限速、按 `CoercionPolicy::Round` 转换类型的镜像规则
+    pub fn new(source: &str, destination: &str) -> Self {
+        MirrorRule {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            scale: 1.0,
+            offset: 0.0,
+            min_interval: Duration::ZERO,
+            coercion: CoercionPolicy::default(),
+        }
+    }
+
+    /// 规则是否需要缩放
+    pub fn is_scaled(&self) -> bool {
+        self.scale != 1.0 || self.offset != 0.0
+    }
+
+    /// 对值应用缩放
+    ///
+    /// 不需要缩放时原样返回；需要缩放时数值转换为 `Double`，
+    /// 非数值类型返回转换错误。
+    pub fn apply(&self, value: OpcValue) -> Result<OpcValue, OpcValueError> {
+        if !self.is_scaled() {
+            return Ok(value);
+        }
+        match value.as_f64() {
+            Some(v) => Ok(OpcValue::Double(v * self.scale + self.offset)),
+            None => Err(OpcValueError::conversion_error(format!(
+                "Cannot scale {} value",
+                value.type_name()
+            ))),
+        }
+    }
+}
+
+/// 一次 `pump` 调用的结果
+#[derive(Debug, Default)]
+pub struct MirrorReport {
+    /// 成功写入的项数
+    pub written: usize,
+    /// 因限速推迟到下次的项数
+    pub deferred: usize,
+    /// 因源质量不是 Good 而跳过的项数
+    pub skipped: usize,
+    /// 写入前经过舍入或饱和调整的项数
+    pub adjusted: usize,
+    /// 写入失败的目标项及错误
+    pub errors: Vec<(String, OpcError)>,
+}
+
+/// 数值类型转换时溢出或损失精度的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CoercionPolicy {
+    /// 超出目标类型范围或会损失精度（例如小数转换为整数）时返回错误
+    Error,
+    /// 超出范围时取目标类型的最小值或最大值，小数舍入到最接近的整数
+    Saturate,
+    /// 小数舍入到最接近的整数，超出范围时返回错误
+    #[default]
+    Round,
+}
+
+/// 类型转换的结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coerced {
+    /// 转换后的值
+    pub value: OpcValue,
+    /// 值是否经过舍入或饱和调整
+    pub adjusted: bool,
+}
+
+/// 按 `CoercionPolicy::Round` 将值转换为与模板值相同的类型
+///
+/// 见 `coerce_with`。
+pub fn coerce_to(value: OpcValue, template: &OpcValue) -> Result<OpcValue, OpcValueError> {
+    coerce_with(value, template, CoercionPolicy::default()).map(|coerced| coerced.value)
+}
+
+/// 将值转换为与模板值相同的类型
+///
+/// 用于把源项的值转换为目标项的类型。数值之间的转换按 `policy` 处理溢出和精度损失，
+/// 目标为字符串时使用值的文本形式，数组不做转换。
+/// 转换为 `Float` 时只检查范围，不检查有效数字的损失；
+/// 转换为 `Bool` 时，0 以外的值为 `true`，0 和 1 以外的值视为损失精度。
+pub fn coerce_with(value: OpcValue, template: &OpcValue, policy: CoercionPolicy) -> Result<Coerced, OpcValueError> {
+    let exact = |value| Ok(Coerced { value, adjusted: false });
+    if std::mem::discriminant(&value) == std::mem::discriminant(template) {
+        return exact(value);
+    }
+
+    if let OpcValue::String(_) = template {
+        let text = match &value {
+            OpcValue::String(s) => s.clone(),
+            OpcValue::Decimal(d) => d.to_string(),
+            other => match other.as_f64() {
+                Some(v) if !other.is_array() => v.to_string(),
+                _ => return Err(OpcValueError::type_mismatch("String", other.type_name())),
+            },
+        };
+        return exact(OpcValue::String(text));
+    }
+
+    // 文本直接解析为十进制数，不经过浮点数
+    if let (OpcValue::Decimal(_), OpcValue::String(s)) = (template, &value) {
+        return exact(OpcValue::Decimal(s.parse()?));
+    }
+
+    let number = match &value {
+        OpcValue::String(s) => s.trim().parse::<f64>().ok(),
+        other => other.as_f64(),
+    }
+    .ok_or_else(|| OpcValueError::type_mismatch(template.type_name(), value.type_name()))?;
+
+    let out_of_range = || {
+        OpcValueError::conversion_error(format!(
+            "Value {} is out of range for {}",
+            number,
+            template.type_name()
+        ))
+    };
+    let inexact = || {
+        OpcValueError::conversion_error(format!(
+            "Value {} cannot be represented exactly as {}",
+            number,
+            template.type_name()
+        ))
+    };
+    // 将已缩放的数值舍入并检查范围 [min, end)，返回结果和是否调整
+    //
+    // 整数类型的范围以 2 的幂表示，在 f64 中是精确的；`i64::MAX as f64` 会向上舍入到 2^63，
+    // 不能作为闭区间的上界。饱和时返回 `end`，由 `as` 转换饱和到类型的最大值。
+    let fit = |scaled: f64, min: f64, end: f64| -> Result<(f64, bool), OpcValueError> {
+        if scaled.is_nan() {
+            return Err(out_of_range());
+        }
+        let rounded = scaled.round();
+        if rounded < min || rounded >= end {
+            return match policy {
+                CoercionPolicy::Saturate => Ok((rounded.clamp(min, end), true)),
+                _ => Err(out_of_range()),
+            };
+        }
+        if rounded != scaled && policy == CoercionPolicy::Error {
+            return Err(inexact());
+        }
+        Ok((rounded, rounded != scaled))
+    };
+    // 整数之间直接转换，不经过浮点数
+    macro_rules! exact_integer {
+        ($int:expr, $ty:ty) => {
+            match <$ty>::try_from($int) {
+                Ok(fitted) => (fitted, false),
+                Err(_) if policy == CoercionPolicy::Saturate => {
+                    (if $int < 0 { <$ty>::MIN } else { <$ty>::MAX }, true)
+                }
+                Err(_) => return Err(out_of_range()),
+            }
+        };
+    }
+    macro_rules! integer {
+        ($variant:ident, $ty:ty) => {{
+            let (fitted, adjusted) = match integer_value(&value) {
+                Some(int) => exact_integer!(int, $ty),
+                None => {
+                    let end = (<$ty>::MAX / 2 + 1) as f64 * 2.0;
+                    let (fitted, adjusted) = fit(number, <$ty>::MIN as f64, end)?;
+                    (fitted as $ty, adjusted)
+                }
+            };
+            (OpcValue::$variant(fitted), adjusted)
+        }};
+    }
+
+    let (value, adjusted) = match template {
+        OpcValue::Int8(_) => integer!(Int8, i8),
+        OpcValue::UInt8(_) => integer!(UInt8, u8),
+        OpcValue::Int16(_) => integer!(Int16, i16),
+        OpcValue::UInt16(_) => integer!(UInt16, u16),
+        OpcValue::Int32(_) => integer!(Int32, i32),
+        OpcValue::UInt32(_) => integer!(UInt32, u32),
+        OpcValue::Int64(_) => integer!(Int64, i64),
+        OpcValue::UInt64(_) => integer!(UInt64, u64),
+        OpcValue::INT(_) => integer!(INT, isize),
+        OpcValue::UINT(_) => integer!(UINT, usize),
+        OpcValue::Float(_) => {
+            let max = f32::MAX as f64;
+            if number.is_finite() && number.abs() > max {
+                match policy {
+                    CoercionPolicy::Saturate => (OpcValue::Float(number.clamp(-max, max) as f32), true),
+                    _ => return Err(out_of_range()),
+                }
+            } else {
+                (OpcValue::Float(number as f32), false)
+            }
+        }
+        OpcValue::Double(_) => (OpcValue::Double(number), false),
+        OpcValue::Bool(_) => {
+            let lossy = number != 0.0 && number != 1.0;
+            if lossy && policy == CoercionPolicy::Error {
+                return Err(inexact());
+            }
+            (OpcValue::Bool(number != 0.0), lossy)
+        }
+        OpcValue::Date(_) => (OpcValue::Date(number), false),
+        OpcValue::Decimal(_) => (OpcValue::Decimal(OpcDecimal::from_f64(number)?), false),
+        OpcValue::Cy(_) => {
+            let (fitted, adjusted) = match integer_value(&value) {
+                Some(int) => exact_integer!(int * 10000, i64),
+                None => {
+                    let (fitted, adjusted) = fit(number * 10000.0, i64::MIN as f64, 2f64.powi(63))?;
+                    (fitted as i64, adjusted)
+                }
+            };
+            (OpcValue::Cy(fitted), adjusted)
+        }
+        _ => return Err(OpcValueError::type_mismatch(template.type_name(), value.type_name())),
+    };
+    Ok(Coerced { value, adjusted })
+}
+
+/// 整数值，其他类型为 `None`
+fn integer_value(value: &OpcValue) -> Option<i128> {
+    match value {
+        OpcValue::Int8(v) => Some(*v as i128),
+        OpcValue::UInt8(v) => Some(*v as i128),
+        OpcValue::Int16(v) => Some(*v as i128),
+        OpcValue::UInt16(v) => Some(*v as i128),
+        OpcValue::Int32(v) => Some(*v as i128),
+        OpcValue::UInt32(v) => Some(*v as i128),
+        OpcValue::Int64(v) => Some(*v as i128),
+        OpcValue::UInt64(v) => Some(*v as i128),
+        OpcValue::INT(v) => Some(*v as i128),
+        OpcValue::UINT(v) => Some(*v as i128),
+        _ => None,
+    }
+}
+
+/// 检查规则列表：至少一条规则，每个源项只能出现一次
+///
+/// 目标项按源项名查找，同一个源项的多条规则会互相覆盖。
+fn check_rules(rules: &[MirrorRule]) -> OpcResult<()> {
+    if rules.is_empty() {
+        return Err(OpcError::invalid_parameters("Mirror requires at least one rule"));
+    }
+    let mut sources = HashSet::with_capacity(rules.len());
+    match rules.iter().find(|rule| !sources.insert(rule.source.as_str())) {
+        Some(rule) => Err(OpcError::invalid_parameters(format!(
+            "Source item '{}' is used by more than one mirror rule",
+            rule.source
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// 源订阅回调，只保留每个项的最新数据变化
+struct MirrorQueue {
+    latest: Mutex<HashMap<String, (OpcValue, OpcQuality)>>,
+}
+
+impl OpcDataCallback for MirrorQueue {
+    fn on_data_change(&self, _group_name: &str, item_name: &str, value: OpcValue, quality: OpcQuality, _timestamp: u64) {
+        lock_or_recover(&self.latest).insert(item_name.to_string(), (value, quality));
+    }
+}
+
+/// 一个目标项的镜像状态
+struct MirrorTarget {
+    rule: MirrorRule,
+    item: OpcItem,
+    /// 目标项的值类型模板，创建时读取失败则为 `None`（不做类型转换）
+    template: Option<OpcValue>,
+    last_write: Cell<Option<Instant>>,
+}
+
+/// OPC DA 到 OPC DA 的镜像
+///
+/// 在源服务器和目标服务器上各创建一个组，订阅源项，
+/// 并在 `pump` 中将数据变化写入目标项。释放 `Mirror` 时两个组一起释放。
+pub struct Mirror {
+    /// 按源项名索引的目标项，必须先于组释放
+    targets: HashMap<String, MirrorTarget>,
+    /// 源订阅的数据变化队列
+    queue: Arc<MirrorQueue>,
+    /// 源项，必须先于组释放
+    _source_items: Vec<OpcItem>,
+    source_group: OpcGroup,
+    destination_group: OpcGroup,
+}
+
+impl Mirror {
+    /// 创建镜像并启用源订阅
+    ///
+    /// # 参数
+    /// - `source`: 源服务器
+    /// - `destination`: 目标服务器
+    /// - `group_name`: 在两个服务器上创建的组名
+    /// - `update_rate`: 源组的更新速率（毫秒）
+    /// - `rules`: 镜像规则
+    ///
+    /// # 返回值
+    /// - `Ok(Mirror)`: 所有源项和目标项都已添加，订阅已启用
+    /// - `Err(OpcError::InvalidParameters)`: 没有规则，或多条规则使用同一个源项
+    /// - `Err(OpcError)`: 创建组、添加项或启用订阅失败
+    ///
+    /// # 注意
+    /// 创建时会读取一次每个目标项，以确定写入时使用的值类型。
+    pub fn new(
+        source: &OpcServer,
+        destination: &OpcServer,
+        group_name: &str,
+        update_rate: u32,
+        rules: Vec<MirrorRule>,
+    ) -> OpcResult<Self> {
+        check_rules(&rules)?;
+
+        let source_group = source.create_group(group_name, true, update_rate, 0.0)?;
+        let destination_group = destination.create_group(group_name, false, update_rate, 0.0)?;
+
+        let mut source_items = Vec::with_capacity(rules.len());
+        let mut targets = HashMap::with_capacity(rules.len());
+        for rule in rules {
+            source_items.push(source_group.add_item(&rule.source)?);
+            let item = destination_group.add_item(&rule.destination)?;
+            let template = item.read_sync().ok().map(|(value, _, _)| value);
+            targets.insert(
+                rule.source.clone(),
+                MirrorTarget {
+                    rule,
+                    item,
+                    template,
+                    last_write: Cell::new(None),
+                },
+            );
+        }
+
+        let queue = Arc::new(MirrorQueue {
+            latest: Mutex::new(HashMap::new()),
+        });
+        source_group.enable_async_subscription(queue.clone())?;
+
+        Ok(Mirror {
+            targets,
+            queue,
+            _source_items: source_items,
+            source_group,
+            destination_group,
+        })
+    }
+
+    /// 将排队的数据变化写入目标服务器
+    ///
+    /// 必须在创建 `Mirror` 的线程中调用。每个项只写入最新值；
+    /// 未到最小写入间隔的项保留在队列中，下次调用时再写入。
+    /// 单个项的失败不会影响其他项，错误在结果中返回。
+    pub fn pump(&self) -> MirrorReport {
+        let pending = std::mem::take(&mut *lock_or_recover(&self.queue.latest));
+        let mut report = MirrorReport::default();
+        let mut deferred = HashMap::new();
+
+        for (source_name, (value, quality)) in pending {
+            let Some(target) = self.targets.get(&source_name) else {
+                continue;
+            };
+            if quality != OpcQuality::Good {
+                report.skipped += 1;
+                continue;
+            }
+            if let Some(last_write) = target.last_write.get() {
+                if last_write.elapsed() < target.rule.min_interval {
+                    deferred.insert(source_name, (value, quality));
+                    report.deferred += 1;
+                    continue;
+                }
+            }
+
+            match self.write_target(target, value) {
+                Ok(adjusted) => {
+                    report.written += 1;
+                    report.adjusted += usize::from(adjusted);
+                }
+                Err(e) => report.errors.push((target.rule.destination.clone(), e)),
+            }
+        }
+
+        if !deferred.is_empty() {
+            let mut latest = lock_or_recover(&self.queue.latest);
+            for (name, change) in deferred {
+                // 推迟期间到达的新值优先
+                latest.entry(name).or_insert(change);
+            }
+        }
+
+        report
+    }
+
+    /// 写入一个目标项，返回值是否经过舍入或饱和调整
+    fn write_target(&self, target: &MirrorTarget, value: OpcValue) -> OpcResult<bool> {
+        let mut value = target.rule.apply(value)?;
+        let mut adjusted = false;
+        if let Some(template) = &target.template {
+            let coerced = coerce_with(value, template, target.rule.coercion)?;
+            value = coerced.value;
+            adjusted = coerced.adjusted;
+        }
+        target.item.write_sync(&value)?;
+        target.last_write.set(Some(Instant::now()));
+        Ok(adjusted)
+    }
+
+    /// 源组
+    pub fn source_group(&self) -> &OpcGroup {
+        &self.source_group
+    }
+
+    /// 目标组
+    pub fn destination_group(&self) -> &OpcGroup {
+        &self.destination_group
+    }
+
+    /// 镜像规则
+    pub fn rules(&self) -> Vec<&MirrorRule> {
+        self.targets.values().map(|target| &target.rule).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_scaling() {
+        let rule = MirrorRule::new("Raw", "Scaled");
+        assert_eq!(rule.apply(OpcValue::Int16(5)).unwrap(), OpcValue::Int16(5));
+
+        let scaled = MirrorRule {
+            scale: 0.5,
+            offset: 1.0,
+            ..rule
+        };
+        assert_eq!(scaled.apply(OpcValue::Int16(5)).unwrap(), OpcValue::Double(3.5));
+        assert!(scaled.apply(OpcValue::String("x".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_rules_reject_duplicate_sources() {
+        assert!(matches!(check_rules(&[]), Err(OpcError::InvalidParameters(_))));
+        let rules = vec![MirrorRule::new("A", "X"), MirrorRule::new("B", "X")];
+        assert!(check_rules(&rules).is_ok());
+        let rules = vec![MirrorRule::new("A", "X"), MirrorRule::new("B", "Y"), MirrorRule::new("A", "Z")];
+        assert!(matches!(check_rules(&rules), Err(OpcError::InvalidParameters(msg)) if msg.contains("'A'")));
+    }
+
+    #[test]
+    fn test_coerce_to_destination_type() {
+        assert_eq!(coerce_to(OpcValue::Double(41.6), &OpcValue::Int16(0)).unwrap(), OpcValue::Int16(42));
+        assert_eq!(coerce_to(OpcValue::Int32(1), &OpcValue::Bool(false)).unwrap(), OpcValue::Bool(true));
+        assert_eq!(coerce_to(OpcValue::String("2.5".to_string()), &OpcValue::Float(0.0)).unwrap(), OpcValue::Float(2.5));
+        assert_eq!(coerce_to(OpcValue::Int32(7), &OpcValue::String(String::new())).unwrap(), OpcValue::String("7".to_string()));
+        assert_eq!(coerce_to(OpcValue::Double(1.5), &OpcValue::Cy(0)).unwrap(), OpcValue::Cy(15000));
+        assert!(coerce_to(OpcValue::Int32(300), &OpcValue::UInt8(0)).is_err());
+        assert!(coerce_to(OpcValue::ArrayInt32(vec![1]), &OpcValue::Int32(0)).is_err());
+    }
+
+    #[test]
+    fn test_coercion_policies() {
+        let int16 = OpcValue::Int16(0);
+        let coerce = |value, policy| coerce_with(value, &int16, policy);
+
+        assert!(coerce(OpcValue::Double(41.6), CoercionPolicy::Error).is_err());
+        assert_eq!(
+            coerce(OpcValue::Double(42.0), CoercionPolicy::Error).unwrap(),
+            Coerced { value: OpcValue::Int16(42), adjusted: false }
+        );
+        assert_eq!(
+            coerce(OpcValue::Double(41.6), CoercionPolicy::Round).unwrap(),
+            Coerced { value: OpcValue::Int16(42), adjusted: true }
+        );
+        assert!(coerce(OpcValue::Double(40000.0), CoercionPolicy::Round).is_err());
+        assert_eq!(
+            coerce(OpcValue::Double(40000.0), CoercionPolicy::Saturate).unwrap(),
+            Coerced { value: OpcValue::Int16(i16::MAX), adjusted: true }
+        );
+        assert_eq!(
+            coerce_with(OpcValue::Int32(-1), &OpcValue::UInt8(0), CoercionPolicy::Saturate).unwrap().value,
+            OpcValue::UInt8(0)
+        );
+        assert!(coerce(OpcValue::Double(f64::NAN), CoercionPolicy::Saturate).is_err());
+        assert!(coerce_with(OpcValue::Double(1e300), &OpcValue::Float(0.0), CoercionPolicy::Round).is_err());
+        assert!(coerce_with(OpcValue::Int32(2), &OpcValue::Bool(false), CoercionPolicy::Error).is_err());
+        assert_eq!(
+            coerce_with(OpcValue::Double(1.23456), &OpcValue::Cy(0), CoercionPolicy::Round).unwrap(),
+            Coerced { value: OpcValue::Cy(12346), adjusted: true }
+        );
+    }
+
+    #[test]
+    fn test_coercion_64bit_bounds() {
+        // 2^63 超出 i64 的范围
+        let two_63 = 2f64.powi(63);
+        assert!(coerce_with(OpcValue::Double(two_63), &OpcValue::Int64(0), CoercionPolicy::Round).is_err());
+        assert_eq!(
+            coerce_with(OpcValue::Double(two_63), &OpcValue::Int64(0), CoercionPolicy::Saturate).unwrap(),
+            Coerced { value: OpcValue::Int64(i64::MAX), adjusted: true }
+        );
+        assert_eq!(
+            coerce_with(OpcValue::Double(-two_63), &OpcValue::Int64(0), CoercionPolicy::Error).unwrap().value,
+            OpcValue::Int64(i64::MIN)
+        );
+        assert!(coerce_with(OpcValue::Double(2f64.powi(64)), &OpcValue::UInt64(0), CoercionPolicy::Round).is_err());
+
+        // 整数之间的转换保留全部精度
+        assert_eq!(
+            coerce_with(OpcValue::UInt64(u64::MAX - 1), &OpcValue::Int64(0), CoercionPolicy::Saturate).unwrap(),
+            Coerced { value: OpcValue::Int64(i64::MAX), adjusted: true }
+        );
+        assert!(coerce_with(OpcValue::UInt64(u64::MAX), &OpcValue::Int64(0), CoercionPolicy::Round).is_err());
+        assert_eq!(
+            coerce_with(OpcValue::Int64(i64::MAX - 1), &OpcValue::UInt64(0), CoercionPolicy::Error).unwrap().value,
+            OpcValue::UInt64(i64::MAX as u64 - 1)
+        );
+        assert_eq!(
+            coerce_with(OpcValue::UInt64(9_007_199_254_740_993), &OpcValue::Int64(0), CoercionPolicy::Error).unwrap().value,
+            OpcValue::Int64(9_007_199_254_740_993)
+        );
+        assert_eq!(
+            coerce_with(OpcValue::Int64(i64::MAX), &OpcValue::Cy(0), CoercionPolicy::Saturate).unwrap(),
+            Coerced { value: OpcValue::Cy(i64::MAX), adjusted: true }
+        );
+    }
+}
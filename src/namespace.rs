@@ -0,0 +1,223 @@
+//! 命名空间导出模块
+//!
+//! 这个模块将服务器浏览得到的项名列表导出为 CSV 或 JSON，
+//! 用于工程文档和离线编辑配置。
+//!
+//! 每个项导出为一条记录，包含完整的项 ID、按分隔符拆分的路径和最后一级名称；
+//! `JsonTree` 格式（需要 `serde` 特性）则按路径组织为嵌套的树，由 `serde_json` 生成。
+//! 工具库的浏览接口只返回项名，因此不包含项属性（数据类型、访问权限等）。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::NamespaceFormat;
+//!
+//! let count = server.export_namespace("namespace.csv", NamespaceFormat::Csv)?;
+//! println!("导出了 {} 个项", count);
+//! ```
+
+#[cfg(feature = "serde")]
+use std::collections::BTreeMap;
+use crate::item_id::ItemIdRules;
+
+/// 命名空间导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NamespaceFormat {
+    /// CSV，列为 `item_id,path,name`，路径各级用 `/` 连接
+    Csv,
+    /// JSON 数组，每个元素为 `{"item_id", "path", "name"}`，路径为字符串数组
+    Json,
+    /// JSON 树，每个节点为 `{"name", "item_id"?, "children"?}`，同级节点按名称排序；
+    /// 只有项对应的节点有 `item_id`，只有分支节点有 `children`
+    #[cfg(feature = "serde")]
+    JsonTree,
+}
+
+/// 命名空间中的一个项
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamespaceEntry {
+    /// 完整的项 ID
+    pub item_id: String,
+    /// 项所在的路径（不含最后一级名称）
+    pub path: Vec<String>,
+    /// 最后一级名称
+    pub name: String,
+}
+
+impl NamespaceEntry {
+    /// 按项 ID 约定拆分项 ID
+    pub fn from_item_id(item_id: &str, rules: &ItemIdRules) -> Self {
+        let mut segments: Vec<String> = item_id
+            .split(rules.separator)
+            .map(|segment| segment.to_string())
+            .collect();
+        let name = segments.pop().unwrap_or_default();
+        NamespaceEntry {
+            item_id: item_id.to_string(),
+            path: segments,
+            name,
+        }
+    }
+}
+
+/// 将项名列表渲染为指定格式的文本
+pub fn render_namespace<S: AsRef<str>>(item_names: &[S], rules: &ItemIdRules, format: NamespaceFormat) -> String {
+    let entries = item_names
+        .iter()
+        .map(|name| NamespaceEntry::from_item_id(name.as_ref(), rules));
+
+    match format {
+        NamespaceFormat::Csv => {
+            let mut out = String::from("item_id,path,name\n");
+            for entry in entries {
+                out.push_str(&format!(
+                    "{},{},{}\n",
+                    csv_field(&entry.item_id),
+                    csv_field(&entry.path.join("/")),
+                    csv_field(&entry.name)
+                ));
+            }
+            out
+        }
+        NamespaceFormat::Json => {
+            let records: Vec<String> = entries
+                .map(|entry| {
+                    let path: Vec<String> = entry.path.iter().map(|s| json_string(s)).collect();
+                    format!(
+                        "  {{\"item_id\": {}, \"path\": [{}], \"name\": {}}}",
+                        json_string(&entry.item_id),
+                        path.join(", "),
+                        json_string(&entry.name)
+                    )
+                })
+                .collect();
+            if records.is_empty() {
+                "[]\n".to_string()
+            } else {
+                format!("[\n{}\n]\n", records.join(",\n"))
+            }
+        }
+        #[cfg(feature = "serde")]
+        NamespaceFormat::JsonTree => {
+            let mut root = TreeNode::default();
+            for entry in entries {
+                let mut node = &mut root;
+                for segment in entry.path.iter().chain(std::iter::once(&entry.name)) {
+                    node = node.children.entry(segment.clone()).or_default();
+                }
+                node.item_id = Some(entry.item_id);
+            }
+            // 节点只含字符串，序列化不会失败
+            let mut out = serde_json::to_string_pretty(&tree_nodes(&root.children)).unwrap_or_default();
+            out.push('\n');
+            out
+        }
+    }
+}
+
+/// 命名空间树的节点
+#[cfg(feature = "serde")]
+#[derive(Default)]
+struct TreeNode {
+    item_id: Option<String>,
+    children: BTreeMap<String, TreeNode>,
+}
+
+/// JSON 树中的一个节点
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+struct TreeJson<'a> {
+    name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    item_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    children: Vec<TreeJson<'a>>,
+}
+
+/// 把同级节点转换为按名称排序的 JSON 节点
+#[cfg(feature = "serde")]
+fn tree_nodes(nodes: &BTreeMap<String, TreeNode>) -> Vec<TreeJson<'_>> {
+    nodes
+        .iter()
+        .map(|(name, node)| TreeJson {
+            name,
+            item_id: node.item_id.as_deref(),
+            children: tree_nodes(&node.children),
+        })
+        .collect()
+}
+
+/// CSV 字段转义（RFC 4180）
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// JSON 字符串转义
+pub(crate) fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_namespace_csv() {
+        let rules = ItemIdRules::default();
+        let csv = render_namespace(&["Random.Int2", "Plant.Line, 1.Speed", "Root"], &rules, NamespaceFormat::Csv);
+        assert_eq!(
+            csv,
+            "item_id,path,name\nRandom.Int2,Random,Int2\n\"Plant.Line, 1.Speed\",\"Plant/Line, 1\",Speed\nRoot,,Root\n"
+        );
+    }
+
+    #[test]
+    fn test_render_namespace_json() {
+        let rules = ItemIdRules {
+            separator: '/',
+            ..Default::default()
+        };
+        let json = render_namespace(&["Channel1/Dev\"1\"/Tag"], &rules, NamespaceFormat::Json);
+        assert_eq!(
+            json,
+            "[\n  {\"item_id\": \"Channel1/Dev\\\"1\\\"/Tag\", \"path\": [\"Channel1\", \"Dev\\\"1\\\"\"], \"name\": \"Tag\"}\n]\n"
+        );
+        assert_eq!(render_namespace::<&str>(&[], &rules, NamespaceFormat::Json), "[]\n");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_render_namespace_json_tree() {
+        let rules = ItemIdRules::default();
+        let json = render_namespace(&["Random.Int2", "Random", "Bucket.A.B"], &rules, NamespaceFormat::JsonTree);
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, serde_json::json!([
+            {"name": "Bucket", "children": [
+                {"name": "A", "children": [{"name": "B", "item_id": "Bucket.A.B"}]}
+            ]},
+            {"name": "Random", "item_id": "Random", "children": [
+                {"name": "Int2", "item_id": "Random.Int2"}
+            ]}
+        ]));
+        assert!(json.find("Bucket").unwrap() < json.find("Random").unwrap());
+        assert_eq!(render_namespace::<&str>(&[], &rules, NamespaceFormat::JsonTree), "[]\n");
+    }
+}
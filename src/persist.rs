@@ -0,0 +1,159 @@
+//! 状态持久化模块
+//!
+//! 这个模块提供计算通道（累积量、计数器、统计值）的检查点功能：
+//! 周期性地将状态保存到磁盘，并在重启后恢复，使网关重启不会清零生产计数。
+//!
+//! ## 原子写入
+//!
+//! 检查点先写入同目录下的临时文件并刷新到磁盘，再重命名为目标文件。
+//! 写入过程中断电或崩溃时，目标文件要么是旧的完整检查点，要么是新的完整检查点。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::{ComputedChannels, Checkpointer};
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! let channels = Arc::new(ComputedChannels::new());
+//! channels.track("FIC101.PV");
+//!
+//! let mut checkpointer = Checkpointer::new("totals.ckpt", Duration::from_secs(60));
+//! checkpointer.restore(&*channels)?;
+//!
+//! loop {
+//!     // ... 处理其他工作 ...
+//!     checkpointer.maybe_save(&*channels)?;
+//! }
+//! ```
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use crate::error::OpcResult;
+
+/// 可以保存和恢复检查点的状态
+pub trait Checkpoint {
+    /// 将当前状态编码为检查点文本
+    fn checkpoint(&self) -> String;
+
+    /// 从检查点文本恢复状态
+    ///
+    /// 返回恢复的条目数。格式错误时返回 `OpcError::InvalidParameters`。
+    fn restore(&self, data: &str) -> OpcResult<usize>;
+
+    /// 是否需要在间隔到期之前保存，`Checkpointer::maybe_save` 此时立即保存
+    ///
+    /// 默认为 `false`。
+    fn checkpoint_due(&self) -> bool {
+        false
+    }
+
+    /// `checkpoint` 返回的文本已经写入磁盘后调用，默认不做任何处理
+    fn checkpoint_saved(&self) {}
+}
+
+/// 原子地写入文件
+///
+/// 先写入 `<path>.tmp` 并同步到磁盘，再重命名为 `path`。
+pub fn write_atomic(path: &Path, contents: &[u8]) -> OpcResult<()> {
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+
+    {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+    }
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// 周期性检查点
+///
+/// 记录上次保存的时间，`maybe_save` 只在超过间隔后才写入磁盘，
+/// 可以在应用的主循环中频繁调用。
+#[derive(Debug)]
+pub struct Checkpointer {
+    path: PathBuf,
+    interval: Duration,
+    last_saved: Option<Instant>,
+}
+
+impl Checkpointer {
+    /// 创建检查点，保存到 `path`，间隔为 `interval`
+    pub fn new(path: impl Into<PathBuf>, interval: Duration) -> Self {
+        Checkpointer {
+            path: path.into(),
+            interval,
+            last_saved: None,
+        }
+    }
+
+    /// 检查点文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 立即保存检查点
+    pub fn save<C: Checkpoint + ?Sized>(&mut self, state: &C) -> OpcResult<()> {
+        write_atomic(&self.path, state.checkpoint().as_bytes())?;
+        state.checkpoint_saved();
+        self.last_saved = Some(Instant::now());
+        Ok(())
+    }
+
+    /// 距上次保存超过间隔，或状态要求保存（`Checkpoint::checkpoint_due`）时保存检查点，返回是否保存
+    pub fn maybe_save<C: Checkpoint + ?Sized>(&mut self, state: &C) -> OpcResult<bool> {
+        let due = state.checkpoint_due()
+            || self
+                .last_saved
+                .is_none_or(|last_saved| last_saved.elapsed() >= self.interval);
+        if due {
+            self.save(state)?;
+        }
+        Ok(due)
+    }
+
+    /// 从检查点文件恢复状态
+    ///
+    /// 文件不存在时视为首次启动，返回 `Ok(0)`。
+    pub fn restore<C: Checkpoint + ?Sized>(&self, state: &C) -> OpcResult<usize> {
+        match fs::read_to_string(&self.path) {
+            Ok(data) => state.restore(&data),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compute::ComputedChannels;
+    use crate::types::{OpcDataCallback, OpcQuality, OpcValue};
+
+    #[test]
+    fn test_checkpoint_round_trip() {
+        let path = std::env::temp_dir().join(format!("opcda-checkpoint-{}.ckpt", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let channels = ComputedChannels::new();
+        channels.track("Flow");
+        channels.on_data_change("G", "Flow", OpcValue::Double(2.5), OpcQuality::Good, 0);
+        channels.on_data_change("G", "Flow", OpcValue::Double(0.0), OpcQuality::Good, 4_000);
+
+        let mut checkpointer = Checkpointer::new(&path, Duration::from_secs(3600));
+        assert_eq!(checkpointer.restore(&channels).unwrap(), 0);
+        assert!(checkpointer.maybe_save(&channels).unwrap());
+        assert!(!checkpointer.maybe_save(&channels).unwrap());
+
+        let restored = ComputedChannels::new();
+        assert_eq!(checkpointer.restore(&restored).unwrap(), 1);
+        assert_eq!(restored.total("Flow", Duration::from_secs(1)), Some(10.0));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
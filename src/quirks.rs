@@ -0,0 +1,208 @@
+//! 厂商兼容性（Quirk）模块
+//!
+//! 不同厂商的 OPC DA 服务器在实现细节上存在差异，部分服务器有已知的缺陷。
+//! 这个模块提供了按厂商信息（`GetStatus` 返回的 vendor 字符串）匹配的兼容性配置，
+//! 由库在内部自动应用相应的规避措施，避免在每个应用中重复编写这些处理逻辑。
+//!
+//! ## 支持的规避措施
+//!
+//! - `refresh_after_subscribe`: 启用订阅后立即刷新组，获取初始值
+//! - `ignore_server_timestamps`: 忽略服务器时间戳，使用本地接收时间
+//! - `deadband_ignored`: 服务器忽略死区设置，在客户端按相对变化过滤数据变化
+//!   （变化量超过上次分发值的死区百分比才分发；不是按 EU 量程计算的 OPC 死区）
+//!
+//! ## 内置配置
+//!
+//! - Matrikon: 订阅后不会主动推送初始值，需要刷新
+//! - Kepware: 部分版本忽略组的百分比死区
+//! - RSLinx: 时间戳不可靠，使用本地时间
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::{OpcClient, QuirkProfile};
+//!
+//! let client = OpcClient::new()?;
+//! let server = client.connect_to_local_server("Matrikon.OPC.Simulation.1")?;
+//!
+//! // 根据厂商信息自动选择兼容性配置
+//! let profile = server.detect_quirks()?;
+//! println!("使用兼容性配置: {}", profile.name);
+//!
+//! // 或者手动指定
+//! server.set_quirks(QuirkProfile::kepware());
+//! ```
+
+/// 厂商兼容性配置
+///
+/// 描述某一类服务器需要的规避措施。`vendor_patterns` 中的任一模式
+/// 出现在服务器厂商信息中（不区分大小写）即视为匹配。
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct QuirkProfile {
+    /// 配置名称，用于日志和诊断
+    pub name: String,
+    /// 厂商信息匹配模式（不区分大小写的子串匹配）
+    pub vendor_patterns: Vec<String>,
+    /// 启用异步订阅后立即刷新组
+    pub refresh_after_subscribe: bool,
+    /// 忽略服务器提供的时间戳，改用本地接收时间
+    pub ignore_server_timestamps: bool,
+    /// 服务器不执行死区过滤，由客户端按相对变化过滤
+    ///
+    /// 工具包不提供项的 EU 量程，客户端无法实现 OPC 死区。变化量超过上次分发值
+    /// 绝对值的死区百分比才分发，因此接近零的值几乎每次变化都会分发。
+    pub deadband_ignored: bool,
+}
+
+impl QuirkProfile {
+    /// 创建不包含任何规避措施的空配置
+    pub fn none() -> Self {
+        QuirkProfile {
+            name: "none".to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Matrikon 服务器的内置配置
+    pub fn matrikon() -> Self {
+        QuirkProfile {
+            name: "Matrikon".to_string(),
+            vendor_patterns: vec!["matrikon".to_string()],
+            refresh_after_subscribe: true,
+            ..Default::default()
+        }
+    }
+
+    /// Kepware (KEPServerEX) 服务器的内置配置
+    pub fn kepware() -> Self {
+        QuirkProfile {
+            name: "Kepware".to_string(),
+            vendor_patterns: vec!["kepware".to_string(), "kepserver".to_string()],
+            deadband_ignored: true,
+            ..Default::default()
+        }
+    }
+
+    /// RSLinx 服务器的内置配置
+    pub fn rslinx() -> Self {
+        QuirkProfile {
+            name: "RSLinx".to_string(),
+            vendor_patterns: vec!["rslinx".to_string(), "rockwell".to_string()],
+            ignore_server_timestamps: true,
+            ..Default::default()
+        }
+    }
+
+    /// 所有内置配置
+    pub fn builtin() -> Vec<QuirkProfile> {
+        vec![Self::matrikon(), Self::kepware(), Self::rslinx()]
+    }
+
+    /// 检查配置是否匹配给定的厂商信息
+    pub fn matches(&self, vendor_info: &str) -> bool {
+        let vendor = vendor_info.to_lowercase();
+        self.vendor_patterns
+            .iter()
+            .any(|pattern| !pattern.is_empty() && vendor.contains(&pattern.to_lowercase()))
+    }
+
+    /// 在内置配置中查找匹配的配置，找不到时返回空配置
+    pub fn for_vendor(vendor_info: &str) -> Self {
+        QuirkRegistry::new().lookup(vendor_info)
+    }
+
+    /// 是否包含任何规避措施
+    pub fn is_empty(&self) -> bool {
+        !self.refresh_after_subscribe && !self.ignore_server_timestamps && !self.deadband_ignored
+    }
+}
+
+/// 兼容性配置注册表
+///
+/// 包含内置配置，并允许应用注册自定义配置。
+/// 用户注册的配置优先于内置配置匹配。
+#[derive(Debug, Clone)]
+pub struct QuirkRegistry {
+    profiles: Vec<QuirkProfile>,
+}
+
+impl QuirkRegistry {
+    /// 创建包含内置配置的注册表
+    pub fn new() -> Self {
+        QuirkRegistry {
+            profiles: QuirkProfile::builtin(),
+        }
+    }
+
+    /// 创建空注册表（不包含内置配置）
+    pub fn empty() -> Self {
+        QuirkRegistry {
+            profiles: Vec::new(),
+        }
+    }
+
+    /// 注册自定义配置，优先于已有配置匹配
+    pub fn register(&mut self, profile: QuirkProfile) {
+        self.profiles.insert(0, profile);
+    }
+
+    /// 查找与厂商信息匹配的第一个配置，找不到时返回空配置
+    pub fn lookup(&self, vendor_info: &str) -> QuirkProfile {
+        self.profiles
+            .iter()
+            .find(|profile| profile.matches(vendor_info))
+            .cloned()
+            .unwrap_or_else(QuirkProfile::none)
+    }
+
+    /// 已注册的所有配置
+    pub fn profiles(&self) -> &[QuirkProfile] {
+        &self.profiles
+    }
+}
+
+impl Default for QuirkRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_profiles_match_vendor() {
+        assert_eq!(QuirkProfile::for_vendor("Matrikon Consulting Inc (780) 448-1010").name, "Matrikon");
+        assert_eq!(QuirkProfile::for_vendor("KEPServerEX V6").name, "Kepware");
+        assert_eq!(QuirkProfile::for_vendor("Rockwell Software RSLinx").name, "RSLinx");
+
+        let unknown = QuirkProfile::for_vendor("Some Other Vendor");
+        assert_eq!(unknown.name, "none");
+        assert!(unknown.is_empty());
+    }
+
+    #[test]
+    fn test_registry_custom_profile_takes_priority() {
+        let mut registry = QuirkRegistry::new();
+        registry.register(QuirkProfile {
+            name: "PatchedMatrikon".to_string(),
+            vendor_patterns: vec!["Matrikon".to_string()],
+            ..Default::default()
+        });
+
+        let profile = registry.lookup("matrikon simulation server");
+        assert_eq!(profile.name, "PatchedMatrikon");
+        assert!(!profile.refresh_after_subscribe);
+    }
+
+    #[test]
+    fn test_empty_pattern_never_matches() {
+        let profile = QuirkProfile {
+            vendor_patterns: vec![String::new()],
+            ..Default::default()
+        };
+        assert!(!profile.matches("anything"));
+        assert!(QuirkRegistry::empty().lookup("Matrikon").is_empty());
+    }
+}
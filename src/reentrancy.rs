@@ -0,0 +1,214 @@
+//! 回调重入保护模块
+//!
+//! 在数据变化回调中同步调用库的接口（例如在回调里对同一个组执行 `read_sync`）
+//! 可能使服务器的回调线程等待自己，导致死锁，而且很难定位。
+//! 库在分发回调期间记录当前线程正在分发哪个服务器连接的通知，这期间对该服务器
+//! 及其组和项的调用直接返回 `OpcError::ReentrantCall`，错误信息中包含被拒绝的操作名。
+//! 对其他服务器连接的调用不受影响。
+//!
+//! 确实需要在收到数据变化后访问同一个服务器时，可以：
+//!
+//! - 调用 `defer`，操作交给启用订阅的线程，在该线程调用 `run_deferred` 时执行
+//! - 把请求发给其他线程，例如 `Writer` 或 `async` 特性的工作线程
+//!
+//! 不访问服务器的接口（例如有效期内的 `read_cached`、质量汇总）不受限制。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::reentrancy;
+//!
+//! impl OpcDataCallback for Interlock {
+//!     fn on_data_change(&self, _group: &str, item: &str, value: OpcValue, _q: OpcQuality, _ts: u64) {
+//!         if item == "Tank1.Level" && value.as_f64() > Some(95.0) {
+//!             // 回调在服务器的回调线程中执行，访问不到创建组的线程中的项。
+//!             // 操作交给启用订阅的线程，PUMP 是该线程中的线程局部项
+//!             reentrancy::defer(|| {
+//!                 PUMP.with(|pump| pump.write_sync(&OpcValue::Bool(false)).ok());
+//!             });
+//!         }
+//!     }
+//! }
+//!
+//! // 创建组并启用订阅的线程
+//! loop {
+//!     reentrancy::run_deferred();
+//!     std::thread::sleep(Duration::from_millis(100));
+//! }
+//! ```
+
+use std::cell::RefCell;
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
+use crate::error::{OpcError, OpcResult};
+use crate::types::lock_or_recover;
+
+/// 等待执行的操作
+type Deferred = Box<dyn FnOnce() + Send>;
+
+/// 正在分发的回调
+#[derive(Debug, Clone, Copy)]
+struct Dispatch {
+    /// 通知所属的服务器连接，`None` 表示未知
+    server: Option<u64>,
+    /// 启用订阅的线程，`defer` 的操作交给它执行
+    owner: ThreadId,
+}
+
+thread_local! {
+    /// 当前线程正在执行的回调分发，最后一个为最内层
+    static DISPATCHES: RefCell<Vec<Dispatch>> = const { RefCell::new(Vec::new()) };
+}
+
+/// 等待执行的操作和执行它们的线程
+static DEFERRED: Mutex<Vec<(ThreadId, Deferred)>> = Mutex::new(Vec::new());
+
+/// 当前线程是否正在执行数据变化回调
+pub fn in_callback() -> bool {
+    DISPATCHES.with(|dispatches| !dispatches.borrow().is_empty())
+}
+
+/// 把操作交给启用订阅的线程，在回调返回后执行
+///
+/// 在回调中调用时，操作加入启用当前订阅的线程的队列，该线程调用 `run_deferred` 时
+/// 按加入的顺序执行，这时已经不在回调中，可以访问服务器。创建组的线程通常在
+/// 自己的循环中周期性调用 `run_deferred`；通知在该线程中分发时（例如组调用结束后
+/// 分发排队的通知），最外层的分发结束后立即执行。
+///
+/// 不在回调中时立即执行。
+pub fn defer(operation: impl FnOnce() + Send + 'static) {
+    let owner = DISPATCHES.with(|dispatches| dispatches.borrow().last().map(|dispatch| dispatch.owner));
+    match owner {
+        Some(owner) => lock_or_recover(&DEFERRED).push((owner, Box::new(operation))),
+        None => operation(),
+    }
+}
+
+/// 执行交给当前线程的操作
+///
+/// 在回调中调用时不执行，留到回调结束后。返回执行的操作数。
+pub fn run_deferred() -> usize {
+    if in_callback() {
+        return 0;
+    }
+    let current = thread::current().id();
+    let operations: Vec<Deferred> = {
+        let mut deferred = lock_or_recover(&DEFERRED);
+        let (mine, others) = std::mem::take(&mut *deferred)
+            .into_iter()
+            .partition(|(owner, _)| *owner == current);
+        *deferred = others;
+        mine.into_iter().map(|(_, operation)| operation).collect()
+    };
+    let count = operations.len();
+    // 操作内再次调用 defer 会立即执行
+    for operation in operations {
+        operation();
+    }
+    count
+}
+
+/// 检查当前线程是否可以访问服务器连接 `server`
+///
+/// 当前线程正在分发同一个服务器连接的通知时返回错误。`server` 为 `None`
+/// 表示调用方不知道所属的连接，不拒绝。
+pub(crate) fn check(operation: &str, server: Option<u64>) -> OpcResult<()> {
+    let reentrant = server.is_some()
+        && DISPATCHES.with(|dispatches| dispatches.borrow().iter().any(|dispatch| dispatch.server == server));
+    if reentrant {
+        Err(OpcError::ReentrantCall(format!(
+            "{} cannot be called from a data change callback of the same server; use reentrancy::defer or another thread",
+            operation
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// 回调分发范围，存在期间当前线程被标记为在分发服务器 `server` 的通知
+///
+/// 最外层的范围结束时执行交给当前线程的 `defer` 操作。
+pub(crate) struct CallbackScope(());
+
+impl CallbackScope {
+    pub(crate) fn enter(server: Option<u64>, owner: ThreadId) -> Self {
+        DISPATCHES.with(|dispatches| dispatches.borrow_mut().push(Dispatch { server, owner }));
+        CallbackScope(())
+    }
+}
+
+impl Drop for CallbackScope {
+    fn drop(&mut self) {
+        let outermost = DISPATCHES.with(|dispatches| {
+            let mut dispatches = dispatches.borrow_mut();
+            dispatches.pop();
+            dispatches.is_empty()
+        });
+        if outermost {
+            run_deferred();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_scope_rejects_same_server_only() {
+        let owner = thread::current().id();
+        assert!(check("read_sync", Some(1)).is_ok());
+        {
+            let _outer = CallbackScope::enter(Some(1), owner);
+            let _inner = CallbackScope::enter(None, owner);
+            assert!(matches!(check("read_sync", Some(1)), Err(OpcError::ReentrantCall(_))));
+            assert!(check("read_sync", Some(2)).is_ok());
+            assert!(check("read_sync", None).is_ok());
+        }
+        assert!(check("read_sync", Some(1)).is_ok());
+    }
+
+    #[test]
+    fn test_defer_runs_on_owner_thread_after_callback() {
+        let owner = thread::current().id();
+        let log = Arc::new(Mutex::new(Vec::new()));
+
+        // 回调在其他线程中分发，操作交给启用订阅的线程
+        let callback_log = Arc::clone(&log);
+        thread::spawn(move || {
+            let _scope = CallbackScope::enter(Some(1), owner);
+            defer(move || {
+                let nested_log = Arc::clone(&callback_log);
+                callback_log.lock().unwrap().push((thread::current().id(), in_callback()));
+                defer(move || nested_log.lock().unwrap().push((thread::current().id(), false)));
+            });
+        })
+        .join()
+        .unwrap();
+        assert!(log.lock().unwrap().is_empty());
+
+        assert_eq!(run_deferred(), 1);
+        assert_eq!(*log.lock().unwrap(), vec![(owner, false), (owner, false)]);
+        assert_eq!(run_deferred(), 0);
+    }
+
+    #[test]
+    fn test_defer_on_owner_thread_runs_after_outermost_scope() {
+        let owner = thread::current().id();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        {
+            let _outer = CallbackScope::enter(Some(3), owner);
+            {
+                let _inner = CallbackScope::enter(Some(3), owner);
+                let inner_log = Arc::clone(&log);
+                defer(move || inner_log.lock().unwrap().push(in_callback()));
+                assert_eq!(run_deferred(), 0);
+            }
+            // 内层范围结束时仍在回调中，不执行
+            assert!(log.lock().unwrap().is_empty());
+        }
+        assert_eq!(*log.lock().unwrap(), vec![false]);
+        assert!(!in_callback());
+    }
+}
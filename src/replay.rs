@@ -0,0 +1,325 @@
+//! 录制与回放模块（需要 `binary` 特性）
+//!
+//! `SessionRecorder` 作为组的订阅消费者，把收到的每个数据变化（组名、项名、值、质量、
+//! 时间戳）连同距录制开始的时间写入文件。`SessionRecording` 读取录制文件，
+//! 通过同一个 `OpcDataCallback` 接口按原速、加速或不等待地回放，
+//! 用于离线开发和下游逻辑的回归测试，不需要连接服务器。
+//!
+//! ## 文件格式
+//!
+//! 文件以 `MAGIC` 开头，之后每条记录为：
+//!
+//! - 距录制开始的时间，微秒，`u64` 小端
+//! - 编码长度，`u32` 小端
+//! - `DataChangeEvent::encode` 的编码
+//!
+//! 每条记录写入后立即刷新到文件。录制进程被中止时文件末尾可能有不完整的记录，
+//! 读取时忽略它。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::replay::{ReplaySpeed, SessionRecorder, SessionRecording};
+//! use std::sync::Arc;
+//!
+//! // 现场录制
+//! let recorder = Arc::new(SessionRecorder::create("line1.rec")?);
+//! group.enable_async_subscription(recorder.clone())?;
+//!
+//! // 离线回放，10 倍速
+//! let recording = SessionRecording::load("line1.rec")?;
+//! recording.replay(&my_callback, ReplaySpeed::Accelerated(10.0))?;
+//! ```
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use crate::error::{OpcError, OpcResult};
+use crate::types::{lock_or_recover, DataChangeEvent, OpcDataCallback, OpcQuality, OpcTimestamp, OpcValue};
+
+/// 录制文件头
+pub const MAGIC: &[u8; 8] = b"OPCDAREC";
+
+/// 每条记录的固定头部长度：时间和编码长度
+const RECORD_HEADER: usize = 12;
+
+struct RecorderState {
+    writer: BufWriter<File>,
+    recorded: u64,
+    dropped: u64,
+    last_error: Option<String>,
+}
+
+/// 把订阅的数据变化录制到文件
+pub struct SessionRecorder {
+    started: Instant,
+    state: Mutex<RecorderState>,
+}
+
+impl SessionRecorder {
+    /// 创建录制文件，已存在的文件被覆盖
+    ///
+    /// 录制时间从此刻开始计算。
+    ///
+    /// # 返回值
+    /// - `Ok(SessionRecorder)`: 创建成功
+    /// - `Err(OpcError::Io)`: 创建或写入文件失败
+    pub fn create(path: impl AsRef<Path>) -> OpcResult<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        writer.flush()?;
+        Ok(SessionRecorder {
+            started: Instant::now(),
+            state: Mutex::new(RecorderState {
+                writer,
+                recorded: 0,
+                dropped: 0,
+                last_error: None,
+            }),
+        })
+    }
+
+    /// 录制一个数据变化
+    pub fn record(&self, change: &DataChangeEvent) -> OpcResult<()> {
+        let offset = self.started.elapsed().as_micros() as u64;
+        let encoded = change.encode();
+        let mut state = lock_or_recover(&self.state);
+        let result = write_record(&mut state.writer, offset, &encoded);
+        match &result {
+            Ok(()) => state.recorded += 1,
+            Err(e) => {
+                state.dropped += 1;
+                state.last_error = Some(e.to_string());
+            }
+        }
+        result
+    }
+
+    /// 已录制的数据变化数
+    pub fn recorded(&self) -> u64 {
+        lock_or_recover(&self.state).recorded
+    }
+
+    /// 因写入失败被丢弃的数据变化数
+    pub fn dropped(&self) -> u64 {
+        lock_or_recover(&self.state).dropped
+    }
+
+    /// 最近一次写入失败的原因
+    pub fn last_error(&self) -> Option<String> {
+        lock_or_recover(&self.state).last_error.clone()
+    }
+}
+
+fn write_record(writer: &mut BufWriter<File>, offset: u64, encoded: &[u8]) -> OpcResult<()> {
+    let len = u32::try_from(encoded.len())
+        .map_err(|_| OpcError::InvalidParameters("Data change too large to record".to_string()))?;
+    writer.write_all(&offset.to_le_bytes())?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(encoded)?;
+    writer.flush()?;
+    Ok(())
+}
+
+impl OpcDataCallback for SessionRecorder {
+    fn on_data_change(&self, group_name: &str, item_name: &str, value: OpcValue, quality: OpcQuality, timestamp: u64) {
+        // 错误已记录在 dropped 和 last_error 中
+        let _ = self.record(&DataChangeEvent {
+            group: group_name.to_string(),
+            item: item_name.to_string(),
+            value,
+            quality,
+            timestamp: OpcTimestamp::from_unix_ms(timestamp),
+        });
+    }
+}
+
+/// 录制文件中的一个数据变化
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedChange {
+    /// 距录制开始的时间
+    pub offset: Duration,
+    /// 数据变化
+    pub change: DataChangeEvent,
+}
+
+/// 回放速度
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ReplaySpeed {
+    /// 按录制时的间隔回放
+    #[default]
+    Original,
+    /// 间隔除以给定倍数，必须是正的有限数
+    Accelerated(f64),
+    /// 不等待，连续交付所有变化
+    Unpaced,
+}
+
+/// 读取到内存的录制文件
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionRecording {
+    changes: Vec<RecordedChange>,
+}
+
+impl SessionRecording {
+    /// 读取录制文件
+    ///
+    /// # 返回值
+    /// - `Ok(SessionRecording)`: 读取成功，末尾不完整的记录被忽略
+    /// - `Err(OpcError::Io)`: 读取文件失败
+    /// - `Err(OpcError::InvalidParameters)`: 不是录制文件，或记录无法解码
+    pub fn load(path: impl AsRef<Path>) -> OpcResult<Self> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// 从录制文件的内容解析
+    pub fn from_bytes(bytes: &[u8]) -> OpcResult<Self> {
+        let mut rest = bytes
+            .strip_prefix(MAGIC.as_slice())
+            .ok_or_else(|| OpcError::InvalidParameters("Invalid recording: missing header".to_string()))?;
+        let mut changes = Vec::new();
+        while rest.len() >= RECORD_HEADER {
+            let (header, body) = rest.split_at(RECORD_HEADER);
+            let offset = u64::from_le_bytes(header[..8].try_into().unwrap_or_default());
+            let len = u32::from_le_bytes(header[8..].try_into().unwrap_or_default()) as usize;
+            if body.len() < len {
+                break;
+            }
+            let change = DataChangeEvent::decode(&body[..len]).map_err(|e| {
+                OpcError::InvalidParameters(format!("Invalid recording: record {}: {}", changes.len() + 1, e))
+            })?;
+            changes.push(RecordedChange {
+                offset: Duration::from_micros(offset),
+                change,
+            });
+            rest = &body[len..];
+        }
+        Ok(SessionRecording { changes })
+    }
+
+    /// 所有数据变化，按录制顺序排列
+    pub fn changes(&self) -> &[RecordedChange] {
+        &self.changes
+    }
+
+    /// 数据变化数
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// 是否没有数据变化
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// 从录制开始到最后一个数据变化的时长
+    pub fn duration(&self) -> Duration {
+        self.changes.last().map(|recorded| recorded.offset).unwrap_or_default()
+    }
+
+    /// 录制中出现的项名，按名称排序
+    pub fn item_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.changes.iter().map(|recorded| recorded.change.item.clone()).collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// 在当前线程中把所有数据变化交付给回调
+    ///
+    /// 按 `speed` 在两次交付之间等待，阻塞直到回放结束。
+    /// 返回交付的数据变化数。
+    ///
+    /// # 返回值
+    /// - `Ok(usize)`: 回放完成
+    /// - `Err(OpcError::InvalidParameters)`: 加速倍数不是正的有限数
+    pub fn replay(&self, callback: &dyn OpcDataCallback, speed: ReplaySpeed) -> OpcResult<usize> {
+        let factor = match speed {
+            ReplaySpeed::Original => Some(1.0),
+            ReplaySpeed::Accelerated(factor) if factor.is_finite() && factor > 0.0 => Some(factor),
+            ReplaySpeed::Accelerated(factor) => {
+                return Err(OpcError::InvalidParameters(format!("Invalid replay speed: {}", factor)));
+            }
+            ReplaySpeed::Unpaced => None,
+        };
+        let started = Instant::now();
+        for recorded in &self.changes {
+            if let Some(factor) = factor {
+                let due = recorded.offset.div_f64(factor);
+                if let Some(wait) = due.checked_sub(started.elapsed()) {
+                    std::thread::sleep(wait);
+                }
+            }
+            let change = &recorded.change;
+            callback.on_data_change(
+                &change.group,
+                &change.item,
+                change.value.clone(),
+                change.quality,
+                change.timestamp.unix_ms(),
+            );
+        }
+        Ok(self.changes.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Collector(Mutex<Vec<(String, OpcValue, OpcQuality, u64)>>);
+
+    impl OpcDataCallback for Collector {
+        fn on_data_change(&self, _group_name: &str, item_name: &str, value: OpcValue, quality: OpcQuality, timestamp: u64) {
+            self.0.lock().unwrap().push((item_name.to_string(), value, quality, timestamp));
+        }
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let path = std::env::temp_dir().join(format!("opcda-replay-{}.rec", std::process::id()));
+        let recorder = SessionRecorder::create(&path).unwrap();
+        recorder.on_data_change("G", "FT101", OpcValue::Double(1.5), OpcQuality::Good, 1_000);
+        std::thread::sleep(Duration::from_millis(40));
+        recorder.on_data_change("G", "Names", OpcValue::ArrayString(vec!["a".to_string()]), OpcQuality::Bad, 2_000);
+        assert_eq!(recorder.recorded(), 2);
+        drop(recorder);
+
+        // 中止时留下的不完整记录被忽略
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes.extend_from_slice(&1u64.to_le_bytes());
+        bytes.extend_from_slice(&100u32.to_le_bytes());
+        bytes.extend_from_slice(&[1, 2, 3]);
+        let recording = SessionRecording::from_bytes(&bytes).unwrap();
+        assert_eq!(recording, SessionRecording::load(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(recording.len(), 2);
+        assert!(recording.duration() >= Duration::from_millis(40));
+        assert_eq!(recording.item_names(), vec!["FT101".to_string(), "Names".to_string()]);
+
+        let collector = Collector::default();
+        let started = Instant::now();
+        assert_eq!(recording.replay(&collector, ReplaySpeed::Accelerated(4.0)).unwrap(), 2);
+        assert!(started.elapsed() >= Duration::from_millis(10));
+        assert_eq!(*collector.0.lock().unwrap(), vec![
+            ("FT101".to_string(), OpcValue::Double(1.5), OpcQuality::Good, 1_000),
+            ("Names".to_string(), OpcValue::ArrayString(vec!["a".to_string()]), OpcQuality::Bad, 2_000),
+        ]);
+        assert!(recording.replay(&collector, ReplaySpeed::Accelerated(0.0)).is_err());
+    }
+
+    #[test]
+    fn test_invalid_recording() {
+        assert!(matches!(SessionRecording::from_bytes(b"not a recording"), Err(OpcError::InvalidParameters(_))));
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        bytes.extend_from_slice(&[0xff, 0xff]);
+        assert!(matches!(SessionRecording::from_bytes(&bytes), Err(OpcError::InvalidParameters(_))));
+        assert!(SessionRecording::from_bytes(MAGIC).unwrap().is_empty());
+    }
+}
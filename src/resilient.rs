@@ -0,0 +1,559 @@
+//! 自动重连模块
+//!
+//! `ResilientConnection` 按保存的配置建立服务器连接、创建组、添加项并启用订阅，
+//! 之后周期性地调用 `GetStatus` 作为心跳。心跳失败或服务器报告 `Failed` 时，
+//! 释放整个会话，按退避间隔重新连接，并用同一个数据回调恢复订阅。
+//! 断开时数据回调收到 `on_connection_interrupted` 而不是 `on_subscription_closed`，
+//! 因为订阅在重连后继续；只有 `ResilientConnection` 本身被释放时订阅才关闭。
+//!
+//! 连接状态的变化通过 `ConnectionListener` 报告为 `ConnectionEvent`。
+//!
+//! 断开期间的数据变化无法补回。`set_backfill` 打开后，重连时在重新启用订阅之前
+//! 读取每个组的所有项，通过 `OpcDataCallback::on_backfill` 作为一批交给回调，
+//! 下游存储可以据此明确地填补断开期间的空白。
+//!
+//! ## 线程模型
+//!
+//! 与 `Mirror` 相同，OPC 对象只能在创建它们的线程中使用。
+//! 应用在该线程中周期性调用 `maintain`，心跳和重连都在调用中执行。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::{ConnectionEvent, GroupConfig, OpcClient, ReconnectPolicy, ResilientConnection};
+//!
+//! let client = OpcClient::new()?;
+//! let groups = vec![GroupConfig::new("Plant", 1000, &["FIC101.PV", "TI205.PV"])];
+//! let mut connection = ResilientConnection::new(
+//!     &client,
+//!     "host=10.0.0.5;progid=Kepware.KEPServerEX.V6".parse()?,
+//!     groups,
+//!     collector.clone(),
+//!     ReconnectPolicy::default(),
+//! );
+//! connection.set_listener(|event: &ConnectionEvent| eprintln!("{}", event));
+//!
+//! loop {
+//!     connection.maintain();
+//!     std::thread::sleep(std::time::Duration::from_millis(500));
+//! }
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use crate::backend::{DaGroup, DaItem, DaServer, Subscriptions};
+use crate::client::OpcClient;
+use crate::connection::ConnectionString;
+use crate::error::{OpcError, OpcResult};
+use crate::group::OpcGroup;
+use crate::server::OpcServer;
+use crate::types::{DataChangeEvent, OpcDataCallback, ServerState};
+
+/// 组的配置，重连后按此重新创建
+///
+/// 启用 `config` 特性时也用于配置文件中的组，`active` 默认为 `true`，`deadband` 默认为 0。
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "config", derive(serde::Deserialize))]
+#[cfg_attr(feature = "config", serde(deny_unknown_fields))]
+pub struct GroupConfig {
+    /// 组名
+    pub name: String,
+    /// 是否激活
+    #[cfg_attr(feature = "config", serde(default = "default_active"))]
+    pub active: bool,
+    /// 请求的更新速率（毫秒）
+    pub update_rate: u32,
+    /// 死区值（百分比）
+    #[cfg_attr(feature = "config", serde(default))]
+    pub deadband: f64,
+    /// 项名
+    #[cfg_attr(feature = "config", serde(default))]
+    pub items: Vec<String>,
+}
+
+#[cfg(feature = "config")]
+fn default_active() -> bool {
+    true
+}
+
+impl GroupConfig {
+    /// 创建激活的、无死区的组配置
+    pub fn new<S: AsRef<str>>(name: &str, update_rate: u32, items: &[S]) -> Self {
+        GroupConfig {
+            name: name.to_string(),
+            active: true,
+            update_rate,
+            deadband: 0.0,
+            items: items.iter().map(|item| item.as_ref().to_string()).collect(),
+        }
+    }
+}
+
+/// 心跳和重连的时间设置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// 心跳间隔
+    pub heartbeat_interval: Duration,
+    /// 第一次重连前的等待时间，之后每次失败加倍
+    pub initial_backoff: Duration,
+    /// 重连等待时间的上限
+    pub max_backoff: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        ReconnectPolicy {
+            heartbeat_interval: Duration::from_secs(5),
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+/// 连接状态的变化
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionEvent {
+    /// 连接已断开
+    Lost {
+        /// 断开的原因
+        reason: String,
+    },
+    /// 一次重连尝试失败
+    ReconnectFailed {
+        /// 自断开以来的尝试次数
+        attempt: u32,
+        /// 失败的原因
+        error: String,
+        /// 距下次尝试的等待时间
+        retry_in: Duration,
+    },
+    /// 连接已建立或恢复，订阅已重新启用
+    Restored {
+        /// 自断开以来的尝试次数，首次连接为 1
+        attempts: u32,
+        /// 重新添加失败的项
+        failed_items: Vec<String>,
+    },
+}
+
+impl fmt::Display for ConnectionEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionEvent::Lost { reason } => write!(f, "connection lost: {}", reason),
+            ConnectionEvent::ReconnectFailed { attempt, error, retry_in } => write!(
+                f,
+                "reconnect attempt {} failed: {} (retrying in {}ms)",
+                attempt,
+                error,
+                retry_in.as_millis()
+            ),
+            ConnectionEvent::Restored { attempts, failed_items } => {
+                write!(f, "connection restored after {} attempt(s)", attempts)?;
+                if !failed_items.is_empty() {
+                    write!(f, ", {} item(s) could not be added", failed_items.len())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// 连接状态变化的接收者
+pub trait ConnectionListener {
+    /// 连接状态变化时调用
+    fn on_connection_event(&mut self, event: &ConnectionEvent);
+}
+
+impl<F: FnMut(&ConnectionEvent)> ConnectionListener for F {
+    fn on_connection_event(&mut self, event: &ConnectionEvent) {
+        self(event)
+    }
+}
+
+/// 一次连接中创建的对象，按字段顺序释放：项、组、服务器
+struct Session<S: DaServer = OpcServer> {
+    _items: Vec<<S::Group as DaGroup>::Item>,
+    groups: Vec<S::Group>,
+    server: S,
+}
+
+impl<S: DaServer> Session<S>
+where
+    S::Group: Subscriptions,
+{
+    /// 在新连接上按配置创建组和项并启用订阅，返回会话和添加失败的项
+    ///
+    /// `backfill` 为 `true` 时，每个组在启用订阅之前读取所有项并交给 `on_backfill`。
+    /// 中途失败时先中断已经启用的订阅再释放会话：回调在重连后重新注册，
+    /// 不能收到 `on_subscription_closed`。
+    fn establish(
+        server: S,
+        groups: &[GroupConfig],
+        callback: &Arc<dyn OpcDataCallback>,
+        backfill: bool,
+    ) -> OpcResult<(Self, Vec<String>)> {
+        // 对象创建后立即放入会话，中途失败时由会话按项、组、服务器的顺序释放
+        let mut session = Session {
+            _items: Vec::new(),
+            groups: Vec::with_capacity(groups.len()),
+            server,
+        };
+        match session.populate(groups, callback, backfill) {
+            Ok(failed_items) => Ok((session, failed_items)),
+            Err(err) => {
+                session.interrupt(&err.to_string());
+                Err(err)
+            }
+        }
+    }
+
+    fn populate(
+        &mut self,
+        groups: &[GroupConfig],
+        callback: &Arc<dyn OpcDataCallback>,
+        backfill: bool,
+    ) -> OpcResult<Vec<String>> {
+        let mut failed_items = Vec::new();
+        for config in groups {
+            let group = self.server.create_group(&config.name, config.active, config.update_rate, config.deadband)?;
+            self.groups.push(group);
+            let group = &self.groups[self.groups.len() - 1];
+            let first_item = self._items.len();
+            for name in &config.items {
+                match group.add_item(name) {
+                    Ok(item) => self._items.push(item),
+                    Err(OpcError::LimitExceeded(msg)) => return Err(OpcError::LimitExceeded(msg)),
+                    Err(_) => failed_items.push(name.clone()),
+                }
+            }
+            if backfill {
+                let batch: Vec<DataChangeEvent> = self._items[first_item..]
+                    .iter()
+                    .filter_map(|item| {
+                        let (value, quality, timestamp) = item.read_sync().ok()?;
+                        Some(DataChangeEvent {
+                            group: config.name.clone(),
+                            item: item.name().to_string(),
+                            value,
+                            quality,
+                            timestamp,
+                        })
+                    })
+                    .collect();
+                callback.on_backfill(&config.name, &batch);
+            }
+            group.enable_async_subscription(Arc::clone(callback))?;
+        }
+        Ok(failed_items)
+    }
+
+    /// 中断所有组的订阅，之后释放会话时回调不再收到关闭通知
+    fn interrupt(&self, reason: &str) {
+        for group in &self.groups {
+            group.interrupt_subscriptions(reason);
+        }
+    }
+}
+
+/// 自动重连的服务器连接
+pub struct ResilientConnection<'a> {
+    /// 当前会话，必须先于客户端释放
+    session: Option<Session>,
+    client: &'a OpcClient,
+    connection: ConnectionString,
+    groups: Vec<GroupConfig>,
+    callback: Arc<dyn OpcDataCallback>,
+    policy: ReconnectPolicy,
+    listener: Option<Box<dyn ConnectionListener + 'a>>,
+    /// 上次心跳的时间
+    last_heartbeat: Option<Instant>,
+    /// 下次允许重连的时间
+    next_attempt: Option<Instant>,
+    /// 自断开以来的尝试次数
+    attempts: u32,
+    /// 当前的重连等待时间
+    backoff: Duration,
+    /// 重连时是否读取所有项交给 `on_backfill`
+    backfill: bool,
+    /// 是否曾经连接成功，首次连接不需要补读
+    was_connected: bool,
+}
+
+impl<'a> ResilientConnection<'a> {
+    /// 创建自动重连的连接
+    ///
+    /// 只保存配置，第一次连接在第一次调用 `maintain` 时进行。
+    ///
+    /// # 参数
+    /// - `client`: 用于建立连接的客户端
+    /// - `connection`: 服务器连接字符串
+    /// - `groups`: 每次连接后创建的组和项
+    /// - `callback`: 所有组共用的数据回调，每次连接后重新注册
+    /// - `policy`: 心跳和重连的时间设置
+    pub fn new(
+        client: &'a OpcClient,
+        connection: ConnectionString,
+        groups: Vec<GroupConfig>,
+        callback: Arc<dyn OpcDataCallback>,
+        policy: ReconnectPolicy,
+    ) -> Self {
+        ResilientConnection {
+            session: None,
+            client,
+            connection,
+            groups,
+            callback,
+            policy,
+            listener: None,
+            last_heartbeat: None,
+            next_attempt: None,
+            attempts: 0,
+            backoff: policy.initial_backoff,
+            backfill: false,
+            was_connected: false,
+        }
+    }
+
+    /// 设置连接状态变化的接收者
+    pub fn set_listener(&mut self, listener: impl ConnectionListener + 'a) {
+        self.listener = Some(Box::new(listener));
+    }
+
+    /// 设置重连时是否补读
+    ///
+    /// 打开后，每次断开后重新连接时，每个组在重新启用订阅之前读取所有项，
+    /// 结果作为一批交给数据回调的 `on_backfill`，之后才恢复正常的数据变化通知。
+    /// 读取失败的项不在批中。首次连接不补读。默认关闭。
+    pub fn set_backfill(&mut self, enabled: bool) {
+        self.backfill = enabled;
+    }
+
+    /// 检查连接并在需要时重连，返回调用后是否已连接
+    ///
+    /// 已连接时只在超过心跳间隔后访问服务器；未连接时只在退避时间到达后尝试重连，
+    /// 因此可以在主循环中频繁调用。
+    pub fn maintain(&mut self) -> bool {
+        let now = Instant::now();
+        if self.session.is_some() {
+            let due = self
+                .last_heartbeat
+                .is_none_or(|last| now.duration_since(last) >= self.policy.heartbeat_interval);
+            if due {
+                self.last_heartbeat = Some(now);
+                if let Err(reason) = self.heartbeat() {
+                    self.disconnect(reason);
+                }
+            }
+        }
+
+        if self.session.is_none() && self.next_attempt.is_none_or(|at| now >= at) {
+            self.attempt();
+        }
+        self.session.is_some()
+    }
+
+    /// 当前是否已连接
+    pub fn is_connected(&self) -> bool {
+        self.session.is_some()
+    }
+
+    /// 当前连接的服务器
+    pub fn server(&self) -> Option<&OpcServer> {
+        self.session.as_ref().map(|session| &session.server)
+    }
+
+    /// 当前连接中按名称查找组
+    pub fn group(&self, name: &str) -> Option<&OpcGroup> {
+        self.session
+            .as_ref()
+            .and_then(|session| session.groups.iter().find(|group| group.name() == name))
+    }
+
+    /// 连接配置
+    pub fn groups(&self) -> &[GroupConfig] {
+        &self.groups
+    }
+
+    /// 主动断开当前连接，下次 `maintain` 时按退避时间重连
+    pub fn disconnect(&mut self, reason: impl Into<String>) {
+        let Some(session) = self.session.take() else {
+            return;
+        };
+        let reason = reason.into();
+        // 同一个回调会在重连后重新注册，不能关闭它的订阅
+        session.interrupt(&reason);
+        drop(session);
+
+        self.attempts = 0;
+        self.backoff = self.policy.initial_backoff;
+        self.next_attempt = None;
+        self.emit(ConnectionEvent::Lost { reason });
+    }
+
+    fn heartbeat(&self) -> Result<(), String> {
+        let Some(session) = &self.session else {
+            return Ok(());
+        };
+        match session.server.get_status() {
+            Ok((ServerState::Failed, _)) => Err("server reports state Failed".to_string()),
+            Ok(_) => Ok(()),
+            Err(e) => Err(format!("heartbeat failed: {}", e)),
+        }
+    }
+
+    fn attempt(&mut self) {
+        self.attempts += 1;
+        match self.establish() {
+            Ok((session, failed_items)) => {
+                self.session = Some(session);
+                self.was_connected = true;
+                self.last_heartbeat = Some(Instant::now());
+                self.next_attempt = None;
+                self.backoff = self.policy.initial_backoff;
+                let attempts = std::mem::take(&mut self.attempts);
+                self.emit(ConnectionEvent::Restored { attempts, failed_items });
+            }
+            Err(e) => {
+                let retry_in = self.backoff;
+                self.next_attempt = Some(Instant::now() + retry_in);
+                self.backoff = (self.backoff * 2).min(self.policy.max_backoff);
+                self.emit(ConnectionEvent::ReconnectFailed {
+                    attempt: self.attempts,
+                    error: e.to_string(),
+                    retry_in,
+                });
+            }
+        }
+    }
+
+    /// 建立连接并按配置创建组和项，返回会话和添加失败的项
+    fn establish(&self) -> OpcResult<(Session, Vec<String>)> {
+        let backfill = self.backfill && self.was_connected;
+        Session::establish(self.client.connect(&self.connection)?, &self.groups, &self.callback, backfill)
+    }
+
+    fn emit(&mut self, event: ConnectionEvent) {
+        if let Some(listener) = self.listener.as_mut() {
+            listener.on_connection_event(&event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::SimServer;
+    use crate::types::{OpcQuality, OpcValue, SubscriptionCloseReason};
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_event_display() {
+        let lost = ConnectionEvent::Lost {
+            reason: "heartbeat failed: timeout".to_string(),
+        };
+        assert_eq!(lost.to_string(), "connection lost: heartbeat failed: timeout");
+
+        let failed = ConnectionEvent::ReconnectFailed {
+            attempt: 2,
+            error: "Connection failed: refused".to_string(),
+            retry_in: Duration::from_secs(2),
+        };
+        assert_eq!(
+            failed.to_string(),
+            "reconnect attempt 2 failed: Connection failed: refused (retrying in 2000ms)"
+        );
+
+        let restored = ConnectionEvent::Restored {
+            attempts: 3,
+            failed_items: vec!["Gone.Tag".to_string()],
+        };
+        assert_eq!(
+            restored.to_string(),
+            "connection restored after 3 attempt(s), 1 item(s) could not be added"
+        );
+    }
+
+    #[test]
+    fn test_failed_establish_interrupts_subscriptions() {
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<String>>);
+
+        impl OpcDataCallback for Recorder {
+            fn on_data_change(&self, group_name: &str, item_name: &str, _value: OpcValue, _quality: OpcQuality, _timestamp: u64) {
+                self.0.lock().unwrap().push(format!("{}/{}", group_name, item_name));
+            }
+
+            fn on_subscription_closed(&self, group_name: &str, reason: &SubscriptionCloseReason) {
+                self.0.lock().unwrap().push(format!("{} closed: {}", group_name, reason));
+            }
+
+            fn on_connection_interrupted(&self, group_name: &str, reason: &str) {
+                self.0.lock().unwrap().push(format!("{} interrupted: {}", group_name, reason));
+            }
+        }
+
+        let recorder = Arc::new(Recorder::default());
+        let callback: Arc<dyn OpcDataCallback> = recorder.clone();
+        let server = SimServer::new().with_tag("A", OpcValue::Int32(1));
+        let _existing = server.create_group("Existing", true, 500, 0.0).unwrap();
+
+        // 第二个组重名，创建失败时第一个组已经启用订阅
+        let groups = vec![GroupConfig::new("Plant", 1000, &["A"]), GroupConfig::new("Existing", 1000, &["A"])];
+        let err = Session::establish(server, &groups, &callback, false).err().unwrap();
+        assert!(matches!(err, OpcError::GroupCreationFailed(_)));
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            vec![format!("Plant interrupted: {}", err)]
+        );
+
+        // 成功建立的会话照常分发
+        let groups = vec![GroupConfig::new("Plant", 1000, &["A", "Missing"])];
+        let server = SimServer::new().with_tag("A", OpcValue::Int32(1));
+        let (session, failed_items) = Session::establish(server, &groups, &callback, false).unwrap();
+        assert_eq!(failed_items, vec!["Missing".to_string()]);
+        session.server.set_value("A", OpcValue::Int32(2), OpcQuality::Good).unwrap();
+        session.interrupt("heartbeat failed");
+        drop(session);
+        assert_eq!(recorder.0.lock().unwrap()[1..], [
+            "Plant/A".to_string(),
+            "Plant interrupted: heartbeat failed".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn test_backfill_batch_precedes_subscription() {
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<String>>);
+
+        impl OpcDataCallback for Recorder {
+            fn on_data_change(&self, group_name: &str, item_name: &str, value: OpcValue, _quality: OpcQuality, _timestamp: u64) {
+                self.0.lock().unwrap().push(format!("{}/{} = {:?}", group_name, item_name, value));
+            }
+
+            fn on_backfill(&self, group_name: &str, batch: &[DataChangeEvent]) {
+                let items: Vec<String> = batch.iter().map(|event| format!("{} = {:?}", event.item, event.value)).collect();
+                self.0.lock().unwrap().push(format!("{} backfill [{}]", group_name, items.join(", ")));
+            }
+        }
+
+        let recorder = Arc::new(Recorder::default());
+        let callback: Arc<dyn OpcDataCallback> = recorder.clone();
+        let groups = vec![GroupConfig::new("Plant", 1000, &["A", "B", "Missing"])];
+        let server = SimServer::new().with_tag("A", OpcValue::Int32(1)).with_tag("B", OpcValue::Int32(2));
+
+        let (session, failed_items) = Session::establish(server, &groups, &callback, true).unwrap();
+        assert_eq!(failed_items, vec!["Missing".to_string()]);
+        session.server.set_value("A", OpcValue::Int32(3), OpcQuality::Good).unwrap();
+        assert_eq!(*recorder.0.lock().unwrap(), vec![
+            "Plant backfill [A = Int32(1), B = Int32(2)]".to_string(),
+            "Plant/A = Int32(3)".to_string(),
+        ]);
+
+        // 未打开时不补读
+        recorder.0.lock().unwrap().clear();
+        let server = SimServer::new().with_tag("A", OpcValue::Int32(1)).with_tag("B", OpcValue::Int32(2));
+        let (_session, _) = Session::establish(server, &groups, &callback, false).unwrap();
+        assert!(recorder.0.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,120 @@
+//! 线性缩放模块
+//!
+//! `Scaling` 描述原始值范围（例如 4-20 mA 对应的 0-27648 计数）到工程单位范围
+//! （例如 0-150 °C）的线性映射，可附加到 `OpcItem` 上，由 `read_scaled` 和
+//! `write_scaled` 自动转换。
+//!
+//! 启用 `clamp` 时工程值限制在 `eu_lo` 和 `eu_hi` 之间：读取时超出量程的原始值
+//! （例如断线时的 3.6 mA）被限制在量程端点，写入时超出量程的设定值不会写到设备。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::Scaling;
+//!
+//! let item = group.add_item("Channel1.Device1.TT101")?;
+//! item.set_scaling(Some(Scaling::new(0.0, 27648.0, 0.0, 150.0).clamped()))?;
+//!
+//! let (temperature, quality, _ts) = item.read_scaled()?;
+//! item.write_scaled(75.0)?;
+//! ```
+
+use crate::error::{OpcError, OpcResult};
+
+/// 原始值到工程单位的线性缩放
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scaling {
+    /// 原始值量程下限
+    pub raw_lo: f64,
+    /// 原始值量程上限
+    pub raw_hi: f64,
+    /// 工程单位量程下限，对应 `raw_lo`
+    pub eu_lo: f64,
+    /// 工程单位量程上限，对应 `raw_hi`
+    pub eu_hi: f64,
+    /// 是否把工程值限制在量程之内
+    pub clamp: bool,
+}
+
+impl Scaling {
+    /// 创建不限制量程的缩放
+    pub fn new(raw_lo: f64, raw_hi: f64, eu_lo: f64, eu_hi: f64) -> Self {
+        Scaling {
+            raw_lo,
+            raw_hi,
+            eu_lo,
+            eu_hi,
+            clamp: false,
+        }
+    }
+
+    /// 返回限制量程的副本
+    pub fn clamped(self) -> Self {
+        Scaling { clamp: true, ..self }
+    }
+
+    /// 检查缩放是否可用：端点都是有限值，且两个量程都不为空
+    ///
+    /// # 返回值
+    /// - `Ok(())`: 可以双向转换
+    /// - `Err(OpcError::InvalidParameters)`: 端点无效
+    pub fn validate(&self) -> OpcResult<()> {
+        let ends = [self.raw_lo, self.raw_hi, self.eu_lo, self.eu_hi];
+        if ends.iter().any(|end| !end.is_finite()) {
+            return Err(OpcError::invalid_parameters(format!("Scaling ends must be finite: {:?}", self)));
+        }
+        if self.raw_lo == self.raw_hi || self.eu_lo == self.eu_hi {
+            return Err(OpcError::invalid_parameters(format!("Scaling ranges must not be empty: {:?}", self)));
+        }
+        Ok(())
+    }
+
+    /// 原始值转换为工程值
+    pub fn to_eu(&self, raw: f64) -> f64 {
+        let eu = self.eu_lo + (raw - self.raw_lo) * (self.eu_hi - self.eu_lo) / (self.raw_hi - self.raw_lo);
+        self.limit(eu)
+    }
+
+    /// 工程值转换为原始值，启用 `clamp` 时先把工程值限制在量程之内
+    pub fn to_raw(&self, eu: f64) -> f64 {
+        let eu = self.limit(eu);
+        self.raw_lo + (eu - self.eu_lo) * (self.raw_hi - self.raw_lo) / (self.eu_hi - self.eu_lo)
+    }
+
+    fn limit(&self, eu: f64) -> f64 {
+        if self.clamp {
+            eu.clamp(self.eu_lo.min(self.eu_hi), self.eu_lo.max(self.eu_hi))
+        } else {
+            eu
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_four_to_twenty() {
+        let scaling = Scaling::new(4.0, 20.0, 0.0, 150.0);
+        assert_eq!(scaling.to_eu(12.0), 75.0);
+        assert_eq!(scaling.to_raw(75.0), 12.0);
+        assert_eq!(scaling.to_eu(2.0), -18.75);
+
+        let clamped = scaling.clamped();
+        assert_eq!(clamped.to_eu(2.0), 0.0);
+        assert_eq!(clamped.to_raw(200.0), 20.0);
+
+        // 反向量程
+        let reversed = Scaling::new(0.0, 100.0, 10.0, 0.0).clamped();
+        assert_eq!(reversed.to_eu(150.0), 0.0);
+        assert_eq!(reversed.to_raw(2.5), 75.0);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Scaling::new(0.0, 27648.0, 0.0, 150.0).validate().is_ok());
+        assert!(Scaling::new(5.0, 5.0, 0.0, 1.0).validate().is_err());
+        assert!(Scaling::new(0.0, 1.0, 0.0, f64::NAN).validate().is_err());
+    }
+}
@@ -0,0 +1,226 @@
+//! 资源作用域模块
+//!
+//! 这个模块提供类似 `std::thread::scope` 的结构化作用域 `OpcScope`。
+//! 在作用域内创建的服务器连接、组和项归作用域所有，作用域结束时
+//! （包括闭包 panic 时）按正确的顺序释放：
+//!
+//! 1. 关闭所有组的订阅
+//! 2. 释放所有项
+//! 3. 释放所有组
+//! 4. 断开所有服务器连接
+//!
+//! 同一类资源按创建的相反顺序释放。作用域内获得的引用不能逃逸出闭包，
+//! 因此不会出现使用已释放资源的情况。
+//!
+//! `scope_with` 为其他后端（例如 `sim::SimServer`）提供同样的作用域。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::{OpcClient, scope};
+//!
+//! let client = OpcClient::new()?;
+//! let value = scope(|s| -> opc_da_client::OpcResult<_> {
+//!     let server = s.connect_to_local_server(&client, "Matrikon.OPC.Simulation.1")?;
+//!     let group = s.create_group(server, "ScopedGroup", true, 1000, 0.0)?;
+//!     let item = s.add_item(group, "Random.Int2")?;
+//!     let (value, _, _) = item.read_sync()?;
+//!     Ok(value)
+//! })?; // 项、组、连接在这里按顺序释放
+//! ```
+
+use std::cell::RefCell;
+use std::ptr::NonNull;
+use crate::backend::{DaGroup, DaServer, Subscriptions};
+use crate::client::OpcClient;
+use crate::error::OpcResult;
+use crate::server::OpcServer;
+use crate::types::SubscriptionCloseReason;
+
+/// 组中项的类型
+type ItemOf<S> = <<S as DaServer>::Group as DaGroup>::Item;
+
+/// 在作用域内执行闭包，结束时按顺序释放作用域内的所有 OPC 资源
+///
+/// 闭包 panic 时同样会释放资源，然后继续传播 panic。
+pub fn scope<R>(f: impl FnOnce(&OpcScope) -> R) -> R {
+    scope_with(f)
+}
+
+/// 与 `scope` 相同，资源来自后端 `S`
+pub fn scope_with<S: DaServer, R>(f: impl FnOnce(&OpcScope<S>) -> R) -> R
+where
+    S::Group: Subscriptions,
+{
+    let scope = OpcScope::new();
+    f(&scope)
+}
+
+/// OPC 资源作用域
+///
+/// 通过 `scope` 或 `scope_with` 函数获得。资源保存在堆上且在作用域结束前不会移动或释放，
+/// 因此返回的引用在整个闭包内有效。
+// 资源以 `Box::into_raw` 得到的指针保存，只在 Drop 中用 `Box::from_raw` 释放，
+// 指针的来源在整个作用域内保持有效
+pub struct OpcScope<S: DaServer = OpcServer>
+where
+    S::Group: Subscriptions,
+{
+    servers: RefCell<Vec<NonNull<S>>>,
+    groups: RefCell<Vec<NonNull<S::Group>>>,
+    items: RefCell<Vec<NonNull<ItemOf<S>>>>,
+}
+
+impl<S: DaServer> OpcScope<S>
+where
+    S::Group: Subscriptions,
+{
+    fn new() -> Self {
+        OpcScope {
+            servers: RefCell::new(Vec::new()),
+            groups: RefCell::new(Vec::new()),
+            items: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// 将资源放入作用域并返回其引用
+    fn keep<T>(list: &RefCell<Vec<NonNull<T>>>, value: T) -> &T {
+        let ptr = NonNull::from(Box::leak(Box::new(value)));
+        list.borrow_mut().push(ptr);
+        // SAFETY: 指针来自 Box::leak，作用域只在 Drop 中（即所有引用都结束后）释放资源
+        unsafe { ptr.as_ref() }
+    }
+
+    /// 按创建的相反顺序释放一类资源
+    fn release<T>(list: &mut RefCell<Vec<NonNull<T>>>) {
+        while let Some(ptr) = list.get_mut().pop() {
+            // SAFETY: 指针来自 keep 中的 Box::leak，且只在这里释放一次
+            drop(unsafe { Box::from_raw(ptr.as_ptr()) });
+        }
+    }
+
+    /// 将已创建的服务器连接交给作用域管理
+    pub fn adopt_server(&self, server: S) -> &S {
+        Self::keep(&self.servers, server)
+    }
+
+    /// 将已创建的组交给作用域管理
+    pub fn adopt_group(&self, group: S::Group) -> &S::Group {
+        Self::keep(&self.groups, group)
+    }
+
+    /// 将已创建的项交给作用域管理
+    pub fn adopt_item(&self, item: ItemOf<S>) -> &ItemOf<S> {
+        Self::keep(&self.items, item)
+    }
+
+    /// 创建组，组归作用域所有
+    pub fn create_group(
+        &self,
+        server: &S,
+        name: &str,
+        active: bool,
+        req_update_rate: u32,
+        deadband: f64,
+    ) -> OpcResult<&S::Group> {
+        Ok(self.adopt_group(server.create_group(name, active, req_update_rate, deadband)?))
+    }
+
+    /// 添加项，项归作用域所有
+    pub fn add_item(&self, group: &S::Group, name: &str) -> OpcResult<&ItemOf<S>> {
+        Ok(self.adopt_item(group.add_item(name)?))
+    }
+}
+
+impl OpcScope {
+    /// 连接到本地服务器，连接归作用域所有
+    pub fn connect_to_local_server(&self, client: &OpcClient, server_name: &str) -> OpcResult<&OpcServer> {
+        Ok(self.adopt_server(client.connect_to_local_server(server_name)?))
+    }
+
+    /// 连接到远程服务器，连接归作用域所有
+    pub fn connect_to_server(&self, client: &OpcClient, hostname: &str, server_name: &str) -> OpcResult<&OpcServer> {
+        Ok(self.adopt_server(client.connect_to_server(hostname, server_name)?))
+    }
+}
+
+impl<S: DaServer> Drop for OpcScope<S>
+where
+    S::Group: Subscriptions,
+{
+    fn drop(&mut self) {
+        for group in self.groups.get_mut().iter().rev() {
+            // SAFETY: 组尚未释放
+            unsafe { group.as_ref() }.close_subscriptions(&SubscriptionCloseReason::GroupDropped);
+        }
+        Self::release(&mut self.items);
+        Self::release(&mut self.groups);
+        Self::release(&mut self.servers);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use crate::backend::DaItem;
+    use crate::sim::SimServer;
+    use crate::types::{OpcDataCallback, OpcQuality, OpcValue};
+
+    /// 记录关闭通知的组名
+    #[derive(Default)]
+    struct Closed(Mutex<Vec<String>>);
+
+    impl OpcDataCallback for Closed {
+        fn on_data_change(&self, _group_name: &str, _item_name: &str, _value: OpcValue, _quality: OpcQuality, _timestamp: u64) {}
+
+        fn on_subscription_closed(&self, group_name: &str, _reason: &SubscriptionCloseReason) {
+            self.0.lock().unwrap().push(group_name.to_string());
+        }
+    }
+
+    #[test]
+    fn test_scope_releases_resources_in_order() {
+        let closed = Arc::new(Closed::default());
+        let server = SimServer::new().with_tag("Tank1.Level", OpcValue::Double(3.5));
+
+        let value = scope_with(|s: &OpcScope<SimServer>| -> OpcResult<OpcValue> {
+            let first = s.create_group(&server, "First", true, 1000, 0.0)?;
+            // 作用域拥有的连接在它的组之后释放
+            let other = s.adopt_server(SimServer::new());
+            let second = s.create_group(other, "Second", true, 1000, 0.0)?;
+            first.enable_async_subscription(closed.clone())?;
+            second.enable_async_subscription(closed.clone())?;
+            let item = s.add_item(first, "Tank1.Level")?;
+            // 作用域内组名被占用
+            assert!(server.create_group("First", true, 1000, 0.0).is_err());
+            Ok(item.read_sync()?.0)
+        })
+        .unwrap();
+
+        assert_eq!(value, OpcValue::Double(3.5));
+        // 订阅按组创建的相反顺序关闭，每个组只通知一次
+        assert_eq!(*closed.0.lock().unwrap(), vec!["Second".to_string(), "First".to_string()]);
+        // 组已经释放，组名可以重新使用
+        assert!(server.create_group("First", true, 1000, 0.0).is_ok());
+    }
+
+    #[test]
+    fn test_scope_releases_resources_on_panic() {
+        let closed = Arc::new(Closed::default());
+        let server = SimServer::new().with_tag("Tank1.Level", OpcValue::Double(3.5));
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            scope_with(|s: &OpcScope<SimServer>| {
+                let group = s.create_group(&server, "Panicking", true, 1000, 0.0).unwrap();
+                group.enable_async_subscription(closed.clone()).unwrap();
+                s.add_item(group, "Tank1.Level").unwrap();
+                panic!("closure failed");
+            })
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(*closed.0.lock().unwrap(), vec!["Panicking".to_string()]);
+        assert!(server.create_group("Panicking", true, 1000, 0.0).is_ok());
+    }
+}
@@ -0,0 +1,398 @@
+//! 脚本钩子模块（需要 `scripting` 特性）
+//!
+//! 简单的联锁逻辑（例如"液位高于 95 时停泵"）写在应用代码里，每次调整都要重新编译。
+//! `ScriptHooks` 把 [rhai](https://rhai.rs) 脚本挂在项上：项收到数据变化时，
+//! 在回调线程中依次运行挂在该项上的脚本，脚本调用 `write` 请求的写入交给 `ScriptWriter` 执行。
+//!
+//! 脚本中可用的变量和函数：
+//!
+//! - `group`、`item`：通知所属的组名和项名
+//! - `value`：新值。布尔值和字符串保持原类型，整数为 rhai 整数，其他数值为浮点数，
+//!   数组为 rhai 数组
+//! - `quality`：`"Good"`、`"Uncertain"` 或 `"Bad"`
+//! - `timestamp`：Unix 毫秒
+//! - `last(item)`：钩子最近收到的另一个项的值，没有收到过时为 `()`
+//! - `write(item, value)`：请求写入。值可以是布尔值、整数（在 `i32` 范围内写为 `Int32`，
+//!   否则为 `Int64`）、浮点数（`Double`）或字符串
+//!
+//! ## 沙箱和限制
+//!
+//! 脚本不能导入模块、不能调用 `eval`，`print` 和 `debug` 的输出被丢弃；
+//! rhai 本身没有文件和网络访问。每次运行受 `ScriptLimits` 限制：
+//! 超过操作数或运行时长的脚本被终止。
+//!
+//! 写入在脚本成功结束后按调用顺序执行，脚本出错或被终止时不执行任何写入。
+//! 脚本错误和写入失败不影响其他脚本，计入 `errors`，最近的原因可以通过 `last_error` 查看。
+//!
+//! 脚本在数据变化回调中运行，写入同一个服务器会被重入保护拒绝（见 `reentrancy` 模块）。
+//! 写入应通过 `Writer` 或交给其他线程。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::{ScriptHooks, Writer};
+//! use std::sync::Arc;
+//!
+//! let writer = Writer::spawn(&conn, &["Pump.Cmd"])?;
+//! let hooks = Arc::new(ScriptHooks::new(writer));
+//! hooks.attach("Tank.Level", r#"
+//!     if quality == "Good" && value > 95.0 { write("Pump.Cmd", 0); }
+//! "#)?;
+//! group.add_callback(hooks.clone())?;
+//! ```
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Scope, AST};
+use crate::error::{OpcError, OpcResult};
+use crate::types::{lock_or_recover, OpcDataCallback, OpcQuality, OpcValue};
+use crate::writer::Writer;
+
+/// 每次运行脚本的限制
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScriptLimits {
+    /// 最多执行的操作数，0 表示不限制
+    pub max_operations: u64,
+    /// 最长运行时长，在执行操作之间检查
+    pub max_time: Duration,
+}
+
+impl Default for ScriptLimits {
+    fn default() -> Self {
+        ScriptLimits {
+            max_operations: 100_000,
+            max_time: Duration::from_millis(10),
+        }
+    }
+}
+
+/// 执行脚本请求的写入
+///
+/// 在数据变化回调的线程中调用。
+pub trait ScriptWriter: Send + Sync {
+    /// 写入一个项
+    fn write(&self, item_name: &str, value: OpcValue) -> OpcResult<()>;
+}
+
+impl<F: Fn(&str, OpcValue) -> OpcResult<()> + Send + Sync> ScriptWriter for F {
+    fn write(&self, item_name: &str, value: OpcValue) -> OpcResult<()> {
+        self(item_name, value)
+    }
+}
+
+impl ScriptWriter for Writer {
+    fn write(&self, item_name: &str, value: OpcValue) -> OpcResult<()> {
+        Writer::write(self, item_name, value)
+    }
+}
+
+/// 正在当前线程运行的脚本
+struct Run {
+    started: Instant,
+    /// 脚本请求的写入
+    writes: Vec<(String, OpcValue)>,
+}
+
+thread_local! {
+    static RUN: RefCell<Option<Run>> = const { RefCell::new(None) };
+}
+
+#[derive(Default)]
+struct HookState {
+    errors: u64,
+    last_error: Option<String>,
+}
+
+/// 挂在项上的 rhai 脚本
+pub struct ScriptHooks {
+    engine: Engine,
+    limits: ScriptLimits,
+    writer: Box<dyn ScriptWriter>,
+    /// 项名 -> 按挂载顺序排列的脚本
+    scripts: Mutex<HashMap<String, Vec<Arc<AST>>>>,
+    /// 项名 -> 最近收到的值，供 `last` 使用
+    values: Arc<Mutex<HashMap<String, OpcValue>>>,
+    state: Mutex<HookState>,
+}
+
+impl ScriptHooks {
+    /// 创建钩子，脚本请求的写入交给 `writer`
+    pub fn new(writer: impl ScriptWriter + 'static) -> Self {
+        let limits = ScriptLimits::default();
+        let values = Arc::new(Mutex::new(HashMap::new()));
+        ScriptHooks {
+            engine: build_engine(limits, Arc::clone(&values)),
+            limits,
+            writer: Box::new(writer),
+            scripts: Mutex::new(HashMap::new()),
+            values,
+            state: Mutex::new(HookState::default()),
+        }
+    }
+
+    /// 使用指定的运行限制
+    pub fn with_limits(mut self, limits: ScriptLimits) -> Self {
+        self.engine = build_engine(limits, Arc::clone(&self.values));
+        self.limits = limits;
+        self
+    }
+
+    /// 每次运行脚本的限制
+    pub fn limits(&self) -> ScriptLimits {
+        self.limits
+    }
+
+    /// 编译脚本并挂在项上，在已挂载的脚本之后运行
+    ///
+    /// # 返回值
+    /// - `Ok(())`: 挂载成功
+    /// - `Err(OpcError::InvalidParameters)`: 脚本有语法错误
+    pub fn attach(&self, item_name: &str, source: &str) -> OpcResult<()> {
+        let ast = self
+            .engine
+            .compile(source)
+            .map_err(|e| OpcError::invalid_parameters(format!("Script for {} does not compile: {}", item_name, e)))?;
+        lock_or_recover(&self.scripts)
+            .entry(item_name.to_string())
+            .or_default()
+            .push(Arc::new(ast));
+        Ok(())
+    }
+
+    /// 移除项上的所有脚本，返回是否有脚本被移除
+    pub fn detach(&self, item_name: &str) -> bool {
+        lock_or_recover(&self.scripts).remove(item_name).is_some()
+    }
+
+    /// 挂有脚本的项名，按名称排序
+    pub fn attached_items(&self) -> Vec<String> {
+        let mut items: Vec<String> = lock_or_recover(&self.scripts).keys().cloned().collect();
+        items.sort();
+        items
+    }
+
+    /// 运行挂在项上的脚本并执行请求的写入
+    ///
+    /// 返回成功执行的写入数。脚本错误和写入失败不中断其他脚本，
+    /// 计入 `errors` 并记录在 `last_error` 中。
+    pub fn run(&self, group_name: &str, item_name: &str, value: &OpcValue, quality: OpcQuality, timestamp: u64) -> usize {
+        lock_or_recover(&self.values).insert(item_name.to_string(), value.clone());
+        let scripts = match lock_or_recover(&self.scripts).get(item_name) {
+            Some(scripts) => scripts.clone(),
+            None => return 0,
+        };
+
+        let mut written = 0;
+        for ast in scripts {
+            let mut scope = Scope::new();
+            scope.push_constant("group", group_name.to_string());
+            scope.push_constant("item", item_name.to_string());
+            scope.push_constant("value", to_dynamic(value));
+            scope.push_constant("quality", quality.to_string());
+            scope.push_constant("timestamp", timestamp as i64);
+
+            RUN.with(|run| *run.borrow_mut() = Some(Run { started: Instant::now(), writes: Vec::new() }));
+            let result = self.engine.run_ast_with_scope(&mut scope, &ast);
+            let writes = RUN.with(|run| run.borrow_mut().take()).map(|run| run.writes).unwrap_or_default();
+
+            if let Err(e) = result {
+                let reason = match *e {
+                    EvalAltResult::ErrorTerminated(..) => {
+                        format!("exceeded the time limit of {:?}", self.limits.max_time)
+                    }
+                    e => e.to_string(),
+                };
+                self.record_error(format!("Script for {} failed: {}", item_name, reason));
+                continue;
+            }
+            for (target, value) in writes {
+                match self.writer.write(&target, value) {
+                    Ok(()) => written += 1,
+                    Err(e) => self.record_error(format!("Script for {} could not write {}: {}", item_name, target, e)),
+                }
+            }
+        }
+        written
+    }
+
+    /// 脚本错误和写入失败的次数
+    pub fn errors(&self) -> u64 {
+        lock_or_recover(&self.state).errors
+    }
+
+    /// 最近一次错误的原因
+    pub fn last_error(&self) -> Option<String> {
+        lock_or_recover(&self.state).last_error.clone()
+    }
+
+    fn record_error(&self, error: String) {
+        let mut state = lock_or_recover(&self.state);
+        state.errors += 1;
+        state.last_error = Some(error);
+    }
+}
+
+impl OpcDataCallback for ScriptHooks {
+    fn on_data_change(&self, group_name: &str, item_name: &str, value: OpcValue, quality: OpcQuality, timestamp: u64) {
+        // 错误已记录在 errors 和 last_error 中
+        self.run(group_name, item_name, &value, quality, timestamp);
+    }
+}
+
+/// 创建沙箱化的引擎并注册 `write` 和 `last`
+fn build_engine(limits: ScriptLimits, values: Arc<Mutex<HashMap<String, OpcValue>>>) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_operations(limits.max_operations)
+        .set_max_modules(0)
+        .set_module_resolver(rhai::module_resolvers::DummyModuleResolver::new())
+        .set_max_call_levels(32)
+        .set_max_string_size(64 * 1024)
+        .set_max_array_size(10_000)
+        .set_max_map_size(10_000)
+        .disable_symbol("eval")
+        .on_print(|_| {})
+        .on_debug(|_, _, _| {});
+
+    let max_time = limits.max_time;
+    engine.on_progress(move |_| {
+        let expired = RUN.with(|run| run.borrow().as_ref().is_some_and(|run| run.started.elapsed() > max_time));
+        expired.then_some(Dynamic::UNIT)
+    });
+
+    engine.register_fn("write", |item_name: &str, value: Dynamic| -> Result<(), Box<EvalAltResult>> {
+        let value = from_dynamic(value)?;
+        RUN.with(|run| {
+            if let Some(run) = run.borrow_mut().as_mut() {
+                run.writes.push((item_name.to_string(), value));
+            }
+        });
+        Ok(())
+    });
+    engine.register_fn("last", move |item_name: &str| -> Dynamic {
+        lock_or_recover(&values).get(item_name).map(to_dynamic).unwrap_or(Dynamic::UNIT)
+    });
+    engine
+}
+
+/// 把项的值转换为脚本中的值
+fn to_dynamic(value: &OpcValue) -> Dynamic {
+    fn ints<T: Copy + Into<i64>>(values: &[T]) -> Dynamic {
+        values.iter().map(|v| Dynamic::from_int((*v).into())).collect::<Array>().into()
+    }
+
+    match value {
+        OpcValue::Bool(v) => Dynamic::from_bool(*v),
+        OpcValue::String(v) => v.clone().into(),
+        OpcValue::Int8(v) => Dynamic::from_int((*v).into()),
+        OpcValue::UInt8(v) => Dynamic::from_int((*v).into()),
+        OpcValue::Int16(v) => Dynamic::from_int((*v).into()),
+        OpcValue::UInt16(v) => Dynamic::from_int((*v).into()),
+        OpcValue::Int32(v) => Dynamic::from_int((*v).into()),
+        OpcValue::UInt32(v) => Dynamic::from_int((*v).into()),
+        OpcValue::Int64(v) => Dynamic::from_int(*v),
+        OpcValue::INT(v) => Dynamic::from_int(*v as i64),
+        OpcValue::UInt64(v) => i64::try_from(*v).map_or(Dynamic::from_float(*v as f64), Dynamic::from_int),
+        OpcValue::UINT(v) => i64::try_from(*v).map_or(Dynamic::from_float(*v as f64), Dynamic::from_int),
+        OpcValue::ArrayInt16(v) => ints(v),
+        OpcValue::ArrayUInt16(v) => ints(v),
+        OpcValue::ArrayInt32(v) => ints(v),
+        OpcValue::ArrayUInt32(v) => ints(v),
+        OpcValue::ArrayInt64(v) => ints(v),
+        OpcValue::ArrayUInt64(v) => v.iter().map(|v| to_dynamic(&OpcValue::UInt64(*v))).collect::<Array>().into(),
+        OpcValue::ArrayFloat(v) => v.iter().map(|v| Dynamic::from_float(*v as f64)).collect::<Array>().into(),
+        OpcValue::ArrayDouble(v) => v.iter().map(|v| Dynamic::from_float(*v)).collect::<Array>().into(),
+        OpcValue::ArrayBool(v) => v.iter().map(|v| Dynamic::from_bool(*v)).collect::<Array>().into(),
+        OpcValue::ArrayString(v) => v.iter().map(|v| Dynamic::from(v.clone())).collect::<Array>().into(),
+        other => other.as_f64().map_or(Dynamic::UNIT, Dynamic::from_float),
+    }
+}
+
+/// 把脚本中的值转换为要写入的值
+fn from_dynamic(value: Dynamic) -> Result<OpcValue, Box<EvalAltResult>> {
+    if let Ok(v) = value.as_bool() {
+        Ok(OpcValue::Bool(v))
+    } else if let Ok(v) = value.as_int() {
+        Ok(i32::try_from(v).map_or(OpcValue::Int64(v), OpcValue::Int32))
+    } else if let Ok(v) = value.as_float() {
+        Ok(OpcValue::Double(v))
+    } else if value.is_string() {
+        Ok(OpcValue::String(value.into_string()?))
+    } else {
+        Err(format!("Cannot write a value of type {}", value.type_name()).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 记录写入的项名和值
+    fn recording_hooks() -> (ScriptHooks, Arc<Mutex<Vec<(String, OpcValue)>>>) {
+        let writes = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&writes);
+        let hooks = ScriptHooks::new(move |item_name: &str, value: OpcValue| {
+            sink.lock().unwrap().push((item_name.to_string(), value));
+            Ok(())
+        });
+        (hooks, writes)
+    }
+
+    #[test]
+    fn test_script_writes_on_threshold() {
+        let (hooks, writes) = recording_hooks();
+        hooks
+            .attach("Tank.Level", r#"if quality == "Good" && value > 95.0 { write("Pump.Cmd", 0); write("Alarm", true); }"#)
+            .unwrap();
+        hooks.attach("Tank.Level", r#"if last("Mode") == "auto" { write("Log", item + "@" + timestamp); }"#).unwrap();
+        assert_eq!(hooks.attached_items(), vec!["Tank.Level".to_string()]);
+
+        hooks.on_data_change("G", "Tank.Level", OpcValue::Double(80.0), OpcQuality::Good, 1);
+        hooks.on_data_change("G", "Tank.Level", OpcValue::Double(97.5), OpcQuality::Bad, 2);
+        assert!(writes.lock().unwrap().is_empty());
+
+        hooks.on_data_change("G", "Mode", OpcValue::String("auto".to_string()), OpcQuality::Good, 3);
+        assert_eq!(hooks.run("G", "Tank.Level", &OpcValue::Float(97.5), OpcQuality::Good, 4), 3);
+        assert_eq!(
+            *writes.lock().unwrap(),
+            vec![
+                ("Pump.Cmd".to_string(), OpcValue::Int32(0)),
+                ("Alarm".to_string(), OpcValue::Bool(true)),
+                ("Log".to_string(), OpcValue::String("Tank.Level@4".to_string())),
+            ]
+        );
+        assert_eq!(hooks.errors(), 0);
+
+        assert!(hooks.detach("Tank.Level"));
+        assert!(hooks.attached_items().is_empty());
+    }
+
+    #[test]
+    fn test_limits_and_sandbox() {
+        let (hooks, writes) = recording_hooks();
+        let hooks = hooks.with_limits(ScriptLimits { max_operations: 1_000, max_time: Duration::from_secs(1) });
+        assert!(matches!(hooks.attach("A", "if {"), Err(OpcError::InvalidParameters(_))));
+
+        // 超过操作数的脚本被终止，之前请求的写入不执行
+        hooks.attach("A", r#"write("Out", 1); loop { }"#).unwrap();
+        // eval 被禁用，导入模块在运行时失败
+        assert!(hooks.attach("A", r#"eval("write(\"Out\", 2)");"#).is_err());
+        hooks.attach("A", r#"write("Out", 2); import "secret" as s;"#).unwrap();
+        hooks.attach("A", r#"write("Out", [1, 2]);"#).unwrap();
+        hooks.attach("A", r#"write("Out", 3);"#).unwrap();
+        assert_eq!(hooks.run("G", "A", &OpcValue::Int16(1), OpcQuality::Good, 0), 1);
+        assert_eq!(*writes.lock().unwrap(), vec![("Out".to_string(), OpcValue::Int32(3))]);
+        assert_eq!(hooks.errors(), 3);
+        assert!(hooks.last_error().unwrap().contains("Cannot write a value of type array"));
+
+        // 超过运行时长的脚本被终止
+        let (hooks, writes) = recording_hooks();
+        let hooks = hooks.with_limits(ScriptLimits { max_operations: 0, max_time: Duration::from_millis(20) });
+        hooks.attach("B", r#"write("Out", 1); loop { }"#).unwrap();
+        assert_eq!(hooks.run("G", "B", &OpcValue::Bool(true), OpcQuality::Good, 0), 0);
+        assert!(writes.lock().unwrap().is_empty());
+        assert!(hooks.last_error().unwrap().contains("time limit"), "{:?}", hooks.last_error());
+    }
+}
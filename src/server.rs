@@ -23,11 +23,167 @@
 //! `OpcServer` 不是线程安全的，因为底层的 OPC COM 对象可能有线程限制。
 //! 建议在创建 `OpcServer` 的同一线程中使用它。
 
+use std::cell::{Cell, RefCell};
+use std::path::Path;
 use std::ptr;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use crate::cache::UnknownItemCache;
+use crate::diagnostics::{GroupStats, ServerDiagnostics};
 use crate::error::{OpcError, OpcResult};
 use crate::group::OpcGroup;
+use crate::item_id::ItemIdRules;
+use crate::leaks::{capture_backtrace, LiveResource, ResourceKind};
+use crate::limits::ResourceLimits;
+use crate::namespace::{render_namespace, NamespaceFormat};
+use crate::persist::write_atomic;
+use crate::quirks::{QuirkProfile, QuirkRegistry};
+#[cfg(feature = "serde")]
+use crate::snapshot::{render_snapshot, SnapshotEntry};
+use crate::types::{OpcCallbackContainer, ServerState, SubscriptionCloseReason};
 use crate::utils;
 
+/// 组名已被占用时的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateGroupPolicy {
+    /// 直接返回错误
+    #[default]
+    Fail,
+    /// 依次尝试 `名称~2`、`名称~3` …，最多尝试 `max_attempts` 个名称（包括原名称）
+    AutoSuffix {
+        /// 最多尝试的名称数量
+        max_attempts: usize,
+    },
+}
+
+/// 按策略创建组时实际采用的方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroupCreation {
+    /// 使用请求的名称创建了新组
+    Created,
+    /// 请求的名称被占用，使用带后缀的名称创建了新组
+    Suffixed {
+        /// 实际使用的组名
+        name: String,
+    },
+}
+
+/// 下一个服务器连接编号
+static NEXT_SERVER_ID: AtomicU64 = AtomicU64::new(1);
+
+/// 服务器与其创建的组共享的状态
+#[derive(Default)]
+pub(crate) struct ServerShared {
+    /// 连接编号，用于识别通知所属的服务器
+    pub id: u64,
+    /// 主机名
+    pub host: String,
+    /// 服务器名
+    pub server_name: String,
+    /// 服务器连接是否仍然存在
+    pub alive: Cell<bool>,
+    /// 连接创建时的调用栈
+    pub backtrace: Option<String>,
+    /// 厂商兼容性配置（新建的组会继承此配置）
+    pub quirks: RefCell<QuirkProfile>,
+    /// 软限制
+    pub limits: Cell<ResourceLimits>,
+    /// 未知项否定缓存
+    pub unknown_items: UnknownItemCache,
+    /// 所有组的订阅，用于在连接断开时通知消费者
+    pub subscriptions: RefCell<Vec<Weak<OpcCallbackContainer>>>,
+    /// 所有组的运行统计，用于诊断报告
+    pub groups: RefCell<Vec<std::rc::Weak<GroupStats>>>,
+}
+
+impl ServerShared {
+    /// 登记组的订阅
+    pub fn register_subscription(&self, container: &Arc<OpcCallbackContainer>) {
+        let mut subscriptions = self.subscriptions.borrow_mut();
+        subscriptions.retain(|weak| weak.strong_count() > 0);
+        subscriptions.push(Arc::downgrade(container));
+    }
+    
+    /// 登记组的运行统计
+    pub fn register_group(&self, stats: &Rc<GroupStats>) {
+        let mut groups = self.groups.borrow_mut();
+        groups.retain(|weak| weak.strong_count() > 0);
+        groups.push(Rc::downgrade(stats));
+    }
+    
+    /// 仍然存在的组
+    fn live_groups(&self) -> Vec<Rc<GroupStats>> {
+        self.groups
+            .borrow()
+            .iter()
+            .filter_map(std::rc::Weak::upgrade)
+            .filter(|stats| stats.alive.get())
+            .collect()
+    }
+    
+    /// 本连接中是否有同名的组仍然存在
+    pub fn has_live_group(&self, name: &str) -> bool {
+        self.live_groups().iter().any(|stats| stats.name == name)
+    }
+    
+    /// 检查是否可以再创建一个组
+    pub fn check_group_limit(&self) -> OpcResult<()> {
+        self.limits.get().check_group(self.live_groups().len())
+    }
+    
+    /// 检查是否可以向组中再添加一个项
+    pub fn check_item_limit(&self, group: &GroupStats) -> OpcResult<()> {
+        let total: usize = self.live_groups().iter().map(|stats| stats.items.borrow().len()).sum();
+        self.limits.get().check_item(&group.name, group.items.borrow().len(), total)
+    }
+    
+    /// 生成服务器的诊断信息
+    pub fn diagnostics(&self) -> ServerDiagnostics {
+        ServerDiagnostics {
+            host: self.host.clone(),
+            server_name: self.server_name.clone(),
+            quirks: self.quirks.borrow().name.clone(),
+            unknown_items: self.unknown_items.items().len(),
+            groups: self
+                .groups
+                .borrow()
+                .iter()
+                .filter_map(std::rc::Weak::upgrade)
+                .map(|stats| stats.diagnostics())
+                .collect(),
+        }
+    }
+    
+    /// 列出服务器连接和由其创建的仍然存在的组与项
+    pub fn live_resources(&self) -> Vec<LiveResource> {
+        let name = format!("{}@{}", self.server_name, self.host);
+        let server = self.alive.get().then(|| LiveResource {
+            kind: ResourceKind::Server,
+            name: name.clone(),
+            owner: None,
+            backtrace: self.backtrace.clone(),
+        });
+        let groups: Vec<_> = self
+            .groups
+            .borrow()
+            .iter()
+            .filter_map(std::rc::Weak::upgrade)
+            .flat_map(|stats| stats.live_resources(&name))
+            .collect();
+        server.into_iter().chain(groups).collect()
+    }
+    
+    /// 关闭所有仍然存在的订阅
+    pub fn close_subscriptions(&self, reason: &SubscriptionCloseReason) {
+        let subscriptions: Vec<_> = self.subscriptions.borrow_mut().drain(..).collect();
+        for container in subscriptions.iter().filter_map(Weak::upgrade) {
+            container.close(reason);
+        }
+    }
+}
+
 /// OPC 服务器连接
 /// 
 /// 表示到 OPC DA 服务器的活动连接。通过这个对象可以：
@@ -39,6 +195,7 @@ use crate::utils;
 /// 
 /// - `ptr`: 指向底层 OPC 服务器对象的指针
 /// - `host_ptr`: 指向主机对象的指针（用于资源清理）
+/// - `shared`: 与所创建的组共享的状态（兼容性配置、未知项否定缓存、订阅列表、组统计）
 /// 
 /// ## 示例
 /// 
@@ -60,6 +217,8 @@ pub struct OpcServer {
     ptr: *mut std::ffi::c_void,
     /// 指向主机对象的指针（需要与服务器一起清理）
     host_ptr: *mut std::ffi::c_void,
+    /// 与组共享的状态
+    shared: Rc<ServerShared>,
 }
 
 impl OpcServer {
@@ -68,13 +227,29 @@ impl OpcServer {
     /// # 参数
     /// - `server_ptr`: 指向底层 OPC 服务器对象的指针
     /// - `host_ptr`: 指向主机对象的指针
+    /// - `hostname`: 主机名
+    /// - `server_name`: 服务器名
     /// 
     /// # 注意
     /// 这个方法仅供内部使用，用户应该通过 `OpcClient::connect_to_server` 获取 `OpcServer` 实例。
-    pub(crate) fn new(server_ptr: *mut std::ffi::c_void, host_ptr: *mut std::ffi::c_void) -> Self {
+    pub(crate) fn new(
+        server_ptr: *mut std::ffi::c_void,
+        host_ptr: *mut std::ffi::c_void,
+        hostname: &str,
+        server_name: &str,
+    ) -> Self {
         OpcServer {
             ptr: server_ptr,
             host_ptr,
+            shared: Rc::new(ServerShared {
+                id: NEXT_SERVER_ID.fetch_add(1, Ordering::Relaxed),
+                host: hostname.to_string(),
+                server_name: server_name.to_string(),
+                alive: Cell::new(true),
+                backtrace: capture_backtrace(),
+                quirks: RefCell::new(QuirkProfile::none()),
+                ..Default::default()
+            }),
         }
     }
     
@@ -84,17 +259,19 @@ impl OpcServer {
     /// 
     /// # 返回值
     /// - `Ok((state, vendor_info))`: 成功获取状态信息
-    ///   - `state`: 服务器状态码（具体含义取决于服务器实现）
+    ///   - `state`: 服务器状态
     ///   - `vendor_info`: 厂商信息字符串
     /// - `Err(OpcError)`: 获取状态失败
     /// 
-    /// # 服务器状态码
-    /// 常见的 OPC 服务器状态包括：
-    /// - 1: 运行中 (Running)
-    /// - 2: 失败 (Failed)
-    /// - 3: 无配置 (No Config)
-    /// - 4: 挂起 (Suspended)
-    /// - 5: 测试 (Test)
+    /// # 服务器状态
+    /// 原始状态码转换为 `ServerState`：
+    /// - 1: 运行中 (`Running`)
+    /// - 2: 失败 (`Failed`)
+    /// - 3: 无配置 (`NoConfig`)
+    /// - 4: 挂起 (`Suspended`)
+    /// - 5: 测试 (`Test`)
+    /// - 6: 通讯故障 (`CommFault`)
+    /// - 其他: `Unknown(code)`
     /// 
     /// # 示例
     /// ```
@@ -115,7 +292,8 @@ impl OpcServer {
     /// # 注意
     /// - 厂商信息字符串由服务器提供，格式和内容因厂商而异
     /// - 如果服务器不提供厂商信息，返回空字符串
-    pub fn get_status(&self) -> OpcResult<(u32, String)> {
+    pub fn get_status(&self) -> OpcResult<(ServerState, String)> {
+        crate::reentrancy::check("OpcServer::get_status", Some(self.shared.id))?;
         let mut state: u32 = 0;
         let mut vendor_info_ptr: *mut u16 = ptr::null_mut();
         
@@ -135,7 +313,7 @@ impl OpcServer {
                 String::new()
             };
             
-            Ok((state, vendor_info))
+            Ok((ServerState::from_raw(state), vendor_info))
         } else {
             Err(OpcError::operation_failed("Failed to get server status"))
         }
@@ -192,6 +370,9 @@ impl OpcServer {
         requested_update_rate: u32,
         deadband: f64,
     ) -> OpcResult<OpcGroup> {
+        crate::reentrancy::check("OpcServer::create_group", Some(self.shared.id))?;
+        self.shared.check_group_limit()?;
+        
         // 将组名转换为 UTF-16 宽字符串
         let group_name_wide = utils::to_wide_string(name);
         let mut actual_update_rate: u32 = 0;
@@ -211,7 +392,20 @@ impl OpcServer {
         };
         
         if result == 0 && !group_ptr.is_null() {
-            Ok(OpcGroup::new(group_ptr))
+            let quirks = self.quirks();
+            let stats = Rc::new(GroupStats {
+                name: name.to_string(),
+                active,
+                requested_update_rate,
+                actual_update_rate,
+                deadband,
+                quirks: quirks.name.clone(),
+                alive: Cell::new(true),
+                backtrace: capture_backtrace(),
+                ..Default::default()
+            });
+            self.shared.register_group(&stats);
+            Ok(OpcGroup::new(group_ptr, name, quirks, deadband, Rc::clone(&self.shared), stats))
         } else {
             Err(OpcError::GroupCreationFailed(
                 format!("Failed to create group '{}'", name)
@@ -219,6 +413,39 @@ impl OpcServer {
         }
     }
     
+    /// 按组名占用策略创建 OPC 组
+    /// 
+    /// 服务器上可能残留崩溃的客户端实例留下的同名组，此时 `create_group` 会失败。
+    /// `DuplicateGroupPolicy::AutoSuffix` 在创建失败时依次尝试 `名称~2`、`名称~3` …；
+    /// 本连接中仍然存在的同名组会直接跳过，不访问服务器。
+    /// 
+    /// # 参数
+    /// - `name`、`active`、`requested_update_rate`、`deadband`: 同 `create_group`
+    /// - `policy`: 组名被占用时的处理策略
+    /// 
+    /// # 返回值
+    /// - `Ok((OpcGroup, GroupCreation))`: 创建的组及实际采用的方式
+    /// - `Err(OpcError)`: 所有候选名称都创建失败，返回最后一次的错误；
+    ///   超过软限制等与组名无关的错误会立即返回
+    /// 
+    /// # 注意
+    /// 工具库不区分创建失败的原因，因此任何 `GroupCreationFailed` 都会尝试下一个名称。
+    pub fn create_group_with_policy(
+        &self,
+        name: &str,
+        active: bool,
+        requested_update_rate: u32,
+        deadband: f64,
+        policy: DuplicateGroupPolicy,
+    ) -> OpcResult<(OpcGroup, GroupCreation)> {
+        create_with_policy(
+            name,
+            policy,
+            |candidate| self.shared.has_live_group(candidate),
+            |candidate| self.create_group(candidate, active, requested_update_rate, deadband),
+        )
+    }
+    
     /// 获取服务器中所有可用的项名
     /// 
     /// 这个方法浏览服务器命名空间，返回所有可访问的数据项名称。
@@ -261,6 +488,7 @@ impl OpcServer {
     ///   - "Random.*" (随机数项)
     ///   - "Triangle Waves.*" (三角波形项)
     pub fn get_item_names(&self) -> OpcResult<Vec<String>> {
+        crate::reentrancy::check("OpcServer::get_item_names", Some(self.shared.id))?;
         let mut item_names_ptr: *mut *mut u16 = ptr::null_mut();
         let mut count: u32 = 0;
         
@@ -294,6 +522,173 @@ impl OpcServer {
         }
     }
     
+    /// 根据服务器厂商信息检测并应用兼容性配置
+    /// 
+    /// 使用内置配置（Matrikon、Kepware、RSLinx）进行匹配。
+    /// 检测到的配置会应用于之后通过 `create_group` 创建的组。
+    /// 
+    /// # 返回值
+    /// - `Ok(QuirkProfile)`: 生效的配置，没有匹配时为空配置
+    /// - `Err(OpcError)`: 获取服务器状态失败
+    pub fn detect_quirks(&self) -> OpcResult<QuirkProfile> {
+        self.detect_quirks_with(&QuirkRegistry::new())
+    }
+    
+    /// 使用指定的注册表检测并应用兼容性配置
+    /// 
+    /// # 参数
+    /// - `registry`: 包含自定义配置的注册表
+    pub fn detect_quirks_with(&self, registry: &QuirkRegistry) -> OpcResult<QuirkProfile> {
+        let (_, vendor_info) = self.get_status()?;
+        let profile = registry.lookup(&vendor_info);
+        self.set_quirks(profile.clone());
+        Ok(profile)
+    }
+    
+    /// 手动设置兼容性配置
+    /// 
+    /// # 注意
+    /// 只影响之后创建的组，已创建的组保持原有配置。
+    pub fn set_quirks(&self, profile: QuirkProfile) {
+        *self.shared.quirks.borrow_mut() = profile;
+    }
+    
+    /// 获取当前生效的兼容性配置
+    pub fn quirks(&self) -> QuirkProfile {
+        self.shared.quirks.borrow().clone()
+    }
+    
+    /// 检测服务器的项 ID 约定
+    /// 
+    /// 浏览服务器命名空间，根据项名推断层级分隔符。
+    /// 返回的约定可用于在添加项之前校验和规范化配置中的项 ID。
+    /// 
+    /// # 返回值
+    /// - `Ok(ItemIdRules)`: 推断出的约定（默认区分大小写）
+    /// - `Err(OpcError)`: 浏览服务器失败
+    /// 
+    /// # 注意
+    /// - 工具库不提供直接查询分隔符的接口，结果来自对项名的统计
+    /// - 大小写敏感性无法推断，如服务器不区分大小写请手动设置 `case_sensitive`
+    pub fn detect_item_id_rules(&self) -> OpcResult<ItemIdRules> {
+        let item_names = self.get_item_names()?;
+        Ok(ItemIdRules::infer(&item_names))
+    }
+    
+    /// 将服务器命名空间导出到文件
+    /// 
+    /// 浏览服务器的全部项名，按推断的项 ID 约定拆分路径，以 CSV 或 JSON 格式写入文件。
+    /// 文件以原子方式写入，导出失败时不会留下不完整的文件。
+    /// 
+    /// # 参数
+    /// - `path`: 输出文件路径
+    /// - `format`: 导出格式
+    /// 
+    /// # 返回值
+    /// - `Ok(usize)`: 导出的项数
+    /// - `Err(OpcError)`: 浏览服务器或写入文件失败
+    /// 
+    /// # 注意
+    /// - 工具库一次返回全部项名，不支持分页浏览，大型命名空间需要相应的内存
+    /// - 只导出项名和路径，工具库不提供项属性的浏览接口
+    pub fn export_namespace(&self, path: impl AsRef<Path>, format: NamespaceFormat) -> OpcResult<usize> {
+        let item_names = self.get_item_names()?;
+        let rules = ItemIdRules::infer(&item_names);
+        let contents = render_namespace(&item_names, &rules, format);
+        write_atomic(path.as_ref(), contents.as_bytes())?;
+        Ok(item_names.len())
+    }
+    
+    /// 读取一组项并生成 JSON 快照
+    /// 
+    /// 创建一个临时的非活动组（名称被占用时自动加后缀），逐个添加并同步读取项，
+    /// 返回时释放该组。格式见 `snapshot` 模块。
+    /// 
+    /// # 参数
+    /// - `items`: 项 ID 列表
+    /// 
+    /// # 返回值
+    /// - `Ok(String)`: JSON 文档；单个项添加或读取失败时该项只有 `error` 字段
+    /// - `Err(OpcError)`: 创建临时组失败
+    #[cfg(feature = "serde")]
+    pub fn snapshot_to_json(&self, items: &[&str]) -> OpcResult<String> {
+        let (group, _) = self.create_group_with_policy(
+            "Snapshot",
+            false,
+            0,
+            0.0,
+            DuplicateGroupPolicy::AutoSuffix { max_attempts: 16 },
+        )?;
+        let entries: Vec<SnapshotEntry<&str>> = items
+            .iter()
+            .map(|&item_id| (item_id, group.add_item(item_id).and_then(|item| item.read_sync())))
+            .collect();
+        Ok(render_snapshot(&entries))
+    }
+    
+    /// 设置软限制
+    /// 
+    /// 创建组或添加项会超过限制时返回 `OpcError::LimitExceeded`，不访问服务器。
+    /// 已经存在的组和项不受影响。
+    /// 
+    /// # 参数
+    /// - `limits`: 新的限制，`ResourceLimits::default()` 表示不限制
+    pub fn set_limits(&self, limits: ResourceLimits) {
+        self.shared.limits.set(limits);
+    }
+    
+    /// 获取当前生效的软限制
+    pub fn limits(&self) -> ResourceLimits {
+        self.shared.limits.get()
+    }
+    
+    /// 设置未知项否定缓存的有效时间
+    /// 
+    /// 添加失败的项名在有效时间内会被记住，再次添加时直接返回
+    /// `OpcError::ItemNotFound` 而不访问服务器。缓存默认禁用（有效时间为零），
+    /// 设置为零可再次禁用。
+    /// 
+    /// 启用后每一次添加失败都会被缓存，不区分失败的原因：因权限或服务器暂时繁忙
+    /// 而失败的项在有效时间内同样不会重新尝试。
+    /// 
+    /// # 参数
+    /// - `ttl`: 缓存有效时间
+    pub fn set_unknown_item_ttl(&self, ttl: Duration) {
+        self.shared.unknown_items.set_ttl(ttl);
+    }
+    
+    /// 获取当前缓存为未知的项名
+    pub fn unknown_items(&self) -> Vec<String> {
+        self.shared.unknown_items.items()
+    }
+    
+    /// 清空未知项否定缓存
+    /// 
+    /// 在修正服务器配置后调用，使之前失败的项可以立即重新尝试添加。
+    pub fn clear_unknown_items(&self) {
+        self.shared.unknown_items.clear();
+    }
+    
+    /// 关闭由此服务器创建的所有组的订阅
+    /// 
+    /// 每个订阅的回调会收到一次 `on_subscription_closed` 通知，之后不再收到数据变化。
+    /// 应用检测到服务器关闭或致命的连接错误时可以调用此方法，
+    /// 让下游消费者及时结束，而不是在没有数据的订阅上等待。
+    /// 
+    /// # 参数
+    /// - `reason`: 关闭原因
+    /// 
+    /// # 注意
+    /// 服务器连接释放时会自动以 `SubscriptionCloseReason::Disconnected` 关闭所有订阅。
+    pub fn close_subscriptions(&self, reason: SubscriptionCloseReason) {
+        self.shared.close_subscriptions(&reason);
+    }
+    
+    /// 与组共享的状态（内部使用）
+    pub(crate) fn shared(&self) -> &Rc<ServerShared> {
+        &self.shared
+    }
+    
     /// 获取原始服务器指针（内部使用）
     /// 
     /// # 注意
@@ -311,13 +706,19 @@ impl Drop for OpcServer {
     /// 这会释放服务器和主机对象，确保没有资源泄漏。
     /// 
     /// # 清理顺序
-    /// 1. 释放服务器对象 (`opc_server_free`)
-    /// 2. 释放主机对象 (`opc_host_free`)
+    /// 1. 关闭所有仍然存在的订阅
+    /// 2. 释放服务器对象 (`opc_server_free`)
+    /// 3. 释放主机对象 (`opc_host_free`)
     /// 
     /// # 注意
     /// - 调用此方法后，不应再使用此服务器或由其创建的任何组/项
     /// - 资源清理是自动的，用户通常不需要手动调用
     fn drop(&mut self) {
+        self.shared.alive.set(false);
+        // 通知仍在订阅的消费者连接已结束
+        self.shared.close_subscriptions(&SubscriptionCloseReason::Disconnected(
+            "server connection released".to_string()
+        ));
         unsafe {
             // 先释放服务器对象
             crate::ffi::opc_server_free(self.ptr);
@@ -325,4 +726,125 @@ impl Drop for OpcServer {
             crate::ffi::opc_host_free(self.host_ptr);
         }
     }
-}
\ No newline at end of file
+}
+
+/// `OpcServer::create_group_with_policy` 的实现，对任何后端的组通用
+///
+/// `in_use` 判断候选名称是否被本连接中仍然存在的组占用，`create` 创建组。
+fn create_with_policy<G>(
+    name: &str,
+    policy: DuplicateGroupPolicy,
+    in_use: impl Fn(&str) -> bool,
+    mut create: impl FnMut(&str) -> OpcResult<G>,
+) -> OpcResult<(G, GroupCreation)> {
+    let max_attempts = match policy {
+        DuplicateGroupPolicy::Fail => 1,
+        DuplicateGroupPolicy::AutoSuffix { max_attempts } => max_attempts.max(1),
+    };
+    
+    let mut last_error = None;
+    for attempt in 1..=max_attempts {
+        let candidate = if attempt == 1 {
+            name.to_string()
+        } else {
+            format!("{}~{}", name, attempt)
+        };
+        if max_attempts > 1 && in_use(&candidate) {
+            continue;
+        }
+        match create(&candidate) {
+            Ok(group) => {
+                let creation = if attempt == 1 {
+                    GroupCreation::Created
+                } else {
+                    GroupCreation::Suffixed { name: candidate }
+                };
+                return Ok((group, creation));
+            }
+            Err(err @ OpcError::GroupCreationFailed(_)) => last_error = Some(err),
+            Err(err) => return Err(err),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| OpcError::GroupCreationFailed(format!(
+        "All {} candidate names for group '{}' are in use by this connection",
+        max_attempts, name
+    ))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{DaGroup, DaServer};
+    use crate::sim::{SimGroup, SimServer};
+
+    fn create_on(server: &SimServer, name: &str, policy: DuplicateGroupPolicy) -> OpcResult<(SimGroup, GroupCreation)> {
+        create_with_policy(name, policy, |_| false, |candidate| server.create_group(candidate, true, 1000, 0.0))
+    }
+
+    #[test]
+    fn test_auto_suffix_skips_names_left_on_the_server() {
+        let server = SimServer::new();
+        // 崩溃的客户端实例留下的组
+        let _stale = server.create_group("Line1", true, 1000, 0.0).unwrap();
+        let _stale2 = server.create_group("Line1~2", true, 1000, 0.0).unwrap();
+
+        let (group, creation) = create_on(&server, "Line1", DuplicateGroupPolicy::AutoSuffix { max_attempts: 5 }).unwrap();
+        assert_eq!(group.name(), "Line1~3");
+        assert_eq!(creation, GroupCreation::Suffixed { name: "Line1~3".to_string() });
+
+        let (group, creation) = create_on(&server, "Line2", DuplicateGroupPolicy::AutoSuffix { max_attempts: 5 }).unwrap();
+        assert_eq!(group.name(), "Line2");
+        assert_eq!(creation, GroupCreation::Created);
+    }
+
+    #[test]
+    fn test_policy_failures() {
+        let server = SimServer::new();
+        let _stale = server.create_group("Line1", true, 1000, 0.0).unwrap();
+
+        assert!(matches!(
+            create_on(&server, "Line1", DuplicateGroupPolicy::Fail),
+            Err(OpcError::GroupCreationFailed(_))
+        ));
+        // 所有候选名称都被占用时返回最后一次的错误
+        let _stale2 = server.create_group("Line1~2", true, 1000, 0.0).unwrap();
+        assert!(matches!(
+            create_on(&server, "Line1", DuplicateGroupPolicy::AutoSuffix { max_attempts: 2 }),
+            Err(OpcError::GroupCreationFailed(_))
+        ));
+
+        // 与组名无关的错误立即返回
+        server.set_state(ServerState::Failed);
+        let mut attempts = 0;
+        let result = create_with_policy("Line3", DuplicateGroupPolicy::AutoSuffix { max_attempts: 5 }, |_| false, |candidate| {
+            attempts += 1;
+            server.create_group(candidate, true, 1000, 0.0)
+        });
+        assert!(matches!(result, Err(OpcError::OperationFailed(_))));
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn test_live_groups_of_this_connection_are_skipped_without_creating() {
+        let server = SimServer::new();
+        let mut tried = Vec::new();
+        let (group, _) = create_with_policy(
+            "Line1",
+            DuplicateGroupPolicy::AutoSuffix { max_attempts: 3 },
+            |candidate| candidate != "Line1~3",
+            |candidate| {
+                tried.push(candidate.to_string());
+                server.create_group(candidate, true, 1000, 0.0)
+            },
+        )
+        .unwrap();
+        assert_eq!(group.name(), "Line1~3");
+        assert_eq!(tried, vec!["Line1~3".to_string()]);
+
+        // 所有候选名称都被本连接占用
+        let result = create_with_policy("Line2", DuplicateGroupPolicy::AutoSuffix { max_attempts: 2 }, |_| true, |candidate| {
+            server.create_group(candidate, true, 1000, 0.0)
+        });
+        assert!(matches!(result, Err(OpcError::GroupCreationFailed(message)) if message.contains("in use by this connection")));
+    }
+}
@@ -0,0 +1,239 @@
+//! 多服务器会话管理模块
+//!
+//! `OpcSessionManager` 按名称登记多个服务器连接，在第一次使用时连接，
+//! 并把连接以 `SharedOpcClient` 句柄的形式交给调用方。句柄是 `Send + Sync` 且可以克隆，
+//! 管理器本身也可以通过 `Arc` 在线程之间共享。
+//!
+//! 调用 `check_health` 对已连接的服务器执行一次 `GetStatus`；失败或服务器报告
+//! `Failed` 的连接被丢弃，下一次 `session` 时重新连接。
+//!
+//! 所有会话共用一个工作线程，线程中只有一个 `OpcClient`，OPC 库在第一次连接时初始化、
+//! 在管理器和所有句柄释放后停止一次。
+//!
+//! ## 注意
+//!
+//! - 所有会话的请求在同一个线程中依次执行，一个服务器的阻塞调用（例如连接无响应的主机）
+//!   会让其他会话的调用等待它完成。
+//! - 丢弃连接只是释放管理器持有的句柄。调用方仍持有的客户端、组和项句柄继续使用旧的连接，
+//!   全部释放后旧连接才在工作线程中释放。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::OpcSessionManager;
+//! use std::sync::Arc;
+//!
+//! let sessions = Arc::new(OpcSessionManager::new());
+//! let line1 = sessions.register("host=10.0.0.5;progid=Kepware.KEPServerEX.V6".parse()?);
+//! sessions.register_as("line2", "host=10.0.0.6;progid=Kepware.KEPServerEX.V6".parse()?);
+//!
+//! let client = sessions.session(&line1)?;
+//! let group = client.create_group("Plant", true, 1000, 0.0)?;
+//!
+//! // 监控线程
+//! let monitor = Arc::clone(&sessions);
+//! std::thread::spawn(move || loop {
+//!     for (name, status) in monitor.check_health() {
+//!         println!("{}: {:?}", name, status);
+//!     }
+//!     std::thread::sleep(std::time::Duration::from_secs(10));
+//! });
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use crate::connection::ConnectionString;
+use crate::error::{OpcError, OpcResult};
+use crate::shared::SharedOpcClient;
+use crate::types::{lock_or_recover, ServerState};
+use crate::worker::Worker;
+
+/// 会话在一次健康检查后的状态
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionStatus {
+    /// 尚未连接，或者之前的连接已被丢弃
+    NotConnected,
+    /// 连接正常，附带服务器报告的状态
+    Healthy(ServerState),
+    /// 检查失败，连接已被丢弃，附带原因
+    Dropped(String),
+}
+
+/// 一个登记的连接
+struct Slot {
+    connection: ConnectionString,
+    client: Mutex<Option<SharedOpcClient>>,
+}
+
+/// 多服务器会话管理器
+#[derive(Default)]
+pub struct OpcSessionManager {
+    slots: Mutex<HashMap<String, Arc<Slot>>>,
+    /// 所有会话共用的工作线程，第一次连接时启动
+    worker: Mutex<Option<Arc<Worker>>>,
+}
+
+impl OpcSessionManager {
+    /// 创建空的管理器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 连接的默认名称：`主机/ProgID`
+    pub fn session_key(connection: &ConnectionString) -> String {
+        format!("{}/{}", connection.host, connection.progid)
+    }
+
+    /// 以默认名称（`session_key`）登记连接，返回名称
+    pub fn register(&self, connection: ConnectionString) -> String {
+        let name = Self::session_key(&connection);
+        self.register_as(&name, connection);
+        name
+    }
+
+    /// 以指定名称登记连接，不立即连接
+    ///
+    /// 名称已经登记时替换原来的连接，管理器持有的旧连接被丢弃。
+    pub fn register_as(&self, name: &str, connection: ConnectionString) {
+        let slot = Arc::new(Slot {
+            connection,
+            client: Mutex::new(None),
+        });
+        lock_or_recover(&self.slots).insert(name.to_string(), slot);
+    }
+
+    /// 移除登记的连接，返回名称是否存在
+    pub fn remove(&self, name: &str) -> bool {
+        lock_or_recover(&self.slots).remove(name).is_some()
+    }
+
+    /// 所有登记的名称，按名称排序
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = lock_or_recover(&self.slots).keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// 获取连接的句柄，尚未连接时先连接
+    ///
+    /// 同一名称的并发调用只连接一次。
+    ///
+    /// # 返回值
+    /// - `Ok(SharedOpcClient)`: 已连接的客户端句柄
+    /// - `Err(OpcError::InvalidParameters)`: 名称没有登记
+    /// - `Err(OpcError)`: 连接失败，下一次调用时重试
+    pub fn session(&self, name: &str) -> OpcResult<SharedOpcClient> {
+        let slot = self.slot(name)?;
+        let mut client = lock_or_recover(&slot.client);
+        if let Some(client) = client.as_ref() {
+            return Ok(client.clone());
+        }
+        let connected = SharedOpcClient::connect_with(&self.worker()?, &slot.connection)?;
+        *client = Some(connected.clone());
+        Ok(connected)
+    }
+
+    /// 共用的工作线程，尚未启动或启动失败过时启动
+    fn worker(&self) -> OpcResult<Arc<Worker>> {
+        let mut worker = lock_or_recover(&self.worker);
+        if let Some(worker) = worker.as_ref() {
+            return Ok(Arc::clone(worker));
+        }
+        let started = Worker::start_blocking("opcda-sessions")?;
+        *worker = Some(Arc::clone(&started));
+        Ok(started)
+    }
+
+    /// 名称对应的连接当前是否已建立
+    pub fn is_connected(&self, name: &str) -> bool {
+        self.slot(name)
+            .map(|slot| lock_or_recover(&slot.client).is_some())
+            .unwrap_or(false)
+    }
+
+    /// 丢弃名称对应的连接，下一次 `session` 时重新连接
+    ///
+    /// 返回之前是否已连接。
+    pub fn disconnect(&self, name: &str) -> bool {
+        self.slot(name)
+            .map(|slot| lock_or_recover(&slot.client).take().is_some())
+            .unwrap_or(false)
+    }
+
+    /// 检查所有已连接的服务器，丢弃不健康的连接
+    ///
+    /// 不会建立新的连接。返回每个登记的名称及其状态，按名称排序。
+    ///
+    /// 检查期间不持有会话的锁，同时进行的 `session` 调用不必等待 `GetStatus` 返回。
+    pub fn check_health(&self) -> Vec<(String, SessionStatus)> {
+        let mut slots: Vec<(String, Arc<Slot>)> = lock_or_recover(&self.slots)
+            .iter()
+            .map(|(name, slot)| (name.clone(), Arc::clone(slot)))
+            .collect();
+        slots.sort_by(|a, b| a.0.cmp(&b.0));
+
+        slots
+            .into_iter()
+            .map(|(name, slot)| {
+                let client = lock_or_recover(&slot.client).clone();
+                let Some(client) = client else {
+                    return (name, SessionStatus::NotConnected);
+                };
+                let reason = match client.get_status() {
+                    Ok((ServerState::Failed, _)) => "server reports state Failed".to_string(),
+                    Ok((state, _)) => return (name, SessionStatus::Healthy(state)),
+                    Err(e) => e.to_string(),
+                };
+                // 检查期间连接可能已被替换，只丢弃检查过的那个连接
+                let mut current = lock_or_recover(&slot.client);
+                if current.as_ref().is_some_and(|current| current.same_connection(&client)) {
+                    current.take();
+                }
+                (name, SessionStatus::Dropped(reason))
+            })
+            .collect()
+    }
+
+    fn slot(&self, name: &str) -> OpcResult<Arc<Slot>> {
+        lock_or_recover(&self.slots)
+            .get(name)
+            .cloned()
+            .ok_or_else(|| OpcError::invalid_parameters(format!("No session registered as '{}'", name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_and_lookup() {
+        let sessions = OpcSessionManager::new();
+        let key = sessions.register(ConnectionString::new("10.0.0.5", "Kepware.KEPServerEX.V6"));
+        assert_eq!(key, "10.0.0.5/Kepware.KEPServerEX.V6");
+        sessions.register_as("line2", ConnectionString::new("10.0.0.6", "Kepware.KEPServerEX.V6"));
+        assert_eq!(sessions.names(), vec![key.clone(), "line2".to_string()]);
+
+        assert!(matches!(sessions.session("missing"), Err(OpcError::InvalidParameters(_))));
+        assert!(!sessions.is_connected(&key));
+        assert_eq!(
+            sessions.check_health(),
+            vec![
+                (key.clone(), SessionStatus::NotConnected),
+                ("line2".to_string(), SessionStatus::NotConnected),
+            ]
+        );
+        assert!(sessions.remove("line2"));
+        assert!(!sessions.remove("line2"));
+    }
+
+    // 非 Windows 平台上连接失败，错误返回给调用方且不保留连接
+    #[cfg(not(windows))]
+    #[test]
+    fn test_failed_connect_is_retried_later() {
+        let sessions = OpcSessionManager::new();
+        let key = sessions.register(ConnectionString::new("localhost", "Matrikon.OPC.Simulation.1"));
+        assert!(sessions.session(&key).err().unwrap().is_unsupported_platform());
+        assert!(!sessions.is_connected(&key));
+    }
+}
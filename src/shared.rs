@@ -0,0 +1,234 @@
+//! 线程安全句柄模块
+//!
+//! `OpcServer`、`OpcGroup` 和 `OpcItem` 封装的 COM 对象只能在创建它们的线程中使用，
+//! 因此这些类型既不是 `Send` 也不是 `Sync`。这个模块提供对应的
+//! `SharedOpcClient`、`SharedOpcGroup` 和 `SharedOpcItem`：它们是 `Send + Sync`，
+//! 可以移动到其他线程或通过 `Arc` 在线程池中共享，调用会阻塞直到得到结果。
+//!
+//! 与 `Writer` 和 `async` 特性的 `AsyncOpcClient` 相同，句柄背后是一个专用的工作线程，
+//! 三者使用同一个内部实现。
+//! 工作线程自己创建客户端并连接服务器，拥有所有 COM 对象，按收到的顺序依次执行请求；
+//! 多个线程的并发调用因此被串行化。
+//!
+//! ## 注意
+//!
+//! 数据变化回调可能在工作线程中执行。在该线程中调用句柄的方法会等待自己，
+//! 因此这种调用直接返回 `OpcError::ReentrantCall`。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::{ConnectionString, OpcValue, SharedOpcClient};
+//! use std::sync::Arc;
+//!
+//! let conn: ConnectionString = "progid=Matrikon.OPC.Simulation.1".parse()?;
+//! let client = SharedOpcClient::connect(&conn)?;
+//! let group = client.create_group("Plant", true, 1000, 0.0)?;
+//! let item = Arc::new(group.add_item("Bucket Brigade.Int4")?);
+//!
+//! let handles: Vec<_> = (0..4)
+//!     .map(|n| {
+//!         let item = Arc::clone(&item);
+//!         std::thread::spawn(move || item.write_sync(&OpcValue::Int32(n)))
+//!     })
+//!     .collect();
+//! ```
+
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use crate::connection::ConnectionString;
+use crate::error::{OpcError, OpcResult};
+use crate::server::DuplicateGroupPolicy;
+use crate::types::{ChannelCallback, DataChangeEvent, OpcQuality, OpcTimestamp, OpcValue, ServerState};
+use crate::worker::{Request, ServerLink, Worker};
+
+/// 线程安全的 OPC 客户端，对应一个服务器连接
+///
+/// 克隆得到的句柄共享同一个工作线程和服务器连接。
+/// 最后一个句柄（包括组和项的句柄）释放后，服务器连接在工作线程中释放。
+#[derive(Clone)]
+pub struct SharedOpcClient {
+    link: Arc<ServerLink>,
+}
+
+impl SharedOpcClient {
+    /// 启动工作线程并连接到服务器
+    ///
+    /// # 返回值
+    /// - `Ok(SharedOpcClient)`: 工作线程已连接到服务器
+    /// - `Err(OpcError)`: 创建客户端或连接服务器失败
+    ///
+    /// # 注意
+    /// `connection.readonly` 为 `true` 时，所有项的 `write_sync` 都返回 `OpcError::InvalidParameters`。
+    pub fn connect(connection: &ConnectionString) -> OpcResult<Self> {
+        let worker = Worker::start_blocking("opcda-shared")?;
+        Self::connect_with(&worker, connection)
+    }
+
+    /// 在已有的工作线程中连接到服务器
+    pub(crate) fn connect_with(worker: &Arc<Worker>, connection: &ConnectionString) -> OpcResult<Self> {
+        Ok(SharedOpcClient {
+            link: ServerLink::connect(worker, "SharedOpcClient::connect", connection)?,
+        })
+    }
+
+    /// 两个句柄是否使用同一个服务器连接
+    pub(crate) fn same_connection(&self, other: &SharedOpcClient) -> bool {
+        Arc::ptr_eq(&self.link, &other.link)
+    }
+
+    /// 获取服务器状态，同 `OpcServer::get_status`
+    pub fn get_status(&self) -> OpcResult<(ServerState, String)> {
+        self.link.worker.call("SharedOpcClient::get_status", |reply| Request::Status {
+            server: self.link.id,
+            reply,
+        })
+    }
+
+    /// 创建组，参数与 `OpcServer::create_group` 相同
+    pub fn create_group(&self, name: &str, active: bool, update_rate: u32, deadband: f64) -> OpcResult<SharedOpcGroup> {
+        let id = self.link.worker.call("SharedOpcClient::create_group", |reply| Request::CreateGroup {
+            server: self.link.id,
+            name: name.to_string(),
+            active,
+            update_rate,
+            deadband,
+            policy: DuplicateGroupPolicy::Fail,
+            reply,
+        })?;
+        Ok(SharedOpcGroup {
+            id,
+            name: name.to_string(),
+            link: Arc::clone(&self.link),
+        })
+    }
+}
+
+/// 线程安全的 OPC 组
+///
+/// 释放组句柄时，组和组内的项在工作线程中一起释放，
+/// 之后该组的项句柄的读写返回错误。
+pub struct SharedOpcGroup {
+    id: u64,
+    name: String,
+    link: Arc<ServerLink>,
+}
+
+impl SharedOpcGroup {
+    /// 组名
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 向组中添加项
+    pub fn add_item(&self, name: &str) -> OpcResult<SharedOpcItem> {
+        let id = self.link.worker.call("SharedOpcGroup::add_item", |reply| Request::AddItem {
+            group: self.id,
+            name: name.to_string(),
+            reply,
+        })?;
+        Ok(SharedOpcItem {
+            id,
+            name: name.to_string(),
+            link: Arc::clone(&self.link),
+        })
+    }
+
+    /// 刷新组中的所有项，结果通过订阅送达
+    pub fn refresh(&self) -> OpcResult<()> {
+        self.link
+            .worker
+            .call("SharedOpcGroup::refresh", |reply| Request::Refresh { group: self.id, reply })
+    }
+
+    /// 订阅组的数据变化，同 `OpcGroup::subscribe`
+    ///
+    /// 每次调用返回一个新的接收端，每个接收端都收到全部数据变化。
+    /// 组释放后接收端在取完剩余的事件后返回断开。
+    pub fn subscribe(&self) -> OpcResult<Receiver<DataChangeEvent>> {
+        let (sender, receiver) = mpsc::channel();
+        let callback = Arc::new(ChannelCallback::new(sender));
+        self.link.worker.call("SharedOpcGroup::subscribe", |reply| Request::Subscribe {
+            group: self.id,
+            callback,
+            reply,
+        })?;
+        Ok(receiver)
+    }
+}
+
+impl Drop for SharedOpcGroup {
+    fn drop(&mut self) {
+        self.link.worker.notify(Request::ReleaseGroup(self.id));
+    }
+}
+
+/// 线程安全的 OPC 项
+pub struct SharedOpcItem {
+    id: u64,
+    name: String,
+    link: Arc<ServerLink>,
+}
+
+impl SharedOpcItem {
+    /// 项名
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 同步读取项值，同 `OpcItem::read_sync`
+    pub fn read_sync(&self) -> OpcResult<(OpcValue, OpcQuality, OpcTimestamp)> {
+        self.link
+            .worker
+            .call("SharedOpcItem::read_sync", |reply| Request::Read { item: self.id, reply })
+    }
+
+    /// 同步写入项值，同 `OpcItem::write_sync`
+    ///
+    /// # 返回值
+    /// - `Ok(())`: 写入成功
+    /// - `Err(OpcError::InvalidParameters)`: 连接字符串为只读
+    /// - `Err(OpcError)`: 写入失败
+    pub fn write_sync(&self, value: &OpcValue) -> OpcResult<()> {
+        if self.link.readonly {
+            return Err(OpcError::invalid_parameters(format!(
+                "Cannot write '{}' on a readonly connection",
+                self.name
+            )));
+        }
+        self.link.worker.call("SharedOpcItem::write_sync", |reply| Request::Write {
+            item: self.id,
+            value: value.clone(),
+            reply,
+        })
+    }
+}
+
+impl Drop for SharedOpcItem {
+    fn drop(&mut self) {
+        self.link.worker.notify(Request::ReleaseItem(self.id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_handles_are_send_and_sync() {
+        assert_send_sync::<SharedOpcClient>();
+        assert_send_sync::<SharedOpcGroup>();
+        assert_send_sync::<SharedOpcItem>();
+    }
+
+    // 非 Windows 平台上工作线程创建客户端失败，错误返回给调用方
+    #[cfg(not(windows))]
+    #[test]
+    fn test_connect_reports_worker_setup_errors() {
+        let conn = ConnectionString::new("localhost", "Matrikon.OPC.Simulation.1");
+        let err = SharedOpcClient::connect(&conn).err().unwrap();
+        assert!(err.is_unsupported_platform());
+    }
+}
@@ -0,0 +1,841 @@
+//! 内存模拟服务器模块
+//!
+//! `SimServer` 是纯 Rust 实现的 OPC DA 服务器，实现了 `backend` 模块的
+//! `DaServer`/`DaGroup`/`DaItem`，不依赖 DLL，可以在任何操作系统上运行。
+//! 用于针对 trait 编写的应用代码的单元测试，以及在 Linux CI 上测试读写和订阅逻辑。
+//!
+//! ## 标签
+//!
+//! - `add_tag` 登记的是 bucket brigade 标签：可写，写入什么就读回什么
+//! - `add_signal` 登记由信号发生器驱动的只读标签：锯齿波（`Ramp`）、正弦波（`Sine`）
+//!   或随机值（`Random`）。发生器产生的数值按登记时模板值的类型转换，
+//!   整数和布尔类型先四舍五入，超出范围时取最小值或最大值
+//! - `standard` 创建带有常用 Matrikon 模拟项名（`Random.Int2`、`Bucket Brigade.Real8` 等）的模拟器，
+//!   可以直接运行针对 Matrikon 编写的代码
+//!
+//! ## 时钟
+//!
+//! 模拟器使用自己的时钟，创建时取当前时间，之后只在调用 `advance` 或 `run_until` 时前进。
+//! 信号在 `advance` 时重新计算，值变化的标签更新时间戳并通知订阅。
+//! 随机信号使用固定种子的伪随机数（可用 `set_seed` 更改），同样的调用序列产生同样的数据，
+//! 测试结果可以重现。写入和 `set_value` 使用模拟时钟的当前时间作为时间戳。
+//!
+//! ## 行为
+//!
+//! - 添加未登记的项返回 `OpcError::ItemNotFound`
+//! - 写入的值按标签当前值的类型转换（`mirror::coerce_to`），只读标签拒绝写入
+//! - `set_value`、`set_quality`、写入和 `advance` 都会立即通知包含该项、已订阅且激活的组，
+//!   回调在调用线程中同步执行
+//! - `refresh` 把组中所有项的当前值交付给订阅，组未激活或未订阅时返回错误
+//! - `set_latency` 让同步读写和刷新先阻塞指定时长，用于测试超时处理
+//! - 服务器状态不是 `Running` 时，创建组、添加项和读写都返回错误，
+//!   `get_status` 仍然可用
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::backend::{DaGroup, DaItem, DaServer};
+//! use opc_da_client::sim::SimServer;
+//! use opc_da_client::{OpcQuality, OpcValue};
+//!
+//! let server = SimServer::new()
+//!     .with_tag("Tank1.Level", OpcValue::Double(3.5))
+//!     .with_tag("Tank1.Alarm", OpcValue::Bool(false));
+//! let group = server.create_group("G", true, 1000, 0.0)?;
+//! let level = group.add_item("Tank1.Level")?;
+//! group.enable_async_subscription(callback)?;
+//!
+//! server.set_value("Tank1.Level", OpcValue::Double(7.0), OpcQuality::Good)?;
+//! level.write_sync(&OpcValue::Int32(5))?; // 保存为 Double(5.0)
+//!
+//! server.add_signal("Tank1.Flow", OpcValue::Float(0.0), SimSignal::Sine {
+//!     amplitude: 10.0,
+//!     offset: 50.0,
+//!     period: Duration::from_secs(60),
+//! })?;
+//! server.advance(Duration::from_secs(15)); // Tank1.Flow = 60.0，订阅收到通知
+//! ```
+
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::rc::{Rc, Weak};
+use std::sync::Arc;
+use std::time::Duration;
+use crate::backend::{DaGroup, DaItem, DaServer, Subscriptions};
+use crate::error::{OpcError, OpcResult};
+use crate::mirror::{coerce_to, coerce_with, CoercionPolicy};
+use crate::types::{OpcDataCallback, OpcQuality, OpcTimestamp, OpcValue, ServerState, SubscriptionCloseReason};
+
+/// `get_status` 返回的默认厂商信息
+const DEFAULT_VENDOR: &str = "opc_da_client simulator";
+
+/// 默认的随机数种子
+const DEFAULT_SEED: u64 = 0x2545_F491_4F6C_DD1D;
+
+/// 驱动标签值的信号发生器
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SimSignal {
+    /// 在一个周期内从 `min` 线性上升到 `max`，然后回到 `min`
+    Ramp {
+        /// 周期开始时的值
+        min: f64,
+        /// 周期结束前趋近的值
+        max: f64,
+        /// 周期
+        period: Duration,
+    },
+    /// `offset + amplitude * sin(2π t / period)`
+    Sine {
+        /// 振幅
+        amplitude: f64,
+        /// 中心值
+        offset: f64,
+        /// 周期
+        period: Duration,
+    },
+    /// 每次 `advance` 时取 `[min, max)` 中均匀分布的随机值
+    Random {
+        /// 下限
+        min: f64,
+        /// 上限
+        max: f64,
+    },
+}
+
+impl SimSignal {
+    /// 检查参数
+    fn validate(&self) -> OpcResult<()> {
+        let valid = match *self {
+            SimSignal::Ramp { min, max, period } => min.is_finite() && max.is_finite() && !period.is_zero(),
+            SimSignal::Sine { amplitude, offset, period } => amplitude.is_finite() && offset.is_finite() && !period.is_zero(),
+            SimSignal::Random { min, max } => min.is_finite() && max.is_finite() && min <= max,
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(OpcError::InvalidParameters(format!("Invalid simulation signal: {:?}", self)))
+        }
+    }
+
+    /// 模拟时钟经过 `elapsed` 时的值
+    fn sample(&self, elapsed: Duration, rng: &Cell<u64>) -> f64 {
+        match *self {
+            SimSignal::Ramp { min, max, period } => {
+                let phase = (elapsed.as_nanos() % period.as_nanos()) as f64 / period.as_nanos() as f64;
+                min + (max - min) * phase
+            }
+            SimSignal::Sine { amplitude, offset, period } => {
+                let phase = (elapsed.as_nanos() % period.as_nanos()) as f64 / period.as_nanos() as f64;
+                offset + amplitude * (std::f64::consts::TAU * phase).sin()
+            }
+            SimSignal::Random { min, max } => min + (max - min) * next_random(rng),
+        }
+    }
+}
+
+/// xorshift64* 伪随机数，返回 `[0, 1)` 中的值
+fn next_random(state: &Cell<u64>) -> f64 {
+    let mut x = state.get();
+    x ^= x >> 12;
+    x ^= x << 25;
+    x ^= x >> 27;
+    state.set(x);
+    (x.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// 把发生器的数值转换为模板值的类型
+fn shape(value: f64, template: &OpcValue) -> OpcValue {
+    let value = match template {
+        OpcValue::Float(_) | OpcValue::Double(_) | OpcValue::Date(_) | OpcValue::Decimal(_) | OpcValue::String(_) => value,
+        _ => value.round(),
+    };
+    coerce_with(OpcValue::Double(value), template, CoercionPolicy::Saturate)
+        .map(|coerced| coerced.value)
+        .unwrap_or(OpcValue::Double(value))
+}
+
+/// 模拟器中的一个标签
+#[derive(Debug, Clone)]
+struct SimTag {
+    value: OpcValue,
+    quality: OpcQuality,
+    timestamp: OpcTimestamp,
+    writable: bool,
+    signal: Option<SimSignal>,
+}
+
+/// 服务器与其组和项共享的状态
+struct SimState {
+    vendor: RefCell<String>,
+    server_state: Cell<ServerState>,
+    tags: RefCell<BTreeMap<String, SimTag>>,
+    groups: RefCell<Vec<Weak<SimGroupState>>>,
+    /// 模拟时钟的起点（UTC Unix 毫秒）
+    clock_start: u64,
+    /// 模拟时钟已经前进的时长
+    elapsed: Cell<Duration>,
+    latency: Cell<Duration>,
+    rng: Cell<u64>,
+}
+
+impl SimState {
+    /// 模拟时钟的当前时间
+    fn now(&self) -> OpcTimestamp {
+        OpcTimestamp::from_unix_ms(self.clock_start + self.elapsed.get().as_millis() as u64)
+    }
+
+    /// 模拟操作延迟
+    fn delay(&self) {
+        let latency = self.latency.get();
+        if !latency.is_zero() {
+            std::thread::sleep(latency);
+        }
+    }
+
+    /// 服务器不在运行时返回错误
+    fn check_running(&self) -> OpcResult<()> {
+        match self.server_state.get() {
+            ServerState::Running => Ok(()),
+            state => Err(OpcError::operation_failed(format!("Simulated server is {}", state))),
+        }
+    }
+
+    /// 把标签的当前值交付给包含该项、已订阅且激活的组
+    fn notify(&self, item_name: &str) {
+        let Some(tag) = self.tags.borrow().get(item_name).cloned() else {
+            return;
+        };
+        // 先收集回调再调用，回调中可以再次访问模拟器
+        let targets: Vec<(String, Arc<dyn OpcDataCallback>)> = {
+            let mut groups = self.groups.borrow_mut();
+            groups.retain(|group| group.strong_count() > 0);
+            groups
+                .iter()
+                .filter_map(Weak::upgrade)
+                .filter(|group| group.active && group.items.borrow().iter().any(|name| name == item_name))
+                .filter_map(|group| group.callback.borrow().clone().map(|callback| (group.name.clone(), callback)))
+                .collect()
+        };
+        for (group_name, callback) in targets {
+            callback.on_data_change(&group_name, item_name, tag.value.clone(), tag.quality, tag.timestamp.unix_ms());
+        }
+    }
+}
+
+/// 内存模拟服务器
+pub struct SimServer {
+    state: Rc<SimState>,
+}
+
+impl Default for SimServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SimServer {
+    /// 创建没有标签、处于运行状态的模拟器
+    pub fn new() -> Self {
+        SimServer {
+            state: Rc::new(SimState {
+                vendor: RefCell::new(DEFAULT_VENDOR.to_string()),
+                server_state: Cell::new(ServerState::Running),
+                tags: RefCell::new(BTreeMap::new()),
+                groups: RefCell::new(Vec::new()),
+                clock_start: OpcTimestamp::now().unix_ms(),
+                elapsed: Cell::new(Duration::ZERO),
+                latency: Cell::new(Duration::ZERO),
+                rng: Cell::new(DEFAULT_SEED),
+            }),
+        }
+    }
+
+    /// 创建带有常用 Matrikon 模拟项的模拟器
+    ///
+    /// - `Bucket Brigade.<类型>`: 可写标签，类型为 `Boolean`、`Int1`、`Int2`、`Int4`、
+    ///   `UInt1`、`UInt2`、`UInt4`、`Real4`、`Real8`、`String`
+    /// - `Random.<类型>`: 随机值，类型为 `Boolean`、`Int1`、`Int2`、`Int4`、`UInt2`、`Real4`、`Real8`
+    /// - `Saw-toothed Waves.<类型>`: 周期 60 秒的锯齿波，类型为 `Int2`、`Real4`、`Real8`
+    /// - `Sine Waves.Real8`: 周期 60 秒、振幅 100 的正弦波
+    pub fn standard() -> Self {
+        let server = Self::new();
+        let types = [
+            ("Boolean", OpcValue::Bool(false)),
+            ("Int1", OpcValue::Int8(0)),
+            ("Int2", OpcValue::Int16(0)),
+            ("Int4", OpcValue::Int32(0)),
+            ("UInt1", OpcValue::UInt8(0)),
+            ("UInt2", OpcValue::UInt16(0)),
+            ("UInt4", OpcValue::UInt32(0)),
+            ("Real4", OpcValue::Float(0.0)),
+            ("Real8", OpcValue::Double(0.0)),
+            ("String", OpcValue::String(String::new())),
+        ];
+        for (suffix, template) in &types {
+            server.add_tag(&format!("Bucket Brigade.{}", suffix), template.clone());
+        }
+        let minute = Duration::from_secs(60);
+        let signals = [
+            ("Random.Boolean", OpcValue::Bool(false), SimSignal::Random { min: 0.0, max: 1.0 }),
+            ("Random.Int1", OpcValue::Int8(0), SimSignal::Random { min: -128.0, max: 127.0 }),
+            ("Random.Int2", OpcValue::Int16(0), SimSignal::Random { min: -32768.0, max: 32767.0 }),
+            ("Random.Int4", OpcValue::Int32(0), SimSignal::Random { min: -1_000_000.0, max: 1_000_000.0 }),
+            ("Random.UInt2", OpcValue::UInt16(0), SimSignal::Random { min: 0.0, max: 65535.0 }),
+            ("Random.Real4", OpcValue::Float(0.0), SimSignal::Random { min: -1000.0, max: 1000.0 }),
+            ("Random.Real8", OpcValue::Double(0.0), SimSignal::Random { min: -1000.0, max: 1000.0 }),
+            ("Saw-toothed Waves.Int2", OpcValue::Int16(0), SimSignal::Ramp { min: 0.0, max: 100.0, period: minute }),
+            ("Saw-toothed Waves.Real4", OpcValue::Float(0.0), SimSignal::Ramp { min: 0.0, max: 100.0, period: minute }),
+            ("Saw-toothed Waves.Real8", OpcValue::Double(0.0), SimSignal::Ramp { min: 0.0, max: 100.0, period: minute }),
+            ("Sine Waves.Real8", OpcValue::Double(0.0), SimSignal::Sine { amplitude: 100.0, offset: 0.0, period: minute }),
+        ];
+        for (name, template, signal) in signals {
+            // 参数都是有效的
+            let _ = server.add_signal(name, template, signal);
+        }
+        server
+    }
+
+    /// 登记一个可写标签，质量为 Good
+    pub fn with_tag(self, name: &str, value: OpcValue) -> Self {
+        self.add_tag(name, value);
+        self
+    }
+
+    /// 登记一个可写标签，质量为 Good
+    ///
+    /// 同名标签已存在时替换它，不通知订阅。
+    pub fn add_tag(&self, name: &str, value: OpcValue) {
+        self.state.tags.borrow_mut().insert(name.to_string(), SimTag {
+            value,
+            quality: OpcQuality::Good,
+            timestamp: self.state.now(),
+            writable: true,
+            signal: None,
+        });
+    }
+
+    /// 登记一个由信号发生器驱动的只读标签，质量为 Good
+    ///
+    /// 值的类型与 `template` 相同，初始值为模拟时钟当前时刻的信号值。
+    /// 同名标签已存在时替换它，不通知订阅。
+    ///
+    /// # 返回值
+    /// - `Ok(())`: 登记成功
+    /// - `Err(OpcError::InvalidParameters)`: 周期为零、参数不是有限数，或随机信号的下限大于上限
+    pub fn add_signal(&self, name: &str, template: OpcValue, signal: SimSignal) -> OpcResult<()> {
+        signal.validate()?;
+        let value = shape(signal.sample(self.state.elapsed.get(), &self.state.rng), &template);
+        self.state.tags.borrow_mut().insert(name.to_string(), SimTag {
+            value,
+            quality: OpcQuality::Good,
+            timestamp: self.state.now(),
+            writable: false,
+            signal: Some(signal),
+        });
+        Ok(())
+    }
+
+    /// 设置标签是否可写
+    pub fn set_writable(&self, name: &str, writable: bool) -> OpcResult<()> {
+        self.state
+            .tags
+            .borrow_mut()
+            .get_mut(name)
+            .map(|tag| tag.writable = writable)
+            .ok_or_else(|| OpcError::ItemNotFound(name.to_string()))
+    }
+
+    /// 以服务器一侧的变化更新标签，并通知订阅
+    ///
+    /// 值不做类型转换，可以用来模拟服务器改变项的类型。
+    pub fn set_value(&self, name: &str, value: OpcValue, quality: OpcQuality) -> OpcResult<()> {
+        {
+            let mut tags = self.state.tags.borrow_mut();
+            let tag = tags.get_mut(name).ok_or_else(|| OpcError::ItemNotFound(name.to_string()))?;
+            tag.value = value;
+            tag.quality = quality;
+            tag.timestamp = self.state.now();
+        }
+        self.state.notify(name);
+        Ok(())
+    }
+
+    /// 改变标签的质量，并通知订阅
+    ///
+    /// 信号标签之后产生的值保持这个质量，直到再次设置。
+    pub fn set_quality(&self, name: &str, quality: OpcQuality) -> OpcResult<()> {
+        {
+            let mut tags = self.state.tags.borrow_mut();
+            let tag = tags.get_mut(name).ok_or_else(|| OpcError::ItemNotFound(name.to_string()))?;
+            tag.quality = quality;
+            tag.timestamp = self.state.now();
+        }
+        self.state.notify(name);
+        Ok(())
+    }
+
+    /// 让模拟时钟前进，重新计算所有信号标签并通知值变化的项
+    ///
+    /// 返回值变化的标签数。
+    pub fn advance(&self, duration: Duration) -> usize {
+        self.state.elapsed.set(self.state.elapsed.get() + duration);
+        let now = self.state.now();
+        let elapsed = self.state.elapsed.get();
+        let changed: Vec<String> = self
+            .state
+            .tags
+            .borrow_mut()
+            .iter_mut()
+            .filter_map(|(name, tag)| {
+                let value = shape(tag.signal?.sample(elapsed, &self.state.rng), &tag.value);
+                if value == tag.value {
+                    return None;
+                }
+                tag.value = value;
+                tag.timestamp = now;
+                Some(name.clone())
+            })
+            .collect();
+        for name in &changed {
+            self.state.notify(name);
+        }
+        changed.len()
+    }
+
+    /// 按固定步长推进模拟时钟，直到条件满足或推进的时间达到上限
+    ///
+    /// 开始前和每一步之后调用 `condition`，返回 `true` 时停止。每一步与 `advance` 相同，
+    /// 值变化的项在这一步中通知订阅，测试可以用它确定地等待看门狗到期、
+    /// 信号越过阈值等，而不用等待真实时间。最后一步不会超过 `limit`。
+    ///
+    /// # 参数
+    /// - `step`: 每一步推进的时间，为零时按 1 毫秒处理
+    /// - `limit`: 最多推进的时间
+    /// - `condition`: 停止条件
+    ///
+    /// # 返回值
+    /// - `Some(推进的时间)`: 条件已满足
+    /// - `None`: 推进了 `limit` 后条件仍未满足
+    pub fn run_until(&self, step: Duration, limit: Duration, mut condition: impl FnMut(&Self) -> bool) -> Option<Duration> {
+        let step = step.max(Duration::from_millis(1));
+        let mut elapsed = Duration::ZERO;
+        loop {
+            if condition(self) {
+                return Some(elapsed);
+            }
+            if elapsed >= limit {
+                return None;
+            }
+            let next = step.min(limit - elapsed);
+            self.advance(next);
+            elapsed += next;
+        }
+    }
+
+    /// 模拟时钟的当前时间
+    pub fn clock(&self) -> OpcTimestamp {
+        self.state.now()
+    }
+
+    /// 设置随机信号的种子
+    pub fn set_seed(&self, seed: u64) {
+        // xorshift 的状态不能为零
+        self.state.rng.set(if seed == 0 { DEFAULT_SEED } else { seed });
+    }
+
+    /// 设置同步读写和刷新的延迟，默认为零
+    pub fn set_latency(&self, latency: Duration) {
+        self.state.latency.set(latency);
+    }
+
+    /// 标签的当前值、质量和时间戳
+    pub fn value(&self, name: &str) -> Option<(OpcValue, OpcQuality, OpcTimestamp)> {
+        self.state
+            .tags
+            .borrow()
+            .get(name)
+            .map(|tag| (tag.value.clone(), tag.quality, tag.timestamp))
+    }
+
+    /// 设置 `get_status` 报告的服务器状态
+    pub fn set_state(&self, state: ServerState) {
+        self.state.server_state.set(state);
+    }
+
+    /// 设置 `get_status` 报告的厂商信息
+    pub fn set_vendor(&self, vendor: &str) {
+        *self.state.vendor.borrow_mut() = vendor.to_string();
+    }
+}
+
+impl DaServer for SimServer {
+    type Group = SimGroup;
+
+    fn get_status(&self) -> OpcResult<(ServerState, String)> {
+        Ok((self.state.server_state.get(), self.state.vendor.borrow().clone()))
+    }
+
+    fn create_group(&self, name: &str, active: bool, requested_update_rate: u32, _deadband: f64) -> OpcResult<SimGroup> {
+        self.state.check_running()?;
+        let mut groups = self.state.groups.borrow_mut();
+        groups.retain(|group| group.strong_count() > 0);
+        if groups.iter().filter_map(Weak::upgrade).any(|group| group.name == name) {
+            return Err(OpcError::GroupCreationFailed(format!("Failed to create group '{}'", name)));
+        }
+        let group = Rc::new(SimGroupState {
+            name: name.to_string(),
+            active,
+            update_rate: requested_update_rate,
+            items: RefCell::new(Vec::new()),
+            callback: RefCell::new(None),
+        });
+        groups.push(Rc::downgrade(&group));
+        Ok(SimGroup {
+            group,
+            server: Rc::clone(&self.state),
+        })
+    }
+
+    fn get_item_names(&self) -> OpcResult<Vec<String>> {
+        self.state.check_running()?;
+        Ok(self.state.tags.borrow().keys().cloned().collect())
+    }
+}
+
+/// 组的状态，由组和它的项共享
+struct SimGroupState {
+    name: String,
+    active: bool,
+    update_rate: u32,
+    /// 组中的项名，同一项添加多次时出现多次
+    items: RefCell<Vec<String>>,
+    callback: RefCell<Option<Arc<dyn OpcDataCallback>>>,
+}
+
+/// 模拟服务器中的组
+pub struct SimGroup {
+    group: Rc<SimGroupState>,
+    server: Rc<SimState>,
+}
+
+impl DaGroup for SimGroup {
+    type Item = SimItem;
+
+    fn name(&self) -> &str {
+        &self.group.name
+    }
+
+    fn is_active(&self) -> bool {
+        self.group.active
+    }
+
+    fn actual_update_rate(&self) -> u32 {
+        self.group.update_rate
+    }
+
+    fn add_item(&self, name: &str) -> OpcResult<SimItem> {
+        self.server.check_running()?;
+        if !self.server.tags.borrow().contains_key(name) {
+            return Err(OpcError::ItemNotFound(format!("Failed to add item '{}' to group", name)));
+        }
+        self.group.items.borrow_mut().push(name.to_string());
+        Ok(SimItem {
+            name: name.to_string(),
+            group: Rc::clone(&self.group),
+            server: Rc::clone(&self.server),
+        })
+    }
+
+    fn enable_async_subscription(&self, callback: Arc<dyn OpcDataCallback>) -> OpcResult<()> {
+        *self.group.callback.borrow_mut() = Some(callback);
+        Ok(())
+    }
+
+    fn refresh(&self) -> OpcResult<()> {
+        self.server.delay();
+        self.server.check_running()?;
+        let callback = self.group.callback.borrow().clone();
+        let callback = match callback {
+            Some(callback) if self.group.active => callback,
+            _ => return Err(OpcError::operation_failed("Failed to refresh group")),
+        };
+        let mut names = self.group.items.borrow().clone();
+        names.sort();
+        names.dedup();
+        for name in names {
+            let tag = self.server.tags.borrow().get(&name).cloned();
+            if let Some(tag) = tag {
+                callback.on_data_change(&self.group.name, &name, tag.value, tag.quality, tag.timestamp.unix_ms());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 模拟服务器中的项
+pub struct SimItem {
+    name: String,
+    group: Rc<SimGroupState>,
+    server: Rc<SimState>,
+}
+
+impl DaItem for SimItem {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn read_sync(&self) -> OpcResult<(OpcValue, OpcQuality, OpcTimestamp)> {
+        self.server.delay();
+        self.server.check_running()?;
+        self.server
+            .tags
+            .borrow()
+            .get(&self.name)
+            .map(|tag| (tag.value.clone(), tag.quality, tag.timestamp))
+            .ok_or_else(|| OpcError::ItemNotFound(self.name.clone()))
+    }
+
+    fn write_sync(&self, value: &OpcValue) -> OpcResult<()> {
+        self.server.delay();
+        self.server.check_running()?;
+        {
+            let mut tags = self.server.tags.borrow_mut();
+            let tag = tags.get_mut(&self.name).ok_or_else(|| OpcError::ItemNotFound(self.name.clone()))?;
+            if !tag.writable {
+                return Err(OpcError::operation_failed(format!("Item '{}' is read-only", self.name)));
+            }
+            tag.value = coerce_to(value.clone(), &tag.value)?;
+            tag.quality = OpcQuality::Good;
+            tag.timestamp = self.server.now();
+        }
+        self.server.notify(&self.name);
+        Ok(())
+    }
+}
+
+/// 与 `OpcGroup` 相同：组释放后不再交付通知，订阅的回调收到一次
+/// `on_subscription_closed(GroupDropped)`，组名可以重新使用。
+/// 项仍然可以读写，但不再属于任何已登记的组。
+impl Drop for SimGroup {
+    fn drop(&mut self) {
+        self.server
+            .groups
+            .borrow_mut()
+            .retain(|group| group.strong_count() > 0 && !std::ptr::eq(group.as_ptr(), Rc::as_ptr(&self.group)));
+        self.close_subscriptions(&SubscriptionCloseReason::GroupDropped);
+    }
+}
+
+/// 与 `OpcGroup` 相同：关闭或中断后不再交付通知，消费者只收到一次关闭或中断通知
+impl Subscriptions for SimGroup {
+    fn close_subscriptions(&self, reason: &SubscriptionCloseReason) {
+        let callback = self.group.callback.borrow_mut().take();
+        if let Some(callback) = callback {
+            callback.on_subscription_closed(&self.group.name, reason);
+        }
+    }
+
+    fn interrupt_subscriptions(&self, reason: &str) {
+        let callback = self.group.callback.borrow_mut().take();
+        if let Some(callback) = callback {
+            callback.on_connection_interrupted(&self.group.name, reason);
+        }
+    }
+}
+
+impl Drop for SimItem {
+    fn drop(&mut self) {
+        let mut items = self.group.items.borrow_mut();
+        if let Some(index) = items.iter().position(|name| *name == self.name) {
+            items.remove(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<(String, String, OpcValue)>>);
+
+    impl OpcDataCallback for Recorder {
+        fn on_data_change(&self, group_name: &str, item_name: &str, value: OpcValue, _quality: OpcQuality, _timestamp: u64) {
+            self.0.lock().unwrap().push((group_name.to_string(), item_name.to_string(), value));
+        }
+    }
+
+    /// 只依赖 trait 的应用代码
+    fn double_setpoint<S: DaServer>(server: &S) -> OpcResult<OpcValue> {
+        let group = server.create_group("Setpoints", false, 0, 0.0)?;
+        let item = group.add_item("FIC101.SP")?;
+        let value = item.read_sync()?.0.as_f64().unwrap_or(0.0);
+        item.write_sync(&OpcValue::Double(value * 2.0))?;
+        Ok(item.read_sync()?.0)
+    }
+
+    #[test]
+    fn test_generic_code_against_simulator() {
+        let server = SimServer::new().with_tag("FIC101.SP", OpcValue::Int32(21));
+        assert_eq!(double_setpoint(&server).unwrap(), OpcValue::Int32(42));
+
+        server.set_writable("FIC101.SP", false).unwrap();
+        assert!(double_setpoint(&server).is_err());
+
+        server.set_state(ServerState::Suspended);
+        assert!(matches!(double_setpoint(&server), Err(OpcError::OperationFailed(_))));
+        assert_eq!(server.get_status().unwrap().0, ServerState::Suspended);
+    }
+
+    #[test]
+    fn test_subscription() {
+        let server = SimServer::new()
+            .with_tag("A", OpcValue::Double(1.0))
+            .with_tag("B", OpcValue::Bool(false));
+        let active = server.create_group("Active", true, 500, 0.0).unwrap();
+        let inactive = server.create_group("Inactive", false, 500, 0.0).unwrap();
+        assert!(server.create_group("Active", true, 500, 0.0).is_err());
+        assert!(matches!(active.add_item("Missing"), Err(OpcError::ItemNotFound(_))));
+
+        let recorder = Arc::new(Recorder::default());
+        let a = active.add_item("A").unwrap();
+        let _b = active.add_item("B").unwrap();
+        let _a2 = inactive.add_item("A").unwrap();
+        assert!(active.refresh().is_err());
+        active.enable_async_subscription(recorder.clone()).unwrap();
+        inactive.enable_async_subscription(recorder.clone()).unwrap();
+        assert!(inactive.refresh().is_err());
+
+        active.refresh().unwrap();
+        a.write_sync(&OpcValue::Double(2.0)).unwrap();
+        server.set_value("B", OpcValue::Bool(true), OpcQuality::Uncertain).unwrap();
+        drop(a);
+        server.set_value("A", OpcValue::Double(3.0), OpcQuality::Good).unwrap();
+
+        let changes: Vec<(String, OpcValue)> = recorder
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(group, item, value)| (format!("{}/{}", group, item), value.clone()))
+            .collect();
+        assert_eq!(changes, vec![
+            ("Active/A".to_string(), OpcValue::Double(1.0)),
+            ("Active/B".to_string(), OpcValue::Bool(false)),
+            ("Active/A".to_string(), OpcValue::Double(2.0)),
+            ("Active/B".to_string(), OpcValue::Bool(true)),
+        ]);
+        assert_eq!(server.value("B").unwrap().1, OpcQuality::Uncertain);
+    }
+
+    #[test]
+    fn test_dropped_group_closes_subscription() {
+        #[derive(Default)]
+        struct Closed(Mutex<Vec<String>>);
+
+        impl OpcDataCallback for Closed {
+            fn on_data_change(&self, _group_name: &str, item_name: &str, _value: OpcValue, _quality: OpcQuality, _timestamp: u64) {
+                self.0.lock().unwrap().push(item_name.to_string());
+            }
+
+            fn on_subscription_closed(&self, group_name: &str, reason: &SubscriptionCloseReason) {
+                self.0.lock().unwrap().push(format!("{} closed: {}", group_name, reason));
+            }
+        }
+
+        let server = SimServer::new().with_tag("A", OpcValue::Double(1.0));
+        let group = server.create_group("G", true, 500, 0.0).unwrap();
+        let item = group.add_item("A").unwrap();
+        let callback = Arc::new(Closed::default());
+        group.enable_async_subscription(callback.clone()).unwrap();
+
+        // 项比组活得久
+        drop(group);
+        item.write_sync(&OpcValue::Double(2.0)).unwrap();
+        assert_eq!(*callback.0.lock().unwrap(), vec!["G closed: group dropped".to_string()]);
+        assert!(server.create_group("G", true, 500, 0.0).is_ok());
+    }
+
+    #[test]
+    fn test_signals_and_clock() {
+        let server = SimServer::new();
+        let start = server.clock();
+        let minute = Duration::from_secs(60);
+        server.add_signal("Ramp", OpcValue::Int16(0), SimSignal::Ramp { min: 0.0, max: 100.0, period: minute }).unwrap();
+        server.add_signal("Sine", OpcValue::Double(0.0), SimSignal::Sine { amplitude: 10.0, offset: 50.0, period: minute }).unwrap();
+        server.add_signal("Coin", OpcValue::Bool(false), SimSignal::Random { min: 0.0, max: 1.0 }).unwrap();
+        assert!(server.add_signal("Bad", OpcValue::Double(0.0), SimSignal::Ramp { min: 0.0, max: 1.0, period: Duration::ZERO }).is_err());
+
+        let group = server.create_group("G", true, 1000, 0.0).unwrap();
+        let ramp = group.add_item("Ramp").unwrap();
+        let recorder = Arc::new(Recorder::default());
+        group.enable_async_subscription(recorder.clone()).unwrap();
+        assert!(ramp.write_sync(&OpcValue::Int16(5)).is_err());
+
+        assert!(server.advance(Duration::from_secs(15)) >= 2);
+        let (value, quality, timestamp) = ramp.read_sync().unwrap();
+        assert_eq!(value, OpcValue::Int16(25));
+        assert_eq!(quality, OpcQuality::Good);
+        assert_eq!(timestamp.unix_ms(), start.unix_ms() + 15_000);
+        assert_eq!(server.value("Sine").unwrap().0, OpcValue::Double(60.0));
+        assert!(matches!(server.value("Coin").unwrap().0, OpcValue::Bool(_)));
+
+        // 质量在之后的信号值中保持
+        server.set_quality("Ramp", OpcQuality::Bad).unwrap();
+        server.advance(Duration::from_secs(15));
+        assert_eq!(ramp.read_sync().unwrap().0, OpcValue::Int16(50));
+        assert_eq!(ramp.read_sync().unwrap().1, OpcQuality::Bad);
+
+        let changes: Vec<OpcValue> = recorder.0.lock().unwrap().iter().map(|(_, _, value)| value.clone()).collect();
+        assert_eq!(changes, vec![OpcValue::Int16(25), OpcValue::Int16(25), OpcValue::Int16(50)]);
+    }
+
+    #[test]
+    fn test_run_until() {
+        let server = SimServer::new();
+        let start = server.clock();
+        let minute = Duration::from_secs(60);
+        server.add_signal("Ramp", OpcValue::Int16(0), SimSignal::Ramp { min: 0.0, max: 100.0, period: minute }).unwrap();
+        let group = server.create_group("G", true, 1000, 0.0).unwrap();
+        let _ramp = group.add_item("Ramp").unwrap();
+        let recorder = Arc::new(Recorder::default());
+        group.enable_async_subscription(recorder.clone()).unwrap();
+
+        let above = |threshold: f64| {
+            move |server: &SimServer| server.value("Ramp").unwrap().0.as_f64().unwrap() >= threshold
+        };
+        // 已经满足时不推进
+        assert_eq!(server.run_until(Duration::from_secs(5), minute, above(0.0)), Some(Duration::ZERO));
+        assert_eq!(server.run_until(Duration::from_secs(5), minute, above(30.0)), Some(Duration::from_secs(20)));
+        assert_eq!(server.clock().unix_ms(), start.unix_ms() + 20_000);
+        let changes: Vec<OpcValue> = recorder.0.lock().unwrap().iter().map(|(_, _, value)| value.clone()).collect();
+        assert_eq!(changes, vec![OpcValue::Int16(8), OpcValue::Int16(17), OpcValue::Int16(25), OpcValue::Int16(33)]);
+
+        // 最后一步截断到上限
+        assert_eq!(server.run_until(Duration::from_secs(3), Duration::from_secs(10), above(200.0)), None);
+        assert_eq!(server.clock().unix_ms(), start.unix_ms() + 30_000);
+    }
+
+    #[test]
+    fn test_standard_random_is_reproducible() {
+        let sample = |seed: u64| {
+            let server = SimServer::standard();
+            server.set_seed(seed);
+            server.advance(Duration::from_secs(1));
+            (server.value("Random.Int2").unwrap().0, server.value("Random.Real8").unwrap().0)
+        };
+        assert_eq!(sample(7), sample(7));
+        assert_ne!(sample(7), sample(8));
+
+        let server = SimServer::standard();
+        assert!(server.get_item_names().unwrap().contains(&"Bucket Brigade.UInt2".to_string()));
+        server.set_latency(Duration::from_millis(20));
+        let group = server.create_group("G", false, 0, 0.0).unwrap();
+        let item = group.add_item("Bucket Brigade.Real8").unwrap();
+        let started = std::time::Instant::now();
+        item.write_sync(&OpcValue::Int32(3)).unwrap();
+        assert_eq!(item.read_sync().unwrap().0, OpcValue::Double(3.0));
+        assert!(started.elapsed() >= Duration::from_millis(40));
+    }
+}
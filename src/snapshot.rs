@@ -0,0 +1,119 @@
+//! 值快照导出模块
+//!
+//! 把一组项的当前值、质量和时间戳导出为 JSON 文档，用于调试、工单附件
+//! 或交给其他系统。`OpcServer::snapshot_to_json` 读取服务器上的项并生成文档，
+//! `render_snapshot` 可以渲染任意来源的读取结果。
+//!
+//! ## 格式
+//!
+//! 顶层对象以项 ID 为键，按请求的顺序排列。值、质量和时间戳使用 `serde` 特性的
+//! 序列化形式，值为带 `type` 和 `value` 字段的标记对象，与 `OpcValue` 的 JSON
+//! 表示相同，展开到记录中：
+//!
+//! ```json
+//! {
+//!   "Random.Int2": {
+//!     "type": "Int16",
+//!     "value": 42,
+//!     "quality": "Good",
+//!     "timestamp": 1700000000000
+//!   },
+//!   "Plant.Missing": {
+//!     "error": "Item not found: Plant.Missing"
+//!   }
+//! }
+//! ```
+//!
+//! - 时间戳为 UTC Unix 毫秒
+//! - NaN 和无穷大为 `null`
+//! - 读取失败的项只有 `error` 字段
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! let json = server.snapshot_to_json(&["Random.Int2", "Random.Real8"])?;
+//! std::fs::write("snapshot.json", json)?;
+//! ```
+
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use crate::error::OpcResult;
+use crate::types::{OpcQuality, OpcTimestamp, OpcValue};
+
+/// 一个项的读取结果：项 ID 和值、质量、时间戳
+pub type SnapshotEntry<S> = (S, OpcResult<(OpcValue, OpcQuality, OpcTimestamp)>);
+
+/// 快照中一个项的记录
+#[derive(serde::Serialize)]
+#[serde(untagged)]
+enum Record<'a> {
+    Reading {
+        #[serde(flatten)]
+        value: &'a OpcValue,
+        quality: &'a OpcQuality,
+        timestamp: &'a OpcTimestamp,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+/// 按顺序序列化为 JSON 对象，同一项 ID 只保留第一次的结果
+struct Snapshot<'a, S>(&'a [SnapshotEntry<S>]);
+
+impl<S: AsRef<str>> Serialize for Snapshot<'_, S> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        let mut seen = std::collections::HashSet::new();
+        let mut map = serializer.serialize_map(None)?;
+        for (item_id, reading) in self.0.iter().filter(|(item_id, _)| seen.insert(item_id.as_ref())) {
+            let record = match reading {
+                Ok((value, quality, timestamp)) => Record::Reading { value, quality, timestamp },
+                Err(e) => Record::Failed { error: e.to_string() },
+            };
+            map.serialize_entry(item_id.as_ref(), &record)?;
+        }
+        map.end()
+    }
+}
+
+/// 将读取结果渲染为 JSON 快照文档
+///
+/// 同一项 ID 出现多次时只保留第一次的结果。
+pub fn render_snapshot<S: AsRef<str>>(entries: &[SnapshotEntry<S>]) -> String {
+    // 键都是字符串，值的序列化不会失败
+    let mut json = serde_json::to_string_pretty(&Snapshot(entries)).unwrap_or_default();
+    json.push('\n');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::OpcError;
+
+    #[test]
+    fn test_render_snapshot() {
+        let entries = vec![
+            ("Random.Int2", Ok((OpcValue::Int16(42), OpcQuality::Good, OpcTimestamp::from_unix_ms(1_000)))),
+            ("Plant.Missing", Err(OpcError::ItemNotFound("Plant.Missing".to_string()))),
+            ("Random.Int2", Ok((OpcValue::Int16(0), OpcQuality::Bad, OpcTimestamp::from_unix_ms(0)))),
+            ("Random.Real8", Ok((OpcValue::Double(f64::NAN), OpcQuality::Uncertain, OpcTimestamp::from_unix_ms(2_000)))),
+        ];
+        let json = render_snapshot(&entries);
+        let keys: Vec<usize> = ["Random.Int2", "Plant.Missing", "Random.Real8"]
+            .iter()
+            .map(|key| json.find(&format!("\"{}\"", key)).unwrap())
+            .collect();
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, serde_json::json!({
+            "Random.Int2": {"type": "Int16", "value": 42, "quality": "Good", "timestamp": 1000},
+            "Plant.Missing": {"error": "Item not found: Plant.Missing"},
+            "Random.Real8": {"type": "Double", "value": null, "quality": "Uncertain", "timestamp": 2000},
+        }));
+        // 值部分与 OpcValue 的 serde 表示相同
+        let value: OpcValue = serde_json::from_value(serde_json::json!({"type": "Int16", "value": 42})).unwrap();
+        assert_eq!(value, OpcValue::Int16(42));
+        assert_eq!(render_snapshot::<&str>(&[]), "{}\n");
+    }
+}
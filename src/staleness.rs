@@ -0,0 +1,319 @@
+//! 数据停滞检测模块
+//!
+//! 传感器卡死或上游采集中断时，服务器常常继续以 Good 质量报告最后的值，
+//! 应用无法从质量上发现。`StalenessMonitor` 记录每个项的时间戳最后一次前进的时刻，
+//! 超过设定时长没有前进的项报告 `Stale`，之后收到时间戳前进的通知时报告 `Recovered`。
+//!
+//! 与 `AnomalyDetector` 的冻结检测不同，这里只看时间戳，不看值和质量；
+//! 而且停滞由 `check` 按本地时钟判断，不依赖于收到通知，
+//! 因此服务器完全不再推送的项也能被发现。
+//!
+//! `StalenessMonitor` 实现了 `OpcDataCallback`，作为组的额外消费者
+//! （`OpcGroup::add_callback`）加入后自动跟踪收到通知的项；
+//! 调用 `watch` 可以在第一次通知之前开始跟踪，从未收到通知的项同样会报告停滞。
+//! 应用需要周期性调用 `check`，间隔决定了发现停滞的延迟。
+//!
+//! 事件交给 `StalenessHandler`，或者用 `StalenessMonitor::channel` 以通道接收。
+//! 一个监视器按项名跟踪，多个组包含同名项时应为每个组创建单独的监视器。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::{StalenessEvent, StalenessMonitor};
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! let (monitor, events) = StalenessMonitor::channel(Duration::from_secs(30));
+//! let monitor = Arc::new(monitor);
+//! monitor.set_timeout("FT101.PV", Duration::from_secs(5));
+//!
+//! group.enable_async_subscription(my_callback)?;
+//! group.add_callback(monitor.clone())?;
+//!
+//! loop {
+//!     monitor.check();
+//!     while let Ok(event) = events.try_recv() {
+//!         eprintln!("{}", event);
+//!     }
+//!     std::thread::sleep(Duration::from_secs(1));
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use crate::types::{lock_or_recover, OpcDataCallback, OpcQuality, OpcTimestamp, OpcValue};
+
+/// 停滞事件
+#[derive(Debug, Clone, PartialEq)]
+pub enum StalenessEvent {
+    /// 项的时间戳超过设定时长没有前进
+    Stale {
+        /// 最近一次收到通知的组名，从未收到通知时为空
+        group_name: String,
+        /// 项名
+        item_name: String,
+        /// 最后一个时间戳，从未收到通知时为 `None`
+        last_timestamp: Option<OpcTimestamp>,
+        /// 时间戳没有前进的时长
+        stale_for: Duration,
+    },
+    /// 停滞的项收到了时间戳前进的通知
+    Recovered {
+        /// 组名
+        group_name: String,
+        /// 项名
+        item_name: String,
+        /// 新的时间戳
+        timestamp: OpcTimestamp,
+        /// 停滞的总时长
+        stale_for: Duration,
+    },
+}
+
+impl StalenessEvent {
+    /// 事件的项名
+    pub fn item_name(&self) -> &str {
+        match self {
+            StalenessEvent::Stale { item_name, .. } | StalenessEvent::Recovered { item_name, .. } => item_name,
+        }
+    }
+}
+
+impl std::fmt::Display for StalenessEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StalenessEvent::Stale { item_name, last_timestamp: Some(timestamp), stale_for, .. } => {
+                write!(f, "{} stale for {:?} (last timestamp {})", item_name, stale_for, timestamp)
+            }
+            StalenessEvent::Stale { item_name, last_timestamp: None, stale_for, .. } => {
+                write!(f, "{} stale for {:?} (no data received)", item_name, stale_for)
+            }
+            StalenessEvent::Recovered { item_name, timestamp, stale_for, .. } => {
+                write!(f, "{} recovered at {} after {:?}", item_name, timestamp, stale_for)
+            }
+        }
+    }
+}
+
+/// 停滞事件处理
+///
+/// `Stale` 在调用 `check` 的线程中交付，`Recovered` 在数据变化回调的线程中交付，
+/// 实现应尽快返回。
+pub trait StalenessHandler: Send + Sync {
+    /// 发生停滞或恢复时调用
+    fn on_staleness(&self, event: &StalenessEvent);
+}
+
+impl<F: Fn(&StalenessEvent) + Send + Sync> StalenessHandler for F {
+    fn on_staleness(&self, event: &StalenessEvent) {
+        self(event)
+    }
+}
+
+/// 每个项的跟踪状态
+#[derive(Debug)]
+struct ItemState {
+    group_name: String,
+    last_timestamp: Option<u64>,
+    /// 时间戳最后一次前进（或开始跟踪）的本地时刻
+    advanced_at: Instant,
+    /// 单独设置的停滞时长
+    timeout: Option<Duration>,
+    stale: bool,
+}
+
+impl ItemState {
+    fn new(now: Instant) -> Self {
+        ItemState {
+            group_name: String::new(),
+            last_timestamp: None,
+            advanced_at: now,
+            timeout: None,
+            stale: false,
+        }
+    }
+}
+
+/// 按项跟踪时间戳的停滞监视器
+pub struct StalenessMonitor {
+    timeout: Duration,
+    handler: Box<dyn StalenessHandler>,
+    items: Mutex<HashMap<String, ItemState>>,
+}
+
+impl StalenessMonitor {
+    /// 创建监视器，时间戳超过 `timeout` 没有前进的项视为停滞
+    pub fn new(timeout: Duration, handler: impl StalenessHandler + 'static) -> Self {
+        StalenessMonitor {
+            timeout,
+            handler: Box::new(handler),
+            items: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 创建以通道交付事件的监视器
+    ///
+    /// 接收端断开后事件被丢弃。
+    pub fn channel(timeout: Duration) -> (Self, Receiver<StalenessEvent>) {
+        let (sender, receiver) = mpsc::channel();
+        let monitor = Self::new(timeout, move |event: &StalenessEvent| {
+            let _ = sender.send(event.clone());
+        });
+        (monitor, receiver)
+    }
+
+    /// 默认的停滞时长
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// 从现在开始跟踪项，不等待第一次通知
+    ///
+    /// 已经在跟踪的项不受影响。
+    pub fn watch(&self, item_name: &str) {
+        lock_or_recover(&self.items)
+            .entry(item_name.to_string())
+            .or_insert_with(|| ItemState::new(Instant::now()));
+    }
+
+    /// 为单个项设置停滞时长，并开始跟踪该项
+    pub fn set_timeout(&self, item_name: &str, timeout: Duration) {
+        lock_or_recover(&self.items)
+            .entry(item_name.to_string())
+            .or_insert_with(|| ItemState::new(Instant::now()))
+            .timeout = Some(timeout);
+    }
+
+    /// 停止跟踪项
+    pub fn unwatch(&self, item_name: &str) {
+        lock_or_recover(&self.items).remove(item_name);
+    }
+
+    /// 当前停滞的项名，按名称排序
+    pub fn stale_items(&self) -> Vec<String> {
+        let mut stale: Vec<String> = lock_or_recover(&self.items)
+            .iter()
+            .filter(|(_, state)| state.stale)
+            .map(|(name, _)| name.clone())
+            .collect();
+        stale.sort();
+        stale
+    }
+
+    /// 检查所有跟踪的项，对新停滞的项交付 `Stale` 事件
+    ///
+    /// 每次停滞只报告一次。返回新停滞的项数。
+    pub fn check(&self) -> usize {
+        let events = self.check_at(Instant::now());
+        for event in &events {
+            self.handler.on_staleness(event);
+        }
+        events.len()
+    }
+
+    fn check_at(&self, now: Instant) -> Vec<StalenessEvent> {
+        let mut items = lock_or_recover(&self.items);
+        let mut events: Vec<StalenessEvent> = items
+            .iter_mut()
+            .filter_map(|(item_name, state)| {
+                let stale_for = now.saturating_duration_since(state.advanced_at);
+                if state.stale || stale_for < state.timeout.unwrap_or(self.timeout) {
+                    return None;
+                }
+                state.stale = true;
+                Some(StalenessEvent::Stale {
+                    group_name: state.group_name.clone(),
+                    item_name: item_name.clone(),
+                    last_timestamp: state.last_timestamp.map(OpcTimestamp::from_unix_ms),
+                    stale_for,
+                })
+            })
+            .collect();
+        events.sort_by(|a, b| a.item_name().cmp(b.item_name()));
+        events
+    }
+
+    /// 记录一次通知，时间戳前进且项处于停滞时返回 `Recovered`
+    fn record_at(&self, group_name: &str, item_name: &str, timestamp: u64, now: Instant) -> Option<StalenessEvent> {
+        let mut items = lock_or_recover(&self.items);
+        let state = items
+            .entry(item_name.to_string())
+            .or_insert_with(|| ItemState::new(now));
+        if state.group_name != group_name {
+            state.group_name = group_name.to_string();
+        }
+        if state.last_timestamp.is_some_and(|last| timestamp <= last) {
+            return None;
+        }
+        state.last_timestamp = Some(timestamp);
+        let stale_for = now.saturating_duration_since(state.advanced_at);
+        state.advanced_at = now;
+        if !state.stale {
+            return None;
+        }
+        state.stale = false;
+        Some(StalenessEvent::Recovered {
+            group_name: group_name.to_string(),
+            item_name: item_name.to_string(),
+            timestamp: OpcTimestamp::from_unix_ms(timestamp),
+            stale_for,
+        })
+    }
+}
+
+impl OpcDataCallback for StalenessMonitor {
+    fn on_data_change(&self, group_name: &str, item_name: &str, _value: OpcValue, _quality: OpcQuality, timestamp: u64) {
+        if let Some(event) = self.record_at(group_name, item_name, timestamp, Instant::now()) {
+            self.handler.on_staleness(&event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stale_and_recovered() {
+        let (monitor, _events) = StalenessMonitor::channel(Duration::from_secs(10));
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        assert!(monitor.record_at("G", "FT101", 1_000, at(0)).is_none());
+        assert!(monitor.check_at(at(5)).is_empty());
+        // 时间戳没有前进的通知不重新计时
+        assert!(monitor.record_at("G", "FT101", 1_000, at(8)).is_none());
+
+        let stale = monitor.check_at(at(12));
+        assert_eq!(stale, vec![StalenessEvent::Stale {
+            group_name: "G".to_string(),
+            item_name: "FT101".to_string(),
+            last_timestamp: Some(OpcTimestamp::from_unix_ms(1_000)),
+            stale_for: Duration::from_secs(12),
+        }]);
+        assert!(monitor.check_at(at(20)).is_empty());
+        assert_eq!(monitor.stale_items(), vec!["FT101".to_string()]);
+
+        assert_eq!(monitor.record_at("G", "FT101", 2_000, at(30)), Some(StalenessEvent::Recovered {
+            group_name: "G".to_string(),
+            item_name: "FT101".to_string(),
+            timestamp: OpcTimestamp::from_unix_ms(2_000),
+            stale_for: Duration::from_secs(30),
+        }));
+        assert!(monitor.stale_items().is_empty());
+    }
+
+    #[test]
+    fn test_watched_item_without_data() {
+        let (monitor, events) = StalenessMonitor::channel(Duration::ZERO);
+        monitor.watch("TI205");
+        monitor.set_timeout("FT101", Duration::from_secs(3600));
+        assert_eq!(monitor.check(), 1);
+
+        let event = events.try_recv().unwrap();
+        assert!(matches!(event, StalenessEvent::Stale { ref item_name, last_timestamp: None, .. } if item_name == "TI205"));
+        assert!(events.try_recv().is_err());
+    }
+}
@@ -0,0 +1,303 @@
+//! 标签别名模块
+//!
+//! `TagMap` 把应用代码中使用的符号名（例如 `"reactor_temp"`）映射到服务器上的项 ID
+//! （例如 `"Channel1.Device1.Reactor.PV"`），并可以为每个标签附加 `Scaling`：
+//! 添加到组时缩放附加到项上，读写经由 `OpcItem::read_scaled` 和 `write_scaled`
+//! 转换为工程单位和原始值。`Tag::scaled` 按 `raw * scale + offset` 构造缩放。
+//! 项 ID 变化时只需修改映射，应用代码不变。
+//!
+//! 启用 `config` 特性时，`OpcConfig` 的 `[tags]` 表可以声明标签：
+//!
+//! ```toml
+//! [tags.reactor_temp]
+//! item = "Channel1.Device1.Reactor.PV"
+//! scale = 0.1
+//! offset = -40.0
+//! ```
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::{Tag, TagMap, OpcValue};
+//!
+//! let mut tags = TagMap::new();
+//! tags.insert("reactor_temp", Tag::scaled("Channel1.Device1.Reactor.PV", 0.1, -40.0));
+//! tags.insert("pump_run", Tag::new("Channel1.Device1.Pump.Run"));
+//!
+//! let items = tags.add_to(&group)?;
+//! let (temp, _quality, _ts) = items.read("reactor_temp")?;
+//! items.write("pump_run", &OpcValue::Bool(true))?;
+//! ```
+
+use std::collections::BTreeMap;
+use crate::error::{OpcError, OpcResult};
+use crate::group::OpcGroup;
+use crate::item::OpcItem;
+use crate::scaling::Scaling;
+use crate::types::{OpcQuality, OpcTimestamp, OpcValue, OpcValueError};
+
+/// 一个标签：项 ID 和线性缩放
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "config", derive(serde::Deserialize))]
+#[cfg_attr(feature = "config", serde(from = "TagConfig"))]
+pub struct Tag {
+    /// 服务器上的项 ID
+    pub item_id: String,
+    /// 附加到项上的缩放，`None` 表示不缩放
+    pub scaling: Option<Scaling>,
+}
+
+impl Tag {
+    /// 创建不缩放的标签
+    pub fn new(item_id: &str) -> Self {
+        Tag {
+            item_id: item_id.to_string(),
+            scaling: None,
+        }
+    }
+
+    /// 创建按 `scaling` 缩放的标签
+    pub fn with_scaling(item_id: &str, scaling: Scaling) -> Self {
+        Tag {
+            scaling: Some(scaling),
+            ..Tag::new(item_id)
+        }
+    }
+
+    /// 创建缩放的标签，工程值 = 原始值 * `scale` + `offset`
+    ///
+    /// 等价于原始值 0 ~ 1 对应工程值 `offset` ~ `offset + scale` 的 `Scaling`。
+    /// `scale` 为 0 时缩放无效，添加到组时返回错误。
+    pub fn scaled(item_id: &str, scale: f64, offset: f64) -> Self {
+        Self::with_scaling(item_id, Scaling::new(0.0, 1.0, offset, offset + scale))
+    }
+
+    /// 标签是否需要缩放
+    pub fn is_scaled(&self) -> bool {
+        self.scaling.is_some()
+    }
+
+    /// 原始值转换为工程值
+    ///
+    /// 不需要缩放时原样返回；需要缩放时数值转换为 `Double`，
+    /// 非数值类型返回转换错误。
+    pub fn to_engineering(&self, raw: OpcValue) -> Result<OpcValue, OpcValueError> {
+        match &self.scaling {
+            Some(scaling) => Ok(OpcValue::Double(scaling.to_eu(scalar(&raw)?))),
+            None => Ok(raw),
+        }
+    }
+
+    /// 工程值转换为原始值，`to_engineering` 的逆运算
+    ///
+    /// 缩放无效（例如 `scale` 为 0）时无法求逆，返回转换错误。
+    pub fn to_raw(&self, engineering: &OpcValue) -> Result<OpcValue, OpcValueError> {
+        let Some(scaling) = &self.scaling else {
+            return Ok(engineering.clone());
+        };
+        scaling.validate().map_err(|e| {
+            OpcValueError::conversion_error(format!("Cannot invert scaling of {}: {}", self.item_id, e))
+        })?;
+        Ok(OpcValue::Double(scaling.to_raw(scalar(engineering)?)))
+    }
+}
+
+/// 配置文件中的标签声明
+#[cfg(feature = "config")]
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TagConfig {
+    item: String,
+    #[serde(default = "default_scale")]
+    scale: f64,
+    #[serde(default)]
+    offset: f64,
+}
+
+#[cfg(feature = "config")]
+fn default_scale() -> f64 {
+    1.0
+}
+
+#[cfg(feature = "config")]
+impl From<TagConfig> for Tag {
+    fn from(config: TagConfig) -> Self {
+        if config.scale == 1.0 && config.offset == 0.0 {
+            Tag::new(&config.item)
+        } else {
+            Tag::scaled(&config.item, config.scale, config.offset)
+        }
+    }
+}
+
+fn scalar(value: &OpcValue) -> Result<f64, OpcValueError> {
+    value.as_f64().ok_or_else(|| {
+        OpcValueError::conversion_error(format!("Cannot scale {} value", value.type_name()))
+    })
+}
+
+/// 符号名到标签的映射
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "config", derive(serde::Deserialize))]
+#[cfg_attr(feature = "config", serde(transparent))]
+pub struct TagMap {
+    tags: BTreeMap<String, Tag>,
+}
+
+impl TagMap {
+    /// 创建空的映射
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加标签，返回同名的旧标签
+    pub fn insert(&mut self, alias: &str, tag: Tag) -> Option<Tag> {
+        self.tags.insert(alias.to_string(), tag)
+    }
+
+    /// 移除标签
+    pub fn remove(&mut self, alias: &str) -> Option<Tag> {
+        self.tags.remove(alias)
+    }
+
+    /// 按符号名查找标签
+    pub fn get(&self, alias: &str) -> Option<&Tag> {
+        self.tags.get(alias)
+    }
+
+    /// 符号名对应的项 ID
+    ///
+    /// # 返回值
+    /// - `Ok(&str)`: 项 ID
+    /// - `Err(OpcError::InvalidParameters)`: 没有这个标签
+    pub fn resolve(&self, alias: &str) -> OpcResult<&str> {
+        self.tag(alias).map(|tag| tag.item_id.as_str())
+    }
+
+    /// 项 ID 对应的符号名，用于翻译数据变化回调中的项名
+    ///
+    /// 多个标签指向同一个项时返回按名称排序的第一个。
+    pub fn alias_of(&self, item_id: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(_, tag)| tag.item_id == item_id)
+            .map(|(alias, _)| alias.as_str())
+    }
+
+    /// 所有符号名，按名称排序
+    pub fn aliases(&self) -> impl Iterator<Item = &str> {
+        self.tags.keys().map(String::as_str)
+    }
+
+    /// 标签数量
+    pub fn len(&self) -> usize {
+        self.tags.len()
+    }
+
+    /// 映射是否为空
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    /// 把所有标签的项添加到组中
+    ///
+    /// 标签的缩放通过 `OpcItem::set_scaling` 附加到项上。任一项添加失败或缩放无效时
+    /// 返回错误，错误信息中包含符号名和项 ID。
+    pub fn add_to(&self, group: &OpcGroup) -> OpcResult<TaggedItems> {
+        let mut items = BTreeMap::new();
+        for (alias, tag) in &self.tags {
+            let item = group
+                .add_item(&tag.item_id)
+                .and_then(|item| item.set_scaling(tag.scaling).map(|()| item))
+                .map_err(|e| OpcError::operation_failed(format!("tag '{}' ({}): {}", alias, tag.item_id, e)))?;
+            items.insert(alias.clone(), (tag.clone(), item));
+        }
+        Ok(TaggedItems { items })
+    }
+
+    fn tag(&self, alias: &str) -> OpcResult<&Tag> {
+        self.tags
+            .get(alias)
+            .ok_or_else(|| OpcError::invalid_parameters(format!("Unknown tag '{}'", alias)))
+    }
+}
+
+/// 按符号名访问的项，读写时应用标签的缩放
+pub struct TaggedItems {
+    items: BTreeMap<String, (Tag, OpcItem)>,
+}
+
+impl TaggedItems {
+    /// 同步读取标签
+    ///
+    /// 缩放的标签通过 `OpcItem::read_scaled` 读取，返回 `Double` 工程值；
+    /// 不缩放的标签返回原始值。
+    pub fn read(&self, alias: &str) -> OpcResult<(OpcValue, OpcQuality, OpcTimestamp)> {
+        let (tag, item) = self.entry(alias)?;
+        if !tag.is_scaled() {
+            return item.read_sync();
+        }
+        let (eu, quality, timestamp) = item.read_scaled()?;
+        Ok((OpcValue::Double(eu), quality, timestamp))
+    }
+
+    /// 同步写入标签
+    ///
+    /// 缩放的标签通过 `OpcItem::write_scaled` 把工程值转换为原始值后写入，
+    /// 值必须是数值；不缩放的标签原样写入。
+    pub fn write(&self, alias: &str, value: &OpcValue) -> OpcResult<()> {
+        let (tag, item) = self.entry(alias)?;
+        if !tag.is_scaled() {
+            return item.write_sync(value);
+        }
+        item.write_scaled(scalar(value)?)
+    }
+
+    /// 符号名对应的项
+    pub fn item(&self, alias: &str) -> Option<&OpcItem> {
+        self.items.get(alias).map(|(_, item)| item)
+    }
+
+    /// 符号名对应的标签
+    pub fn tag(&self, alias: &str) -> Option<&Tag> {
+        self.items.get(alias).map(|(tag, _)| tag)
+    }
+
+    fn entry(&self, alias: &str) -> OpcResult<(&Tag, &OpcItem)> {
+        self.items
+            .get(alias)
+            .map(|(tag, item)| (tag, item))
+            .ok_or_else(|| OpcError::invalid_parameters(format!("Unknown tag '{}'", alias)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scaling_round_trip() {
+        let close = |value: OpcValue, expected: f64| matches!(value, OpcValue::Double(v) if (v - expected).abs() < 1e-9);
+        let tag = Tag::scaled("Channel1.Device1.Reactor.PV", 0.1, -40.0);
+        assert!(close(tag.to_engineering(OpcValue::Int32(1000)).unwrap(), 60.0));
+        assert!(close(tag.to_raw(&OpcValue::Double(60.0)).unwrap(), 1000.0));
+        assert!(tag.to_engineering(OpcValue::String("x".to_string())).is_err());
+
+        let ranged = Tag::with_scaling("TT101", Scaling::new(0.0, 27648.0, 0.0, 150.0).clamped());
+        assert_eq!(ranged.to_engineering(OpcValue::Int32(13824)).unwrap(), OpcValue::Double(75.0));
+        assert_eq!(ranged.to_raw(&OpcValue::Double(200.0)).unwrap(), OpcValue::Double(27648.0));
+        assert!(Tag::scaled("A", 0.0, 1.0).to_raw(&OpcValue::Double(1.0)).is_err());
+
+        let plain = Tag::new("Channel1.Device1.Pump.Run");
+        assert_eq!(plain.to_raw(&OpcValue::Bool(true)).unwrap(), OpcValue::Bool(true));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let mut tags = TagMap::new();
+        tags.insert("reactor_temp", Tag::scaled("Channel1.Device1.Reactor.PV", 0.1, 0.0));
+        assert_eq!(tags.resolve("reactor_temp").unwrap(), "Channel1.Device1.Reactor.PV");
+        assert_eq!(tags.alias_of("Channel1.Device1.Reactor.PV"), Some("reactor_temp"));
+        assert!(matches!(tags.resolve("missing"), Err(OpcError::InvalidParameters(_))));
+    }
+}
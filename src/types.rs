@@ -7,6 +7,8 @@
 //! 
 //! - `OpcValue`: OPC 值枚举，支持多种数据类型
 //! - `OpcQuality`: OPC 质量指示器
+//! - `OpcDecimal`: 定点十进制数（`VT_DECIMAL`）
+//! - `OpcTimestamp`: 时间戳（Unix 毫秒，可转换为 FILETIME 和 `SystemTime`）
 //! - `OpcValueError`: 值转换错误
 //! - `OpcDataCallback`: 异步数据变化回调接口
 //! - `OpcCallbackContainer`: 回调容器（内部使用）
@@ -16,7 +18,13 @@
 //! `OpcValue` 支持 `TryFrom` 转换到 Rust 原生类型，
 //! 方便用户将 OPC 值转换为具体的 Rust 类型。
 
-use std::sync::Arc;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread::ThreadId;
+use crate::quirks::QuirkProfile;
+use crate::reentrancy::CallbackScope;
+use crate::writes::WriteTracker;
 #[cfg(windows)]
 use windows::Win32::System::Com as olecom;
 
@@ -75,10 +83,10 @@ const VT_ILLEGAL: u32 = 0xffff;
 const VT_ILLEGALMASKED: u32 = 0xfff;
 const VT_TYPEMASK: u32 = 0xfff;
 
-// Windows DECIMAL structure (simplified)
+// Windows DECIMAL structure
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
-struct Decimal {
+pub(crate) struct RawDecimal {
     w_reserved: u16,
     scale: u8,
     sign: u8,
@@ -86,42 +94,221 @@ struct Decimal {
     lo64: u64,
 }
 
-impl Decimal {
-    fn to_string(&self) -> String {
-        // Convert DECIMAL to string representation
-        // DECIMAL is stored as 96-bit integer (hi32 + lo64) with scale
-        // sign: 0 for positive, 0x80 for negative
-        // scale: number of decimal digits (0-28)
-        
-        let is_negative = self.sign == 0x80;
-        
-        // Combine hi32 and lo64 to get 96-bit value
-        // lo64 is already 64 bits, hi32 is high 32 bits
-        // We'll use u128 for simplicity
-        let value_lo = self.lo64 as u128;
-        let value_hi = (self.hi32 as u128) << 64;
-        let mut value = value_lo + value_hi;
-        
-        if is_negative {
-            value = (!value).wrapping_add(1); // Two's complement for negative
+/// DECIMAL 尾数的最大值（96 位）
+const DECIMAL_MAX_MANTISSA: u128 = (1 << 96) - 1;
+
+/// 定点十进制数（`VT_DECIMAL`）
+/// 
+/// 与 Windows `DECIMAL` 的表示相同：96 位无符号尾数、0 到 28 的小数位数和符号，
+/// 值为 `±mantissa / 10^scale`。文本形式保留全部小数位，例如尾数 1250、
+/// 小数位数 2 显示为 `12.50`；相等比较按数值进行，`12.5` 与 `12.50` 相等。
+/// 
+/// ## 示例
+/// 
+/// ```ignore
+/// use opc_da_client::{OpcDecimal, OpcValue};
+/// 
+/// let price: OpcDecimal = "12.50".parse()?;
+/// assert_eq!(price.mantissa(), 1250);
+/// assert_eq!(price.scale(), 2);
+/// item.write_sync(&OpcValue::Decimal(price))?;
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OpcDecimal {
+    mantissa: u128,
+    scale: u8,
+    negative: bool,
+}
+
+impl OpcDecimal {
+    /// 最大小数位数
+    pub const MAX_SCALE: u8 = 28;
+    
+    /// 从尾数、小数位数和符号创建
+    /// 
+    /// 尾数超过 96 位或小数位数超过 28 时返回错误。
+    pub fn new(mantissa: u128, scale: u8, negative: bool) -> Result<Self, OpcValueError> {
+        if mantissa > DECIMAL_MAX_MANTISSA {
+            return Err(OpcValueError::conversion_error("Decimal mantissa exceeds 96 bits"));
         }
-        
-        // Apply scaling: divide by 10^scale
-        let scale = self.scale as u32;
-        if scale == 0 {
-            format!("{}", value as i128)
-        } else {
-            let divisor = 10u128.pow(scale);
-            let integer_part = value / divisor;
-            let fractional_part = value % divisor;
-            
-            if fractional_part == 0 {
-                format!("{}", integer_part as i128)
-            } else {
-                // Format with proper decimal places
-                format!("{}.{:0width$}", integer_part as i128, fractional_part, width = scale as usize)
+        if scale > Self::MAX_SCALE {
+            return Err(OpcValueError::conversion_error(format!("Decimal scale {} exceeds 28", scale)));
+        }
+        Ok(OpcDecimal { mantissa, scale, negative })
+    }
+    
+    /// 从浮点数创建，使用能精确还原该浮点数的最短十进制形式
+    pub fn from_f64(value: f64) -> Result<Self, OpcValueError> {
+        if !value.is_finite() {
+            return Err(OpcValueError::conversion_error(format!("Cannot represent {} as Decimal", value)));
+        }
+        let text = value.to_string();
+        // 小数位数超过 28 时舍入到 28 位
+        match text.split_once('.') {
+            Some((_, fraction)) if fraction.len() > Self::MAX_SCALE as usize => {
+                format!("{:.*}", Self::MAX_SCALE as usize, value).parse()
             }
+            _ => text.parse(),
+        }
+    }
+    
+    /// 尾数（不含符号）
+    pub fn mantissa(&self) -> u128 {
+        self.mantissa
+    }
+    
+    /// 小数位数
+    pub fn scale(&self) -> u8 {
+        self.scale
+    }
+    
+    /// 是否为负数，零总是返回 `false`
+    pub fn is_negative(&self) -> bool {
+        self.negative && self.mantissa != 0
+    }
+    
+    /// 转换为浮点数，可能损失精度
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self.mantissa as f64 / 10f64.powi(self.scale as i32);
+        if self.is_negative() { -magnitude } else { magnitude }
+    }
+    
+    /// 去掉末尾的零后的尾数和小数位数
+    fn normalized(&self) -> (u128, u8) {
+        let (mut mantissa, mut scale) = (self.mantissa, self.scale);
+        while scale > 0 && mantissa % 10 == 0 {
+            mantissa /= 10;
+            scale -= 1;
+        }
+        (mantissa, scale)
+    }
+    
+    pub(crate) fn from_raw(raw: &RawDecimal) -> Result<Self, OpcValueError> {
+        let mantissa = ((raw.hi32 as u128) << 64) | raw.lo64 as u128;
+        Self::new(mantissa, raw.scale, raw.sign & 0x80 != 0)
+    }
+    
+    pub(crate) fn to_raw(self) -> RawDecimal {
+        RawDecimal {
+            w_reserved: VT_DECIMAL as u16,
+            scale: self.scale,
+            sign: if self.is_negative() { 0x80 } else { 0 },
+            hi32: (self.mantissa >> 64) as u32,
+            lo64: self.mantissa as u64,
+        }
+    }
+}
+
+impl PartialEq for OpcDecimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.is_negative() == other.is_negative() && self.normalized() == other.normalized()
+    }
+}
+
+impl Eq for OpcDecimal {}
+
+impl From<i64> for OpcDecimal {
+    fn from(value: i64) -> Self {
+        OpcDecimal {
+            mantissa: value.unsigned_abs() as u128,
+            scale: 0,
+            negative: value < 0,
+        }
+    }
+}
+
+impl From<u64> for OpcDecimal {
+    fn from(value: u64) -> Self {
+        OpcDecimal {
+            mantissa: value as u128,
+            scale: 0,
+            negative: false,
+        }
+    }
+}
+
+impl std::str::FromStr for OpcDecimal {
+    type Err = OpcValueError;
+    
+    /// 解析 `[+-]整数[.小数]` 形式的文本
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || OpcValueError::conversion_error(format!("Invalid decimal '{}'", s));
+        let text = s.trim();
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if integer.is_empty() && fraction.is_empty() {
+            return Err(invalid());
+        }
+        let scale = u8::try_from(fraction.len()).map_err(|_| invalid())?;
+        let mut mantissa: u128 = 0;
+        for c in integer.chars().chain(fraction.chars()) {
+            let digit = c.to_digit(10).ok_or_else(invalid)?;
+            mantissa = mantissa
+                .checked_mul(10)
+                .and_then(|m| m.checked_add(digit as u128))
+                .filter(|&m| m <= DECIMAL_MAX_MANTISSA)
+                .ok_or_else(|| OpcValueError::conversion_error(format!("Decimal '{}' is out of range", s)))?;
+        }
+        Self::new(mantissa, scale, negative)
+    }
+}
+
+impl std::fmt::Display for OpcDecimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_negative() {
+            f.write_str("-")?;
+        }
+        if self.scale == 0 {
+            return write!(f, "{}", self.mantissa);
         }
+        let divisor = 10u128.pow(self.scale as u32);
+        write!(
+            f,
+            "{}.{:0width$}",
+            self.mantissa / divisor,
+            self.mantissa % divisor,
+            width = self.scale as usize
+        )
+    }
+}
+
+#[cfg(feature = "rust_decimal")]
+impl From<OpcDecimal> for rust_decimal::Decimal {
+    fn from(value: OpcDecimal) -> Self {
+        // 尾数不超过 96 位、小数位数不超过 28，总在 rust_decimal 的范围内
+        let mut decimal = rust_decimal::Decimal::from_i128_with_scale(value.mantissa as i128, value.scale as u32);
+        decimal.set_sign_negative(value.is_negative());
+        decimal
+    }
+}
+
+#[cfg(feature = "rust_decimal")]
+impl From<rust_decimal::Decimal> for OpcDecimal {
+    fn from(value: rust_decimal::Decimal) -> Self {
+        OpcDecimal {
+            mantissa: value.mantissa().unsigned_abs(),
+            scale: value.scale() as u8,
+            negative: value.is_sign_negative(),
+        }
+    }
+}
+
+/// 以文本形式序列化，保留全部小数位
+#[cfg(feature = "serde")]
+impl serde::Serialize for OpcDecimal {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for OpcDecimal {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
     }
 }
 
@@ -154,6 +341,8 @@ impl Decimal {
 /// }
 /// ```
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
 pub enum OpcValue {
     /// 8位有符号整数
     Int8(i8),
@@ -184,7 +373,7 @@ pub enum OpcValue {
     /// 货币类型 (64位整数，缩放10000)
     Cy(i64),
     /// 小数类型 (96位整数，缩放因子)
-    Decimal(String), // TODO: 实现合适的十进制类型
+    Decimal(OpcDecimal),
     /// 日期类型 (OLE自动化日期)
     Date(f64),
     /// UTF-8 字符串
@@ -242,6 +431,7 @@ pub enum OpcValue {
 /// assert_eq!(raw, 192);
 /// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OpcQuality {
     /// 良好质量数据
     /// 
@@ -291,6 +481,299 @@ impl std::fmt::Display for OpcQuality {
     }
 }
 
+/// OPC 时间戳
+/// 
+/// 以 Unix 毫秒保存，提供与 FILETIME、`SystemTime` 以及（`chrono` 特性）
+/// `chrono::DateTime<Utc>` 之间的转换。所有时间都是 UTC。
+/// `Display` 输出 ISO 8601 UTC 时间（例如 `2023-11-14T22:13:20.123Z`），
+/// 不受 `format::set_default_timestamp_style` 影响。
+/// 
+/// ## 示例
+/// 
+/// ```ignore
+/// use opc_da_client::OpcTimestamp;
+/// 
+/// let (value, quality, timestamp) = item.read_sync()?;
+/// let age = std::time::SystemTime::now().duration_since(timestamp.to_system_time());
+/// println!("{} @ {}", value, timestamp);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OpcTimestamp(u64);
+
+impl OpcTimestamp {
+    /// 从 Unix 毫秒创建
+    pub fn from_unix_ms(timestamp_ms: u64) -> Self {
+        OpcTimestamp(timestamp_ms)
+    }
+    
+    /// 从 Windows FILETIME（自 1601-01-01 起的 100 纳秒间隔数）创建，早于 1970 年的时间为 0
+    pub fn from_filetime(filetime: u64) -> Self {
+        OpcTimestamp(crate::format::filetime_to_unix_ms(filetime))
+    }
+    
+    /// 当前时间
+    pub fn now() -> Self {
+        Self::from(std::time::SystemTime::now())
+    }
+    
+    /// Unix 毫秒
+    pub fn unix_ms(&self) -> u64 {
+        self.0
+    }
+    
+    /// Windows FILETIME
+    pub fn to_filetime(&self) -> u64 {
+        crate::format::unix_ms_to_filetime(self.0)
+    }
+    
+    /// 转换为 `SystemTime`
+    pub fn to_system_time(&self) -> std::time::SystemTime {
+        std::time::UNIX_EPOCH + std::time::Duration::from_millis(self.0)
+    }
+    
+    /// 转换为 `chrono::DateTime<Utc>`，超出 chrono 范围时返回 `None`
+    #[cfg(feature = "chrono")]
+    pub fn to_chrono_utc(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        i64::try_from(self.0).ok().and_then(chrono::DateTime::from_timestamp_millis)
+    }
+}
+
+/// 序列化为 UTC Unix 毫秒整数，不受进程级时间戳格式影响
+///
+/// 需要其他格式的字段使用 `format::iso8601` 或 `format::filetime`
+/// （`#[serde(with = "...")]`），或在自定义实现中调用 `TimestampStyle::serialize`。
+#[cfg(feature = "serde")]
+impl serde::Serialize for OpcTimestamp {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.0)
+    }
+}
+
+/// 接受 Unix 毫秒整数或 ISO 8601 UTC 字符串
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for OpcTimestamp {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(TimestampVisitor)
+    }
+}
+
+#[cfg(feature = "serde")]
+struct TimestampVisitor;
+
+#[cfg(feature = "serde")]
+impl serde::de::Visitor<'_> for TimestampVisitor {
+    type Value = OpcTimestamp;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "a timestamp as an integer or an ISO 8601 UTC string")
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<OpcTimestamp, E> {
+        Ok(OpcTimestamp(value))
+    }
+
+    fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<OpcTimestamp, E> {
+        let value = u64::try_from(value).map_err(|_| E::custom(format!("negative timestamp {}", value)))?;
+        self.visit_u64(value)
+    }
+
+    fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<OpcTimestamp, E> {
+        crate::format::parse_iso8601(value)
+            .map(OpcTimestamp)
+            .ok_or_else(|| E::custom(format!("invalid ISO 8601 timestamp '{}'", value)))
+    }
+}
+
+impl From<u64> for OpcTimestamp {
+    fn from(timestamp_ms: u64) -> Self {
+        OpcTimestamp(timestamp_ms)
+    }
+}
+
+impl From<OpcTimestamp> for u64 {
+    fn from(timestamp: OpcTimestamp) -> Self {
+        timestamp.0
+    }
+}
+
+/// 早于 1970 年的时间为 0
+impl From<std::time::SystemTime> for OpcTimestamp {
+    fn from(time: std::time::SystemTime) -> Self {
+        let ms = time
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX));
+        OpcTimestamp(ms)
+    }
+}
+
+impl std::fmt::Display for OpcTimestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&crate::format::format_iso8601(self.0))
+    }
+}
+
+/// OPC 服务器状态（`OPCSERVERSTATE`）
+/// 
+/// `Display` 输出 `describe::set_language` 设置语言的描述，默认英文。
+/// 
+/// ## 示例
+/// 
+/// ```ignore
+/// use opc_da_client::ServerState;
+/// 
+/// let state = ServerState::from_raw(1);
+/// assert_eq!(state, ServerState::Running);
+/// assert_eq!(state.to_raw(), 1);
+/// assert_eq!(ServerState::from_raw(9), ServerState::Unknown(9));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ServerState {
+    /// 运行中，原始值 1
+    Running,
+    /// 故障，原始值 2
+    Failed,
+    /// 未配置，原始值 3
+    NoConfig,
+    /// 挂起，原始值 4
+    Suspended,
+    /// 测试模式，原始值 5
+    Test,
+    /// 与下层设备通讯故障，原始值 6
+    CommFault,
+    /// 规范中未定义的状态
+    Unknown(u32),
+}
+
+impl ServerState {
+    /// 从原始状态码创建
+    pub fn from_raw(state: u32) -> Self {
+        match state {
+            1 => ServerState::Running,
+            2 => ServerState::Failed,
+            3 => ServerState::NoConfig,
+            4 => ServerState::Suspended,
+            5 => ServerState::Test,
+            6 => ServerState::CommFault,
+            other => ServerState::Unknown(other),
+        }
+    }
+    
+    /// 转换为原始状态码
+    pub fn to_raw(&self) -> u32 {
+        match self {
+            ServerState::Running => 1,
+            ServerState::Failed => 2,
+            ServerState::NoConfig => 3,
+            ServerState::Suspended => 4,
+            ServerState::Test => 5,
+            ServerState::CommFault => 6,
+            ServerState::Unknown(state) => *state,
+        }
+    }
+    
+    /// 服务器是否正常运行
+    pub fn is_running(&self) -> bool {
+        *self == ServerState::Running
+    }
+}
+
+impl std::fmt::Display for ServerState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&crate::describe::describe_server_state(self.to_raw(), crate::describe::language()))
+    }
+}
+
+/// 一组项的质量汇总
+/// 
+/// 按每个项最近一次通知的质量统计，用于画面上的"通讯正常"等汇总指示。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QualitySummary {
+    /// Good 质量的项数
+    pub good: usize,
+    /// Uncertain 质量的项数
+    pub uncertain: usize,
+    /// Bad 质量的项数
+    pub bad: usize,
+    /// Bad 质量的项名（按名称排序）
+    pub bad_items: Vec<String>,
+}
+
+impl QualitySummary {
+    /// 统计的项总数
+    pub fn total(&self) -> usize {
+        self.good + self.uncertain + self.bad
+    }
+    
+    /// Bad 质量项的比例，没有项时为 0
+    pub fn bad_ratio(&self) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            total => self.bad as f64 / total as f64,
+        }
+    }
+}
+
+/// 每个项最新质量的增量统计
+#[derive(Default)]
+struct QualityTracker {
+    qualities: HashMap<String, OpcQuality>,
+    good: usize,
+    uncertain: usize,
+    bad: usize,
+    /// Bad 比例的报警阈值
+    threshold: Option<f64>,
+    /// 上次通知时是否超过阈值
+    exceeded: bool,
+}
+
+impl QualityTracker {
+    fn counter(&mut self, quality: OpcQuality) -> &mut usize {
+        match quality {
+            OpcQuality::Good => &mut self.good,
+            OpcQuality::Uncertain => &mut self.uncertain,
+            OpcQuality::Bad => &mut self.bad,
+        }
+    }
+    
+    /// 记录项的最新质量
+    fn update(&mut self, item_name: &str, quality: OpcQuality) {
+        match self.qualities.insert(item_name.to_string(), quality) {
+            Some(previous) if previous == quality => return,
+            Some(previous) => *self.counter(previous) -= 1,
+            None => {}
+        }
+        *self.counter(quality) += 1;
+    }
+    
+    fn summary(&self) -> QualitySummary {
+        let mut bad_items: Vec<String> = self
+            .qualities
+            .iter()
+            .filter(|(_, quality)| **quality == OpcQuality::Bad)
+            .map(|(name, _)| name.clone())
+            .collect();
+        bad_items.sort();
+        QualitySummary {
+            good: self.good,
+            uncertain: self.uncertain,
+            bad: self.bad,
+            bad_items,
+        }
+    }
+    
+    /// 阈值状态发生变化时返回新的状态
+    fn check_threshold(&mut self) -> Option<bool> {
+        let threshold = self.threshold?;
+        let total = self.good + self.uncertain + self.bad;
+        let exceeded = total > 0 && self.bad as f64 / total as f64 > threshold;
+        if exceeded == self.exceeded {
+            return None;
+        }
+        self.exceeded = exceeded;
+        Some(exceeded)
+    }
+}
+
 /// Error type for value conversions
 #[derive(Debug, Clone, PartialEq, thiserror::Error)]
 pub enum OpcValueError {
@@ -370,6 +853,17 @@ impl TryFrom<OpcValue> for f64 {
     }
 }
 
+impl TryFrom<OpcValue> for OpcDecimal {
+    type Error = OpcValueError;
+    
+    fn try_from(value: OpcValue) -> Result<Self, Self::Error> {
+        match value {
+            OpcValue::Decimal(v) => Ok(v),
+            _ => Err(OpcValueError::type_mismatch("Decimal", value.type_name())),
+        }
+    }
+}
+
 impl TryFrom<OpcValue> for String {
     type Error = OpcValueError;
     
@@ -517,6 +1011,36 @@ impl OpcValue {
         }
     }
     
+    /// Check whether the value is an array variant
+    pub fn is_array(&self) -> bool {
+        self.raw_type() & VT_ARRAY != 0
+    }
+    
+    /// Convert numeric values to f64
+    ///
+    /// Returns `None` for strings, decimals and arrays.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            OpcValue::Int8(v) => Some(*v as f64),
+            OpcValue::UInt8(v) => Some(*v as f64),
+            OpcValue::Int16(v) => Some(*v as f64),
+            OpcValue::UInt16(v) => Some(*v as f64),
+            OpcValue::Int32(v) => Some(*v as f64),
+            OpcValue::UInt32(v) => Some(*v as f64),
+            OpcValue::Int64(v) => Some(*v as f64),
+            OpcValue::UInt64(v) => Some(*v as f64),
+            OpcValue::INT(v) => Some(*v as f64),
+            OpcValue::UINT(v) => Some(*v as f64),
+            OpcValue::Float(v) => Some(*v as f64),
+            OpcValue::Double(v) => Some(*v),
+            OpcValue::Bool(v) => Some(if *v { 1.0 } else { 0.0 }),
+            OpcValue::Cy(v) => Some(*v as f64 / 10000.0),
+            OpcValue::Decimal(v) => Some(v.to_f64()),
+            OpcValue::Date(v) => Some(*v),
+            _ => None,
+        }
+    }
+    
     /// Get the raw value type code for FFI (VARTYPE value)
     pub fn raw_type(&self) -> u32 {
         match self {
@@ -873,11 +1397,10 @@ impl OpcValue {
             }
             VT_DECIMAL => {
                 if value.is_null() {
-                    return Ok(OpcValue::Decimal("0".to_string()));
+                    return Ok(OpcValue::Decimal(OpcDecimal::default()));
                 }
-                let decimal_ptr = value as *const Decimal;
-                let decimal = unsafe { &*decimal_ptr };
-                Ok(OpcValue::Decimal(decimal.to_string()))
+                let decimal = unsafe { &*(value as *const RawDecimal) };
+                Ok(OpcValue::Decimal(OpcDecimal::from_raw(decimal)?))
             }
             _ => {
                 // For unsupported types, return as string serialized
@@ -888,15 +1411,619 @@ impl OpcValue {
     }
 }
 
+/// 写入数组值时构造的 SAFEARRAY，释放时连同其中的 BSTR 一起销毁
+#[cfg(windows)]
+pub(crate) struct OwnedSafeArray(*mut olecom::SAFEARRAY);
+
+#[cfg(windows)]
+impl OwnedSafeArray {
+    /// 为数组值构造一维、下界为 0 的 SAFEARRAY，非数组值返回 `Ok(None)`
+    pub(crate) fn from_value(value: &OpcValue) -> Result<Option<Self>, OpcValueError> {
+        let array = match value {
+            OpcValue::ArrayInt16(v) => Self::from_slice(VT_I2, v)?,
+            OpcValue::ArrayUInt16(v) => Self::from_slice(VT_UI2, v)?,
+            OpcValue::ArrayInt32(v) => Self::from_slice(VT_I4, v)?,
+            OpcValue::ArrayUInt32(v) => Self::from_slice(VT_UI4, v)?,
+            OpcValue::ArrayInt64(v) => Self::from_slice(VT_I8, v)?,
+            OpcValue::ArrayUInt64(v) => Self::from_slice(VT_UI8, v)?,
+            OpcValue::ArrayFloat(v) => Self::from_slice(VT_R4, v)?,
+            OpcValue::ArrayDouble(v) => Self::from_slice(VT_R8, v)?,
+            OpcValue::ArrayBool(v) => {
+                // VARIANT_BOOL: VARIANT_TRUE 为 -1
+                let flags: Vec<i16> = v.iter().map(|&b| if b { -1 } else { 0 }).collect();
+                Self::from_slice(VT_BOOL, &flags)?
+            }
+            OpcValue::ArrayString(v) => Self::from_strings(v, |bstrs| Self::from_slice(VT_BSTR, bstrs))?,
+            _ => return Ok(None),
+        };
+        Ok(Some(array))
+    }
+    
+    /// 为字符串分配 BSTR 并交给 `build` 构造数组
+    ///
+    /// `build` 成功时 BSTR 的所有权已转移给 SAFEARRAY，由 SafeArrayDestroy 释放；
+    /// 失败时元素还没有复制进数组，这里释放已经分配的 BSTR。
+    fn from_strings(
+        strings: &[String],
+        build: impl FnOnce(&[*const u16]) -> Result<Self, OpcValueError>,
+    ) -> Result<Self, OpcValueError> {
+        let bstrs: Vec<*const u16> = strings
+            .iter()
+            .map(|s| {
+                let wide: Vec<u16> = s.encode_utf16().collect();
+                windows::core::BSTR::from_wide(&wide).into_raw()
+            })
+            .collect();
+        build(&bstrs).inspect_err(|_| {
+            for &bstr in &bstrs {
+                drop(unsafe { windows::core::BSTR::from_raw(bstr) });
+            }
+        })
+    }
+    
+    fn from_slice<T: Copy>(element_type: u32, elements: &[T]) -> Result<Self, OpcValueError> {
+        use windows::Win32::System::Ole::{SafeArrayAccessData, SafeArrayCreateVector, SafeArrayUnaccessData};
+        use windows::Win32::System::Variant::VARENUM;
+        
+        let count = u32::try_from(elements.len())
+            .map_err(|_| OpcValueError::conversion_error("Array too large for SAFEARRAY"))?;
+        unsafe {
+            let sa = SafeArrayCreateVector(VARENUM(element_type as u16), 0, count);
+            if sa.is_null() {
+                return Err(OpcValueError::conversion_error("Failed to create SAFEARRAY"));
+            }
+            // 先接管，后续失败时由 Drop 销毁
+            let array = OwnedSafeArray(sa);
+            let mut p_data: *mut std::ffi::c_void = std::ptr::null_mut();
+            if SafeArrayAccessData(sa, &mut p_data).is_err() {
+                return Err(OpcValueError::conversion_error("Failed to access SAFEARRAY data"));
+            }
+            std::ptr::copy_nonoverlapping(elements.as_ptr(), p_data as *mut T, elements.len());
+            let _ = SafeArrayUnaccessData(sa);
+            Ok(array)
+        }
+    }
+    
+    /// 写入接口需要的值指针：指向 SAFEARRAY 指针（对应 VARIANT 的 `parray` 成员）
+    pub(crate) fn value_ptr(&self) -> *const std::ffi::c_void {
+        &self.0 as *const *mut olecom::SAFEARRAY as *const std::ffi::c_void
+    }
+}
+
+#[cfg(windows)]
+impl Drop for OwnedSafeArray {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = windows::Win32::System::Ole::SafeArrayDestroy(self.0);
+        }
+    }
+}
+
+/// 订阅关闭原因
+/// 
+/// 订阅关闭时通过 `OpcDataCallback::on_subscription_closed` 传递给回调，
+/// 使消费者能够区分正常结束和异常断开，而不是在没有数据的订阅上无限等待。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubscriptionCloseReason {
+    /// 组已被释放
+    GroupDropped,
+    /// 与服务器的连接已断开或被释放
+    Disconnected(String),
+    /// 服务器通知即将关闭
+    ServerShutdown(String),
+}
+
+impl std::fmt::Display for SubscriptionCloseReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SubscriptionCloseReason::GroupDropped => write!(f, "group dropped"),
+            SubscriptionCloseReason::Disconnected(reason) => write!(f, "disconnected: {}", reason),
+            SubscriptionCloseReason::ServerShutdown(reason) => write!(f, "server shutdown: {}", reason),
+        }
+    }
+}
+
+/// 一次数据变化
+///
+/// 由 `OpcGroup::subscribe`、`SharedOpcGroup::subscribe` 和 `AsyncOpcGroup::subscribe` 送达，
+/// 也是 `codec` 编码和 `replay` 录制的单位。
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DataChangeEvent {
+    /// 组名
+    pub group: String,
+    /// 项名
+    pub item: String,
+    /// 值
+    pub value: OpcValue,
+    /// 质量
+    pub quality: OpcQuality,
+    /// 时间戳
+    pub timestamp: OpcTimestamp,
+}
+
+/// `ChannelCallback` 使用的发送端
+pub(crate) trait EventSender: Send {
+    /// 发送事件，接收端已丢弃时返回 `false`
+    fn send_event(&self, event: DataChangeEvent) -> bool;
+}
+
+impl EventSender for std::sync::mpsc::Sender<DataChangeEvent> {
+    fn send_event(&self, event: DataChangeEvent) -> bool {
+        self.send(event).is_ok()
+    }
+}
+
+/// 把数据变化转发到通道的订阅回调
+/// 
+/// 订阅关闭时释放发送端，接收端取完剩余的事件后返回断开。
+/// 同步订阅使用 `std::sync::mpsc`，`async` 特性的数据变化流使用 tokio 的通道。
+pub(crate) struct ChannelCallback<S: EventSender = std::sync::mpsc::Sender<DataChangeEvent>> {
+    sender: Mutex<Option<S>>,
+}
+
+impl<S: EventSender> ChannelCallback<S> {
+    pub(crate) fn new(sender: S) -> Self {
+        ChannelCallback {
+            sender: Mutex::new(Some(sender)),
+        }
+    }
+}
+
+impl<S: EventSender> OpcDataCallback for ChannelCallback<S> {
+    fn on_data_change(&self, group_name: &str, item_name: &str, value: OpcValue, quality: OpcQuality, timestamp: u64) {
+        let mut sender = lock_or_recover(&self.sender);
+        let event = DataChangeEvent {
+            group: group_name.to_string(),
+            item: item_name.to_string(),
+            value,
+            quality,
+            timestamp: OpcTimestamp::from_unix_ms(timestamp),
+        };
+        // 接收端已丢弃时不再转发
+        if sender.as_ref().is_some_and(|s| !s.send_event(event)) {
+            sender.take();
+        }
+    }
+    
+    fn on_subscription_closed(&self, _group_name: &str, _reason: &SubscriptionCloseReason) {
+        lock_or_recover(&self.sender).take();
+    }
+}
+
+/// 数据变化的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChangeOrigin {
+    /// 服务器上的值发生了变化
+    #[default]
+    Server,
+    /// 服务器推送的是本客户端刚写入的值（回声）
+    SelfWrite,
+}
+
 /// Callback trait for asynchronous data changes
 pub trait OpcDataCallback: Send + Sync {
     /// Called when data changes for subscribed items
     fn on_data_change(&self, group_name: &str, item_name: &str, value: OpcValue, quality: OpcQuality, timestamp: u64);
+    
+    /// Called when data changes, together with the origin of the change
+    /// 
+    /// The origin is only `ChangeOrigin::SelfWrite` when the group tags echoes
+    /// (`EchoHandling::Tag`). The default implementation ignores the origin
+    /// and calls `on_data_change`.
+    fn on_data_change_with_origin(
+        &self,
+        group_name: &str,
+        item_name: &str,
+        value: OpcValue,
+        quality: OpcQuality,
+        timestamp: u64,
+        _origin: ChangeOrigin,
+    ) {
+        self.on_data_change(group_name, item_name, value, quality, timestamp);
+    }
+    
+    /// Called once when the subscription is closed
+    /// 
+    /// No further data changes are delivered after this call.
+    /// The default implementation does nothing.
+    fn on_subscription_closed(&self, _group_name: &str, _reason: &SubscriptionCloseReason) {}
+    
+    /// Called when the connection behind the subscription is interrupted
+    /// 
+    /// Unlike `on_subscription_closed`, the subscription is not over: an owner
+    /// such as `ResilientConnection` re-registers the same callback once the
+    /// connection is restored, and data changes resume. The default
+    /// implementation does nothing.
+    fn on_connection_interrupted(&self, _group_name: &str, _reason: &str) {}
+    
+    /// Called when the ratio of Bad items crosses the group's quality threshold
+    /// 
+    /// `exceeded` is `true` when the ratio rises above the threshold and `false`
+    /// when it falls back. The default implementation does nothing.
+    fn on_quality_threshold(&self, _group_name: &str, _summary: &QualitySummary, _exceeded: bool) {}
+    
+    /// Called with the current values of a group's items after a reconnect
+    /// 
+    /// Only delivered when the owner enables it, see
+    /// `ResilientConnection::set_backfill`. The batch is read before the
+    /// subscription is re-enabled, so it arrives before any data change of the
+    /// new connection. Items whose read fails are left out. The default
+    /// implementation does nothing.
+    fn on_backfill(&self, _group_name: &str, _batch: &[DataChangeEvent]) {}
+}
+
+/// A data change waiting to be delivered to the user callback
+#[derive(Clone)]
+pub(crate) struct PendingDataChange {
+    pub group_name: String,
+    pub item_name: String,
+    pub value: OpcValue,
+    pub quality: OpcQuality,
+    pub timestamp: u64,
+    pub origin: ChangeOrigin,
+}
+
+/// Dispatch state shared between group calls and the FFI callback
+#[derive(Default)]
+struct DispatchState {
+    /// 正在进行的组调用数量（Refresh、AddItems 等）
+    calls_in_flight: usize,
+    /// 是否正在分发排队的通知
+    draining: bool,
+    /// 组调用期间收到的通知
+    pending: VecDeque<PendingDataChange>,
+}
+
+/// Bounded replay buffer for late-joining consumers
+///
+/// 保存每个项的最新值以及最近 `capacity` 条通知。新的消费者加入时，
+/// 先收到不在最近通知窗口内的项的最新值，再按顺序收到最近的通知，
+/// 这样每个项最后收到的都是其最新值。
+pub(crate) struct ReplayBuffer {
+    capacity: usize,
+    sequence: u64,
+    latest: HashMap<String, (u64, PendingDataChange)>,
+    recent: VecDeque<(u64, PendingDataChange)>,
+}
+
+impl ReplayBuffer {
+    pub(crate) fn new(capacity: usize) -> Self {
+        ReplayBuffer {
+            capacity,
+            sequence: 0,
+            latest: HashMap::new(),
+            recent: VecDeque::with_capacity(capacity),
+        }
+    }
+    
+    /// Record a delivered data change
+    pub(crate) fn record(&mut self, change: &PendingDataChange) {
+        self.sequence += 1;
+        self.latest.insert(change.item_name.clone(), (self.sequence, change.clone()));
+        if self.capacity == 0 {
+            return;
+        }
+        if self.recent.len() == self.capacity {
+            self.recent.pop_front();
+        }
+        self.recent.push_back((self.sequence, change.clone()));
+    }
+    
+    /// Data changes to replay to a new consumer, oldest first
+    pub(crate) fn snapshot(&self) -> Vec<PendingDataChange> {
+        let window_start = self.recent.front().map(|(seq, _)| *seq).unwrap_or(u64::MAX);
+        let mut older: Vec<&(u64, PendingDataChange)> = self
+            .latest
+            .values()
+            .filter(|(seq, _)| *seq < window_start)
+            .collect();
+        older.sort_by_key(|(seq, _)| *seq);
+        
+        older
+            .into_iter()
+            .chain(self.recent.iter())
+            .map(|(_, change)| change.clone())
+            .collect()
+    }
+}
+
+/// A subscription consumer, held strongly or weakly
+/// 
+/// 弱引用的消费者不会被组保持存活，释放后在下一次分发时自动移除。
+#[derive(Clone)]
+enum Consumer {
+    Strong(Arc<dyn OpcDataCallback>),
+    Weak(Weak<dyn OpcDataCallback>),
+}
+
+impl Consumer {
+    fn upgrade(&self) -> Option<Arc<dyn OpcDataCallback>> {
+        match self {
+            Consumer::Strong(callback) => Some(Arc::clone(callback)),
+            Consumer::Weak(callback) => callback.upgrade(),
+        }
+    }
 }
 
 /// Internal callback container for FFI
+///
+/// 某些服务器会在 Refresh/AddItems 调用期间重入地调用 OnDataChange。
+/// 为了避免用户回调在持有锁时被意外重入，组调用进行期间收到的通知会被排队，
+/// 在调用返回后按原顺序分发。
+///
+/// 容器可以有多个消费者，启用重放缓冲区后，新加入的消费者会立即收到
+/// 每个项的最新值和最近的通知。
 pub(crate) struct OpcCallbackContainer {
-    pub callback: Arc<dyn OpcDataCallback>,
+    /// 所属组的名称
+    group_name: String,
+    /// 订阅是否已关闭
+    closed: AtomicBool,
+    /// 接收通知的回调
+    consumers: Mutex<Vec<Consumer>>,
+    /// 组使用的厂商兼容性配置
+    pub quirks: QuirkProfile,
+    /// 组的死区值（百分比），用于客户端相对变化过滤
+    pub deadband: f64,
+    /// 每个项最近一次分发的值，用于客户端相对变化过滤
+    pub last_values: Mutex<HashMap<String, OpcValue>>,
+    /// 重入保护的分发状态
+    dispatch_state: Mutex<DispatchState>,
+    /// 供后加入的消费者使用的重放缓冲区
+    replay: Mutex<Option<ReplayBuffer>>,
+    /// 组的写入跟踪，用于识别回声
+    pub writes: Arc<WriteTracker>,
+    /// 每个项最新质量的统计
+    qualities: Mutex<QualityTracker>,
+    /// 已分发的数据变化通知数
+    notifications: AtomicU64,
+    /// 所属的服务器连接，用于重入检查
+    server: Option<u64>,
+    /// 启用订阅的线程
+    owner: ThreadId,
+}
+
+impl OpcCallbackContainer {
+    /// Create a container for the given callback
+    pub(crate) fn new(group_name: &str, callback: Arc<dyn OpcDataCallback>, quirks: QuirkProfile, deadband: f64) -> Self {
+        OpcCallbackContainer {
+            group_name: group_name.to_string(),
+            closed: AtomicBool::new(false),
+            consumers: Mutex::new(vec![Consumer::Strong(callback)]),
+            quirks,
+            deadband,
+            last_values: Mutex::new(HashMap::new()),
+            dispatch_state: Mutex::new(DispatchState::default()),
+            replay: Mutex::new(None),
+            writes: Arc::new(WriteTracker::default()),
+            qualities: Mutex::new(QualityTracker::default()),
+            notifications: AtomicU64::new(0),
+            server: None,
+            owner: std::thread::current().id(),
+        }
+    }
+    
+    /// Share the group's write tracker for echo detection
+    pub(crate) fn with_write_tracker(mut self, writes: Arc<WriteTracker>) -> Self {
+        self.writes = writes;
+        self
+    }
+    
+    /// Record the server connection the subscription belongs to
+    pub(crate) fn with_server(mut self, server: u64) -> Self {
+        self.server = Some(server);
+        self
+    }
+    
+    /// Enable the replay buffer with the given event capacity, or disable it
+    pub(crate) fn set_replay_capacity(&self, capacity: Option<usize>) {
+        *lock_or_recover(&self.replay) = capacity.map(ReplayBuffer::new);
+    }
+    
+    /// Run a closure against the replay buffer, if enabled
+    pub(crate) fn with_replay<R>(&self, f: impl FnOnce(&ReplayBuffer) -> R) -> Option<R> {
+        lock_or_recover(&self.replay).as_ref().map(f)
+    }
+    
+    /// Summarize the latest quality of every item seen by this subscription
+    pub(crate) fn quality_summary(&self) -> QualitySummary {
+        lock_or_recover(&self.qualities).summary()
+    }
+    
+    /// Number of data changes delivered to consumers
+    pub(crate) fn notifications(&self) -> u64 {
+        self.notifications.load(Ordering::Relaxed)
+    }
+    
+    /// Set the Bad ratio above which consumers are notified, or disable it
+    pub(crate) fn set_quality_threshold(&self, threshold: Option<f64>) {
+        let mut qualities = lock_or_recover(&self.qualities);
+        qualities.threshold = threshold;
+        qualities.exceeded = false;
+    }
+    
+    /// Name of the group this subscription belongs to
+    pub(crate) fn group_name(&self) -> &str {
+        &self.group_name
+    }
+    
+    /// Attach an additional consumer, replaying buffered data changes to it first
+    pub(crate) fn add_consumer(&self, callback: Arc<dyn OpcDataCallback>) {
+        self.attach(Consumer::Strong(callback));
+    }
+    
+    /// Attach a consumer that the subscription does not keep alive
+    pub(crate) fn add_weak_consumer(&self, callback: Weak<dyn OpcDataCallback>) {
+        self.attach(Consumer::Weak(callback));
+    }
+    
+    fn attach(&self, consumer: Consumer) {
+        let Some(callback) = consumer.upgrade() else {
+            return;
+        };
+        // 重放期间到达的通知先排队，保证新消费者收到的顺序正确
+        self.begin_call();
+        let replay = self.with_replay(|buffer| buffer.snapshot()).unwrap_or_default();
+        {
+            let _scope = CallbackScope::enter(self.server, self.owner);
+            for change in replay {
+                callback.on_data_change_with_origin(
+                    &change.group_name,
+                    &change.item_name,
+                    change.value,
+                    change.quality,
+                    change.timestamp,
+                    change.origin,
+                );
+            }
+        }
+        lock_or_recover(&self.consumers).push(consumer);
+        self.end_call();
+    }
+    
+    /// Consumers that are still alive, removing released weak consumers
+    fn live_consumers(&self) -> Vec<Arc<dyn OpcDataCallback>> {
+        let mut consumers = lock_or_recover(&self.consumers);
+        let mut live = Vec::with_capacity(consumers.len());
+        consumers.retain(|consumer| match consumer.upgrade() {
+            Some(callback) => {
+                live.push(callback);
+                true
+            }
+            None => false,
+        });
+        live
+    }
+    
+    fn state(&self) -> std::sync::MutexGuard<'_, DispatchState> {
+        lock_or_recover(&self.dispatch_state)
+    }
+    
+    /// Close the subscription and notify every consumer once
+    /// 
+    /// Queued data changes are delivered before the close notification.
+    pub(crate) fn close(&self, reason: &SubscriptionCloseReason) {
+        self.drain();
+        if self.closed.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let consumers = self.live_consumers();
+        let _scope = CallbackScope::enter(self.server, self.owner);
+        for consumer in &consumers {
+            consumer.on_subscription_closed(&self.group_name, reason);
+        }
+    }
+    
+    /// Stop delivery because the connection was interrupted
+    /// 
+    /// Consumers receive `on_connection_interrupted` instead of a close
+    /// notification, so they stay usable when re-registered on a new connection.
+    pub(crate) fn interrupt(&self, reason: &str) {
+        self.drain();
+        if self.closed.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let consumers = self.live_consumers();
+        let _scope = CallbackScope::enter(self.server, self.owner);
+        for consumer in &consumers {
+            consumer.on_connection_interrupted(&self.group_name, reason);
+        }
+    }
+    
+    /// Check whether the subscription has been closed
+    pub(crate) fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+    
+    /// Deliver a data change, queueing it while a group call is in flight
+    pub(crate) fn dispatch(&self, change: PendingDataChange) {
+        if self.is_closed() {
+            return;
+        }
+        {
+            let mut state = self.state();
+            if state.calls_in_flight > 0 || state.draining {
+                state.pending.push_back(change);
+                return;
+            }
+        }
+        self.deliver(change);
+    }
+    
+    /// Mark the start of a group call
+    pub(crate) fn begin_call(&self) {
+        self.state().calls_in_flight += 1;
+    }
+    
+    /// Mark the end of a group call and deliver queued data changes
+    pub(crate) fn end_call(&self) {
+        {
+            let mut state = self.state();
+            state.calls_in_flight = state.calls_in_flight.saturating_sub(1);
+            // 外层的分发循环会处理新排队的通知
+            if state.draining {
+                return;
+            }
+        }
+        self.drain();
+    }
+    
+    fn drain(&self) {
+        loop {
+            let batch: Vec<PendingDataChange> = {
+                let mut state = self.state();
+                if state.calls_in_flight > 0 || state.pending.is_empty() {
+                    state.draining = false;
+                    return;
+                }
+                state.draining = true;
+                state.pending.drain(..).collect()
+            };
+            for change in batch {
+                self.deliver(change);
+            }
+        }
+    }
+    
+    fn deliver(&self, change: PendingDataChange) {
+        if self.is_closed() {
+            return;
+        }
+        if let Some(buffer) = lock_or_recover(&self.replay).as_mut() {
+            buffer.record(&change);
+        }
+        self.notifications.fetch_add(1, Ordering::Relaxed);
+        let threshold_event = {
+            let mut qualities = lock_or_recover(&self.qualities);
+            qualities.update(&change.item_name, change.quality);
+            qualities
+                .check_threshold()
+                .map(|exceeded| (qualities.summary(), exceeded))
+        };
+        
+        let consumers = self.live_consumers();
+        let _scope = CallbackScope::enter(self.server, self.owner);
+        for consumer in &consumers {
+            consumer.on_data_change_with_origin(
+                &change.group_name,
+                &change.item_name,
+                change.value.clone(),
+                change.quality,
+                change.timestamp,
+                change.origin,
+            );
+        }
+        if let Some((summary, exceeded)) = threshold_event {
+            for consumer in &consumers {
+                consumer.on_quality_threshold(&self.group_name, &summary, exceeded);
+            }
+        }
+    }
+}
+
+/// Lock a mutex, recovering the data if a callback panicked while holding it
+pub(crate) fn lock_or_recover<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
 }
 
 #[cfg(test)]
@@ -1098,4 +2225,394 @@ mod tests {
         }
         unsafe { drop(Box::from_raw(ptr as *mut f64)); }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_callback_container_queues_during_group_call() {
+        struct Recorder {
+            items: Mutex<Vec<String>>,
+        }
+        
+        impl OpcDataCallback for Recorder {
+            fn on_data_change(&self, _group_name: &str, item_name: &str, _value: OpcValue, _quality: OpcQuality, _timestamp: u64) {
+                self.items.lock().unwrap().push(item_name.to_string());
+            }
+        }
+        
+        let recorder = Arc::new(Recorder { items: Mutex::new(Vec::new()) });
+        let container = OpcCallbackContainer::new("G", recorder.clone(), QuirkProfile::none(), 0.0);
+        let change = |item: &str| PendingDataChange {
+            group_name: "G".to_string(),
+            item_name: item.to_string(),
+            value: OpcValue::Int32(1),
+            quality: OpcQuality::Good,
+            timestamp: 0,
+            origin: ChangeOrigin::Server,
+        };
+        
+        container.begin_call();
+        container.dispatch(change("A"));
+        container.begin_call();
+        container.dispatch(change("B"));
+        container.end_call();
+        assert!(recorder.items.lock().unwrap().is_empty());
+        
+        container.end_call();
+        assert_eq!(*recorder.items.lock().unwrap(), vec!["A", "B"]);
+        
+        container.dispatch(change("C"));
+        assert_eq!(recorder.items.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_replay_buffer_snapshot_order() {
+        let change = |item: &str, value: i32| PendingDataChange {
+            group_name: "G".to_string(),
+            item_name: item.to_string(),
+            value: OpcValue::Int32(value),
+            quality: OpcQuality::Good,
+            timestamp: value as u64,
+            origin: ChangeOrigin::Server,
+        };
+        
+        let mut buffer = ReplayBuffer::new(2);
+        buffer.record(&change("A", 1));
+        buffer.record(&change("B", 2));
+        buffer.record(&change("A", 3));
+        buffer.record(&change("C", 4));
+        
+        // B 不在最近窗口内，先重放其最新值；然后是最近两条通知
+        let replay: Vec<(String, OpcValue)> = buffer
+            .snapshot()
+            .into_iter()
+            .map(|c| (c.item_name, c.value))
+            .collect();
+        assert_eq!(replay, vec![
+            ("B".to_string(), OpcValue::Int32(2)),
+            ("A".to_string(), OpcValue::Int32(3)),
+            ("C".to_string(), OpcValue::Int32(4)),
+        ]);
+    }
+
+    #[test]
+    fn test_callback_container_close_notifies_once() {
+        struct Recorder {
+            events: Mutex<Vec<String>>,
+        }
+        
+        impl OpcDataCallback for Recorder {
+            fn on_data_change(&self, _group_name: &str, item_name: &str, _value: OpcValue, _quality: OpcQuality, _timestamp: u64) {
+                self.events.lock().unwrap().push(item_name.to_string());
+            }
+            
+            fn on_subscription_closed(&self, group_name: &str, reason: &SubscriptionCloseReason) {
+                self.events.lock().unwrap().push(format!("closed {}: {}", group_name, reason));
+            }
+        }
+        
+        let recorder = Arc::new(Recorder { events: Mutex::new(Vec::new()) });
+        let container = OpcCallbackContainer::new("G", recorder.clone(), QuirkProfile::none(), 0.0);
+        let change = PendingDataChange {
+            group_name: "G".to_string(),
+            item_name: "A".to_string(),
+            value: OpcValue::Int32(1),
+            quality: OpcQuality::Good,
+            timestamp: 0,
+            origin: ChangeOrigin::Server,
+        };
+        
+        container.begin_call();
+        container.dispatch(change.clone());
+        container.end_call();
+        container.close(&SubscriptionCloseReason::ServerShutdown("maintenance".to_string()));
+        container.close(&SubscriptionCloseReason::GroupDropped);
+        container.dispatch(change);
+        
+        assert_eq!(*recorder.events.lock().unwrap(), vec![
+            "A".to_string(),
+            "closed G: server shutdown: maintenance".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn test_callback_container_interrupt_suppresses_close() {
+        struct Recorder {
+            events: Mutex<Vec<String>>,
+        }
+        
+        impl OpcDataCallback for Recorder {
+            fn on_data_change(&self, _group_name: &str, item_name: &str, _value: OpcValue, _quality: OpcQuality, _timestamp: u64) {
+                self.events.lock().unwrap().push(item_name.to_string());
+            }
+            
+            fn on_subscription_closed(&self, group_name: &str, reason: &SubscriptionCloseReason) {
+                self.events.lock().unwrap().push(format!("closed {}: {}", group_name, reason));
+            }
+            
+            fn on_connection_interrupted(&self, group_name: &str, reason: &str) {
+                self.events.lock().unwrap().push(format!("interrupted {}: {}", group_name, reason));
+            }
+        }
+        
+        let recorder = Arc::new(Recorder { events: Mutex::new(Vec::new()) });
+        let container = OpcCallbackContainer::new("G", recorder.clone(), QuirkProfile::none(), 0.0);
+        container.interrupt("link lost");
+        container.interrupt("link lost");
+        container.close(&SubscriptionCloseReason::GroupDropped);
+        container.dispatch(PendingDataChange {
+            group_name: "G".to_string(),
+            item_name: "A".to_string(),
+            value: OpcValue::Int32(1),
+            quality: OpcQuality::Good,
+            timestamp: 0,
+            origin: ChangeOrigin::Server,
+        });
+        
+        assert_eq!(*recorder.events.lock().unwrap(), vec!["interrupted G: link lost".to_string()]);
+    }
+
+    #[test]
+    fn test_callback_container_weak_consumer() {
+        struct Recorder {
+            events: Mutex<Vec<String>>,
+        }
+        
+        impl OpcDataCallback for Recorder {
+            fn on_data_change(&self, _group_name: &str, item_name: &str, _value: OpcValue, _quality: OpcQuality, _timestamp: u64) {
+                self.events.lock().unwrap().push(item_name.to_string());
+            }
+            
+            fn on_subscription_closed(&self, _group_name: &str, reason: &SubscriptionCloseReason) {
+                self.events.lock().unwrap().push(format!("closed: {}", reason));
+            }
+        }
+        
+        let owner = Arc::new(Recorder { events: Mutex::new(Vec::new()) });
+        let container = OpcCallbackContainer::new("G", owner.clone(), QuirkProfile::none(), 0.0);
+        let change = |item: &str| PendingDataChange {
+            group_name: "G".to_string(),
+            item_name: item.to_string(),
+            value: OpcValue::Int32(1),
+            quality: OpcQuality::Good,
+            timestamp: 0,
+            origin: ChangeOrigin::Server,
+        };
+        
+        let observer = Arc::new(Recorder { events: Mutex::new(Vec::new()) });
+        let weak: Weak<dyn OpcDataCallback> = Arc::downgrade(&observer) as Weak<Recorder>;
+        container.add_weak_consumer(weak);
+        assert_eq!(Arc::strong_count(&observer), 1);
+        container.dispatch(change("A"));
+        assert_eq!(*observer.events.lock().unwrap(), vec!["A"]);
+        
+        let departed = Arc::new(Recorder { events: Mutex::new(Vec::new()) });
+        container.add_weak_consumer(Arc::downgrade(&departed) as Weak<Recorder>);
+        drop(departed);
+        container.dispatch(change("B"));
+        assert_eq!(container.live_consumers().len(), 2);
+        
+        container.close(&SubscriptionCloseReason::GroupDropped);
+        assert_eq!(*observer.events.lock().unwrap(), vec!["A", "B", "closed: group dropped"]);
+    }
+
+    #[test]
+    fn test_channel_callback_closes_receiver() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let container = OpcCallbackContainer::new("G", Arc::new(ChannelCallback::new(sender)), QuirkProfile::none(), 0.0);
+        container.dispatch(PendingDataChange {
+            group_name: "G".to_string(),
+            item_name: "A".to_string(),
+            value: OpcValue::Int32(7),
+            quality: OpcQuality::Good,
+            timestamp: 42,
+            origin: ChangeOrigin::Server,
+        });
+        container.close(&SubscriptionCloseReason::GroupDropped);
+        
+        let events: Vec<DataChangeEvent> = receiver.iter().collect();
+        assert_eq!(events, vec![DataChangeEvent {
+            group: "G".to_string(),
+            item: "A".to_string(),
+            value: OpcValue::Int32(7),
+            quality: OpcQuality::Good,
+            timestamp: OpcTimestamp::from_unix_ms(42),
+        }]);
+    }
+
+    #[test]
+    fn test_callback_container_marks_callback_scope() {
+        struct Probe {
+            seen: Mutex<Vec<bool>>,
+        }
+        
+        impl OpcDataCallback for Probe {
+            fn on_data_change(&self, _group_name: &str, _item_name: &str, _value: OpcValue, _quality: OpcQuality, _timestamp: u64) {
+                self.seen.lock().unwrap().push(crate::reentrancy::in_callback());
+            }
+        }
+        
+        let probe = Arc::new(Probe { seen: Mutex::new(Vec::new()) });
+        let container = OpcCallbackContainer::new("G", probe.clone(), QuirkProfile::none(), 0.0);
+        container.dispatch(PendingDataChange {
+            group_name: "G".to_string(),
+            item_name: "A".to_string(),
+            value: OpcValue::Int32(1),
+            quality: OpcQuality::Good,
+            timestamp: 0,
+            origin: ChangeOrigin::Server,
+        });
+        assert_eq!(*probe.seen.lock().unwrap(), vec![true]);
+        assert!(!crate::reentrancy::in_callback());
+    }
+
+    #[test]
+    fn test_quality_summary_threshold_crossing() {
+        struct Recorder {
+            events: Mutex<Vec<(usize, bool)>>,
+        }
+        
+        impl OpcDataCallback for Recorder {
+            fn on_data_change(&self, _group_name: &str, _item_name: &str, _value: OpcValue, _quality: OpcQuality, _timestamp: u64) {}
+            
+            fn on_quality_threshold(&self, _group_name: &str, summary: &QualitySummary, exceeded: bool) {
+                self.events.lock().unwrap().push((summary.bad, exceeded));
+            }
+        }
+        
+        let recorder = Arc::new(Recorder { events: Mutex::new(Vec::new()) });
+        let container = OpcCallbackContainer::new("G", recorder.clone(), QuirkProfile::none(), 0.0);
+        container.set_quality_threshold(Some(0.3));
+        let change = |item: &str, quality: OpcQuality| PendingDataChange {
+            group_name: "G".to_string(),
+            item_name: item.to_string(),
+            value: OpcValue::Int32(1),
+            quality,
+            timestamp: 0,
+            origin: ChangeOrigin::Server,
+        };
+        
+        container.dispatch(change("A", OpcQuality::Good));
+        container.dispatch(change("B", OpcQuality::Good));
+        container.dispatch(change("C", OpcQuality::Uncertain));
+        container.dispatch(change("B", OpcQuality::Bad));
+        container.dispatch(change("A", OpcQuality::Bad));
+        container.dispatch(change("A", OpcQuality::Good));
+        
+        let summary = container.quality_summary();
+        assert_eq!(summary, QualitySummary {
+            good: 1,
+            uncertain: 1,
+            bad: 1,
+            bad_items: vec!["B".to_string()],
+        });
+        assert!((summary.bad_ratio() - 1.0 / 3.0).abs() < 1e-9);
+        // 1/3 > 0.3 时进入超限，之后 2/3 保持超限不重复通知；A 恢复后仍为 1/3
+        assert_eq!(*recorder.events.lock().unwrap(), vec![(1, true)]);
+        
+        container.dispatch(change("B", OpcQuality::Good));
+        assert_eq!(recorder.events.lock().unwrap().last(), Some(&(0, false)));
+    }
+
+    #[test]
+    fn test_server_state_raw_round_trip() {
+        for raw in 0..8 {
+            assert_eq!(ServerState::from_raw(raw).to_raw(), raw);
+        }
+        assert_eq!(ServerState::from_raw(6), ServerState::CommFault);
+        assert!(ServerState::Running.is_running());
+        assert_eq!(ServerState::NoConfig.to_string(), "No configuration");
+    }
+    
+    #[test]
+    fn test_timestamp_conversions() {
+        let timestamp = OpcTimestamp::from_unix_ms(1_700_000_000_123);
+        assert_eq!(OpcTimestamp::from_filetime(timestamp.to_filetime()), timestamp);
+        assert_eq!(OpcTimestamp::from(timestamp.to_system_time()), timestamp);
+        assert_eq!(u64::from(timestamp), 1_700_000_000_123);
+        assert_eq!(timestamp.to_string(), "2023-11-14T22:13:20.123Z");
+        // 早于 Unix 纪元的时间截断为 0
+        assert_eq!(OpcTimestamp::from_filetime(0).unix_ms(), 0);
+        #[cfg(feature = "chrono")]
+        assert_eq!(timestamp.to_chrono_utc().unwrap().timestamp_millis(), 1_700_000_000_123);
+    }
+    
+    #[test]
+    fn test_decimal_parse_display_and_raw() {
+        let price: OpcDecimal = "-12.50".parse().unwrap();
+        assert_eq!((price.mantissa(), price.scale(), price.is_negative()), (1250, 2, true));
+        assert_eq!(price.to_string(), "-12.50");
+        assert_eq!(price, "-12.5".parse().unwrap());
+        assert_eq!(price.to_f64(), -12.5);
+        assert_eq!(OpcDecimal::from_raw(&price.to_raw()).unwrap(), price);
+        
+        let max = OpcDecimal::new((1 << 96) - 1, 0, false).unwrap();
+        assert_eq!(max.to_string(), "79228162514264337593543950335");
+        assert!("79228162514264337593543950336".parse::<OpcDecimal>().is_err());
+        assert!(OpcDecimal::new(1, 29, false).is_err());
+        assert!("1.2.3".parse::<OpcDecimal>().is_err());
+        assert_eq!(OpcDecimal::from_f64(0.1).unwrap().to_string(), "0.1");
+        assert_eq!(OpcValue::Decimal(price).as_f64(), Some(-12.5));
+        #[cfg(feature = "rust_decimal")]
+        assert_eq!(OpcDecimal::from(rust_decimal::Decimal::from(price)), price);
+    }
+    
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_json() {
+        let event = DataChangeEvent {
+            group: "G".to_string(),
+            item: "Tank1.Level".to_string(),
+            value: OpcValue::Decimal("12.50".parse().unwrap()),
+            quality: OpcQuality::Good,
+            timestamp: OpcTimestamp::from_unix_ms(1_700_000_000_000),
+        };
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(
+            json,
+            r#"{"group":"G","item":"Tank1.Level","value":{"type":"Decimal","value":"12.50"},"quality":"Good","timestamp":1700000000000}"#
+        );
+        assert_eq!(serde_json::from_str::<DataChangeEvent>(&json).unwrap(), event);
+        
+        let array = OpcValue::ArrayInt16(vec![1, -2]);
+        assert_eq!(serde_json::to_string(&array).unwrap(), r#"{"type":"ArrayInt16","value":[1,-2]}"#);
+        assert!(serde_json::from_str::<OpcValue>(r#"{"type":"Int8","value":300}"#).is_err());
+    }
+    
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_timestamp_is_fixed() {
+        let timestamp = OpcTimestamp::from_unix_ms(1_700_000_000_123);
+        assert_eq!(serde_json::to_value(timestamp).unwrap(), serde_json::json!(1_700_000_000_123u64));
+        assert_eq!(serde_json::from_str::<OpcTimestamp>("1700000000123").unwrap(), timestamp);
+        assert_eq!(serde_json::from_str::<OpcTimestamp>(r#""2023-11-14T22:13:20.123Z""#).unwrap(), timestamp);
+        assert!(serde_json::from_str::<OpcTimestamp>("-1").is_err());
+        assert!(serde_json::from_str::<OpcTimestamp>(r#""yesterday""#).is_err());
+    }
+    
+    #[cfg(windows)]
+    #[test]
+    fn test_safe_array_strings() {
+        use windows::Win32::System::Ole::{SafeArrayAccessData, SafeArrayUnaccessData};
+        
+        let strings = vec!["a".to_string(), "温度\"".to_string(), String::new()];
+        let array = OwnedSafeArray::from_value(&OpcValue::ArrayString(strings.clone())).unwrap().unwrap();
+        let read: Vec<String> = unsafe {
+            let mut p_data: *mut std::ffi::c_void = std::ptr::null_mut();
+            SafeArrayAccessData(array.0, &mut p_data).unwrap();
+            // 只借用数组中的 BSTR，由数组负责释放
+            let bstrs = std::slice::from_raw_parts(p_data as *const windows::core::BSTR, strings.len());
+            let read = bstrs.iter().map(|bstr| bstr.to_string()).collect();
+            let _ = SafeArrayUnaccessData(array.0);
+            read
+        };
+        assert_eq!(read, strings);
+        
+        // 构造失败时由 from_strings 释放已分配的 BSTR
+        let mut allocated = 0;
+        let result = OwnedSafeArray::from_strings(&strings, |bstrs| {
+            allocated = bstrs.len();
+            Err(OpcValueError::conversion_error("Failed to access SAFEARRAY data"))
+        });
+        assert!(result.is_err());
+        assert_eq!(allocated, strings.len());
+    }
+}
@@ -0,0 +1,463 @@
+//! 工作线程模块（内部使用）
+//!
+//! OPC 对象不能跨线程使用。`Writer`、`SharedOpcClient` 和 `async` 特性的 `AsyncOpcClient`
+//! 都通过这里的工作线程访问服务器：工作线程创建一个 `OpcClient`，拥有由它建立的所有
+//! 服务器连接、组和项，按收到的顺序依次执行请求。句柄只保存对象的编号。
+//!
+//! 请求的结果通过 `Reply` 回调送回，同步句柄用 `std::sync::mpsc` 等待结果，
+//! 异步句柄用 `tokio::sync::oneshot`。异步请求可以带期限，工作线程取到请求时
+//! 期限已过则不执行，直接返回 `OpcError::Timeout`。所有 `Worker` 引用释放后，
+//! 工作线程按项、组、服务器、客户端的顺序释放对象并退出。
+
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::{self, ThreadId};
+#[cfg(feature = "async")]
+use std::time::Instant;
+use crate::client::OpcClient;
+use crate::connection::ConnectionString;
+use crate::error::{OpcError, OpcResult};
+use crate::group::OpcGroup;
+use crate::item::OpcItem;
+use crate::server::{DuplicateGroupPolicy, OpcServer};
+use crate::types::{OpcDataCallback, OpcQuality, OpcTimestamp, OpcValue, ServerState};
+
+/// 请求结果的接收方
+pub(crate) type Reply<T> = Box<dyn FnOnce(OpcResult<T>) + Send>;
+
+/// 发给工作线程的请求
+pub(crate) enum Request {
+    Connect {
+        connection: ConnectionString,
+        reply: Reply<u64>,
+    },
+    Status {
+        server: u64,
+        reply: Reply<(ServerState, String)>,
+    },
+    CreateGroup {
+        server: u64,
+        name: String,
+        active: bool,
+        update_rate: u32,
+        deadband: f64,
+        policy: DuplicateGroupPolicy,
+        reply: Reply<u64>,
+    },
+    AddItem {
+        group: u64,
+        name: String,
+        reply: Reply<u64>,
+    },
+    Read {
+        item: u64,
+        reply: Reply<(OpcValue, OpcQuality, OpcTimestamp)>,
+    },
+    Write {
+        item: u64,
+        value: OpcValue,
+        reply: Reply<()>,
+    },
+    Refresh {
+        group: u64,
+        reply: Reply<()>,
+    },
+    Subscribe {
+        group: u64,
+        callback: Arc<dyn OpcDataCallback>,
+        reply: Reply<()>,
+    },
+    ReleaseItem(u64),
+    ReleaseGroup(u64),
+    ReleaseServer(u64),
+    /// 带期限的请求，期限已过时不执行
+    #[cfg(feature = "async")]
+    Deadline {
+        deadline: Instant,
+        request: Box<Request>,
+    },
+}
+
+impl Request {
+    /// 去掉请求的期限，期限已过时拒绝请求并返回 `None`
+    #[cfg(feature = "async")]
+    fn admit(self, now: Instant) -> Option<Request> {
+        match self {
+            Request::Deadline { deadline, request } if now < deadline => request.admit(now),
+            Request::Deadline { request, .. } => {
+                request.reject(OpcError::Timeout(
+                    "Deadline passed before the OPC worker thread ran the request".to_string(),
+                ));
+                None
+            }
+            request => Some(request),
+        }
+    }
+
+    /// 不执行请求，把错误交给等待结果的一方
+    #[cfg(feature = "async")]
+    fn reject(self, error: OpcError) {
+        match self {
+            Request::Connect { reply, .. } => reply(Err(error)),
+            Request::Status { reply, .. } => reply(Err(error)),
+            Request::CreateGroup { reply, .. } => reply(Err(error)),
+            Request::AddItem { reply, .. } => reply(Err(error)),
+            Request::Read { reply, .. } => reply(Err(error)),
+            Request::Write { reply, .. } => reply(Err(error)),
+            Request::Refresh { reply, .. } => reply(Err(error)),
+            Request::Subscribe { reply, .. } => reply(Err(error)),
+            Request::Deadline { request, .. } => request.reject(error),
+            Request::ReleaseItem(_) | Request::ReleaseGroup(_) | Request::ReleaseServer(_) => {}
+        }
+    }
+}
+
+/// 工作线程的请求入口
+pub(crate) struct Worker {
+    requests: Sender<Request>,
+    thread: ThreadId,
+}
+
+impl Worker {
+    /// 启动工作线程
+    ///
+    /// 工作线程创建 `OpcClient` 后把结果交给 `ready`，创建失败时线程随即退出，
+    /// 之后的请求返回工作线程已停止的错误。
+    pub(crate) fn start(thread_name: &str, ready: Reply<()>) -> OpcResult<Arc<Worker>> {
+        let (requests, receiver) = mpsc::channel();
+        let handle = thread::Builder::new()
+            .name(thread_name.to_string())
+            .spawn(move || run(receiver, ready))?;
+        Ok(Arc::new(Worker {
+            requests,
+            thread: handle.thread().id(),
+        }))
+    }
+
+    /// 启动工作线程并等待客户端创建完成
+    pub(crate) fn start_blocking(thread_name: &str) -> OpcResult<Arc<Worker>> {
+        let (ready, result) = mpsc::channel();
+        let worker = Self::start(thread_name, sync_reply(ready))?;
+        result.recv().unwrap_or_else(|_| Err(worker_stopped()))?;
+        Ok(worker)
+    }
+
+    /// 发送请求并阻塞等待结果
+    ///
+    /// 在工作线程中调用（例如在数据变化回调中）会等待自己，因此直接返回
+    /// `OpcError::ReentrantCall`。
+    pub(crate) fn call<T: Send + 'static>(&self, operation: &str, request: impl FnOnce(Reply<T>) -> Request) -> OpcResult<T> {
+        if thread::current().id() == self.thread {
+            return Err(OpcError::ReentrantCall(format!(
+                "{} cannot be called from the OPC worker thread",
+                operation
+            )));
+        }
+        let (reply, result) = mpsc::channel();
+        self.requests.send(request(sync_reply(reply))).map_err(|_| worker_stopped())?;
+        result.recv().unwrap_or_else(|_| Err(worker_stopped()))
+    }
+
+    /// 发送请求并异步等待结果
+    ///
+    /// 工作线程取到请求时已经超过 `deadline`，请求不执行，返回 `OpcError::Timeout`。
+    #[cfg(feature = "async")]
+    pub(crate) async fn call_async<T: Send + 'static>(
+        &self,
+        deadline: Option<Instant>,
+        request: impl FnOnce(Reply<T>) -> Request,
+    ) -> OpcResult<T> {
+        let (reply, result) = tokio::sync::oneshot::channel();
+        let request = match deadline {
+            Some(deadline) => Request::Deadline {
+                deadline,
+                request: Box::new(request(async_reply(reply))),
+            },
+            None => request(async_reply(reply)),
+        };
+        self.requests.send(request).map_err(|_| worker_stopped())?;
+        result.await.unwrap_or_else(|_| Err(worker_stopped()))
+    }
+
+    /// 已经停止的工作线程，用于测试
+    #[cfg(test)]
+    pub(crate) fn stopped() -> Arc<Worker> {
+        let (requests, _) = mpsc::channel();
+        Arc::new(Worker {
+            requests,
+            thread: thread::current().id(),
+        })
+    }
+
+    /// 发送不需要结果的请求
+    pub(crate) fn notify(&self, request: Request) {
+        // 工作线程已经退出时对象也已释放
+        let _ = self.requests.send(request);
+    }
+}
+
+/// 工作线程中的一个服务器连接
+///
+/// 客户端、组和项的句柄共享同一个 `ServerLink`，最后一个引用释放时
+/// 服务器连接和它剩余的组、项在工作线程中释放。
+pub(crate) struct ServerLink {
+    pub(crate) worker: Arc<Worker>,
+    pub(crate) id: u64,
+    /// 连接字符串是否为只读
+    pub(crate) readonly: bool,
+}
+
+impl ServerLink {
+    /// 在工作线程中连接服务器
+    pub(crate) fn connect(worker: &Arc<Worker>, operation: &str, connection: &ConnectionString) -> OpcResult<Arc<ServerLink>> {
+        let id = worker.call(operation, |reply| Request::Connect {
+            connection: connection.clone(),
+            reply,
+        })?;
+        Ok(Self::new(worker, id, connection))
+    }
+
+    /// 登记已经在工作线程中建立的连接
+    pub(crate) fn new(worker: &Arc<Worker>, id: u64, connection: &ConnectionString) -> Arc<ServerLink> {
+        Arc::new(ServerLink {
+            worker: Arc::clone(worker),
+            id,
+            readonly: connection.readonly,
+        })
+    }
+}
+
+impl Drop for ServerLink {
+    fn drop(&mut self) {
+        self.worker.notify(Request::ReleaseServer(self.id));
+    }
+}
+
+/// 把结果发到同步通道
+pub(crate) fn sync_reply<T: Send + 'static>(sender: Sender<OpcResult<T>>) -> Reply<T> {
+    Box::new(move |result| {
+        let _ = sender.send(result);
+    })
+}
+
+/// 把结果发到 oneshot 通道
+#[cfg(feature = "async")]
+pub(crate) fn async_reply<T: Send + 'static>(sender: tokio::sync::oneshot::Sender<OpcResult<T>>) -> Reply<T> {
+    Box::new(move |result| {
+        let _ = sender.send(result);
+    })
+}
+
+pub(crate) fn worker_stopped() -> OpcError {
+    OpcError::operation_failed("OPC worker thread has stopped")
+}
+
+/// 工作线程拥有的 OPC 对象
+#[derive(Default)]
+struct Resources {
+    next_id: u64,
+    /// 项编号 -> (所属组编号, 项)
+    items: HashMap<u64, (u64, OpcItem)>,
+    /// 组编号 -> (所属服务器编号, 组)
+    groups: HashMap<u64, (u64, OpcGroup)>,
+    servers: HashMap<u64, OpcServer>,
+}
+
+impl Resources {
+    fn allocate_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn server(&self, id: u64) -> OpcResult<&OpcServer> {
+        self.servers
+            .get(&id)
+            .ok_or_else(|| OpcError::operation_failed("Server connection has been released"))
+    }
+
+    fn group(&self, id: u64) -> OpcResult<&OpcGroup> {
+        self.groups
+            .get(&id)
+            .map(|(_, group)| group)
+            .ok_or_else(|| OpcError::operation_failed("Group has been released"))
+    }
+
+    fn item(&self, id: u64) -> OpcResult<&OpcItem> {
+        self.items
+            .get(&id)
+            .map(|(_, item)| item)
+            .ok_or_else(|| OpcError::operation_failed("Item has been released"))
+    }
+
+    /// 释放组和组内的项，项先于组释放
+    fn release_group(&mut self, id: u64) {
+        self.items.retain(|_, (group, _)| *group != id);
+        self.groups.remove(&id);
+    }
+
+    /// 释放服务器连接及其组和项
+    fn release_server(&mut self, id: u64) {
+        let groups: Vec<u64> = self
+            .groups
+            .iter()
+            .filter(|(_, (server, _))| *server == id)
+            .map(|(group, _)| *group)
+            .collect();
+        for group in groups {
+            self.release_group(group);
+        }
+        self.servers.remove(&id);
+    }
+
+    fn execute(&mut self, client: &OpcClient, request: Request) {
+        match request {
+            Request::Connect { connection, reply } => {
+                let result = client.connect(&connection).map(|server| {
+                    let id = self.allocate_id();
+                    self.servers.insert(id, server);
+                    id
+                });
+                reply(result);
+            }
+            Request::Status { server, reply } => {
+                reply(self.server(server).and_then(|server| server.get_status()));
+            }
+            Request::CreateGroup {
+                server,
+                name,
+                active,
+                update_rate,
+                deadband,
+                policy,
+                reply,
+            } => {
+                let result = self
+                    .server(server)
+                    .and_then(|s| s.create_group_with_policy(&name, active, update_rate, deadband, policy))
+                    .map(|(group, _)| {
+                        let id = self.allocate_id();
+                        self.groups.insert(id, (server, group));
+                        id
+                    });
+                reply(result);
+            }
+            Request::AddItem { group, name, reply } => {
+                let result = self.group(group).and_then(|g| g.add_item(&name)).map(|item| {
+                    let id = self.allocate_id();
+                    self.items.insert(id, (group, item));
+                    id
+                });
+                reply(result);
+            }
+            Request::Read { item, reply } => {
+                reply(self.item(item).and_then(|item| item.read_sync()));
+            }
+            Request::Write { item, value, reply } => {
+                reply(self.item(item).and_then(|item| item.write_sync(&value)));
+            }
+            Request::Refresh { group, reply } => {
+                reply(self.group(group).and_then(|group| group.refresh()));
+            }
+            Request::Subscribe { group, callback, reply } => {
+                let result = self.group(group).and_then(|group| {
+                    group
+                        .add_callback(Arc::clone(&callback))
+                        .or_else(|_| group.enable_async_subscription(callback))
+                });
+                reply(result);
+            }
+            Request::ReleaseItem(id) => {
+                self.items.remove(&id);
+            }
+            Request::ReleaseGroup(id) => self.release_group(id),
+            Request::ReleaseServer(id) => self.release_server(id),
+            #[cfg(feature = "async")]
+            request @ Request::Deadline { .. } => {
+                if let Some(request) = request.admit(Instant::now()) {
+                    self.execute(client, request);
+                }
+            }
+        }
+    }
+}
+
+/// 工作线程：拥有所有 OPC 对象，依次执行请求
+fn run(requests: Receiver<Request>, ready: Reply<()>) {
+    let client = match OpcClient::new() {
+        Ok(client) => {
+            ready(Ok(()));
+            client
+        }
+        Err(err) => {
+            ready(Err(err));
+            return;
+        }
+    };
+
+    let mut resources = Resources::default();
+    for request in requests {
+        resources.execute(&client, request);
+    }
+
+    // 项、组、服务器和客户端必须按此顺序释放
+    drop(resources.items);
+    drop(resources.groups);
+    drop(resources.servers);
+    drop(client);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_worker_thread_calls_are_rejected() {
+        // 测试用的工作线程记录的是当前线程
+        let worker = Worker::stopped();
+        let result = worker.call("SharedOpcItem::read_sync", |reply| Request::Read { item: 1, reply });
+        assert!(matches!(result, Err(OpcError::ReentrantCall(_))));
+    }
+
+    #[test]
+    fn test_stopped_worker_reports_errors() {
+        let worker = Worker::stopped();
+        let result = thread::scope(|scope| {
+            scope
+                .spawn(|| worker.call("SharedOpcClient::get_status", |reply| Request::Status { server: 1, reply }))
+                .join()
+                .unwrap()
+        });
+        assert!(matches!(result, Err(OpcError::OperationFailed(_))));
+    }
+
+    // 非 Windows 平台上工作线程创建客户端失败，错误返回给调用方
+    #[cfg(not(windows))]
+    #[test]
+    fn test_start_reports_client_errors() {
+        let err = Worker::start_blocking("opcda-test").err().unwrap();
+        assert!(err.is_unsupported_platform());
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_expired_requests_are_rejected_without_running() {
+        let now = Instant::now();
+        let (reply, result) = mpsc::channel();
+        let request = Request::Deadline {
+            deadline: now,
+            request: Box::new(Request::Read { item: 1, reply: sync_reply(reply) }),
+        };
+        assert!(request.admit(now).is_none());
+        assert!(matches!(result.recv().unwrap(), Err(OpcError::Timeout(_))));
+
+        let (reply, result) = mpsc::channel::<OpcResult<()>>();
+        let request = Request::Deadline {
+            deadline: now + std::time::Duration::from_secs(1),
+            request: Box::new(Request::Refresh { group: 7, reply: sync_reply(reply) }),
+        };
+        assert!(matches!(request.admit(now), Some(Request::Refresh { group: 7, .. })));
+        // 未过期的请求由工作线程执行，这里没有结果
+        assert!(result.try_recv().is_err());
+    }
+}
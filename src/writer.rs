@@ -0,0 +1,141 @@
+//! 只写句柄模块
+//!
+//! `Writer` 是一个只能写入预先登记的项的句柄。它可以克隆、可以在线程之间传递，
+//! 适合嵌入到命令处理服务中：持有句柄的代码不拥有任何 COM 对象，也无法执行读取。
+//!
+//! 句柄背后是一个专用的工作线程，与 `SharedOpcClient` 使用同一个内部实现。
+//! 工作线程自己创建客户端、连接服务器、创建非激活的组并添加登记的项，
+//! 之后按顺序执行收到的写入请求。
+//! 所有 COM 对象都只在这个线程中创建和释放。最后一个句柄释放后，
+//! 工作线程释放 COM 对象并退出。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::{ConnectionString, OpcValue, Writer};
+//!
+//! let conn: ConnectionString = "host=10.0.0.5;progid=Kepware.KEPServerEX.V6".parse()?;
+//! let writer = Writer::spawn(&conn, &["Line1.Setpoint", "Line1.Start"])?;
+//!
+//! let handle = writer.clone();
+//! std::thread::spawn(move || {
+//!     handle.write("Line1.Start", OpcValue::Bool(true))
+//! });
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::connection::ConnectionString;
+use crate::error::{OpcError, OpcResult};
+use crate::server::DuplicateGroupPolicy;
+use crate::types::OpcValue;
+use crate::worker::{Request, ServerLink, Worker};
+
+/// 只写句柄
+///
+/// 克隆得到的句柄共享同一个工作线程，写入按收到的顺序依次执行。
+#[derive(Clone)]
+pub struct Writer {
+    link: Arc<ServerLink>,
+    items: Arc<Vec<String>>,
+    /// 项名 -> 工作线程中的项编号
+    ids: Arc<HashMap<String, u64>>,
+}
+
+impl Writer {
+    /// 启动工作线程并登记可写的项
+    ///
+    /// 工作线程连接到 `connection` 指定的服务器，创建非激活的组并添加所有项，
+    /// 全部成功后才返回句柄。
+    ///
+    /// # 返回值
+    /// - `Ok(Writer)`: 工作线程已就绪
+    /// - `Err(OpcError::InvalidParameters)`: 连接字符串为只读，或没有登记任何项
+    /// - `Err(OpcError)`: 连接服务器、创建组或添加项失败
+    pub fn spawn<S: AsRef<str>>(connection: &ConnectionString, items: &[S]) -> OpcResult<Self> {
+        if connection.readonly {
+            return Err(OpcError::invalid_parameters("Cannot create a writer for a readonly connection"));
+        }
+        if items.is_empty() {
+            return Err(OpcError::invalid_parameters("A writer needs at least one item"));
+        }
+
+        let worker = Worker::start_blocking("opcda-writer")?;
+        let link = ServerLink::connect(&worker, "Writer::spawn", connection)?;
+        // 残留的同名组（例如上次进程崩溃留下的）不应阻止启动
+        let group = worker.call("Writer::spawn", |reply| Request::CreateGroup {
+            server: link.id,
+            name: "opcda-writer".to_string(),
+            active: false,
+            update_rate: 0,
+            deadband: 0.0,
+            policy: DuplicateGroupPolicy::AutoSuffix { max_attempts: 10 },
+            reply,
+        })?;
+        let items: Vec<String> = items.iter().map(|name| name.as_ref().to_string()).collect();
+        let mut ids = HashMap::new();
+        for name in &items {
+            if ids.contains_key(name) {
+                continue;
+            }
+            let id = worker.call("Writer::spawn", |reply| Request::AddItem {
+                group,
+                name: name.clone(),
+                reply,
+            })?;
+            ids.insert(name.clone(), id);
+        }
+
+        Ok(Writer {
+            link,
+            items: Arc::new(items),
+            ids: Arc::new(ids),
+        })
+    }
+
+    /// 同步写入一个登记的项，等待工作线程返回结果
+    ///
+    /// # 返回值
+    /// - `Ok(())`: 写入成功
+    /// - `Err(OpcError::InvalidParameters)`: 项未登记
+    /// - `Err(OpcError::ReentrantCall)`: 在工作线程中（例如数据变化回调中）调用
+    /// - `Err(OpcError)`: 写入失败或工作线程已停止
+    pub fn write(&self, item_name: &str, value: OpcValue) -> OpcResult<()> {
+        let item = *self.ids.get(item_name).ok_or_else(|| {
+            OpcError::invalid_parameters(format!("Item '{}' is not registered with this writer", item_name))
+        })?;
+        self.link
+            .worker
+            .call("Writer::write", |reply| Request::Write { item, value, reply })
+    }
+
+    /// 登记的项
+    pub fn items(&self) -> &[String] {
+        &self.items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spawn_rejects_readonly_and_reports_setup_errors() {
+        let mut conn = ConnectionString::new("localhost", "Matrikon.OPC.Simulation.1");
+        assert!(Writer::spawn::<&str>(&conn, &[]).is_err());
+
+        conn.readonly = true;
+        assert!(matches!(
+            Writer::spawn(&conn, &["Bucket Brigade.Int4"]),
+            Err(OpcError::InvalidParameters(_))
+        ));
+
+        // 非 Windows 平台上工作线程创建客户端失败，错误返回给调用方
+        #[cfg(not(windows))]
+        {
+            conn.readonly = false;
+            let err = Writer::spawn(&conn, &["Bucket Brigade.Int4"]).err().unwrap();
+            assert!(err.is_unsupported_platform());
+        }
+    }
+}
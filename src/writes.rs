@@ -0,0 +1,189 @@
+//! 写入跟踪模块
+//!
+//! 这个模块记录客户端对每个项最近一次成功的写入，并识别服务器随后推送的
+//! "回声"数据变化，即服务器把我们刚写入的值作为数据变化通知回来。
+//!
+//! 基于订阅构建的控制回路如果不区分回声，会对自己发出的命令再次作出反应。
+//! 组可以选择忽略回声，或者在通知中将其标记为 `ChangeOrigin::SelfWrite`。
+//!
+//! ## 回声识别
+//!
+//! 写入成功后的 `window` 时间内，第一个值与写入值相等的数据变化视为回声。
+//! 数值按 `f64` 比较并允许微小的相对误差，因为服务器可能把写入的值
+//! 转换为项的原生类型（例如 `Double` 写入 `Float` 项）。每次写入最多匹配一次回声。
+//!
+//! ## 示例
+//!
+//! ```ignore
+//! use opc_da_client::{EchoHandling, OpcValue};
+//! use std::time::Duration;
+//!
+//! let group = server.create_group("Control", true, 500, 0.0)?;
+//! group.set_echo_handling(EchoHandling::Suppress, Duration::from_secs(2));
+//!
+//! let setpoint = group.add_item("PIC101.SP")?;
+//! setpoint.write_sync(&OpcValue::Double(42.0))?;
+//! // 服务器推送的 42.0 不会到达回调
+//!
+//! if let Some(last) = group.last_write("PIC101.SP") {
+//!     println!("上次写入 {:?}，{:?} 之前", last.value, last.written_at.elapsed());
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use crate::types::{lock_or_recover, ChangeOrigin, OpcValue};
+
+/// 默认的回声识别时间窗口
+pub const DEFAULT_ECHO_WINDOW: Duration = Duration::from_secs(2);
+
+/// 回声数据变化的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EchoHandling {
+    /// 不识别回声，所有数据变化的来源都是 `ChangeOrigin::Server`
+    #[default]
+    Off,
+    /// 分发回声，并标记为 `ChangeOrigin::SelfWrite`
+    Tag,
+    /// 不分发回声
+    Suppress,
+}
+
+/// 一次成功的写入
+#[derive(Debug, Clone, PartialEq)]
+pub struct LastWrite {
+    /// 写入的值
+    pub value: OpcValue,
+    /// 写入完成的时间
+    pub written_at: Instant,
+    /// 回声是否仍未到达
+    pub echo_pending: bool,
+}
+
+/// 每个项的写入跟踪
+///
+/// 同一组的项和订阅共享一个跟踪器：项写入成功时记录，
+/// 数据变化回调中用于识别回声。
+#[derive(Debug)]
+pub(crate) struct WriteTracker {
+    settings: Mutex<(EchoHandling, Duration)>,
+    writes: Mutex<HashMap<String, LastWrite>>,
+}
+
+impl Default for WriteTracker {
+    fn default() -> Self {
+        WriteTracker {
+            settings: Mutex::new((EchoHandling::Off, DEFAULT_ECHO_WINDOW)),
+            writes: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl WriteTracker {
+    /// 设置回声处理方式和识别时间窗口
+    pub(crate) fn set_echo_handling(&self, handling: EchoHandling, window: Duration) {
+        *lock_or_recover(&self.settings) = (handling, window);
+    }
+
+    /// 当前的回声处理方式和识别时间窗口
+    pub(crate) fn echo_handling(&self) -> (EchoHandling, Duration) {
+        *lock_or_recover(&self.settings)
+    }
+
+    /// 记录一次成功的写入
+    pub(crate) fn record(&self, item_name: &str, value: &OpcValue) {
+        lock_or_recover(&self.writes).insert(
+            item_name.to_string(),
+            LastWrite {
+                value: value.clone(),
+                written_at: Instant::now(),
+                echo_pending: true,
+            },
+        );
+    }
+
+    /// 项最近一次成功的写入
+    pub(crate) fn last_write(&self, item_name: &str) -> Option<LastWrite> {
+        lock_or_recover(&self.writes).get(item_name).cloned()
+    }
+
+    /// 判断数据变化的来源
+    ///
+    /// 返回 `None` 表示该数据变化是回声且应被忽略。
+    pub(crate) fn classify(&self, item_name: &str, value: &OpcValue) -> Option<ChangeOrigin> {
+        let (handling, window) = self.echo_handling();
+        if handling == EchoHandling::Off {
+            return Some(ChangeOrigin::Server);
+        }
+
+        let mut writes = lock_or_recover(&self.writes);
+        let is_echo = match writes.get_mut(item_name) {
+            Some(last) if last.echo_pending
+                && last.written_at.elapsed() <= window
+                && values_match(&last.value, value) =>
+            {
+                last.echo_pending = false;
+                true
+            }
+            _ => false,
+        };
+
+        match (is_echo, handling) {
+            (false, _) => Some(ChangeOrigin::Server),
+            (true, EchoHandling::Suppress) => None,
+            (true, _) => Some(ChangeOrigin::SelfWrite),
+        }
+    }
+}
+
+/// 比较写入值和通知值，数值允许微小的相对误差
+fn values_match(written: &OpcValue, notified: &OpcValue) -> bool {
+    if written == notified {
+        return true;
+    }
+    match (written.as_f64(), notified.as_f64()) {
+        (Some(a), Some(b)) => (a - b).abs() <= a.abs().max(b.abs()) * 1e-6,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_echo_suppressed_once() {
+        let tracker = WriteTracker::default();
+        tracker.set_echo_handling(EchoHandling::Suppress, Duration::from_secs(5));
+        tracker.record("SP", &OpcValue::Double(42.1));
+
+        // 服务器把 Double 转换为 Float 后推送
+        assert_eq!(tracker.classify("SP", &OpcValue::Float(42.1)), None);
+        assert_eq!(tracker.classify("SP", &OpcValue::Float(42.1)), Some(ChangeOrigin::Server));
+        assert!(!tracker.last_write("SP").unwrap().echo_pending);
+    }
+
+    #[test]
+    fn test_echo_tagged_and_other_values_pass() {
+        let tracker = WriteTracker::default();
+        tracker.set_echo_handling(EchoHandling::Tag, Duration::from_secs(5));
+        tracker.record("Cmd", &OpcValue::Bool(true));
+
+        assert_eq!(tracker.classify("Cmd", &OpcValue::Bool(false)), Some(ChangeOrigin::Server));
+        assert_eq!(tracker.classify("Other", &OpcValue::Bool(true)), Some(ChangeOrigin::Server));
+        assert_eq!(tracker.classify("Cmd", &OpcValue::Bool(true)), Some(ChangeOrigin::SelfWrite));
+    }
+
+    #[test]
+    fn test_echo_window_and_off() {
+        let tracker = WriteTracker::default();
+        tracker.record("SP", &OpcValue::Int32(1));
+        assert_eq!(tracker.classify("SP", &OpcValue::Int32(1)), Some(ChangeOrigin::Server));
+
+        tracker.set_echo_handling(EchoHandling::Suppress, Duration::ZERO);
+        tracker.record("SP", &OpcValue::Int32(1));
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(tracker.classify("SP", &OpcValue::Int32(1)), Some(ChangeOrigin::Server));
+    }
+}